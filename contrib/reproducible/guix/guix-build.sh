@@ -58,6 +58,7 @@ time_machine() {
 # Build both the daemon (at the root of the repository) and the GUI (in gui/)
 for project_folder in "" "gui"; do
     PROJECT_ROOT="$PWD/$project_folder"
+    CONTAINER_PROJECT_ROOT="/liana${project_folder:+/$project_folder}"
     PROJECT_VENDOR_DIR="$VENDOR_DIR/$project_folder"
     PROJECT_OUT_DIR="$OUT_DIR/$project_folder"
     PROJECT_PATCHES_ROOT="$PWD/contrib/reproducible/guix/liana-patches/$project_folder"
@@ -117,14 +118,18 @@ for project_folder in "" "gui"; do
     # NOTE: it looks like "--rebuild-cache" is necessary for the IS_GUI variable to
     # be taken into account when building the container (otherwise the GUI container could
     # miss some dependencies).
-    # TODO: only expose the "ui" folder for GUI builds
+    # The GUI depends on the daemon through its path in the repository, so the container
+    # mirrors the repository layout.
+    # TODO: only expose the "gui" folder for GUI builds
     IS_GUI="$IS_GUI" time_machine shell --no-cwd \
-               --expose="$PROJECT_ROOT/src=/liana/src" \
-               --expose="$PROJECT_ROOT/Cargo.toml=/liana/Cargo.toml" \
-               --expose="$BUILD_ROOT/Cargo.lock=/liana/Cargo.lock" \
-               --expose="$PWD/gui/ui/src=/liana/ui/src" \
-               --expose="$PWD/gui/ui/Cargo.toml=/liana/ui/Cargo.toml" \
-               --expose="$PWD/gui/ui/static=/liana/ui/static" \
+               --expose="$PWD/src=/liana/src" \
+               --expose="$PWD/Cargo.toml=/liana/Cargo.toml" \
+               --expose="$BUILD_ROOT/Cargo.lock=$CONTAINER_PROJECT_ROOT/Cargo.lock" \
+               --expose="$PWD/gui/src=/liana/gui/src" \
+               --expose="$PWD/gui/Cargo.toml=/liana/gui/Cargo.toml" \
+               --expose="$PWD/gui/ui/src=/liana/gui/ui/src" \
+               --expose="$PWD/gui/ui/Cargo.toml=/liana/gui/ui/Cargo.toml" \
+               --expose="$PWD/gui/ui/static=/liana/gui/ui/static" \
                --expose="$PWD/contrib/reproducible/guix/build.sh=/liana/build.sh" \
                --expose="$PROJECT_VENDOR_DIR=/vendor" \
                --share="$PROJECT_OUT_DIR=/out" \
@@ -135,7 +140,7 @@ for project_folder in "" "gui"; do
                --rebuild-cache \
                -m $PWD/contrib/reproducible/guix/manifest.scm \
                -- env CC=gcc VENDOR_DIR="$PROJECT_VENDOR_DIR" TARGET_DIR="$PROJECT_OUT_DIR" IS_GUI="$IS_GUI" JOBS="$JOBS" \
-                  /bin/sh -c "cd /liana && ./build.sh"
+                  /bin/sh -c "cd $CONTAINER_PROJECT_ROOT && /liana/build.sh"
 done
 
 set +ex
@@ -49,12 +49,13 @@ pub struct CoinsPanel {
     selected: Vec<usize>,
     labels_edited: LabelsEdited,
     warning: Option<Error>,
-    /// timelock value to pass for the heir to consume a coin.
-    timelock: u16,
+    /// timelock value to pass for the heir to consume a coin. `None` if there is no recovery path
+    /// behind a relative timelock in blocks.
+    timelock: Option<u16>,
 }
 
 impl CoinsPanel {
-    pub fn new(coins: &[Coin], timelock: u16) -> Self {
+    pub fn new(coins: &[Coin], timelock: Option<u16>) -> Self {
         let mut panel = Self {
             labels_edited: LabelsEdited::default(),
             coins: Coins::default(),
@@ -206,7 +207,7 @@ mod tests {
 
     #[test]
     fn test_coins_panel_update_coins() {
-        let mut panel = CoinsPanel::new(&[], 0);
+        let mut panel = CoinsPanel::new(&[], None);
         let txid = bitcoin::Txid::from_str(
             "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
         )
@@ -226,6 +227,8 @@ mod tests {
                 address: dummy_address.clone(),
                 derivation_index: 0.into(),
                 is_change: false,
+                is_frozen: false,
+                reserved_by: Vec::new(),
             },
             Coin {
                 outpoint: bitcoin::OutPoint { txid, vout: 3 },
@@ -236,6 +239,8 @@ mod tests {
                 address: dummy_address.clone(),
                 derivation_index: 1.into(),
                 is_change: false,
+                is_frozen: false,
+                reserved_by: Vec::new(),
             },
             Coin {
                 outpoint: bitcoin::OutPoint { txid, vout: 0 },
@@ -246,6 +251,8 @@ mod tests {
                 address: dummy_address.clone(),
                 derivation_index: 2.into(),
                 is_change: false,
+                is_frozen: false,
+                reserved_by: Vec::new(),
             },
             Coin {
                 outpoint: bitcoin::OutPoint { txid, vout: 1 },
@@ -256,6 +263,8 @@ mod tests {
                 address: dummy_address,
                 derivation_index: 3.into(),
                 is_change: false,
+                is_frozen: false,
+                reserved_by: Vec::new(),
             },
         ]);
 
@@ -157,7 +157,13 @@ impl State for Home {
                         if coin.spend_info.is_none() {
                             if coin.block_height.is_some() {
                                 self.balance += coin.amount;
-                                let timelock = self.wallet.main_descriptor.first_timelock_value();
+                                // Coins only expire if there is a recovery path behind a relative
+                                // timelock in blocks.
+                                let timelock =
+                                    match self.wallet.main_descriptor.first_timelock_value() {
+                                        Some(timelock) => timelock,
+                                        None => continue,
+                                    };
                                 let seq =
                                     remaining_sequence(&coin, cache.blockheight as u32, timelock);
                                 // Warn user for coins that are expiring in less than 10 percent of
@@ -6,9 +6,10 @@ use iced::Command;
 
 use liana::{
     commands::CoinStatus,
+    descriptors::{PathInfo, RecoveryTimelock},
     miniscript::bitcoin::{
         bip32::{DerivationPath, Fingerprint},
-        secp256k1,
+        secp256k1, OutPoint,
     },
 };
 use liana_ui::{component::form, widget::Element};
@@ -25,7 +26,7 @@ use crate::{
         wallet::Wallet,
     },
     daemon::{
-        model::{remaining_sequence, Coin, SpendTx},
+        model::{remaining_sequence, Coin, ExpiringCoin, SpendTx},
         Daemon,
     },
 };
@@ -34,6 +35,8 @@ use liana::miniscript::bitcoin::{Address, Amount};
 
 pub struct RecoveryPanel {
     wallet: Arc<Wallet>,
+    coins: Vec<Coin>,
+    expiring_coins: Vec<ExpiringCoin>,
     recovery_paths: Vec<RecoveryPath>,
     selected_path: Option<usize>,
     warning: Option<Error>,
@@ -45,7 +48,9 @@ pub struct RecoveryPanel {
 impl RecoveryPanel {
     pub fn new(wallet: Arc<Wallet>, coins: &[Coin], blockheight: i32) -> Self {
         Self {
-            recovery_paths: recovery_paths(&wallet, coins, blockheight),
+            recovery_paths: recovery_paths(&wallet, coins, &[], blockheight),
+            coins: coins.to_vec(),
+            expiring_coins: Vec::new(),
             wallet,
             selected_path: None,
             warning: None,
@@ -109,7 +114,26 @@ impl State for RecoveryPanel {
                 Err(e) => self.warning = Some(e),
                 Ok(coins) => {
                     self.warning = None;
-                    self.recovery_paths = recovery_paths(&self.wallet, &coins, cache.blockheight);
+                    self.coins = coins;
+                    self.recovery_paths = recovery_paths(
+                        &self.wallet,
+                        &self.coins,
+                        &self.expiring_coins,
+                        cache.blockheight,
+                    );
+                }
+            },
+            Message::ExpiringCoins(res) => match res {
+                Err(e) => self.warning = Some(e),
+                Ok(expiring_coins) => {
+                    self.warning = None;
+                    self.expiring_coins = expiring_coins;
+                    self.recovery_paths = recovery_paths(
+                        &self.wallet,
+                        &self.coins,
+                        &self.expiring_coins,
+                        cache.blockheight,
+                    );
                 }
             },
             Message::Recovery(res) => match res {
@@ -150,16 +174,26 @@ impl State for RecoveryPanel {
                     let feerate_vb = self.feerate.value.parse::<u64>().expect("Checked before");
                     self.warning = None;
                     let desc = self.wallet.main_descriptor.clone();
-                    let sequence = self
+                    let timelock = self
                         .recovery_paths
                         .get(self.selected_path.expect("A path must be selected"))
-                        .map(|p| p.sequence);
+                        .map(|p| p.timelock);
                     let network = cache.network;
                     return Command::perform(
                         async move {
-                            let psbt = daemon
-                                .create_recovery(address, feerate_vb, sequence)
-                                .await?;
+                            let psbt = match timelock {
+                                Some(RecoveryTimelock::RelativeTime(timelock)) => {
+                                    daemon
+                                        .create_time_recovery(address, feerate_vb, Some(timelock))
+                                        .await?
+                                }
+                                Some(RecoveryTimelock::Relative(sequence)) => {
+                                    daemon
+                                        .create_recovery(address, feerate_vb, Some(sequence))
+                                        .await?
+                                }
+                                _ => daemon.create_recovery(address, feerate_vb, None).await?,
+                            };
                             let outpoints: Vec<_> = psbt
                                 .unsigned_tx
                                 .input
@@ -209,7 +243,24 @@ impl State for RecoveryPanel {
         self.feerate = form::Value::default();
         self.recipient = form::Value::default();
         self.generated = None;
-        Command::perform(
+        self.expiring_coins = Vec::new();
+        let mut commands = Vec::new();
+        // The availability of the recovery paths behind a relative timelock expressed in time
+        // depends on the median time past of the blocks, which is computed by the daemon.
+        if !self
+            .wallet
+            .main_descriptor
+            .policy()
+            .time_recovery_paths()
+            .is_empty()
+        {
+            let daemon = daemon.clone();
+            commands.push(Command::perform(
+                async move { daemon.list_expiring_coins().await.map_err(|e| e.into()) },
+                Message::ExpiringCoins,
+            ));
+        }
+        commands.push(Command::perform(
             async move {
                 daemon
                     .list_coins(&[CoinStatus::Unconfirmed, CoinStatus::Confirmed], &[])
@@ -218,7 +269,8 @@ impl State for RecoveryPanel {
                     .map_err(|e| e.into())
             },
             Message::Coins,
-        )
+        ));
+        Command::batch(commands)
     }
 }
 
@@ -230,40 +282,69 @@ impl From<RecoveryPanel> for Box<dyn State> {
 
 pub struct RecoveryPath {
     threshold: usize,
-    sequence: u16,
+    timelock: RecoveryTimelock,
     origins: Vec<(Fingerprint, HashSet<DerivationPath>)>,
     total_amount: Amount,
     number_of_coins: usize,
 }
 
-fn recovery_paths(wallet: &Wallet, coins: &[Coin], blockheight: i32) -> Vec<RecoveryPath> {
-    wallet
-        .main_descriptor
-        .policy()
-        .recovery_paths()
+impl RecoveryPath {
+    fn new<'a>(
+        timelock: RecoveryTimelock,
+        path: &PathInfo,
+        available_coins: impl Iterator<Item = &'a Coin>,
+    ) -> Self {
+        let (number_of_coins, total_amount) = available_coins.fold(
+            (0, Amount::from_sat(0)),
+            |(number_of_coins, total_amount), coin| {
+                (number_of_coins + 1, total_amount + coin.amount)
+            },
+        );
+        let (threshold, origins) = path.thresh_origins();
+        RecoveryPath {
+            total_amount,
+            number_of_coins,
+            timelock,
+            threshold,
+            origins: origins.into_iter().collect(),
+        }
+    }
+}
+
+fn recovery_paths(
+    wallet: &Wallet,
+    coins: &[Coin],
+    expiring_coins: &[ExpiringCoin],
+    blockheight: i32,
+) -> Vec<RecoveryPath> {
+    let policy = wallet.main_descriptor.policy();
+    let relative_paths = policy.recovery_paths().iter().map(|(&sequence, path)| {
+        let available_coins = coins.iter().filter(|coin| {
+            coin.spend_info.is_none() && remaining_sequence(coin, blockheight as u32, sequence) <= 1
+        });
+        RecoveryPath::new(RecoveryTimelock::Relative(sequence), path, available_coins)
+    });
+    let time_paths = policy
+        .time_recovery_paths()
         .iter()
-        .map(|(&sequence, path)| {
-            let (number_of_coins, total_amount) = coins
+        .map(|(&timelock, path)| {
+            let available: HashSet<OutPoint> = expiring_coins
                 .iter()
                 .filter(|coin| {
-                    coin.spend_info.is_none()
-                        && remaining_sequence(coin, blockheight as u32, sequence) <= 1
+                    coin.time_recovery_paths
+                        .iter()
+                        .any(|p| p.timelock == timelock && p.seconds_remaining == 0)
                 })
-                .fold(
-                    (0, Amount::from_sat(0)),
-                    |(number_of_coins, total_amount), coin| {
-                        (number_of_coins + 1, total_amount + coin.amount)
-                    },
-                );
-
-            let (threshold, origins) = path.thresh_origins();
-            RecoveryPath {
-                total_amount,
-                number_of_coins,
-                sequence,
-                threshold,
-                origins: origins.into_iter().collect(),
-            }
-        })
-        .collect()
+                .map(|coin| coin.outpoint)
+                .collect();
+            let available_coins = coins.iter().filter(move |coin| {
+                coin.spend_info.is_none() && available.contains(&coin.outpoint)
+            });
+            RecoveryPath::new(
+                RecoveryTimelock::RelativeTime(timelock),
+                path,
+                available_coins,
+            )
+        });
+    relative_paths.chain(time_paths).collect()
 }
@@ -82,7 +82,7 @@ pub struct DefineSpend {
     network: Network,
     descriptor: LianaDescriptor,
     curve: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
-    timelock: u16,
+    timelock: Option<u16>,
     coins: Vec<(Coin, bool)>,
     coins_labels: HashMap<String, String>,
     batch_label: form::Value<String>,
@@ -97,7 +97,7 @@ impl DefineSpend {
         network: Network,
         descriptor: LianaDescriptor,
         coins: &[Coin],
-        timelock: u16,
+        timelock: Option<u16>,
     ) -> Self {
         let coins: Vec<(Coin, bool)> = coins
             .iter()
@@ -145,11 +145,16 @@ impl DefineSpend {
     fn sort_coins(&mut self, blockheight: u32) {
         let timelock = self.timelock;
         self.coins.sort_by(|(a, a_selected), (b, b_selected)| {
+            let same_sequence = match timelock {
+                Some(timelock) => {
+                    remaining_sequence(a, blockheight, timelock)
+                        == remaining_sequence(b, blockheight, timelock)
+                }
+                None => a.block_height == b.block_height,
+            };
             if *a_selected && !b_selected || !a_selected && *b_selected {
                 b_selected.cmp(a_selected)
-            } else if remaining_sequence(a, blockheight, timelock)
-                == remaining_sequence(b, blockheight, timelock)
-            {
+            } else if same_sequence {
                 // bigger amount first
                 b.amount.cmp(&a.amount)
             } else {
@@ -21,7 +21,7 @@ use crate::{
 pub fn coins_view<'a>(
     cache: &Cache,
     coins: &'a [Coin],
-    timelock: u16,
+    timelock: Option<u16>,
     selected: &[usize],
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
@@ -54,7 +54,7 @@ pub fn coins_view<'a>(
 #[allow(clippy::collapsible_else_if)]
 fn coin_list_view<'a>(
     coin: &'a Coin,
-    timelock: u16,
+    timelock: Option<u16>,
     blockheight: u32,
     index: usize,
     collapsed: bool,
@@ -114,13 +114,15 @@ fn coin_list_view<'a>(
                                     Container::new(Space::with_width(Length::Fill))
                                         .width(Length::Fill)
                                 })
-                                .push(if coin.spend_info.is_some() {
-                                    badge::spent()
+                                .push_maybe(if coin.spend_info.is_some() {
+                                    Some(badge::spent())
                                 } else if coin.block_height.is_none() {
-                                    badge::unconfirmed()
+                                    Some(badge::unconfirmed())
                                 } else {
-                                    let seq = remaining_sequence(coin, blockheight, timelock);
-                                    coin_sequence_label(seq, timelock as u32)
+                                    timelock.map(|timelock| {
+                                        let seq = remaining_sequence(coin, blockheight, timelock);
+                                        coin_sequence_label(seq, timelock as u32)
+                                    })
                                 })
                                 .spacing(10)
                                 .align_items(Alignment::Center)
@@ -152,7 +154,7 @@ fn coin_list_view<'a>(
                             .width(Length::Fill),
                         )
                         .push_maybe(if coin.spend_info.is_none() {
-                            if let Some(b) = coin.block_height {
+                            if let (Some(b), Some(timelock)) = (coin.block_height, timelock) {
                                 if blockheight > b as u32 + timelock as u32 {
                                     Some(Container::new(
                                         p1_bold("One of the recovery path is available")
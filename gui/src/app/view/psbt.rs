@@ -59,7 +59,7 @@ pub fn psbt_view<'a>(
                     .align_items(Alignment::Center)
                     .spacing(10)
                     .push(Container::new(h3("PSBT")).width(Length::Fill))
-                    .push_maybe(if !tx.sigs.recovery_paths().is_empty() {
+                    .push_maybe(if tx.is_recovery() {
                         Some(badge::recovery())
                     } else {
                         None
@@ -491,17 +491,30 @@ pub fn signatures<'a>(
                             .padding(15)
                             .spacing(10)
                             .push(text("Finalizing this transaction requires:"))
-                            .push_maybe(if tx.sigs.recovery_paths().is_empty() {
+                            .push_maybe(if !tx.is_recovery() {
                                 Some(path_view(
                                     desc_info.primary_path(),
                                     tx.sigs.primary_path(),
                                     keys_aliases,
                                 ))
                             } else {
-                                tx.sigs.recovery_paths().iter().last().map(|(seq, path)| {
-                                    let keys = &desc_info.recovery_paths()[seq];
-                                    path_view(keys, path, keys_aliases)
-                                })
+                                let relative_paths =
+                                    tx.sigs.recovery_paths().iter().map(|(seq, path)| {
+                                        (&desc_info.recovery_paths()[seq], path)
+                                    });
+                                let time_paths =
+                                    tx.sigs.time_recovery_paths().iter().map(|(tl, path)| {
+                                        (&desc_info.time_recovery_paths()[tl], path)
+                                    });
+                                let absolute_paths =
+                                    tx.sigs.absolute_recovery_paths().iter().map(|(tl, path)| {
+                                        (&desc_info.absolute_recovery_paths()[tl], path)
+                                    });
+                                relative_paths
+                                    .chain(time_paths)
+                                    .chain(absolute_paths)
+                                    .last()
+                                    .map(|(keys, path)| path_view(keys, path, keys_aliases))
                             }),
                     )
                 },
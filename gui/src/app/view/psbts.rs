@@ -100,7 +100,7 @@ fn spend_tx_list_view(i: usize, tx: &SpendTx) -> Element<'_, Message> {
                         } else {
                             badge::spend()
                         })
-                        .push(if !tx.sigs.recovery_paths().is_empty() {
+                        .push(if tx.is_recovery() {
                             badge::recovery()
                         } else {
                             let sigs = tx.sigs.primary_path();
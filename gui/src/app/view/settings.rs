@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::str::FromStr;
 
+use chrono::{DateTime, Local, Utc};
+
 use iced::{
     alignment,
     widget::{radio, scrollable, tooltip as iced_tooltip, Space},
@@ -9,7 +11,7 @@ use iced::{
 
 use liana::{
     config::BitcoindRpcAuth,
-    descriptors::{LianaDescriptor, LianaPolicy},
+    descriptors::{LianaDescriptor, LianaPolicy, PathInfo},
     miniscript::bitcoin::{bip32::Fingerprint, Network},
 };
 
@@ -1000,7 +1002,45 @@ fn display_policy(
     keys_aliases: &[(Fingerprint, form::Value<String>)],
 ) -> Element<'_, Message> {
     let (primary_threshold, primary_keys) = policy.primary_path().thresh_origins();
-    let recovery_paths = policy.recovery_paths();
+    // Recovery paths behind a relative timelock in blocks, then the ones expressed in time, then
+    // the ones behind an absolute timelock.
+    let recovery_paths: Vec<(&str, String, PathInfo)> = policy
+        .recovery_paths()
+        .iter()
+        .map(|(sequence, path)| {
+            (
+                "can spend coins inactive for",
+                format!(
+                    "{} blocks (~{})",
+                    sequence,
+                    expire_message_units(*sequence as u32).join(",")
+                ),
+                path.clone(),
+            )
+        })
+        .chain(policy.time_recovery_paths().iter().map(|(timelock, path)| {
+            (
+                "can spend coins inactive for",
+                format!(
+                    "~{}",
+                    duration_message_units(*timelock as u32 * 512 / 60).join(",")
+                ),
+                path.clone(),
+            )
+        }))
+        .chain(
+            policy
+                .absolute_recovery_paths()
+                .iter()
+                .map(|(timelock, path)| {
+                    (
+                        "can spend all coins from",
+                        absolute_timelock_message(*timelock),
+                        path.clone(),
+                    )
+                }),
+        )
+        .collect();
 
     // The iteration over an HashMap keys can have a different order at each refresh
     let mut primary_keys: Vec<Fingerprint> = primary_keys.into_keys().collect();
@@ -1056,7 +1096,7 @@ fn display_policy(
             )
             .push(text("can always spend this wallet's funds (Primary path)")),
     );
-    for (i, (sequence, recovery_path)) in recovery_paths.iter().enumerate() {
+    for (i, (description, duration, recovery_path)) in recovery_paths.iter().enumerate() {
         let (threshold, recovery_keys) = recovery_path.thresh_origins();
 
         // The iteration over an HashMap keys can have a different order at each refresh
@@ -1109,15 +1149,8 @@ fn display_policy(
                         }
                     },
                 ))
-                .push(text("can spend coins inactive for"))
-                .push(
-                    text(format!(
-                        "{} blocks (~{})",
-                        sequence,
-                        expire_message_units(*sequence as u32).join(",")
-                    ))
-                    .bold(),
-                )
+                .push(text(*description))
+                .push(text(duration).bold())
                 .push(text(format!("(Recovery path #{})", i + 1))),
         );
     }
@@ -1130,9 +1163,26 @@ fn display_policy(
         .into()
 }
 
+/// An absolute timelock is either a block height or a UNIX timestamp.
+fn absolute_timelock_message(timelock: u32) -> String {
+    if timelock < 500_000_000 {
+        format!("block {}", timelock)
+    } else {
+        DateTime::<Utc>::from_timestamp(timelock as i64, 0)
+            .expect("Correct unix timestamp")
+            .with_timezone(&Local)
+            .format("%b. %d, %Y")
+            .to_string()
+    }
+}
+
 /// returns y,m,d
 fn expire_message_units(sequence: u32) -> Vec<String> {
-    let mut n_minutes = sequence * 10;
+    duration_message_units(sequence * 10)
+}
+
+/// returns y,m,d
+fn duration_message_units(mut n_minutes: u32) -> Vec<String> {
     let n_years = n_minutes / 525960;
     n_minutes -= n_years * 525960;
     let n_months = n_minutes / 43830;
@@ -114,7 +114,7 @@ pub fn create_spend_tx<'a>(
     recipients: Vec<Element<'a, Message>>,
     is_valid: bool,
     duplicate: bool,
-    timelock: u16,
+    timelock: Option<u16>,
     coins: &[(Coin, bool)],
     coins_labels: &'a HashMap<String, String>,
     batch_label: &form::Value<String>,
@@ -422,7 +422,7 @@ fn coin_list_view<'a>(
     i: usize,
     coin: &Coin,
     coins_labels: &'a HashMap<String, String>,
-    timelock: u16,
+    timelock: Option<u16>,
     blockheight: u32,
     selected: bool,
 ) -> Element<'a, Message> {
@@ -454,13 +454,15 @@ fn coin_list_view<'a>(
                         Container::new(p1_regular("")).width(Length::Fill)
                     },
                 )
-                .push(if coin.spend_info.is_some() {
-                    badge::spent()
+                .push_maybe(if coin.spend_info.is_some() {
+                    Some(badge::spent())
                 } else if coin.block_height.is_none() {
-                    badge::unconfirmed()
+                    Some(badge::unconfirmed())
                 } else {
-                    let seq = remaining_sequence(coin, blockheight, timelock);
-                    coins::coin_sequence_label(seq, timelock as u32)
+                    timelock.map(|timelock| {
+                        let seq = remaining_sequence(coin, blockheight, timelock);
+                        coins::coin_sequence_label(seq, timelock as u32)
+                    })
                 })
                 .spacing(10)
                 .align_items(Alignment::Center)
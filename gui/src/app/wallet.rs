@@ -71,7 +71,12 @@ impl Wallet {
         for (fingerprint, _) in info.primary_path().thresh_origins().1.iter() {
             descriptor_keys.insert(*fingerprint);
         }
-        for path in info.recovery_paths().values() {
+        for path in info
+            .recovery_paths()
+            .values()
+            .chain(info.time_recovery_paths().values())
+            .chain(info.absolute_recovery_paths().values())
+        {
             for (fingerprint, _) in path.thresh_origins().1.iter() {
                 descriptor_keys.insert(*fingerprint);
             }
@@ -139,7 +144,7 @@ impl Wallet {
         datadir_path: &Path,
         network: bitcoin::Network,
     ) -> Result<Self, WalletError> {
-        let hot_signers = match HotSigner::from_datadir(datadir_path, network) {
+        let hot_signers = match HotSigner::from_datadir(datadir_path, network, None) {
             Ok(signers) => signers,
             Err(e) => match e {
                 liana::signer::SignerError::MnemonicStorage(e) => {
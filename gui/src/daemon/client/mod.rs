@@ -183,6 +183,32 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         Ok(res.psbt)
     }
 
+    async fn create_time_recovery(
+        &self,
+        address: Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        timelock: Option<u16>,
+    ) -> Result<Psbt, DaemonError> {
+        let res: CreateRecoveryResult = self.call(
+            "createrecovery",
+            Some(vec![
+                json!(address),
+                json!(feerate_vb),
+                json!(null),
+                json!(null),
+                json!(null),
+                json!(timelock),
+            ]),
+        )?;
+        Ok(res.psbt)
+    }
+
+    async fn list_expiring_coins(&self) -> Result<Vec<ExpiringCoin>, DaemonError> {
+        let res: ListExpiringCoinsResult =
+            self.call("listexpiringcoins", Option::<Request>::None)?;
+        Ok(res.coins)
+    }
+
     async fn get_labels(
         &self,
         items: &HashSet<LabelItem>,
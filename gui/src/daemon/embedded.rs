@@ -132,7 +132,13 @@ impl Daemon for EmbeddedDaemon {
     ) -> Result<CreateSpendResult, DaemonError> {
         self.command(|daemon| {
             daemon
-                .create_spend(destinations, coins_outpoints, feerate_vb, change_address)
+                .create_spend(
+                    destinations,
+                    coins_outpoints,
+                    feerate_vb,
+                    change_address,
+                    false,
+                )
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
@@ -202,6 +208,26 @@ impl Daemon for EmbeddedDaemon {
         .await
     }
 
+    async fn create_time_recovery(
+        &self,
+        address: Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        timelock: Option<u16>,
+    ) -> Result<Psbt, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .create_time_recovery(address, feerate_vb, timelock)
+                .map(|res| res.psbt)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn list_expiring_coins(&self) -> Result<Vec<ExpiringCoin>, DaemonError> {
+        self.command(|daemon| Ok(daemon.list_expiring_coins(None).coins))
+            .await
+    }
+
     async fn get_labels(
         &self,
         items: &HashSet<LabelItem>,
@@ -110,6 +110,19 @@ pub trait Daemon: Debug {
         feerate_vb: u64,
         sequence: Option<u16>,
     ) -> Result<Psbt, DaemonError>;
+    /// Create a recovery transaction through a recovery path behind a relative timelock expressed
+    /// in time, identified by its number of 512 seconds intervals.
+    async fn create_time_recovery(
+        &self,
+        _address: Address<address::NetworkUnchecked>,
+        _feerate_vb: u64,
+        _timelock: Option<u16>,
+    ) -> Result<Psbt, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn list_expiring_coins(&self) -> Result<Vec<model::ExpiringCoin>, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
     async fn list_txs(&self, txid: &[Txid]) -> Result<model::ListTransactionsResult, DaemonError>;
     async fn get_labels(
         &self,
@@ -4,8 +4,8 @@ use liana::descriptors::LianaDescriptor;
 pub use liana::{
     commands::{
         CreateSpendResult, GetAddressResult, GetInfoResult, GetLabelsResult, LabelItem,
-        ListCoinsEntry, ListCoinsResult, ListSpendEntry, ListSpendResult, ListTransactionsResult,
-        TransactionInfo,
+        ListCoinsEntry, ListCoinsResult, ListExpiringCoinsEntry, ListExpiringCoinsResult,
+        ListSpendEntry, ListSpendResult, ListTransactionsResult, TransactionInfo,
     },
     descriptors::{LianaPolicy, PartialSpendInfo, PathSpendInfo},
     miniscript::bitcoin::{
@@ -16,6 +16,7 @@ pub use liana::{
 };
 
 pub type Coin = ListCoinsEntry;
+pub type ExpiringCoin = ListExpiringCoinsEntry;
 
 pub fn remaining_sequence(coin: &Coin, blockheight: u32, timelock: u16) -> u32 {
     if let Some(coin_blockheight) = coin.block_height {
@@ -191,10 +192,22 @@ impl SpendTx {
         if path.sigs_count >= path.threshold {
             return Some(path);
         }
+        self.recovery_paths_sigs()
+            .find(|&path| path.sigs_count >= path.threshold)
+    }
+
+    /// Whether this transaction spends coins through a recovery path.
+    pub fn is_recovery(&self) -> bool {
+        self.recovery_paths_sigs().next().is_some()
+    }
+
+    // The signatures for each recovery path available to this transaction, whatever its timelock.
+    fn recovery_paths_sigs(&self) -> impl Iterator<Item = &PathSpendInfo> {
         self.sigs
             .recovery_paths()
             .values()
-            .find(|&path| path.sigs_count >= path.threshold)
+            .chain(self.sigs.time_recovery_paths().values())
+            .chain(self.sigs.absolute_recovery_paths().values())
     }
 
     pub fn signers(&self) -> HashSet<Fingerprint> {
@@ -203,7 +216,7 @@ impl SpendTx {
             signers.insert(*fg);
         }
 
-        for path in self.sigs.recovery_paths().values() {
+        for path in self.recovery_paths_sigs() {
             for fg in path.signed_pubkeys.keys() {
                 signers.insert(*fg);
             }
@@ -42,6 +42,8 @@ pub enum Message {
     HardwareWallets(HardwareWalletMessage),
     WalletRegistered(Result<(Fingerprint, Option<[u8; 32]>), Error>),
     MnemonicWord(usize, String),
+    MnemonicWordCount(usize),
+    MnemonicPassphrase(String),
     ImportMnemonic(bool),
 }
 
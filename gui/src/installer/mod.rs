@@ -391,16 +391,11 @@ pub async fn install_local_wallet(
         info!("Hot signer mnemonic stored");
     }
 
-    if let Some(signer) = &ctx.recovered_signer {
-        signer
-            .store(
-                &cfg.data_dir().expect("Already checked"),
-                cfg.bitcoin_config.network,
-            )
-            .map_err(|e| Error::Unexpected(format!("Failed to store mnemonic: {}", e)))?;
-
-        info!("Recovered signer mnemonic stored");
-    }
+    store_recovered_signer(
+        &ctx,
+        &cfg.data_dir().expect("Already checked"),
+        cfg.bitcoin_config.network,
+    )?;
 
     // create liana GUI configuration file
     let gui_config_path = create_and_write_file(
@@ -467,13 +462,7 @@ pub async fn create_remote_wallet(
         info!("Hot signer mnemonic stored");
     }
 
-    if let Some(signer) = &ctx.recovered_signer {
-        signer
-            .store(&data_dir, ctx.network)
-            .map_err(|e| Error::Unexpected(format!("Failed to store mnemonic: {}", e)))?;
-
-        info!("Recovered signer mnemonic stored");
-    }
+    store_recovered_signer(&ctx, &data_dir, ctx.network)?;
 
     let mut network_datadir_path = data_dir;
     network_datadir_path.push(ctx.network.to_string());
@@ -554,13 +543,7 @@ pub async fn import_remote_wallet(
         .canonicalize()
         .map_err(|e| Error::Unexpected(format!("Failed to canonicalize datadir path: {}", e)))?;
 
-    if let Some(signer) = &ctx.recovered_signer {
-        signer
-            .store(&data_dir, ctx.network)
-            .map_err(|e| Error::Unexpected(format!("Failed to store mnemonic: {}", e)))?;
-
-        info!("Recovered signer mnemonic stored");
-    }
+    store_recovered_signer(&ctx, &data_dir, ctx.network)?;
 
     let mut network_datadir_path = data_dir;
     network_datadir_path.push(ctx.network.to_string());
@@ -599,6 +582,27 @@ pub async fn import_remote_wallet(
     Ok(gui_config_path)
 }
 
+// Store the mnemonic of the signer recovered by the user, if any. A signer recovered with a BIP39
+// passphrase is only kept in memory, as the passphrase must never be stored along with the words.
+fn store_recovered_signer(
+    ctx: &Context,
+    data_dir: &std::path::Path,
+    network: Network,
+) -> Result<(), Error> {
+    if let Some(signer) = &ctx.recovered_signer {
+        if signer.has_passphrase() {
+            info!("Recovered signer has a BIP39 passphrase, not storing its mnemonic");
+            return Ok(());
+        }
+        signer
+            .store(data_dir, network)
+            .map_err(|e| Error::Unexpected(format!("Failed to store mnemonic: {}", e)))?;
+
+        info!("Recovered signer mnemonic stored");
+    }
+    Ok(())
+}
+
 pub fn create_and_write_file(
     mut network_datadir: PathBuf,
     file_name: &str,
@@ -690,6 +694,10 @@ pub fn extract_daemon_config(ctx: &Context) -> Config {
         data_dir: Some(ctx.data_dir.clone()),
         bitcoin_config: ctx.bitcoin_config.clone(),
         bitcoin_backend: ctx.bitcoin_backend.clone(),
+        refresh_policy: None,
+        event_hook: None,
+        http_rpc_config: None,
+        wallets: Vec::new(),
     }
 }
 
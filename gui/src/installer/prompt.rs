@@ -9,3 +9,4 @@ pub const DEFINE_DESCRIPTOR_FINGERPRINT_TOOLTIP: &str =
 pub const REGISTER_DESCRIPTOR_HELP: &str = "To be used with the wallet, a signing device needs the descriptor. If the descriptor contains one or more keys imported from an external signing device, the descriptor must be registered on it. Registration confirms that the device is able to handle the policy. Registration on a device is not a substitute for backing up the descriptor.";
 pub const MNEMONIC_HELP: &str = "A hot key generated on this computer was used for creating this wallet. It needs to be backed up. \n Keep it in a safe place. Never share it with anyone.";
 pub const RECOVER_MNEMONIC_HELP: &str = "If you were using a hot key (a key stored on the computer) in your wallet, you will need to recover it from mnemonics to be able to sign transactions again. Otherwise you can directly go the next step.";
+pub const RECOVER_MNEMONIC_PASSPHRASE_WARNING: &str = "The passphrase is never written to disk. The key derived from these mnemonics and this passphrase is checked to be part of the wallet, but is not stored on this computer: you will not be able to sign transactions with it in Liana.";
@@ -13,7 +13,7 @@ use crate::{
 };
 
 pub struct BackupMnemonic {
-    words: [&'static str; 12],
+    words: Vec<&'static str>,
     done: bool,
     signer: Arc<Mutex<Signer>>,
 }
@@ -63,7 +63,9 @@ impl Step for BackupMnemonic {
 
 pub struct RecoverMnemonic {
     language: bip39::Language,
-    words: [(String, bool); 12],
+    words: Vec<(String, bool)>,
+    // An optional BIP39 passphrase. It is only kept in memory.
+    passphrase: String,
     current: usize,
     suggestions: Vec<String>,
     error: Option<String>,
@@ -75,7 +77,8 @@ impl Default for RecoverMnemonic {
     fn default() -> Self {
         Self {
             language: bip39::Language::English,
-            words: Default::default(),
+            words: vec![Default::default(); 12],
+            passphrase: String::new(),
             current: 0,
             suggestions: Vec::new(),
             error: None,
@@ -113,6 +116,16 @@ impl Step for RecoverMnemonic {
                     *word = value;
                 }
             }
+            Message::MnemonicWordCount(count) => {
+                self.words.resize(count, Default::default());
+                self.current = self.current.min(count - 1);
+                self.suggestions = Vec::new();
+                self.error = None;
+            }
+            Message::MnemonicPassphrase(passphrase) => {
+                self.passphrase = passphrase;
+                self.error = None;
+            }
             Message::ImportMnemonic(recover) => self.recover = recover,
             Message::Skip => {
                 self.skip = true;
@@ -136,7 +149,14 @@ impl Step for RecoverMnemonic {
             .filter_map(|(s, valid)| if *valid { Some(s.clone()) } else { None })
             .collect();
 
-        let seed = match HotSigner::from_str(ctx.bitcoin_config.network, &words.join(" ")) {
+        // NOTE: the recovered mnemonic is stored in plaintext, so a signer with a BIP39 passphrase
+        // isn't stored at all (see `store_recovered_signer`): the passphrase must never be written
+        // along with the words.
+        let seed = match HotSigner::from_str_with_passphrase(
+            ctx.bitcoin_config.network,
+            &words.join(" "),
+            &self.passphrase,
+        ) {
             Ok(seed) => seed,
             Err(e) => {
                 self.error = Some(e.to_string());
@@ -153,7 +173,12 @@ impl Step for RecoverMnemonic {
             for (fingerprint, _) in info.primary_path().thresh_origins().1.iter() {
                 descriptor_keys.insert(*fingerprint);
             }
-            for (_, path) in info.recovery_paths().iter() {
+            for path in info
+                .recovery_paths()
+                .values()
+                .chain(info.time_recovery_paths().values())
+                .chain(info.absolute_recovery_paths().values())
+            {
                 for (fingerprint, _) in path.thresh_origins().1.iter() {
                     descriptor_keys.insert(*fingerprint);
                 }
@@ -178,6 +203,7 @@ impl Step for RecoverMnemonic {
             progress,
             email,
             &self.words,
+            &self.passphrase,
             self.current,
             &self.suggestions,
             self.recover,
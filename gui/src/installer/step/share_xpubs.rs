@@ -32,7 +32,7 @@ pub struct SignerXpubs {
     signer: Arc<Mutex<Signer>>,
     xpubs: Vec<String>,
     next_account: ChildNumber,
-    words: [&'static str; 12],
+    words: Vec<&'static str>,
     did_backup: bool,
 }
 
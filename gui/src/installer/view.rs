@@ -555,7 +555,7 @@ const BACKUP_WARNING: &str =
 
 pub fn signer_xpubs<'a>(
     xpubs: &'a [String],
-    words: &'a [&'static str; 12],
+    words: &'a [&'static str],
     did_backup: bool,
 ) -> Element<'a, Message> {
     Container::new(
@@ -2188,7 +2188,7 @@ pub fn key_list_view<'a>(
 pub fn backup_mnemonic<'a>(
     progress: (usize, usize),
     email: Option<&'a str>,
-    words: &'a [&'static str; 12],
+    words: &'a [&'static str],
     done: bool,
 ) -> Element<'a, Message> {
     layout(
@@ -2230,7 +2230,8 @@ pub fn backup_mnemonic<'a>(
 pub fn recover_mnemonic<'a>(
     progress: (usize, usize),
     email: Option<&'a str>,
-    words: &'a [(String, bool); 12],
+    words: &'a [(String, bool)],
+    passphrase: &'a str,
     current: usize,
     suggestions: &'a [String],
     recover: bool,
@@ -2246,6 +2247,23 @@ pub fn recover_mnemonic<'a>(
                 Some(
                     Column::new()
                         .align_items(Alignment::Center)
+                        .push(
+                            [12, 24].iter().fold(
+                                Row::new()
+                                    .push(text("Number of words:").small().bold())
+                                    .spacing(10),
+                                |row, count| {
+                                    row.push(radio(
+                                        format!("{} words", count),
+                                        *count,
+                                        Some(words.len()),
+                                        Message::MnemonicWordCount,
+                                    ))
+                                    .spacing(30)
+                                    .align_items(Alignment::Center)
+                                },
+                            ),
+                        )
                         .push(
                             Container::new(if !suggestions.is_empty() {
                                 suggestions.iter().fold(Row::new().spacing(5), |row, sugg| {
@@ -2290,6 +2308,25 @@ pub fn recover_mnemonic<'a>(
                             },
                         ))
                         .push(Space::with_height(Length::Fixed(50.0)))
+                        .push(
+                            Column::new()
+                                .spacing(5)
+                                .push(text("Passphrase (optional):").small().bold())
+                                .push(
+                                    Container::new(
+                                        TextInput::new("Passphrase", passphrase)
+                                            .on_input(Message::MnemonicPassphrase)
+                                            .secure(true),
+                                    )
+                                    .width(Length::Fixed(300.0)),
+                                )
+                                .push_maybe(if passphrase.is_empty() {
+                                    None
+                                } else {
+                                    Some(text(prompt::RECOVER_MNEMONIC_PASSPHRASE_WARNING).small())
+                                }),
+                        )
+                        .push(Space::with_height(Length::Fixed(50.0)))
                         .push_maybe(error.map(|e| card::invalid(text(e).style(color::RED)))),
                 )
             } else {
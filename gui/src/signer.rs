@@ -36,7 +36,7 @@ impl Signer {
         self.key.set_network(network)
     }
 
-    pub fn mnemonic(&self) -> [&'static str; 12] {
+    pub fn mnemonic(&self) -> Vec<&'static str> {
         self.key.words()
     }
 
@@ -48,6 +48,10 @@ impl Signer {
         self.fingerprint
     }
 
+    pub fn has_passphrase(&self) -> bool {
+        self.key.has_passphrase()
+    }
+
     pub fn get_extended_pubkey(&self, path: &DerivationPath) -> Xpub {
         self.key.xpub_at(path, &self.curve)
     }
@@ -61,6 +65,6 @@ impl Signer {
         datadir_root: &std::path::Path,
         network: Network,
     ) -> Result<(), SignerError> {
-        self.key.store(datadir_root, network, &self.curve)
+        self.key.store(datadir_root, network, &self.curve, None)
     }
 }
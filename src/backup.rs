@@ -0,0 +1,178 @@
+//! Wallet backups.
+//!
+//! A backup is a single versioned JSON document containing everything needed to restore a wallet
+//! on a new machine which can't be recovered from the chain: the descriptor, the wallet creation
+//! date, the derivation indexes, the labels, the frozen coins and the stored spend transactions.
+
+use crate::{
+    commands::utils::{deser_fromstr, ser_to_string},
+    database::{DatabaseConnection, LabelItem},
+    descriptors::LianaDescriptor,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error, fmt, path,
+};
+
+use miniscript::bitcoin::{self, bip32, psbt::Psbt, secp256k1};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The version of the backup format we create. Backups with a higher version can't be restored.
+pub const BACKUP_VERSION: u32 = 1;
+
+fn ser_psbts<S: Serializer>(psbts: &[Psbt], s: S) -> Result<S::Ok, S::Error> {
+    s.collect_seq(psbts.iter().map(|psbt| psbt.to_string()))
+}
+
+fn deser_psbts<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Psbt>, D::Error> {
+    Vec::<String>::deserialize(d)?
+        .iter()
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletBackup {
+    /// The version of the backup format.
+    pub version: u32,
+    pub network: bitcoin::Network,
+    #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
+    pub main_descriptor: LianaDescriptor,
+    /// The wallet creation date. The chain is rescanned from there upon restoration.
+    pub timestamp: u32,
+    /// The derivation index for the next receiving address.
+    pub receive_index: u32,
+    /// The derivation index for the next change address.
+    pub change_index: u32,
+    /// A mapping from labelled addresses, txids and outpoints to their label.
+    pub labels: BTreeMap<String, String>,
+    /// The stored spend transactions.
+    #[serde(serialize_with = "ser_psbts", deserialize_with = "deser_psbts")]
+    pub spend_txs: Vec<Psbt>,
+    /// The unspent coins frozen by the user. They are frozen again as they are found upon
+    /// restoration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frozen_coins: Vec<bitcoin::OutPoint>,
+    /// Aliases given to the keys of the descriptor, by their master fingerprint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_aliases: Option<BTreeMap<bip32::Fingerprint, String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreError {
+    UnsupportedVersion(u32),
+    NetworkMismatch {
+        backup: bitcoin::Network,
+        config: bitcoin::Network,
+    },
+    DescriptorMismatch,
+    /// A wallet already exists in this data directory.
+    ExistingWallet(path::PathBuf),
+    InvalidLabelItem(String),
+    InvalidDerivationIndex(u32),
+    /// The rescan of the chain from the wallet creation date couldn't be started.
+    Rescan(String),
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(v) => write!(
+                f,
+                "Unsupported backup version {}. At most version {} is supported.",
+                v, BACKUP_VERSION
+            ),
+            Self::NetworkMismatch { backup, config } => write!(
+                f,
+                "The backup is for network '{}' but we are configured for '{}'.",
+                backup, config
+            ),
+            Self::DescriptorMismatch => write!(
+                f,
+                "The descriptor in the backup is different from the one in our configuration."
+            ),
+            Self::ExistingWallet(path) => write!(
+                f,
+                "A wallet already exists in data directory '{}'. Can only restore a backup to a fresh data directory.",
+                path.display()
+            ),
+            Self::InvalidLabelItem(item) => write!(
+                f,
+                "Invalid labelled item '{}' in backup: must be an address, a txid or an outpoint.",
+                item
+            ),
+            Self::InvalidDerivationIndex(index) => {
+                write!(f, "Invalid derivation index {} in backup.", index)
+            }
+            Self::Rescan(e) => write!(f, "Error when starting rescan: {}", e),
+        }
+    }
+}
+
+impl error::Error for RestoreError {}
+
+impl WalletBackup {
+    /// Check this backup can be restored for a wallet with this network and descriptor.
+    pub fn check(
+        &self,
+        network: bitcoin::Network,
+        main_descriptor: &LianaDescriptor,
+    ) -> Result<(), RestoreError> {
+        if self.version > BACKUP_VERSION {
+            return Err(RestoreError::UnsupportedVersion(self.version));
+        }
+        if self.network != network {
+            return Err(RestoreError::NetworkMismatch {
+                backup: self.network,
+                config: network,
+            });
+        }
+        if &self.main_descriptor != main_descriptor {
+            return Err(RestoreError::DescriptorMismatch);
+        }
+        self.derivation_indexes()?;
+        self.label_items()?;
+        Ok(())
+    }
+
+    fn derivation_indexes(&self) -> Result<(bip32::ChildNumber, bip32::ChildNumber), RestoreError> {
+        let index = |i| {
+            bip32::ChildNumber::from_normal_idx(i)
+                .map_err(|_| RestoreError::InvalidDerivationIndex(i))
+        };
+        Ok((index(self.receive_index)?, index(self.change_index)?))
+    }
+
+    fn label_items(&self) -> Result<HashMap<LabelItem, Option<String>>, RestoreError> {
+        self.labels
+            .iter()
+            .map(|(item, value)| {
+                LabelItem::from_str(item, self.network)
+                    .map(|item| (item, Some(value.clone())))
+                    .ok_or_else(|| RestoreError::InvalidLabelItem(item.clone()))
+            })
+            .collect()
+    }
+
+    /// Fill a fresh database with the content of this backup. The caller is responsible for
+    /// having the chain rescanned from the wallet creation date.
+    pub(crate) fn restore_db(
+        &self,
+        db_conn: &mut Box<dyn DatabaseConnection>,
+        secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    ) -> Result<(), RestoreError> {
+        let (receive_index, change_index) = self.derivation_indexes()?;
+        let labels = self.label_items()?;
+
+        db_conn.set_receive_index(receive_index, secp);
+        db_conn.set_change_index(change_index, secp);
+        db_conn.update_labels(&labels);
+        db_conn.freeze_coins_once_found(&self.frozen_coins);
+        for psbt in &self.spend_txs {
+            db_conn.store_spend(psbt);
+        }
+
+        Ok(())
+    }
+}
@@ -1,17 +1,18 @@
 use std::{
-    env,
+    env, fs,
     io::{self, Write},
     path::PathBuf,
     process, thread, time,
 };
 
-use liana::{config::Config, DaemonHandle, VERSION};
+use liana::{backup::WalletBackup, config::Config, DaemonHandle, VERSION};
 
 fn print_help_exit(code: i32) {
     eprintln!("lianad version {}", VERSION);
     eprintln!("A TOML configuration file is required to run lianad. By default lianad looks for a 'config.toml' file in its data directory. A different one may be provided like so: '--conf <config file path>'.");
     eprintln!("A documented sample is available at 'contrib/lianad_config_example.toml' in the source tree (https://github.com/wizardsardine/liana/blob/v1.0/contrib/lianad_config_example.toml).");
     eprintln!("The default data directory path is a 'liana/' folder in the XDG standard configuration directory for all OSes but Linux ones, where it's '~/.liana/'.");
+    eprintln!("A wallet may be restored from a backup created with the 'createbackup' command like so: '--restore-backup <backup file path>'. The data directory must not exist yet.");
     process::exit(code);
 }
 
@@ -20,25 +21,41 @@ fn print_version() {
     process::exit(0);
 }
 
-fn parse_args(args: Vec<String>) -> Option<PathBuf> {
-    if args.len() == 1 {
-        return None;
-    }
+// The configuration file path and the path of a backup to restore the wallet from, if any.
+struct Args {
+    conf_file: Option<PathBuf>,
+    backup_file: Option<PathBuf>,
+}
 
-    if args[1] == "--help" || args[1] == "-h" {
-        print_help_exit(0)
-    } else if args[1] == "--version" || args[1] == "-v" {
-        print_version()
-    } else if args[1] != "--conf" {
-        eprintln!("Only a single command line argument is supported: --conf. All other configuration parameters must be specified in the configuration file.");
-        print_help_exit(1);
-    }
+fn parse_args(args: Vec<String>) -> Args {
+    let mut parsed = Args {
+        conf_file: None,
+        backup_file: None,
+    };
 
-    if args.len() != 3 {
-        print_help_exit(1);
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            print_help_exit(0)
+        } else if arg == "--version" || arg == "-v" {
+            print_version()
+        }
+
+        let (dest, value) = match (arg.as_str(), args.next()) {
+            ("--conf", Some(value)) if parsed.conf_file.is_none() => (&mut parsed.conf_file, value),
+            ("--restore-backup", Some(value)) if parsed.backup_file.is_none() => {
+                (&mut parsed.backup_file, value)
+            }
+            _ => {
+                eprintln!("Only two command line arguments are supported: --conf and --restore-backup. All other configuration parameters must be specified in the configuration file.");
+                print_help_exit(1);
+                unreachable!();
+            }
+        };
+        *dest = Some(PathBuf::from(value));
     }
 
-    Some(PathBuf::from(args[2].to_owned()))
+    parsed
 }
 
 fn setup_logger(log_level: log::LevelFilter) -> Result<(), fern::InitError> {
@@ -68,7 +85,10 @@ fn setup_logger(log_level: log::LevelFilter) -> Result<(), fern::InitError> {
 
 fn main() {
     let args = env::args().collect();
-    let conf_file = parse_args(args);
+    let Args {
+        conf_file,
+        backup_file,
+    } = parse_args(args);
 
     let config = Config::from_file(conf_file).unwrap_or_else(|e| {
         eprintln!("Error parsing config: {}", e);
@@ -80,7 +100,23 @@ fn main() {
         process::exit(1);
     });
 
-    let handle = DaemonHandle::start_default(config, true).unwrap_or_else(|e| {
+    let backup = backup_file.map(|path| {
+        fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<WalletBackup>(&content).map_err(|e| e.to_string())
+            })
+            .unwrap_or_else(|e| {
+                log::error!("Error reading backup file at '{}': {}", path.display(), e);
+                process::exit(1);
+            })
+    });
+
+    let handle = match backup {
+        Some(backup) => DaemonHandle::restore_default(config, &backup, true),
+        None => DaemonHandle::start_default(config, true),
+    }
+    .unwrap_or_else(|e| {
         log::error!("Error starting Liana daemon: {}", e);
         process::exit(1);
     });
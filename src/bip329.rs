@@ -0,0 +1,198 @@
+//! Labels import and export in the BIP329 format.
+//!
+//! See https://github.com/bitcoin/bips/blob/master/bip-0329.mediawiki. Labels are exported as
+//! JSON Lines, one record per labelled item.
+
+use crate::database::LabelItem;
+
+use std::{error, fmt, str::FromStr};
+
+use miniscript::bitcoin;
+use serde::{Deserialize, Serialize};
+
+/// The type of the item a BIP329 record refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelType {
+    Tx,
+    Addr,
+    Pubkey,
+    Input,
+    Output,
+    Xpub,
+}
+
+impl fmt::Display for LabelType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Tx => write!(f, "tx"),
+            Self::Addr => write!(f, "addr"),
+            Self::Pubkey => write!(f, "pubkey"),
+            Self::Input => write!(f, "input"),
+            Self::Output => write!(f, "output"),
+            Self::Xpub => write!(f, "xpub"),
+        }
+    }
+}
+
+/// A BIP329 record. Fields which are not part of the base format (such as `height` or `value`)
+/// are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Label {
+    #[serde(rename = "type")]
+    pub label_type: LabelType,
+    #[serde(rename = "ref")]
+    pub reference: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spendable: Option<bool>,
+}
+
+impl Label {
+    /// The record for this label on this item.
+    pub fn new(item: &LabelItem, label: String) -> Label {
+        let label_type = match item {
+            LabelItem::Address(..) => LabelType::Addr,
+            LabelItem::Txid(..) => LabelType::Tx,
+            LabelItem::OutPoint(..) => LabelType::Output,
+        };
+        Label {
+            label_type,
+            reference: item.to_string(),
+            label: Some(label),
+            origin: None,
+            spendable: None,
+        }
+    }
+
+    /// The item this record refers to. `None` if we don't support labelling this type of item.
+    pub fn item(&self, network: bitcoin::Network) -> Result<Option<LabelItem>, Bip329Error> {
+        let invalid = || Bip329Error::InvalidReference(self.label_type, self.reference.clone());
+        Ok(Some(match self.label_type {
+            LabelType::Tx => bitcoin::Txid::from_str(&self.reference)
+                .map_err(|_| invalid())?
+                .into(),
+            LabelType::Addr => bitcoin::Address::from_str(&self.reference)
+                .map_err(|_| invalid())?
+                .require_network(network)
+                .map_err(|_| invalid())?
+                .into(),
+            LabelType::Output => bitcoin::OutPoint::from_str(&self.reference)
+                .map_err(|_| invalid())?
+                .into(),
+            LabelType::Pubkey | LabelType::Input | LabelType::Xpub => return Ok(None),
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bip329Error {
+    /// The line at this (1-based) index is not a valid record.
+    InvalidRecord(usize, String),
+    /// This reference is not valid for a record of this type.
+    InvalidReference(LabelType, String),
+}
+
+impl fmt::Display for Bip329Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidRecord(line, msg) => {
+                write!(f, "Invalid BIP329 record at line {}: {}", line, msg)
+            }
+            Self::InvalidReference(label_type, reference) => write!(
+                f,
+                "Invalid reference for a BIP329 '{}' record: '{}'",
+                label_type, reference
+            ),
+        }
+    }
+}
+
+impl error::Error for Bip329Error {}
+
+/// Serialize these records as JSON Lines.
+pub fn to_jsonl(labels: &[Label]) -> String {
+    labels
+        .iter()
+        .map(|label| serde_json::to_string(label).expect("Serialization can't fail"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse records from JSON Lines. Empty lines are ignored.
+pub fn from_jsonl(jsonl: &str) -> Result<Vec<Label>, Bip329Error> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| Bip329Error::InvalidRecord(i + 1, e.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bip329_records() {
+        // The examples from the BIP.
+        let jsonl = r#"{ "type": "tx", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd", "label": "Transaction", "origin": "wpkh([d34db33f/84'/0'/0'])" }
+{ "type": "addr", "ref": "bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c", "label": "Address" }
+{ "type": "pubkey", "ref": "0283409659355b6d1cc3c32decd5d561abaac86c37a353b52895a5e6c196d6f448", "label": "Public Key" }
+{ "type": "input", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:0", "label": "Input" }
+
+{ "type": "output", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1", "label": "Output" , "spendable" : false, "height": 800000 }
+{ "type": "xpub", "ref": "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8", "label": "Extended Public Key" }"#;
+        let labels = from_jsonl(jsonl).unwrap();
+        assert_eq!(labels.len(), 6);
+        assert_eq!(labels[0].label_type, LabelType::Tx);
+        assert_eq!(
+            labels[0].origin.as_deref(),
+            Some("wpkh([d34db33f/84'/0'/0'])")
+        );
+        assert_eq!(labels[4].label_type, LabelType::Output);
+        assert_eq!(labels[4].spendable, Some(false));
+
+        // We only support labels on transactions, addresses and outputs.
+        let items: Vec<_> = labels
+            .iter()
+            .map(|l| l.item(bitcoin::Network::Bitcoin).unwrap())
+            .collect();
+        assert!(matches!(items[0], Some(LabelItem::Txid(..))));
+        assert!(matches!(items[1], Some(LabelItem::Address(..))));
+        assert!(items[2].is_none() && items[3].is_none() && items[5].is_none());
+        assert_eq!(
+            items[4],
+            Some(LabelItem::OutPoint(
+                bitcoin::OutPoint::from_str(
+                    "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1"
+                )
+                .unwrap()
+            ))
+        );
+
+        // An address for another network is invalid.
+        assert!(labels[1].item(bitcoin::Network::Testnet).is_err());
+
+        // Records roundtrip.
+        let item = items[4].clone().unwrap();
+        let mut label = Label::new(&item, "Output".to_string());
+        label.spendable = Some(false);
+        let jsonl = to_jsonl(&[label.clone(), label.clone()]);
+        assert_eq!(
+            jsonl.lines().next().unwrap(),
+            r#"{"type":"output","ref":"f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1","label":"Output","spendable":false}"#
+        );
+        assert_eq!(from_jsonl(&jsonl).unwrap(), vec![label.clone(), label]);
+
+        // The line of an invalid record is reported.
+        let err =
+            from_jsonl("{\"type\": \"tx\", \"ref\": \"a\"}\n\n{\"type\": \"block\"}").unwrap_err();
+        assert!(matches!(err, Bip329Error::InvalidRecord(3, _)));
+    }
+}
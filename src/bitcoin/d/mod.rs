@@ -668,6 +668,18 @@ impl BitcoinD {
         Ok(())
     }
 
+    /// Unload the watchonly wallet from bitcoind, and don't have it loaded again at startup.
+    pub fn unload_watchonly_wallet(&self) -> Result<(), BitcoindError> {
+        self.make_fallible_node_request(
+            "unloadwallet",
+            params!(
+                Json::String(self.watchonly_wallet_path.clone()),
+                Json::Bool(false), // load_on_startup
+            ),
+        )
+        .map(|_| ())
+    }
+
     /// Load the watchonly wallet on bitcoind, if it isn't already.
     pub fn maybe_load_watchonly_wallet(&self) -> Result<(), BitcoindError> {
         if self.list_wallets().contains(&self.watchonly_wallet_path) {
@@ -752,6 +764,19 @@ impl BitcoinD {
         }
 
         // Check our main descriptor is imported in this wallet.
+        if !self.is_descriptor_imported(main_descriptor) {
+            return Err(BitcoindError::Wallet(
+                self.watchonly_wallet_path.clone(),
+                WalletError::MissingDescriptor,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Whether the receive and change descriptors of this multipath descriptor are both imported in
+    // our watchonly wallet.
+    fn is_descriptor_imported(&self, main_descriptor: &LianaDescriptor) -> bool {
         let receive_desc = main_descriptor.receive_descriptor();
         let change_desc = main_descriptor.change_descriptor();
         let desc_list: Vec<_> = self
@@ -773,16 +798,98 @@ impl BitcoinD {
                 }
             })
             .collect();
-        if !desc_list.iter().any(|desc| *receive_desc == *desc)
-            || !desc_list.iter().any(|desc| *change_desc == *desc)
-        {
-            return Err(BitcoindError::Wallet(
-                self.watchonly_wallet_path.clone(),
-                WalletError::MissingDescriptor,
-            ));
+        desc_list.iter().any(|desc| *receive_desc == *desc)
+            && desc_list.iter().any(|desc| *change_desc == *desc)
+    }
+
+    /// Import the descriptors of additional wallets to our watchonly wallet, for those which
+    /// aren't already. Additional wallets are all watched through the watchonly wallet of the main
+    /// descriptor. If a creation timestamp is given for a wallet, its descriptor is imported at
+    /// this timestamp and the block chain is rescanned from there.
+    pub fn maybe_import_descriptors(
+        &self,
+        descs: &[(&LianaDescriptor, Option<u32>)],
+    ) -> Result<(), BitcoindError> {
+        let to_import: Vec<_> = descs
+            .iter()
+            .filter(|(desc, _)| !self.is_descriptor_imported(desc))
+            .collect();
+        if to_import.is_empty() {
+            return Ok(());
         }
+        let desc_json: Vec<Json> = to_import
+            .iter()
+            .flat_map(|(desc, timestamp)| {
+                [desc.receive_descriptor(), desc.change_descriptor()].map(|desc| {
+                    serde_json::json!({
+                        "desc": desc.to_string(),
+                        "timestamp": timestamp.map(Json::from).unwrap_or_else(|| "now".into()),
+                        "active": false,
+                    })
+                })
+            })
+            .collect();
 
-        Ok(())
+        // If none of them needs a rescan, the import is quick. Wait for its result.
+        let rescan_timestamp = match to_import.iter().filter_map(|(_, t)| *t).min() {
+            Some(timestamp) => timestamp,
+            None => {
+                let res =
+                    self.make_wallet_request("importdescriptors", params!(Json::Array(desc_json)));
+                let all_succeeded = res
+                    .as_array()
+                    .map(|results| {
+                        results
+                            .iter()
+                            .all(|res| res.get("success").and_then(Json::as_bool).unwrap_or(false))
+                    })
+                    .unwrap_or(false);
+                if all_succeeded {
+                    return Ok(());
+                }
+                return Err(BitcoindError::Wallet(
+                    self.watchonly_wallet_path.clone(),
+                    WalletError::ImportingDescriptor(res.to_string()),
+                ));
+            }
+        };
+
+        // Otherwise we don't wait for the end of the rescan. Same as in `start_rescan`, retry a
+        // few times until we notice the descriptors were imported at their timestamp. All the
+        // descriptors are imported in a single call, as bitcoind can't perform two rescans at once.
+        self.check_prune_height(rescan_timestamp)?;
+        const NUM_RETRIES: usize = 10;
+        let mut i = 0;
+        loop {
+            if let Err(e) = self
+                .make_noreply_request("importdescriptors", params!(Json::Array(desc_json.clone())))
+            {
+                log::error!(
+                    "Error when calling 'importdescriptors' for additional wallets: {}",
+                    e
+                );
+            }
+
+            i += 1;
+            let all_imported = to_import.iter().all(|(desc, timestamp)| match timestamp {
+                Some(timestamp) => self.check_descs_timestamp(
+                    &[
+                        desc.receive_descriptor().to_string(),
+                        desc.change_descriptor().to_string(),
+                    ],
+                    *timestamp,
+                ),
+                None => self.is_descriptor_imported(desc),
+            });
+            if all_imported {
+                return Ok(());
+            } else if i >= NUM_RETRIES {
+                return Err(BitcoindError::StartRescan);
+            } else {
+                log::debug!("Sleeping a second before retrying to import the descriptors");
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
     }
 
     fn block_chain_info(&self) -> Json {
@@ -1207,6 +1314,22 @@ impl BitcoinD {
         }
     }
 
+    /// Get an estimate of the feerate, per kvB, needed for a transaction to confirm within
+    /// `conf_target` blocks. Returns `None` if bitcoind could not provide an estimate.
+    pub fn estimate_smart_fee(&self, conf_target: u16) -> Option<bitcoin::Amount> {
+        match self.make_fallible_node_request("estimatesmartfee", params!(Json::from(conf_target)))
+        {
+            Ok(json) => json
+                .get("feerate")
+                .and_then(Json::as_f64)
+                .and_then(|a| bitcoin::Amount::from_btc(a).ok()),
+            Err(e) => {
+                log::error!("Error estimating feerate: {}", e);
+                None
+            }
+        }
+    }
+
     /// Get the list of txids spending those outpoints in mempool.
     pub fn mempool_txs_spending_prevouts(
         &self,
@@ -108,6 +108,40 @@ impl Client {
             .map(|bh| bh.time)
     }
 
+    /// Get the median time past of the block at this height, computed from the timestamps of
+    /// this block and of (up to) the 10 blocks before it.
+    pub fn median_time_past(&self, height: i32) -> Result<u32, Error> {
+        let start_height = height.saturating_sub(10).max(0);
+        let count = height_usize_from_i32(height - start_height + 1);
+        let mut times: Vec<u32> = self
+            .0
+            .block_headers(height_usize_from_i32(start_height), count)
+            .map_err(Error::Server)?
+            .headers
+            .iter()
+            .map(|header| header.time)
+            .collect();
+        if times.len() != count {
+            return Err(Error::Server(electrum_client::Error::Message(format!(
+                "Missing block headers up to height {}",
+                height
+            ))));
+        }
+        times.sort_unstable();
+        Ok(times[times.len() / 2])
+    }
+
+    /// Get an estimate of the feerate, per kvB, needed for a transaction to confirm within
+    /// `conf_target` blocks. Returns `None` if the server could not provide an estimate.
+    pub fn estimate_fee(&self, conf_target: u16) -> Result<Option<bitcoin::Amount>, Error> {
+        let feerate = self
+            .0
+            .estimate_fee(conf_target.into())
+            .map_err(Error::Server)?;
+        // The server returns -1 if it does not have enough information to make an estimate.
+        Ok(bitcoin::Amount::from_btc(feerate).ok())
+    }
+
     fn sync_with_confirmation_height_anchor(
         &self,
         request: SyncRequest,
@@ -12,7 +12,7 @@ use crate::{
 };
 pub use d::{MempoolEntry, MempoolEntryFees, SyncProgress};
 
-use std::{fmt, sync};
+use std::{cmp, fmt, sync};
 
 use miniscript::bitcoin::{self, address, bip32::ChildNumber};
 
@@ -21,6 +21,16 @@ type SpentCoin = (bitcoin::OutPoint, bitcoin::Txid, i32, u32);
 
 const COINBASE_MATURITY: i32 = 100;
 
+/// The maximum confirmation target, in blocks, we may ask a fee estimate for. This is the
+/// largest target bitcoind will provide estimates for.
+pub const MAX_FEE_ESTIMATE_TARGET: u16 = 1008;
+
+// Convert a feerate expressed as an amount per kilo virtual byte to a feerate in sats/vb,
+// rounding up. We never return a feerate lower than 1 sat/vb.
+fn feerate_vb_from_kvb(feerate_kvb: bitcoin::Amount) -> u64 {
+    cmp::max((feerate_kvb.to_sat() + 999) / 1_000, 1)
+}
+
 /// Information about a block
 #[derive(Debug, Clone, Eq, PartialEq, Copy)]
 pub struct Block {
@@ -59,6 +69,10 @@ pub trait BitcoinInterface: Send {
     /// Get the timestamp set in the best block's header.
     fn tip_time(&self) -> Option<u32>;
 
+    /// Get the median time past (the median of the timestamps of the block and the 10 blocks
+    /// before it) of the block at this height in the best chain.
+    fn median_time_past(&self, height: i32) -> Option<u32>;
+
     /// Check whether this former tip is part of the current best chain.
     fn is_in_chain(&self, tip: &BlockChainTip) -> bool;
 
@@ -135,6 +149,12 @@ pub trait BitcoinInterface: Send {
     ///
     /// Returns `None` if the transaction is not in the mempool.
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry>;
+
+    /// Estimate the feerate, in sats/vb, for a transaction to be confirmed within `conf_target`
+    /// blocks.
+    ///
+    /// Returns `None` if the backend does not have enough data to provide an estimate.
+    fn estimate_feerate(&self, conf_target: u16) -> Option<u64>;
 }
 
 impl BitcoinInterface for d::BitcoinD {
@@ -387,6 +407,11 @@ impl BitcoinInterface for d::BitcoinD {
         Some(self.get_block_stats(tip.hash)?.time)
     }
 
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        let hash = self.get_block_hash(height)?;
+        Some(self.get_block_stats(hash)?.median_time_past)
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -404,6 +429,11 @@ impl BitcoinInterface for d::BitcoinD {
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         self.mempool_entry(txid)
     }
+
+    fn estimate_feerate(&self, conf_target: u16) -> Option<u64> {
+        self.estimate_smart_fee(conf_target)
+            .map(feerate_vb_from_kvb)
+    }
 }
 
 impl BitcoinInterface for electrum::Electrum {
@@ -591,6 +621,20 @@ impl BitcoinInterface for electrum::Electrum {
     fn tip_time(&self) -> Option<u32> {
         self.client().tip_time().ok()
     }
+
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        self.client().median_time_past(height).ok()
+    }
+
+    fn estimate_feerate(&self, conf_target: u16) -> Option<u64> {
+        match self.client().estimate_fee(conf_target) {
+            Ok(feerate) => feerate.map(feerate_vb_from_kvb),
+            Err(e) => {
+                log::error!("Error estimating feerate: {}", e);
+                None
+            }
+        }
+    }
 }
 
 // FIXME: do we need to repeat the entire trait implemenation? Isn't there a nicer way?
@@ -682,6 +726,10 @@ impl BitcoinInterface for sync::Arc<sync::Mutex<dyn BitcoinInterface + 'static>>
         self.lock().unwrap().tip_time()
     }
 
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        self.lock().unwrap().median_time_past(height)
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -696,6 +744,10 @@ impl BitcoinInterface for sync::Arc<sync::Mutex<dyn BitcoinInterface + 'static>>
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         self.lock().unwrap().mempool_entry(txid)
     }
+
+    fn estimate_feerate(&self, conf_target: u16) -> Option<u64> {
+        self.lock().unwrap().estimate_feerate(conf_target)
+    }
 }
 
 // FIXME: We could avoid this type (and all the conversions entailing allocations) if bitcoind
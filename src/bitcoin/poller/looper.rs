@@ -2,6 +2,7 @@ use crate::{
     bitcoin::{BitcoinInterface, BlockChainTip, UTxO, UTxOAddress},
     database::{Coin, DatabaseConnection, DatabaseInterface},
     descriptors,
+    events::Event,
 };
 
 use std::{collections::HashSet, sync, thread, time};
@@ -91,6 +92,7 @@ fn update_coins(
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             received.push(coin);
         }
@@ -161,6 +163,72 @@ fn update_coins(
     }
 }
 
+fn rollback_event(tip: &BlockChainTip) -> Event {
+    Event::Rollback {
+        height: tip.height,
+        hash: tip.hash,
+    }
+}
+
+// The events corresponding to the updates of our coins which were recorded in database.
+fn coins_events(
+    db_conn: &mut Box<dyn DatabaseConnection>,
+    descs: &[descriptors::SinglePathLianaDesc],
+    secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    updated_coins: UpdatedCoins,
+) -> Vec<Event> {
+    let network = db_conn.network();
+    let UpdatedCoins {
+        received,
+        confirmed,
+        expired,
+        spending,
+        expired_spending,
+        spent,
+    } = updated_coins;
+    let received = received.into_iter().map(move |coin| {
+        let desc = &descs[usize::from(coin.is_change)];
+        Event::Received {
+            outpoint: coin.outpoint,
+            amount: coin.amount.to_sat(),
+            address: desc.derive(coin.derivation_index, secp).address(network),
+            derivation_index: coin.derivation_index,
+            is_change: coin.is_change,
+        }
+    });
+    let confirmed = confirmed
+        .into_iter()
+        .map(|(outpoint, height, time)| Event::Confirmed {
+            outpoint,
+            height,
+            time,
+        });
+    let expired = expired
+        .into_iter()
+        .map(|outpoint| Event::Expired { outpoint });
+    let spend_expired = expired_spending
+        .into_iter()
+        .map(|outpoint| Event::SpendExpired { outpoint });
+    let spending = spending
+        .into_iter()
+        .map(|(outpoint, txid)| Event::Spending { outpoint, txid });
+    let spent = spent
+        .into_iter()
+        .map(|(outpoint, txid, height, time)| Event::Spent {
+            outpoint,
+            txid,
+            height,
+            time,
+        });
+    received
+        .chain(confirmed)
+        .chain(expired)
+        .chain(spend_expired)
+        .chain(spending)
+        .chain(spent)
+        .collect()
+}
+
 // Add new deposit and spend transactions to the database.
 fn add_txs_to_db(
     bit: &impl BitcoinInterface,
@@ -247,6 +315,7 @@ fn updates(
     bit: &mut impl BitcoinInterface,
     descs: &[descriptors::SinglePathLianaDesc],
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    events: &mut Vec<Event>,
 ) {
     // Check if there was a new block before we update our state.
     //
@@ -268,7 +337,8 @@ fn updates(
                     // between our former chain and the new one, then restart fresh.
                     db_conn.rollback_tip(&new_tip);
                     log::info!("Tip was rolled back to '{}'.", new_tip);
-                    return updates(db_conn, bit, descs, secp);
+                    events.push(rollback_event(&new_tip));
+                    return updates(db_conn, bit, descs, secp, events);
                 }
             }
         }
@@ -277,12 +347,13 @@ fn updates(
             // between our former chain and the new one, then restart fresh.
             db_conn.rollback_tip(&reorg_common_ancestor);
             log::info!("Tip was rolled back to '{}'.", &reorg_common_ancestor);
-            return updates(db_conn, bit, descs, secp);
+            events.push(rollback_event(&reorg_common_ancestor));
+            return updates(db_conn, bit, descs, secp, events);
         }
         Err(e) => {
             log::error!("Error syncing wallet: '{}'.", e);
             thread::sleep(time::Duration::from_secs(2));
-            return updates(db_conn, bit, descs, secp);
+            return updates(db_conn, bit, descs, secp, events);
         }
     };
 
@@ -293,7 +364,7 @@ fn updates(
     // If the tip changed while we were polling our Bitcoin interface, start over.
     if bit.chain_tip() != latest_tip {
         log::info!("Chain tip changed while we were updating our state. Starting over.");
-        return updates(db_conn, bit, descs, secp);
+        return updates(db_conn, bit, descs, secp, events);
     }
 
     // Transactions must be added to the DB before coins due to foreign key constraints.
@@ -311,6 +382,7 @@ fn updates(
         db_conn.update_tip(&latest_tip);
         log::debug!("New tip: '{}'", latest_tip);
     }
+    events.extend(coins_events(db_conn, descs, secp, updated_coins));
 
     log::debug!("Updates done.");
 }
@@ -321,6 +393,7 @@ fn rescan_check(
     bit: &mut impl BitcoinInterface,
     descs: &[descriptors::SinglePathLianaDesc],
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    events: &mut Vec<Event>,
 ) {
     log::debug!("Checking the state of an ongoing rescan if there is any");
 
@@ -334,6 +407,7 @@ fn rescan_check(
         if rescan_timestamp.is_none() {
             log::warn!("Backend is rescanning but we didn't ask for it.");
         }
+        events.push(Event::RescanProgress { progress });
     } else if let Some(timestamp) = rescan_timestamp {
         log::info!("Rescan completed on the backend.");
         // TODO: we could check if the timestamp of the descriptors in the Bitcoin backend are
@@ -357,7 +431,10 @@ fn rescan_check(
             "Rolling back our internal tip to '{}' to update our internal state with past transactions.",
             rescan_tip
         );
-        updates(db_conn, bit, descs, secp)
+        events.push(rollback_event(&rescan_tip));
+        updates(db_conn, bit, descs, secp, events);
+        // Only notify the completion once our state was updated with the rescanned blocks.
+        events.push(Event::RescanComplete { timestamp });
     } else {
         log::debug!("No ongoing rescan.");
     }
@@ -383,14 +460,20 @@ pub fn sync_poll_interval() -> time::Duration {
     time::Duration::from_secs(0)
 }
 
-/// Update our state from the Bitcoin backend.
+/// Update our state from the Bitcoin backend. Returns the changes to our state, in the order they
+/// were recorded.
 pub fn poll(
     bit: &mut sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
     db: &sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     descs: &[descriptors::SinglePathLianaDesc],
-) {
+) -> Vec<Event> {
     let mut db_conn = db.connection();
-    updates(&mut db_conn, bit, descs, secp);
-    rescan_check(&mut db_conn, bit, descs, secp);
+    let mut events = Vec::new();
+    updates(&mut db_conn, bit, descs, secp, &mut events);
+    rescan_check(&mut db_conn, bit, descs, secp, &mut events);
+    if !events.is_empty() {
+        db_conn.store_events(&events);
+    }
+    events
 }
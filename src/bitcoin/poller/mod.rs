@@ -1,6 +1,14 @@
 mod looper;
 
-use crate::{bitcoin::BitcoinInterface, database::DatabaseInterface, descriptors};
+use crate::{
+    bitcoin::BitcoinInterface,
+    commands::AutoRefreshState,
+    config::EventHookConfig,
+    database::DatabaseInterface,
+    descriptors,
+    events::{EventHook, EventsNotifier},
+    DaemonControl,
+};
 
 use std::{
     sync::{self, mpsc},
@@ -17,35 +25,132 @@ pub enum PollerMessage {
     PollNow(mpsc::SyncSender<()>),
 }
 
-/// The Bitcoin poller handler.
-pub struct Poller {
-    bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+// A wallet whose state the poller updates.
+struct PolledWallet {
+    // The name of an additional wallet, `None` for the main one.
+    name: Option<String>,
     db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
-    secp: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     // The receive and change descriptors (in this order).
     descs: [descriptors::SinglePathLianaDesc; 2],
+    // Set if we must automatically refresh the coins close to expiry after each poll.
+    refresh_control: Option<DaemonControl>,
+    refresh_state: AutoRefreshState,
 }
 
-impl Poller {
-    pub fn new(
-        bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+impl PolledWallet {
+    fn new(
+        bit: &sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+        name: Option<String>,
         db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
         desc: descriptors::LianaDescriptor,
-    ) -> Poller {
-        let secp = secp256k1::Secp256k1::verification_only();
+        refresh_control: Option<DaemonControl>,
+    ) -> PolledWallet {
         let descs = [
             desc.receive_descriptor().clone(),
             desc.change_descriptor().clone(),
         ];
 
         // On first startup the tip may be NULL. Make sure it's set as the poller relies on it.
-        looper::maybe_initialize_tip(&bit, &db);
+        looper::maybe_initialize_tip(bit, &db);
+
+        PolledWallet {
+            name,
+            db,
+            descs,
+            refresh_control,
+            refresh_state: AutoRefreshState::default(),
+        }
+    }
+}
+
+/// The Bitcoin poller handler.
+pub struct Poller {
+    bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+    secp: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    // The main wallet first, then any additional wallet. They are all updated from the same
+    // Bitcoin backend.
+    wallets: Vec<PolledWallet>,
+    // Set if we must run a command on wallet events.
+    event_hook: Option<EventHook>,
+    // To wake up the threads waiting for new events in the journal.
+    events_notifier: EventsNotifier,
+}
+
+impl Poller {
+    pub fn new(
+        bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+        db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+        desc: descriptors::LianaDescriptor,
+        refresh_control: Option<DaemonControl>,
+        event_hook: Option<EventHookConfig>,
+        events_notifier: EventsNotifier,
+    ) -> Poller {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let main_wallet = PolledWallet::new(&bit, None, db, desc, refresh_control);
 
         Poller {
             bit,
-            db,
             secp,
-            descs,
+            wallets: vec![main_wallet],
+            event_hook: event_hook.map(EventHook::new),
+            events_notifier,
+        }
+    }
+
+    /// Also update the state of this additional wallet on each poll.
+    pub fn add_wallet(
+        &mut self,
+        name: String,
+        db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+        desc: descriptors::LianaDescriptor,
+        refresh_control: Option<DaemonControl>,
+    ) {
+        let wallet = PolledWallet::new(&self.bit, Some(name), db, desc, refresh_control);
+        self.wallets.push(wallet);
+    }
+
+    // Update the state of our wallets from the Bitcoin backend and notify about the changes.
+    fn poll(&mut self) {
+        let mut new_events = false;
+        for wallet in &self.wallets {
+            let events = looper::poll(&mut self.bit, &wallet.db, &self.secp, &wallet.descs);
+            if events.is_empty() {
+                continue;
+            }
+            new_events = true;
+            if let Some(hook) = &self.event_hook {
+                hook.notify(wallet.name.as_deref(), &events);
+            }
+        }
+        if new_events {
+            self.events_notifier.notify();
+        }
+    }
+
+    // Create and store the transactions refreshing the coins which entered the safety margin of
+    // the refresh policy since the last poll.
+    fn maybe_refresh_coins(&mut self) {
+        for wallet in &mut self.wallets {
+            let control = match &wallet.refresh_control {
+                Some(control) => control,
+                None => continue,
+            };
+            match control.auto_refresh_coins(&mut wallet.refresh_state) {
+                Ok(txids) => {
+                    for txid in txids {
+                        log::info!("Stored refresh transaction '{}'.", txid);
+                    }
+                }
+                Err(e) => log::error!("Error creating refresh transactions: '{}'.", e),
+            }
+        }
+    }
+
+    // Let the hook command be run for the events already queued before returning.
+    fn stop_event_hook(&mut self) {
+        if let Some(hook) = self.event_hook.take() {
+            log::info!("Waiting for the event hook to process the queued events.");
+            hook.stop();
         }
     }
 
@@ -85,13 +190,14 @@ impl Poller {
             match receiver.recv_timeout(time_before_poll) {
                 Ok(PollerMessage::Shutdown) => {
                     log::info!("Bitcoin poller was told to shut down.");
+                    self.stop_event_hook();
                     return;
                 }
                 Ok(PollerMessage::PollNow(sender)) => {
                     // We've been asked to poll, don't wait any further and signal completion to
                     // the caller.
                     last_poll = Some(time::Instant::now());
-                    looper::poll(&mut self.bit, &self.db, &self.secp, &self.descs);
+                    self.poll();
                     if let Err(e) = sender.send(()) {
                         log::error!("Error sending immediate poll completion signal: {}.", e);
                     }
@@ -102,6 +208,7 @@ impl Poller {
                 }
                 Err(mpsc::RecvTimeoutError::Disconnected) => {
                     log::error!("Bitcoin poller communication channel got disconnected. Exiting.");
+                    self.stop_event_hook();
                     return;
                 }
             }
@@ -122,7 +229,8 @@ impl Poller {
                 }
             }
 
-            looper::poll(&mut self.bit, &self.db, &self.secp, &self.descs);
+            self.poll();
+            self.maybe_refresh_coins();
         }
     }
 }
@@ -2,12 +2,16 @@
 //!
 //! External interface to the Liana daemon.
 
-mod utils;
+pub(crate) mod utils;
 
 use crate::{
-    bitcoin::BitcoinInterface,
-    database::{Coin, DatabaseConnection, DatabaseInterface},
+    backup::{WalletBackup, BACKUP_VERSION},
+    bip329,
+    bitcoin::{BitcoinInterface, MAX_FEE_ESTIMATE_TARGET},
+    config::RefreshKeychain,
+    database::{BlockInfo, Coin, DatabaseConnection, DatabaseInterface},
     descriptors,
+    events::{Event, JournalEntry},
     miniscript::bitcoin::absolute::LockTime,
     poller::PollerMessage,
     spend::{
@@ -20,16 +24,17 @@ use crate::{
 pub use crate::database::{CoinStatus, LabelItem};
 
 use utils::{
-    deser_addr_assume_checked, deser_amount_from_sats, deser_fromstr, deser_hex, ser_amount,
-    ser_hex, ser_to_string,
+    deser_addr_assume_checked, deser_amount_from_sats, deser_fromstr, deser_hex, deser_vec_fromstr,
+    ser_amount, ser_hex, ser_to_string, ser_vec_to_string,
 };
 
 use std::{
-    collections::{hash_map, HashMap, HashSet},
+    cmp,
+    collections::{hash_map, BTreeMap, HashMap, HashSet},
     convert::TryInto,
     fmt,
     sync::{self, mpsc},
-    time::SystemTime,
+    time::{self, SystemTime},
 };
 
 use miniscript::{
@@ -38,6 +43,36 @@ use miniscript::{
 };
 use serde::{Deserialize, Serialize};
 
+/// The default maximum number of coins to refresh in a single transaction.
+pub const DEFAULT_REFRESH_MAX_INPUTS: usize = 100;
+
+/// The default maximum number of coins to spend in a single migration transaction.
+pub const DEFAULT_MIGRATION_MAX_INPUTS: usize = 100;
+
+/// The label set on the migration transactions.
+pub const MIGRATION_LABEL: &str = "Migration to a new descriptor";
+
+/// The default duration, in seconds, a `waitforevents` call waits for new events.
+pub const DEFAULT_WAIT_FOR_EVENTS_TIMEOUT: u64 = 30;
+
+/// The maximum duration, in seconds, a `waitforevents` call may wait for new events.
+pub const MAX_WAIT_FOR_EVENTS_TIMEOUT: u64 = 300;
+
+/// The maximum length of a label, in bytes.
+pub const MAX_LABEL_LENGTH: usize = 100;
+
+/// The maximum number of events returned by a single `waitforevents` call.
+const MAX_EVENTS_PER_WAIT: u64 = 1_000;
+
+/// The maximum number of `waitforevents` calls which may be waiting for new events at the same
+/// time, across all wallets. Half the number of connections each JSONRPC server accepts, so
+/// waiters can't prevent other commands from being served.
+pub const MAX_EVENT_WAITERS: usize = 8;
+
+/// The expected interval between two blocks, in seconds. Used to compare the expiry of the
+/// recovery paths behind a relative timelock expressed in time to a number of blocks.
+const TARGET_BLOCK_INTERVAL_SECS: u32 = 600;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CommandError {
     NoOutpointForSelfSend,
@@ -61,10 +96,32 @@ pub enum CommandError {
     /// An error that might occur in the racy rescan triggering logic.
     RescanTrigger(String),
     RecoveryNotAvailable,
+    /// There is no recovery path behind this absolute timelock in our descriptor.
+    UnknownAbsoluteTimelock(u32),
+    UnknownTimeTimelock(u16),
+    UnknownSpendingPath(descriptors::SpendingPath),
+    /// The coin can't be spent through the requested spending path in the next block.
+    SpendingPathNotAvailable(bitcoin::OutPoint),
     /// Overflowing or unhardened derivation index.
     InvalidDerivationIndex,
     RbfError(RbfErrorInfo),
     EmptyFilterList,
+    AlreadyConfirmed(bitcoin::OutPoint),
+    NotInMempool(bitcoin::OutPoint),
+    InvalidConfTarget(u16),
+    FeeEstimationUnavailable(u16),
+    InvalidMaxInputs(usize),
+    InvalidTimeout(u64),
+    /// There are already [`MAX_EVENT_WAITERS`] calls waiting for new events.
+    TooManyEventWaiters,
+    Bip329(bip329::Bip329Error),
+    /// The label for the item with this reference is too long.
+    LabelTooLong(String),
+    /// The descriptor to migrate to is the one we are already using.
+    MigrationToSameDescriptor,
+    /// The descriptor to migrate to is not for the network we are operating on.
+    MigrationDescriptorNetwork(bitcoin::Network),
+    NoCoinToMigrate,
 }
 
 impl fmt::Display for CommandError {
@@ -113,17 +170,88 @@ impl fmt::Display for CommandError {
                 f,
                 "No coin currently spendable through this timelocked recovery path."
             ),
+            Self::UnknownAbsoluteTimelock(tl) => write!(
+                f,
+                "There is no recovery path with an absolute timelock of '{}' in the descriptor.",
+                tl
+            ),
+            Self::UnknownTimeTimelock(tl) => write!(
+                f,
+                "There is no recovery path with a relative timelock of '{}' times 512 seconds in the descriptor.",
+                tl
+            ),
+            Self::UnknownSpendingPath(path) => {
+                write!(f, "There is no {} in the descriptor.", path)
+            }
+            Self::SpendingPathNotAvailable(op) => write!(
+                f,
+                "Coin '{}' can't be spent through this spending path in the next block.",
+                op
+            ),
             Self::InvalidDerivationIndex => {
                 write!(f, "Unhardened or overflowing BIP32 derivation index.")
             }
             Self::RbfError(e) => write!(f, "RBF error: '{}'.", e),
             Self::EmptyFilterList => write!(f, "Filter list is empty, should supply None instead."),
+            Self::AlreadyConfirmed(op) => write!(f, "Coin at '{}' is already confirmed.", op),
+            Self::NotInMempool(op) => write!(
+                f,
+                "The transaction creating the coin at '{}' is not in the mempool.",
+                op
+            ),
+            Self::InvalidConfTarget(target) => write!(
+                f,
+                "Invalid confirmation target: {}. Must be between 1 and {} blocks.",
+                target, MAX_FEE_ESTIMATE_TARGET
+            ),
+            Self::FeeEstimationUnavailable(target) => write!(
+                f,
+                "Fee estimation for a confirmation target of {} blocks is not available.",
+                target
+            ),
+            Self::InvalidMaxInputs(max) => write!(
+                f,
+                "Invalid maximum number of inputs per transaction: {}.",
+                max
+            ),
+            Self::InvalidTimeout(timeout) => write!(
+                f,
+                "Invalid timeout: {} seconds. Must be at most {} seconds.",
+                timeout, MAX_WAIT_FOR_EVENTS_TIMEOUT
+            ),
+            Self::TooManyEventWaiters => write!(
+                f,
+                "There are already {} calls waiting for new events, try again later.",
+                MAX_EVENT_WAITERS
+            ),
+            Self::Bip329(e) => write!(f, "{}", e),
+            Self::LabelTooLong(reference) => write!(
+                f,
+                "Label for '{}' is too long: must be at most {} characters.",
+                reference, MAX_LABEL_LENGTH
+            ),
+            Self::MigrationToSameDescriptor => write!(
+                f,
+                "The descriptor to migrate to must be different from the current one."
+            ),
+            Self::MigrationDescriptorNetwork(net) => write!(
+                f,
+                "The descriptor to migrate to has keys that aren't encoded for the '{}' network.",
+                net
+            ),
+            Self::NoCoinToMigrate => write!(f, "No coin to migrate."),
         }
     }
 }
 
 impl std::error::Error for CommandError {}
 
+impl From<bip329::Bip329Error> for CommandError {
+    fn from(e: bip329::Bip329Error) -> Self {
+        CommandError::Bip329(e)
+    }
+}
+
 impl From<SpendCreationError> for CommandError {
     fn from(e: SpendCreationError) -> Self {
         CommandError::SpendCreation(e)
@@ -201,7 +329,23 @@ fn coin_to_candidate(
         must_select,
         sequence,
         ancestor_info,
+        sat_weight: None,
+    }
+}
+
+// Get the coins reserved by the Spend transactions stored in database, along with the txids of
+// the stored transactions spending them.
+fn reserved_coins(
+    db_conn: &mut Box<dyn DatabaseConnection>,
+) -> HashMap<bitcoin::OutPoint, Vec<bitcoin::Txid>> {
+    let mut reserved: HashMap<bitcoin::OutPoint, Vec<bitcoin::Txid>> = HashMap::new();
+    for (psbt, _) in db_conn.list_spend() {
+        let txid = psbt.unsigned_tx.txid();
+        for txin in psbt.unsigned_tx.input {
+            reserved.entry(txin.previous_output).or_default().push(txid);
+        }
     }
+    reserved
 }
 
 impl DaemonControl {
@@ -249,6 +393,24 @@ impl DaemonControl {
         }
     }
 
+    // Get the receive address for the next derivation index, without using it up.
+    fn next_receive_addr(&self, db_conn: &mut Box<dyn DatabaseConnection>) -> SpendOutputAddress {
+        let index = db_conn.receive_index();
+        let desc = self
+            .config
+            .main_descriptor
+            .receive_descriptor()
+            .derive(index, &self.secp);
+        let addr = desc.address(self.config.bitcoin_config.network);
+        SpendOutputAddress {
+            addr,
+            info: Some(AddrInfo {
+                index,
+                is_change: false,
+            }),
+        }
+    }
+
     // Get the change address for the next derivation index.
     fn next_change_addr(&self, db_conn: &mut Box<dyn DatabaseConnection>) -> SpendOutputAddress {
         let index = db_conn.change_index();
@@ -327,6 +489,42 @@ impl DaemonControl {
         }
     }
 
+    /// Describe the spending policy of the wallet, along with the estimated size of the
+    /// satisfaction of an input.
+    pub fn describe_policy(&self) -> DescribePolicyResult {
+        let desc = &self.config.main_descriptor;
+        let policy = desc.policy().description();
+        let primary_path_max_sat_weight = desc.max_sat_weight(true);
+        let max_sat_weight = desc.max_sat_weight(false);
+        let text = format!(
+            "{}Estimated satisfaction size of an input: {} WU (~{} vbytes) using the primary path, up to {} WU (~{} vbytes) using any path.",
+            policy,
+            primary_path_max_sat_weight,
+            desc.max_sat_vbytes(true),
+            max_sat_weight,
+            desc.max_sat_vbytes(false),
+        );
+        DescribePolicyResult {
+            policy,
+            primary_path_max_sat_weight,
+            max_sat_weight,
+            text,
+        }
+    }
+
+    /// Estimate the feerate, in sats/vb, for a transaction to be confirmed within `conf_target`
+    /// blocks.
+    pub fn estimate_fee(&self, conf_target: u16) -> Result<EstimateFeeResult, CommandError> {
+        if !(1..=MAX_FEE_ESTIMATE_TARGET).contains(&conf_target) {
+            return Err(CommandError::InvalidConfTarget(conf_target));
+        }
+        let feerate = self
+            .bitcoin
+            .estimate_feerate(conf_target)
+            .ok_or(CommandError::FeeEstimationUnavailable(conf_target))?;
+        Ok(EstimateFeeResult { feerate })
+    }
+
     /// Get a new deposit address. This will always generate a new deposit address, regardless of
     /// whether it was actually used.
     pub fn get_new_address(&self) -> GetAddressResult {
@@ -409,6 +607,7 @@ impl DaemonControl {
         outpoints: &[bitcoin::OutPoint],
     ) -> ListCoinsResult {
         let mut db_conn = self.db.connection();
+        let mut reserved = reserved_coins(&mut db_conn);
         let coins: Vec<ListCoinsEntry> = db_conn
             .coins(statuses, outpoints)
             .into_values()
@@ -422,13 +621,19 @@ impl DaemonControl {
                     is_immature,
                     is_change,
                     derivation_index,
-                    ..
+                    is_frozen,
                 } = coin;
                 let spend_info = spend_txid.map(|txid| LCSpendInfo {
                     txid,
                     height: spend_block.map(|b| b.height),
                 });
                 let block_height = block_info.map(|b| b.height);
+                // Only report the reservation of coins which are not already being spent.
+                let reserved_by = if spend_txid.is_none() {
+                    reserved.remove(&outpoint).unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
                 let address = self
                     .derived_desc(&coin)
                     .address(self.config.bitcoin_config.network);
@@ -441,18 +646,98 @@ impl DaemonControl {
                     spend_info,
                     is_immature,
                     is_change,
+                    is_frozen,
+                    reserved_by,
                 }
             })
             .collect();
         ListCoinsResult { coins }
     }
 
+    /// Freeze the given coins, so they are never selected automatically when creating a
+    /// transaction. Frozen coins can still be spent by explicitly selecting them.
+    pub fn freeze_coins(&self, outpoints: &[bitcoin::OutPoint]) -> Result<(), CommandError> {
+        let mut db_conn = self.db.connection();
+        let coins = db_conn.coins(&[], outpoints);
+        for op in outpoints {
+            let coin = coins.get(op).ok_or(CommandError::UnknownOutpoint(*op))?;
+            if coin.is_spent() {
+                return Err(CommandError::AlreadySpent(*op));
+            }
+        }
+        db_conn.freeze_coins(outpoints);
+        Ok(())
+    }
+
+    /// Unfreeze the given coins, making them available again for automatic coin selection.
+    pub fn unfreeze_coins(&self, outpoints: &[bitcoin::OutPoint]) -> Result<(), CommandError> {
+        let mut db_conn = self.db.connection();
+        let coins = db_conn.coins(&[], outpoints);
+        if let Some(op) = outpoints.iter().find(|op| !coins.contains_key(op)) {
+            return Err(CommandError::UnknownOutpoint(*op));
+        }
+        db_conn.unfreeze_coins(outpoints);
+        Ok(())
+    }
+
+    /// Create a transaction spending some of our coins to the given destinations.
+    ///
+    /// If no coins are specified, they are selected automatically. In this case the coins spent
+    /// by the Spend transactions stored in database are only considered if `include_reserved` is
+    /// set.
     pub fn create_spend(
         &self,
         destinations: &HashMap<bitcoin::Address<bitcoin::address::NetworkUnchecked>, u64>,
         coins_outpoints: &[bitcoin::OutPoint],
         feerate_vb: u64,
         change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>>,
+        include_reserved: bool,
+    ) -> Result<CreateSpendResult, CommandError> {
+        self.create_spend_inner(
+            destinations,
+            coins_outpoints,
+            feerate_vb,
+            change_address,
+            include_reserved,
+            None,
+        )
+    }
+
+    /// Create a transaction spending coins through the given spending path of the descriptor.
+    /// The parameters are the same as for [`DaemonControl::create_spend`].
+    ///
+    /// Only the coins which can be spent through this path in the next block are considered. For
+    /// a recovery path behind a relative timelock these are the confirmed coins whose timelock
+    /// expired. The nSequence of the inputs (and the nLockTime of the transaction for an absolute
+    /// timelock) is set to satisfy the timelock of the path, and the BIP32 derivations for the keys
+    /// of the other spending paths are pruned from the PSBT inputs.
+    pub fn create_spend_through_path(
+        &self,
+        destinations: &HashMap<bitcoin::Address<bitcoin::address::NetworkUnchecked>, u64>,
+        coins_outpoints: &[bitcoin::OutPoint],
+        feerate_vb: u64,
+        change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>>,
+        include_reserved: bool,
+        spending_path: descriptors::SpendingPath,
+    ) -> Result<CreateSpendResult, CommandError> {
+        self.create_spend_inner(
+            destinations,
+            coins_outpoints,
+            feerate_vb,
+            change_address,
+            include_reserved,
+            Some(spending_path),
+        )
+    }
+
+    fn create_spend_inner(
+        &self,
+        destinations: &HashMap<bitcoin::Address<bitcoin::address::NetworkUnchecked>, u64>,
+        coins_outpoints: &[bitcoin::OutPoint],
+        feerate_vb: u64,
+        change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>>,
+        include_reserved: bool,
+        spending_path: Option<descriptors::SpendingPath>,
     ) -> Result<CreateSpendResult, CommandError> {
         let is_self_send = destinations.is_empty();
         // For self-send, the coins must be specified.
@@ -462,9 +747,78 @@ impl DaemonControl {
         if feerate_vb < 1 {
             return Err(CommandError::InvalidFeerate(feerate_vb));
         }
+        let path_info = spending_path
+            .map(|path| {
+                self.config
+                    .main_descriptor
+                    .policy()
+                    .spending_path(path)
+                    .cloned()
+                    .ok_or(CommandError::UnknownSpendingPath(path))
+            })
+            .transpose()?;
         let mut db_conn = self.db.connection();
         let mut tx_getter = DbTxGetter::new(&self.db);
 
+        // The nSequence of the inputs and the nLockTime of the transaction depend on the timelock
+        // of the spending path, if any.
+        let anti_fee_sniping_locktime = self.anti_fee_sniping_locktime();
+        let (sequence, locktime) = match spending_path {
+            Some(descriptors::SpendingPath::Recovery(timelock)) => match timelock {
+                descriptors::RecoveryTimelock::Relative(tl) => (
+                    Some(bitcoin::Sequence::from_height(tl)),
+                    anti_fee_sniping_locktime,
+                ),
+                descriptors::RecoveryTimelock::RelativeTime(tl) => (
+                    Some(bitcoin::Sequence::from_512_second_intervals(tl)),
+                    anti_fee_sniping_locktime,
+                ),
+                descriptors::RecoveryTimelock::Absolute(tl) => {
+                    let timelock = LockTime::from_consensus(tl);
+                    if !self.is_absolute_timelock_available(timelock) {
+                        return Err(CommandError::RecoveryNotAvailable);
+                    }
+                    // Use the anti fee-sniping locktime if it satisfies the timelock. Despite its
+                    // name, this nSequence enables the nLockTime of the transaction.
+                    let locktime = match anti_fee_sniping_locktime {
+                        lt if timelock.is_implied_by(lt) => lt,
+                        _ => timelock,
+                    };
+                    (Some(bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME), locktime)
+                }
+            },
+            Some(descriptors::SpendingPath::Primary) | None => (None, anti_fee_sniping_locktime),
+        };
+
+        // Whether a coin can be spent through the spending path in the next block. It must be
+        // confirmed to be spent through a recovery path behind a relative timelock.
+        let current_height = self.bitcoin.chain_tip().height;
+        let tip_mtp = match spending_path {
+            Some(descriptors::SpendingPath::Recovery(
+                descriptors::RecoveryTimelock::RelativeTime(_),
+            )) => self.bitcoin.median_time_past(current_height),
+            _ => None,
+        };
+        let mut start_times = HashMap::new();
+        let mut is_spendable = |coin: &Coin| match spending_path {
+            Some(descriptors::SpendingPath::Recovery(descriptors::RecoveryTimelock::Relative(
+                tl,
+            ))) => coin
+                .block_info
+                .map(|b| current_height + 1 >= b.height + i32::from(tl))
+                .unwrap_or(false),
+            Some(descriptors::SpendingPath::Recovery(
+                descriptors::RecoveryTimelock::RelativeTime(tl),
+            )) => match (tip_mtp, coin.block_info) {
+                (Some(tip_mtp), Some(block)) => self
+                    .coin_start_time(&mut start_times, block.height)
+                    .map(|start_time| tip_mtp >= start_time + u32::from(tl) * 512)
+                    .unwrap_or(false),
+                _ => false,
+            },
+            _ => true,
+        };
+
         // Prepare the destination addresses.
         let mut destinations_checked = Vec::with_capacity(destinations.len());
         for (address, value_sat) in destinations {
@@ -492,11 +846,20 @@ impl DaemonControl {
             // From our unconfirmed coins, we only include those that are change outputs
             // since unconfirmed external deposits are more at risk of being dropped
             // unexpectedly from the mempool as they are beyond the user's control.
+            // Coins frozen by the user are never selected automatically, and coins reserved by
+            // another stored Spend transaction only if the caller asked for it.
+            let reserved = if include_reserved {
+                HashMap::new()
+            } else {
+                reserved_coins(&mut db_conn)
+            };
             db_conn
                 .coins(&[CoinStatus::Unconfirmed, CoinStatus::Confirmed], &[])
                 .into_iter()
                 .filter_map(|(op, c)| {
-                    if c.block_info.is_some() {
+                    if c.is_frozen || reserved.contains_key(&op) || !is_spendable(&c) {
+                        None
+                    } else if c.block_info.is_some() {
                         Some((c, None)) // confirmed coins have no ancestor info
                     } else if c.is_change && !c.is_immature {
                         // In case the mempool_entry is None, the coin will be included without
@@ -510,12 +873,7 @@ impl DaemonControl {
                     }
                 })
                 .map(|(c, ancestor_info)| {
-                    coin_to_candidate(
-                        &c,
-                        /*must_select=*/ false,
-                        /*sequence=*/ None,
-                        ancestor_info,
-                    )
+                    coin_to_candidate(&c, /*must_select=*/ false, sequence, ancestor_info)
                 })
                 .collect()
         } else {
@@ -529,6 +887,9 @@ impl DaemonControl {
                 if coin.is_immature {
                     return Err(CommandError::ImmatureCoinbase(*op));
                 }
+                if !is_spendable(coin) {
+                    return Err(CommandError::SpendingPathNotAvailable(*op));
+                }
             }
             coins
                 .into_iter()
@@ -541,12 +902,7 @@ impl DaemonControl {
                     } else {
                         None
                     };
-                    coin_to_candidate(
-                        &c,
-                        /*must_select=*/ true,
-                        /*sequence=*/ None,
-                        ancestor_info,
-                    )
+                    coin_to_candidate(&c, /*must_select=*/ true, sequence, ancestor_info)
                 })
                 .collect()
         };
@@ -555,7 +911,6 @@ impl DaemonControl {
         // derivation index in case any address in the transaction outputs was ours and from the
         // future.
         let change_info = change_address.info;
-        let locktime = self.anti_fee_sniping_locktime();
         let CreateSpendRes {
             psbt,
             has_change,
@@ -584,6 +939,14 @@ impl DaemonControl {
         if has_change {
             self.maybe_increase_next_deriv_index(&mut db_conn, &change_info);
         }
+        // Signers need not be told about the keys from the other spending paths.
+        let psbt = match path_info {
+            Some(path_info) => self
+                .config
+                .main_descriptor
+                .prune_bip32_derivs(psbt, &path_info),
+            None => psbt,
+        };
 
         Ok(CreateSpendResult::Success {
             psbt,
@@ -645,6 +1008,7 @@ impl DaemonControl {
 
         // Finally, insert (or update) the PSBT in database.
         db_conn.store_spend(&psbt);
+        self.record_events(&mut db_conn, &[Event::SpendUpdated { txid }]);
 
         Ok(())
     }
@@ -661,6 +1025,137 @@ impl DaemonControl {
         }
     }
 
+    /// Export all the labels as BIP329 JSON Lines.
+    pub fn export_labels(&self) -> ExportLabelsResult {
+        let mut db_conn = self.db.connection();
+        let labels = db_conn.list_labels();
+
+        // Whether a coin may be spent is only meaningful for our unspent coins.
+        let outpoints: Vec<_> = labels
+            .iter()
+            .filter_map(|(item, _)| match item {
+                LabelItem::OutPoint(op) => Some(*op),
+                _ => None,
+            })
+            .collect();
+        let coins = if outpoints.is_empty() {
+            HashMap::new()
+        } else {
+            db_conn.coins(&[], &outpoints)
+        };
+
+        let origin = self.config.main_descriptor.keys_origin_descriptor();
+        let records: Vec<_> = labels
+            .into_iter()
+            .map(|(item, value)| {
+                let mut record = bip329::Label::new(&item, value);
+                record.origin = Some(origin.clone());
+                if let LabelItem::OutPoint(op) = item {
+                    record.spendable = coins
+                        .get(&op)
+                        .filter(|coin| !coin.is_spent())
+                        .map(|coin| !coin.is_frozen);
+                }
+                record
+            })
+            .collect();
+
+        ExportLabelsResult {
+            labels: bip329::to_jsonl(&records),
+        }
+    }
+
+    /// Import labels from BIP329 JSON Lines. Labels on items we can't label (public keys, inputs
+    /// and xpubs) are skipped. The `spendable` field of outputs is used to freeze or unfreeze
+    /// the corresponding unspent coins.
+    pub fn import_labels(&self, jsonl: &str) -> Result<ImportLabelsResult, CommandError> {
+        let network = self.config.bitcoin_config.network;
+        let mut items = HashMap::new();
+        let mut spendable = HashMap::new();
+        let mut skipped = 0;
+        for record in bip329::from_jsonl(jsonl)? {
+            let item = match record.item(network)? {
+                Some(item) => item,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            if let (LabelItem::OutPoint(op), Some(is_spendable)) = (&item, record.spendable) {
+                spendable.insert(*op, is_spendable);
+            }
+            if let Some(label) = record.label.filter(|l| !l.is_empty()) {
+                if label.len() > MAX_LABEL_LENGTH {
+                    return Err(CommandError::LabelTooLong(record.reference));
+                }
+                items.insert(item, Some(label));
+            }
+        }
+
+        let mut db_conn = self.db.connection();
+        db_conn.update_labels(&items);
+        if !spendable.is_empty() {
+            let outpoints: Vec<_> = spendable.keys().copied().collect();
+            let coins = db_conn.coins(&[], &outpoints);
+            let (to_unfreeze, to_freeze): (Vec<_>, Vec<_>) = coins
+                .values()
+                .filter(|coin| !coin.is_spent())
+                .map(|coin| coin.outpoint)
+                .partition(|op| spendable[op]);
+            db_conn.freeze_coins(&to_freeze);
+            db_conn.unfreeze_coins(&to_unfreeze);
+        }
+
+        Ok(ImportLabelsResult {
+            imported: items.len(),
+            skipped,
+        })
+    }
+
+    /// Create a backup of the wallet, to be restored on a new machine. The key aliases are
+    /// included if provided.
+    pub fn create_backup(
+        &self,
+        key_aliases: Option<BTreeMap<bip32::Fingerprint, String>>,
+    ) -> WalletBackup {
+        let mut db_conn = self.db.connection();
+        let labels = db_conn
+            .list_labels()
+            .into_iter()
+            .map(|(item, value)| (item.to_string(), value))
+            .collect();
+        let spend_txs = db_conn
+            .list_spend()
+            .into_iter()
+            .map(|(psbt, _)| psbt)
+            .collect();
+        let frozen_coins = db_conn
+            .coins(
+                &[
+                    CoinStatus::Unconfirmed,
+                    CoinStatus::Confirmed,
+                    CoinStatus::Spending,
+                ],
+                &[],
+            )
+            .into_values()
+            .filter_map(|c| if c.is_frozen { Some(c.outpoint) } else { None })
+            .collect();
+
+        WalletBackup {
+            version: BACKUP_VERSION,
+            network: self.config.bitcoin_config.network,
+            main_descriptor: self.config.main_descriptor.clone(),
+            timestamp: db_conn.timestamp(),
+            receive_index: db_conn.receive_index().into(),
+            change_index: db_conn.change_index().into(),
+            labels,
+            spend_txs,
+            frozen_coins,
+            key_aliases,
+        }
+    }
+
     pub fn list_spend(
         &self,
         txids: Option<Vec<bitcoin::Txid>>,
@@ -692,6 +1187,7 @@ impl DaemonControl {
     pub fn delete_spend(&self, txid: &bitcoin::Txid) {
         let mut db_conn = self.db.connection();
         db_conn.delete_spend(txid);
+        self.record_events(&mut db_conn, &[Event::SpendDeleted { txid: *txid }]);
     }
 
     /// Finalize and broadcast this stored Spend transaction.
@@ -732,6 +1228,115 @@ impl DaemonControl {
         Ok(())
     }
 
+    /// Create a transaction spending the given unconfirmed coins back to ourselves, in order to
+    /// bump the feerate of their unconfirmed ancestors using CPFP.
+    ///
+    /// The child transaction will pay for its unconfirmed ancestors such that the package as a whole
+    /// has a feerate of `feerate_vb` (in sat/vb). Confirmed coins may be added as inputs if the
+    /// given coins are not enough to pay for the fees.
+    pub fn create_cpfp(
+        &self,
+        outpoints: &[bitcoin::OutPoint],
+        feerate_vb: u64,
+    ) -> Result<CreateSpendResult, CommandError> {
+        if outpoints.is_empty() {
+            return Err(CommandError::NoOutpointForSelfSend);
+        }
+        if feerate_vb < 1 {
+            return Err(CommandError::InvalidFeerate(feerate_vb));
+        }
+        let mut db_conn = self.db.connection();
+        let mut tx_getter = DbTxGetter::new(&self.db);
+
+        // Sanity check the coins to be bumped and get their ancestors' information from the
+        // mempool. If several coins share the same parent transaction, only set the ancestor
+        // information on one of them to avoid paying for the same ancestors more than once.
+        let coins = db_conn.coins(&[], outpoints);
+        for op in outpoints {
+            let coin = coins.get(op).ok_or(CommandError::UnknownOutpoint(*op))?;
+            if coin.is_spent() {
+                return Err(CommandError::AlreadySpent(*op));
+            }
+            if coin.is_immature {
+                return Err(CommandError::ImmatureCoinbase(*op));
+            }
+            if coin.is_confirmed() {
+                return Err(CommandError::AlreadyConfirmed(*op));
+            }
+        }
+        let mut candidate_coins = Vec::with_capacity(outpoints.len());
+        let mut seen_txids = HashSet::with_capacity(outpoints.len());
+        for (op, coin) in &coins {
+            let ancestor_info = self
+                .bitcoin
+                .mempool_entry(&op.txid)
+                .map(AncestorInfo::from)
+                .ok_or(CommandError::NotInMempool(*op))?;
+            let ancestor_info = if seen_txids.insert(op.txid) {
+                Some(ancestor_info)
+            } else {
+                None
+            };
+            candidate_coins.push(coin_to_candidate(
+                coin,
+                /*must_select=*/ true,
+                /*sequence=*/ None,
+                ancestor_info,
+            ));
+        }
+
+        // Confirmed coins may be used as additional inputs if necessary. As for any automatic
+        // coin selection, don't consider frozen or reserved coins.
+        let reserved = reserved_coins(&mut db_conn);
+        candidate_coins.extend(
+            db_conn
+                .coins(&[CoinStatus::Confirmed], &[])
+                .into_values()
+                .filter(|c| !c.is_frozen && !reserved.contains_key(&c.outpoint))
+                .map(|c| {
+                    coin_to_candidate(
+                        &c, /*must_select=*/ false, /*sequence=*/ None,
+                        /*ancestor_info=*/ None,
+                    )
+                }),
+        );
+
+        // Send everything back to a change address of ours.
+        let change_address = self.next_change_addr(&mut db_conn);
+        let change_info = change_address.info;
+        let locktime = self.anti_fee_sniping_locktime();
+        let CreateSpendRes {
+            psbt,
+            has_change,
+            warnings,
+        } = match create_spend(
+            &self.config.main_descriptor,
+            &self.secp,
+            &mut tx_getter,
+            &[], // No destination, only the change address.
+            &candidate_coins,
+            SpendTxFees::Regular(feerate_vb),
+            change_address,
+            locktime,
+        ) {
+            Ok(res) => res,
+            Err(SpendCreationError::CoinSelection(e)) => {
+                return Ok(CreateSpendResult::InsufficientFunds { missing: e.missing });
+            }
+            Err(e) => {
+                return Err(e.into());
+            }
+        };
+        if has_change {
+            self.maybe_increase_next_deriv_index(&mut db_conn, &change_info);
+        }
+
+        Ok(CreateSpendResult::Success {
+            psbt,
+            warnings: warnings.iter().map(|w| w.to_string()).collect(),
+        })
+    }
+
     /// Create PSBT to replace the given transaction using RBF.
     ///
     /// `txid` must point to a PSBT in our database.
@@ -907,13 +1512,19 @@ impl DaemonControl {
                 )
             })
             .collect();
+        let reserved = reserved_coins(&mut db_conn);
         let confirmed_cands: Vec<CandidateCoin> = db_conn
             .coins(&[CoinStatus::Confirmed], &[])
             .into_values()
             .filter_map(|c| {
                 // Make sure we don't have duplicate candidates in case any of the coins are not
-                // currently set as spending in the DB (and are therefore still confirmed).
-                if !prev_coins.contains_key(&c.outpoint) {
+                // currently set as spending in the DB (and are therefore still confirmed). Frozen
+                // coins and coins reserved by another stored Spend transaction are not added
+                // automatically.
+                if !prev_coins.contains_key(&c.outpoint)
+                    && !c.is_frozen
+                    && !reserved.contains_key(&c.outpoint)
+                {
                     Some(coin_to_candidate(
                         &c, /*must_select=*/ false, /*sequence=*/ None,
                         /*ancestor_info=*/ None,
@@ -1038,62 +1649,823 @@ impl DaemonControl {
         ListTransactionsResult { transactions }
     }
 
-    /// Create a transaction that sweeps all coins for which a timelocked recovery path is
-    /// currently available to a provided address with the provided feerate.
-    ///
-    /// The `timelock` parameter can be used to specify which recovery path to use. By default,
-    /// we'll use the first recovery path available.
-    ///
-    /// Note that not all coins may be spendable through a single recovery path at the same time.
-    pub fn create_recovery(
-        &self,
-        address: bitcoin::Address<address::NetworkUnchecked>,
-        feerate_vb: u64,
-        timelock: Option<u16>,
-    ) -> Result<CreateRecoveryResult, CommandError> {
-        if feerate_vb < 1 {
-            return Err(CommandError::InvalidFeerate(feerate_vb));
-        }
-        let mut tx_getter = DbTxGetter::new(&self.db);
+    /// Export the history of the transactions confirmed between two dates, in the order they
+    /// were confirmed, along with the balance of the wallet after each of them.
+    pub fn export_history(&self, start: u32, end: u32) -> ExportHistoryResult {
         let mut db_conn = self.db.connection();
-        let sweep_addr = self.spend_addr(&mut db_conn, self.validate_address(address)?);
+        // Any limit must fit in a SQLite integer.
+        let txids = db_conn.list_txids(start, end, i64::MAX as u64);
+        let labels = db_conn.labels(&txids.iter().map(|txid| LabelItem::Txid(*txid)).collect());
+        let coins = db_conn.coins(&[], &[]);
+
+        // The balance before the first transaction is the value of the coins confirmed before the
+        // start date and not spent by then.
+        let confirmed_before = |block: Option<BlockInfo>| block.map(|b| b.time < start);
+        let mut balance = coins
+            .values()
+            .filter(|c| {
+                confirmed_before(c.block_info) == Some(true)
+                    && confirmed_before(c.spend_block) != Some(true)
+            })
+            .map(|c| c.amount.to_signed().expect("Coin amounts are within range"))
+            .fold(bitcoin::SignedAmount::ZERO, |acc, a| acc + a);
+
+        // Transactions confirmed in the same block must be ordered parents first for the running
+        // balance to be consistent.
+        let mut txs = db_conn.list_wallet_transactions(&txids);
+        txs.sort_by_key(|(_, height, _)| *height);
+        let mut i = 0;
+        while i < txs.len() {
+            let parent = (i + 1..txs.len())
+                .take_while(|j| txs[*j].1 == txs[i].1)
+                .find(|j| {
+                    let parent_txid = txs[*j].0.txid();
+                    txs[i]
+                        .0
+                        .input
+                        .iter()
+                        .any(|txin| txin.previous_output.txid == parent_txid)
+                });
+            if let Some(j) = parent {
+                let parent = txs.remove(j);
+                txs.insert(i, parent);
+            } else {
+                i += 1;
+            }
+        }
+
+        let transactions = txs
+            .into_iter()
+            .map(|(tx, height, time)| {
+                let txid = tx.txid();
+                let (mut received, mut received_count) = (bitcoin::Amount::ZERO, 0);
+                let (mut sent, mut sent_count) = (bitcoin::Amount::ZERO, 0);
+                for coin in coins.values() {
+                    if coin.outpoint.txid == txid {
+                        received += coin.amount;
+                        received_count += 1;
+                    }
+                    if coin.spend_txid == Some(txid) {
+                        sent += coin.amount;
+                        sent_count += 1;
+                    }
+                }
+                let net_amount = bitcoin::SignedAmount::from_sat(
+                    received.to_sat() as i64 - sent.to_sat() as i64,
+                );
+                balance += net_amount;
+
+                // We only know the value of the inputs if they are all ours.
+                let fee = if sent_count == tx.input.len() {
+                    let outputs_value = tx.output.iter().map(|txo| txo.value).sum();
+                    sent.checked_sub(outputs_value)
+                } else {
+                    None
+                };
+                let direction = if sent_count > 0 && received_count == tx.output.len() {
+                    HistoryDirection::SelfTransfer
+                } else if net_amount.is_positive() {
+                    HistoryDirection::Incoming
+                } else {
+                    HistoryDirection::Outgoing
+                };
+
+                HistoryEntry {
+                    txid,
+                    height,
+                    time,
+                    direction,
+                    net_amount,
+                    fee,
+                    balance,
+                    label: labels.get(&txid.to_string()).cloned(),
+                }
+            })
+            .collect();
+
+        ExportHistoryResult { transactions }
+    }
+
+    // The median time past from which the relative timelocks expressed in time of a coin
+    // confirmed at this height start counting: the one of the block before the block the coin
+    // was confirmed in (see BIP68). Memoized in `cache` as it needs to query the Bitcoin backend.
+    fn coin_start_time(&self, cache: &mut HashMap<i32, Option<u32>>, height: i32) -> Option<u32> {
+        *cache
+            .entry(height)
+            .or_insert_with(|| self.bitcoin.median_time_past(height - 1))
+    }
+
+    // Whether a transaction with this nLockTime can be included in the next block. A date is
+    // compared to the median time past of the current tip (see BIP113).
+    fn is_absolute_timelock_available(&self, timelock: LockTime) -> bool {
+        let current_height = self.bitcoin.chain_tip().height;
+        match timelock {
+            LockTime::Blocks(height) => {
+                i64::from(height.to_consensus_u32()) <= current_height.into()
+            }
+            LockTime::Seconds(time) => self
+                .bitcoin
+                .median_time_past(current_height)
+                .map(|tip_mtp| time.to_consensus_u32() < tip_mtp)
+                .unwrap_or(false),
+        }
+    }
 
-        // Query the coins that we can spend through the specified recovery path (if no recovery
-        // path specified, use the first available one) from the database.
+    /// List our confirmed unspent coins along with the number of blocks remaining until each of
+    /// the recovery paths becomes available for them.
+    ///
+    /// For the recovery paths behind a relative timelock expressed in time, the number of seconds
+    /// remaining is computed using the median time past of the blocks, as for consensus. The
+    /// recovery paths behind an absolute timelock don't depend on the coin and aren't reported.
+    ///
+    /// If `max_blocks` is set, only the coins for which the first recovery path becomes available
+    /// within this number of blocks are returned. Coins are sorted by expiry, soonest first.
+    pub fn list_expiring_coins(&self, max_blocks: Option<u32>) -> ListExpiringCoinsResult {
+        let mut db_conn = self.db.connection();
         let current_height = self.bitcoin.chain_tip().height;
-        let timelock =
-            timelock.unwrap_or_else(|| self.config.main_descriptor.first_timelock_value());
-        let height_delta: i32 = timelock.into();
-        let sweepable_coins: Vec<_> = db_conn
+        let policy = self.config.main_descriptor.policy();
+        let timelocks: Vec<u16> = policy.recovery_paths().keys().copied().collect();
+        let time_timelocks: Vec<u16> = policy.time_recovery_paths().keys().copied().collect();
+        // Only query the median time past of blocks if there are recovery paths expressed in time.
+        let tip_mtp = if time_timelocks.is_empty() {
+            None
+        } else {
+            self.bitcoin.median_time_past(current_height)
+        };
+        let mut start_times = HashMap::new();
+
+        let mut coins: Vec<ListExpiringCoinsEntry> = db_conn
             .coins(&[CoinStatus::Confirmed], &[])
             .into_values()
-            .filter_map(|c| {
-                // We are interested in coins available at the *next* block
-                if c.block_info
-                    .map(|b| current_height + 1 >= b.height + height_delta)
+            .filter_map(|coin| {
+                let block_height = coin.block_info?.height;
+                let recovery_paths = timelocks
+                    .iter()
+                    .map(|timelock| {
+                        // Like for create_recovery, we are interested in the *next* block.
+                        let available_at = block_height + i32::from(*timelock);
+                        let blocks_remaining = (available_at - (current_height + 1)).max(0) as u32;
+                        RecoveryPathExpiry {
+                            timelock: *timelock,
+                            blocks_remaining,
+                        }
+                    })
+                    .collect();
+                // Like for create_time_recovery, we are interested in the *next* block: it is
+                // available if the median time past of the current tip reached the end of the
+                // timelock.
+                let time_recovery_paths = tip_mtp
+                    .and_then(|tip_mtp| {
+                        let start_time = self.coin_start_time(&mut start_times, block_height)?;
+                        Some(
+                            time_timelocks
+                                .iter()
+                                .map(|timelock| {
+                                    let available_at = start_time + u32::from(*timelock) * 512;
+                                    TimeRecoveryPathExpiry {
+                                        timelock: *timelock,
+                                        seconds_remaining: available_at.saturating_sub(tip_mtp),
+                                    }
+                                })
+                                .collect(),
+                        )
+                    })
+                    .unwrap_or_default();
+                Some(ListExpiringCoinsEntry {
+                    outpoint: coin.outpoint,
+                    amount: coin.amount,
+                    block_height,
+                    recovery_paths,
+                    time_recovery_paths,
+                })
+            })
+            .filter(|entry| {
+                // Skip the coins for which we can't tell when a relative recovery path becomes
+                // available, for instance if the descriptor only has absolute ones.
+                entry
+                    .blocks_remaining()
+                    .map(|blocks| max_blocks.map(|max| blocks <= max).unwrap_or(true))
                     .unwrap_or(false)
-                {
-                    Some(coin_to_candidate(
-                        &c,
-                        /*must_select=*/ true,
-                        /*sequence=*/ Some(bitcoin::Sequence::from_height(timelock)),
-                        /*ancestor_info=*/ None,
-                    ))
-                } else {
-                    None
+            })
+            .collect();
+        coins.sort_by_key(|entry| (entry.blocks_remaining(), entry.outpoint));
+
+        ListExpiringCoinsResult { coins }
+    }
+
+    /// Get the schedule at which our confirmed coins become spendable through each recovery path.
+    ///
+    /// For each recovery path, the coins are grouped by the block height or the time from which
+    /// they can be spent. For timelocks expressed in blocks the time is estimated from the time of
+    /// the current tip, assuming a block every 10 minutes. For timelocks expressed in time it is
+    /// the median time past the tip must reach (see BIP113).
+    pub fn get_recovery_timeline(&self) -> GetRecoveryTimelineResult {
+        let mut db_conn = self.db.connection();
+        let tip_height = self.bitcoin.chain_tip().height;
+        let tip_time = self.bitcoin.tip_time();
+        let policy = self.config.main_descriptor.policy();
+        let description = policy.description();
+        // Only query the median time past of blocks if there are timelocks expressed in time.
+        let has_time_timelocks = !policy.time_recovery_paths().is_empty()
+            || policy
+                .absolute_recovery_paths()
+                .keys()
+                .any(|tl| *tl >= bitcoin::absolute::LOCK_TIME_THRESHOLD);
+        let tip_mtp = if has_time_timelocks {
+            self.bitcoin.median_time_past(tip_height)
+        } else {
+            None
+        };
+        let mut start_times = HashMap::new();
+
+        let coins: Vec<(bitcoin::OutPoint, bitcoin::Amount, i32)> = db_conn
+            .coins(&[CoinStatus::Confirmed], &[])
+            .into_values()
+            .filter_map(|coin| Some((coin.outpoint, coin.amount, coin.block_info?.height)))
+            .collect();
+        let total_amount = coins
+            .iter()
+            .fold(bitcoin::Amount::from_sat(0), |sum, (_, amount, _)| {
+                sum + *amount
+            });
+
+        // The estimated time of the block at the given height, if it wasn't mined yet.
+        let estimate_time = |height: i32| {
+            if height > tip_height {
+                tip_time.map(|t| t + (height - tip_height) as u32 * TARGET_BLOCK_INTERVAL_SECS)
+            } else {
+                None
+            }
+        };
+        // Whether a median time past has been reached by the tip, and the time otherwise.
+        let time_unlock = |time: u32, available: bool| {
+            if available {
+                (None, None, true)
+            } else {
+                (None, Some(time), false)
+            }
+        };
+
+        let recovery_paths = description
+            .recovery_paths
+            .into_iter()
+            .map(|recov| {
+                // The block height and time from which a coin can be spent, and whether it can be
+                // spent in the next block. Coins are grouped by these, the available ones first.
+                let mut unlocks = BTreeMap::new();
+                for (outpoint, amount, block_height) in &coins {
+                    let (height, time, available) = match recov.timelock {
+                        descriptors::RecoveryTimelock::Relative(timelock) => {
+                            // Like for create_recovery, we are interested in the *next* block.
+                            let height = block_height + i32::from(timelock);
+                            (
+                                Some(height),
+                                estimate_time(height),
+                                height <= tip_height + 1,
+                            )
+                        }
+                        descriptors::RecoveryTimelock::RelativeTime(timelock) => {
+                            match self.coin_start_time(&mut start_times, *block_height) {
+                                Some(start_time) => {
+                                    let time = start_time + u32::from(timelock) * 512;
+                                    time_unlock(time, tip_mtp.map(|m| m >= time).unwrap_or(false))
+                                }
+                                None => (None, None, false),
+                            }
+                        }
+                        descriptors::RecoveryTimelock::Absolute(timelock)
+                            if timelock < bitcoin::absolute::LOCK_TIME_THRESHOLD =>
+                        {
+                            // The nLockTime must be lower than the height of the block including
+                            // the transaction.
+                            let height = timelock as i32 + 1;
+                            (
+                                Some(height),
+                                estimate_time(height),
+                                height <= tip_height + 1,
+                            )
+                        }
+                        descriptors::RecoveryTimelock::Absolute(timelock) => {
+                            time_unlock(timelock, tip_mtp.map(|m| timelock < m).unwrap_or(false))
+                        }
+                    };
+                    let unlock = unlocks
+                        .entry((!available, height, time))
+                        .or_insert_with(|| (bitcoin::Amount::from_sat(0), Vec::new()));
+                    unlock.0 += *amount;
+                    unlock.1.push(*outpoint);
+                }
+                RecoveryTimelinePath {
+                    timelock: recov.timelock,
+                    path: recov.path,
+                    total_amount,
+                    unlocks: unlocks
+                        .into_iter()
+                        .map(|((not_available, height, time), (amount, mut coins))| {
+                            coins.sort();
+                            RecoveryTimelineUnlock {
+                                height,
+                                time,
+                                available: !not_available,
+                                amount,
+                                coins,
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        GetRecoveryTimelineResult {
+            tip_height,
+            recovery_paths,
+        }
+    }
+
+    /// Create self-send transactions refreshing all our coins for which the first recovery path
+    /// becomes available within `max_blocks` blocks, resetting their timelocks.
+    ///
+    /// Coins are spent to a new change address, the same for all the transactions, at most
+    /// `max_inputs` of them per transaction (or [`DEFAULT_REFRESH_MAX_INPUTS`] if not set). The
+    /// coins closest to expiry are batched together first. Frozen coins, immature coins and coins already spent by a stored Spend
+    /// transaction are not refreshed.
+    ///
+    /// The created transactions are not stored, they must be updated using
+    /// [`DaemonControl::update_spend`] as for any other Spend transaction.
+    pub fn refresh_coins(
+        &self,
+        max_blocks: u32,
+        feerate_vb: u64,
+        max_inputs: Option<usize>,
+    ) -> Result<RefreshCoinsResult, CommandError> {
+        let spends = self.create_refresh_spends(
+            max_blocks,
+            feerate_vb,
+            max_inputs,
+            RefreshKeychain::Change,
+            &HashSet::new(),
+        )?;
+        Ok(RefreshCoinsResult {
+            spends: spends.into_iter().map(|(_, spend)| spend).collect(),
+        })
+    }
+
+    // Create the refresh transactions, sending the coins to a new address of the given keychain.
+    // The coins in `excluded` aren't refreshed. Returns the coins spent by each transaction along
+    // with it.
+    fn create_refresh_spends(
+        &self,
+        max_blocks: u32,
+        feerate_vb: u64,
+        max_inputs: Option<usize>,
+        keychain: RefreshKeychain,
+        excluded: &HashSet<bitcoin::OutPoint>,
+    ) -> Result<Vec<(Vec<bitcoin::OutPoint>, CreateSpendResult)>, CommandError> {
+        let max_inputs = max_inputs.unwrap_or(DEFAULT_REFRESH_MAX_INPUTS);
+        if max_inputs < 1 {
+            return Err(CommandError::InvalidMaxInputs(max_inputs));
+        }
+        if feerate_vb < 1 {
+            return Err(CommandError::InvalidFeerate(feerate_vb));
+        }
+
+        let expiring_ops: Vec<bitcoin::OutPoint> = self
+            .list_expiring_coins(Some(max_blocks))
+            .coins
+            .into_iter()
+            .map(|entry| entry.outpoint)
+            .collect();
+        let outpoints: Vec<bitcoin::OutPoint> = {
+            let mut db_conn = self.db.connection();
+            let reserved = reserved_coins(&mut db_conn);
+            let coins = db_conn.coins(&[], &expiring_ops);
+            expiring_ops
+                .into_iter()
+                .filter(|op| {
+                    !excluded.contains(op)
+                        && coins
+                            .get(op)
+                            .map(|c| !c.is_frozen && !c.is_immature && !reserved.contains_key(op))
+                            .unwrap_or(false)
+                })
+                .collect()
+        };
+        if outpoints.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // All the transactions send to the same new address, not to use up a derivation index
+        // per transaction. The index is only used up if at least one of them could be created.
+        let SpendOutputAddress {
+            addr: address,
+            info: addr_info,
+        } = match keychain {
+            RefreshKeychain::Change => self.next_change_addr(&mut self.db.connection()),
+            RefreshKeychain::Receive => self.next_receive_addr(&mut self.db.connection()),
+        };
+        let spends = outpoints
+            .chunks(max_inputs)
+            .map(|batch| {
+                let spend = self.create_spend(
+                    &HashMap::new(),
+                    batch,
+                    feerate_vb,
+                    Some(address.as_unchecked().clone()),
+                    false,
+                )?;
+                Ok((batch.to_vec(), spend))
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        if spends
+            .iter()
+            .any(|(_, spend)| matches!(spend, CreateSpendResult::Success { .. }))
+        {
+            self.maybe_increase_next_deriv_index(&mut self.db.connection(), &addr_info);
+        }
+
+        Ok(spends)
+    }
+
+    /// Create and store the transactions refreshing our coins which entered the safety margin of
+    /// the configured refresh policy, if any. Returns the txids of the stored transactions.
+    ///
+    /// The feerate is estimated for a confirmation within half the safety margin, capped at the
+    /// maximum feerate of the policy. It defaults to this maximum if no estimate is available.
+    /// Coins already spent by a stored Spend transaction are not refreshed again, nor are the coins
+    /// recorded in `state` as already refreshed by a previous call (even if the transaction was
+    /// since deleted).
+    pub fn auto_refresh_coins(
+        &self,
+        state: &mut AutoRefreshState,
+    ) -> Result<Vec<bitcoin::Txid>, CommandError> {
+        let policy = match self.config.refresh_policy {
+            Some(ref policy) => policy,
+            None => return Ok(Vec::new()),
+        };
+        let conf_target = (policy.margin_blocks / 2).clamp(1, MAX_FEE_ESTIMATE_TARGET.into());
+        let feerate_vb = self
+            .bitcoin
+            .estimate_feerate(conf_target as u16)
+            .map(|feerate| cmp::min(feerate, policy.max_feerate))
+            .unwrap_or(policy.max_feerate);
+
+        let mut db_conn = self.db.connection();
+        // Forget about the refreshed coins once they are spent.
+        let refreshed: Vec<_> = state.refreshed.iter().copied().collect();
+        let refreshed_coins = db_conn.coins(&[], &refreshed);
+        state.refreshed.retain(|op| {
+            refreshed_coins
+                .get(op)
+                .map(|c| !c.is_spent())
+                .unwrap_or(false)
+        });
+
+        let spends = self.create_refresh_spends(
+            policy.margin_blocks,
+            feerate_vb,
+            None,
+            policy.keychain,
+            &state.refreshed,
+        )?;
+        let mut txids = Vec::with_capacity(spends.len());
+        let mut unaffordable = HashSet::new();
+        for (outpoints, spend) in spends {
+            match spend {
+                CreateSpendResult::Success { psbt, .. } => {
+                    db_conn.store_spend(&psbt);
+                    txids.push(psbt.unsigned_tx.txid());
+                    state.refreshed.extend(outpoints);
+                }
+                CreateSpendResult::InsufficientFunds { missing } => {
+                    // Only warn once for a given set of coins, not on every poll.
+                    if !state.unaffordable.contains(&outpoints) {
+                        log::warn!(
+                            "Not enough funds to refresh {} coin(s) at {} sat/vb. Missing {} sats.",
+                            outpoints.len(),
+                            feerate_vb,
+                            missing
+                        );
+                    }
+                    unaffordable.insert(outpoints);
                 }
+            }
+        }
+        state.unaffordable = unaffordable;
+        let events: Vec<_> = txids
+            .iter()
+            .map(|txid| Event::SpendUpdated { txid: *txid })
+            .collect();
+        self.record_events(&mut db_conn, &events);
+
+        Ok(txids)
+    }
+
+    /// Get the events recorded in the journal after the given cursor. If there is none yet, wait
+    /// up to `timeout_secs` seconds for new ones to be recorded.
+    ///
+    /// The cursor is the id of the last event received by the caller. Without a cursor the events
+    /// are returned from the start of the journal.
+    pub fn wait_for_events(
+        &self,
+        cursor: Option<u64>,
+        timeout_secs: u64,
+    ) -> Result<WaitForEventsResult, CommandError> {
+        if timeout_secs > MAX_WAIT_FOR_EVENTS_TIMEOUT {
+            return Err(CommandError::InvalidTimeout(timeout_secs));
+        }
+        let cursor = cursor.unwrap_or(0);
+        let deadline = time::Instant::now() + time::Duration::from_secs(timeout_secs);
+        // Registered once we actually need to wait for events.
+        let mut waiter = None;
+
+        loop {
+            // Read the generation before querying the journal, so we can't miss a notification
+            // for events recorded in between.
+            let generation = self.events_notifier.generation();
+            let events = self.db.connection().events(cursor, MAX_EVENTS_PER_WAIT);
+            if let Some(last) = events.last() {
+                return Ok(WaitForEventsResult {
+                    cursor: last.id,
+                    events,
+                });
+            }
+
+            let now = time::Instant::now();
+            if now >= deadline {
+                return Ok(WaitForEventsResult { events, cursor });
+            }
+            if waiter.is_none() {
+                waiter = Some(
+                    self.events_notifier
+                        .register_waiter(MAX_EVENT_WAITERS)
+                        .ok_or(CommandError::TooManyEventWaiters)?,
+                );
+            }
+            self.events_notifier.wait(generation, deadline - now);
+        }
+    }
+
+    // Record these events in the journal and wake up the callers waiting for new ones.
+    fn record_events(&self, db_conn: &mut Box<dyn DatabaseConnection>, events: &[Event]) {
+        if events.is_empty() {
+            return;
+        }
+        db_conn.store_events(events);
+        self.events_notifier.notify();
+    }
+
+    /// Create a transaction that sweeps all coins for which a timelocked recovery path is
+    /// currently available to a provided address with the provided feerate.
+    ///
+    /// The `timelock` parameter can be used to specify which recovery path to use. By default,
+    /// we'll use the first recovery path available.
+    ///
+    /// Note that not all coins may be spendable through a single recovery path at the same time.
+    pub fn create_recovery(
+        &self,
+        address: bitcoin::Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        timelock: Option<u16>,
+    ) -> Result<CreateRecoveryResult, CommandError> {
+        let timelock = match timelock {
+            Some(timelock) => timelock,
+            None => self
+                .config
+                .main_descriptor
+                .first_timelock_value()
+                .ok_or(CommandError::RecoveryNotAvailable)?,
+        };
+        self.create_recovery_spend(
+            address,
+            feerate_vb,
+            &[descriptors::RecoveryTimelock::Relative(timelock)],
+        )
+    }
+
+    /// Create a transaction that sweeps all coins for which a recovery path behind a relative
+    /// timelock expressed in time is currently available to a provided address with the provided
+    /// feerate.
+    ///
+    /// The `timelock` parameter, in number of 512 seconds intervals, can be used to specify which
+    /// recovery path to use. By default, we'll use the first one.
+    ///
+    /// As for consensus, the timelock of a coin starts at the median time past of the block before
+    /// the one it was confirmed in and expires once the median time past of the tip reaches its
+    /// end.
+    pub fn create_time_recovery(
+        &self,
+        address: bitcoin::Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        timelock: Option<u16>,
+    ) -> Result<CreateRecoveryResult, CommandError> {
+        let policy = self.config.main_descriptor.policy();
+        let timelock = match timelock {
+            Some(timelock) if !policy.time_recovery_paths().contains_key(&timelock) => {
+                return Err(CommandError::UnknownTimeTimelock(timelock));
+            }
+            Some(timelock) => timelock,
+            None => *policy
+                .time_recovery_paths()
+                .keys()
+                .next()
+                .ok_or(CommandError::RecoveryNotAvailable)?,
+        };
+        self.create_recovery_spend(
+            address,
+            feerate_vb,
+            &[descriptors::RecoveryTimelock::RelativeTime(timelock)],
+        )
+    }
+
+    /// Create a transaction that sweeps all confirmed coins to a provided address with the
+    /// provided feerate, through a recovery path behind an absolute timelock. Contrary to the
+    /// relative timelocks, such a recovery path becomes available for all coins at once.
+    ///
+    /// The `timelock` parameter can be used to specify which recovery path to use. By default,
+    /// we'll use the first one available.
+    ///
+    /// For a timelock set as a date, the transaction may only be broadcast once the median time
+    /// of the past 11 blocks is past this date.
+    pub fn create_absolute_recovery(
+        &self,
+        address: bitcoin::Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        timelock: Option<u32>,
+    ) -> Result<CreateRecoveryResult, CommandError> {
+        let policy = self.config.main_descriptor.policy();
+        if let Some(timelock) = timelock {
+            if !policy.absolute_recovery_paths().contains_key(&timelock) {
+                return Err(CommandError::UnknownAbsoluteTimelock(timelock));
+            }
+        }
+        let timelocks: Vec<_> = policy
+            .absolute_recovery_paths()
+            .keys()
+            .filter(|tl| timelock.map(|t| t == **tl).unwrap_or(true))
+            .map(|tl| descriptors::RecoveryTimelock::Absolute(*tl))
+            .collect();
+        self.create_recovery_spend(address, feerate_vb, &timelocks)
+    }
+
+    /// Create a transaction that sweeps to a provided address with the provided feerate all the
+    /// confirmed coins for which a recovery path can be used by the given `signers`, identified
+    /// by their master key fingerprint. Each coin is spent through the earliest recovery path
+    /// which is available for it at the next block and whose threshold the signers can meet.
+    /// Coins without such a recovery path are left untouched.
+    ///
+    /// The recovery paths behind a relative timelock are considered by increasing duration. The
+    /// ones behind an absolute timelock come last, as they can be used for coins too recent for
+    /// any relative timelock to have expired. Only absolute timelocks of the same unit (block
+    /// height or date) can be combined in a single transaction.
+    pub fn create_multi_path_recovery(
+        &self,
+        address: bitcoin::Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        signers: &[bip32::Fingerprint],
+    ) -> Result<CreateRecoveryResult, CommandError> {
+        let policy = self.config.main_descriptor.policy();
+
+        // The recovery paths whose threshold the signers can meet, in the order we'll try them.
+        let mut relative_paths: Vec<_> = policy
+            .recovery_paths()
+            .iter()
+            .map(|(tl, path)| {
+                (
+                    descriptors::RecoveryTimelock::Relative(*tl),
+                    u64::from(*tl) * 600,
+                    path,
+                )
+            })
+            .chain(policy.time_recovery_paths().iter().map(|(tl, path)| {
+                (
+                    descriptors::RecoveryTimelock::RelativeTime(*tl),
+                    u64::from(*tl) * 512,
+                    path,
+                )
+            }))
+            .collect();
+        relative_paths.sort_by_key(|(_, duration, _)| *duration);
+        let timelocks: Vec<_> = relative_paths
+            .into_iter()
+            .map(|(tl, _, path)| (tl, path))
+            .chain(
+                policy
+                    .absolute_recovery_paths()
+                    .iter()
+                    .map(|(tl, path)| (descriptors::RecoveryTimelock::Absolute(*tl), path)),
+            )
+            .filter(|(_, path)| {
+                let (threshold, origins) = path.thresh_origins();
+                origins.keys().filter(|fg| signers.contains(fg)).count() >= threshold
             })
+            .map(|(tl, _)| tl)
             .collect();
+
+        self.create_recovery_spend(address, feerate_vb, &timelocks)
+    }
+
+    // Create a transaction sweeping to the given address all the confirmed coins which can be
+    // spent at the next block through one of the recovery paths behind the given `timelocks`.
+    // Each coin is spent through the first of them which is available for it, coins for which
+    // none is available are left untouched. Only absolute timelocks of the same unit as the first
+    // one used can be combined in the transaction.
+    fn create_recovery_spend(
+        &self,
+        address: bitcoin::Address<address::NetworkUnchecked>,
+        feerate_vb: u64,
+        timelocks: &[descriptors::RecoveryTimelock],
+    ) -> Result<CreateRecoveryResult, CommandError> {
+        if feerate_vb < 1 {
+            return Err(CommandError::InvalidFeerate(feerate_vb));
+        }
+        let main_descriptor = &self.config.main_descriptor;
+        let mut tx_getter = DbTxGetter::new(&self.db);
+        let mut db_conn = self.db.connection();
+        let sweep_addr = self.spend_addr(&mut db_conn, self.validate_address(address)?);
+
+        // Find the recovery path to spend each coin through. We are interested in the paths
+        // available at the *next* block. For the relative timelocks expressed in time, that is if
+        // the median time past of the current tip reached the end of their timelock. The nLockTime
+        // required by the absolute timelocks of the paths used is tracked as we go.
+        let current_height = self.bitcoin.chain_tip().height;
+        let tip_mtp = self.bitcoin.median_time_past(current_height);
+        let mut start_times = HashMap::new();
+        let mut sat_weights = HashMap::new();
+        let mut required_locktime: Option<LockTime> = None;
+        let mut sweepable_coins = Vec::new();
+        for coin in db_conn.coins(&[CoinStatus::Confirmed], &[]).into_values() {
+            let coin_height = match coin.block_info {
+                Some(block) => block.height,
+                None => continue,
+            };
+            let timelock = timelocks.iter().copied().find(|tl| match *tl {
+                descriptors::RecoveryTimelock::Relative(blocks) => {
+                    current_height + 1 >= coin_height + i32::from(blocks)
+                }
+                descriptors::RecoveryTimelock::RelativeTime(intervals) => tip_mtp
+                    .zip(self.coin_start_time(&mut start_times, coin_height))
+                    .map(|(tip_mtp, start_time)| tip_mtp >= start_time + u32::from(intervals) * 512)
+                    .unwrap_or(false),
+                descriptors::RecoveryTimelock::Absolute(value) => {
+                    let locktime = LockTime::from_consensus(value);
+                    self.is_absolute_timelock_available(locktime)
+                        && required_locktime
+                            .map(|req| req.is_same_unit(locktime))
+                            .unwrap_or(true)
+                }
+            });
+            let timelock = match timelock {
+                Some(tl) => tl,
+                None => continue,
+            };
+            let sequence = match timelock {
+                descriptors::RecoveryTimelock::Relative(blocks) => {
+                    bitcoin::Sequence::from_height(blocks)
+                }
+                descriptors::RecoveryTimelock::RelativeTime(intervals) => {
+                    bitcoin::Sequence::from_512_second_intervals(intervals)
+                }
+                descriptors::RecoveryTimelock::Absolute(value) => {
+                    let locktime = LockTime::from_consensus(value);
+                    required_locktime = match required_locktime {
+                        Some(req) if locktime.is_implied_by(req) => Some(req),
+                        _ => Some(locktime),
+                    };
+                    // Despite its name, this nSequence enables the nLockTime of the transaction
+                    // (and RBF).
+                    bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME
+                }
+            };
+            // Each input must account for the satisfaction weight of its own spending path. A
+            // timelock which isn't part of the policy is accounted for with the maximum one.
+            let sat_weight = *sat_weights.entry(timelock).or_insert_with(|| {
+                main_descriptor
+                    .spending_path_max_sat_weight(descriptors::SpendingPath::Recovery(timelock))
+            });
+            sweepable_coins.push(CandidateCoin {
+                sat_weight,
+                ..coin_to_candidate(
+                    &coin,
+                    /*must_select=*/ true,
+                    /*sequence=*/ Some(sequence),
+                    /*ancestor_info=*/ None,
+                )
+            });
+        }
         if sweepable_coins.is_empty() {
             return Err(CommandError::RecoveryNotAvailable);
         }
 
+        // Use the anti fee-sniping locktime if it satisfies the absolute timelocks used, if any.
+        let locktime = match (self.anti_fee_sniping_locktime(), required_locktime) {
+            (lt, Some(req)) if !req.is_implied_by(lt) => req,
+            (lt, _) => lt,
+        };
         let sweep_addr_info = sweep_addr.info;
-        let locktime = self.anti_fee_sniping_locktime();
         let CreateSpendRes {
             psbt, has_change, ..
         } = create_spend(
-            &self.config.main_descriptor,
+            main_descriptor,
             &self.secp,
             &mut tx_getter,
             &[], // No destination, only the change address.
@@ -1108,12 +2480,114 @@ impl DaemonControl {
 
         Ok(CreateRecoveryResult { psbt })
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GetInfoDescriptors {
-    pub main: descriptors::LianaDescriptor,
-}
+    /// Create the transactions moving all our coins to the given new descriptor, using the
+    /// primary path.
+    ///
+    /// The coins are spent to the receive addresses of the new descriptor, starting at derivation
+    /// index 0, at most `max_inputs` of them per transaction (or
+    /// [`DEFAULT_MIGRATION_MAX_INPUTS`] if not set). Frozen coins, immature coins and coins
+    /// already spent by a stored Spend transaction are not migrated.
+    ///
+    /// The created transactions are stored as Spend transactions and labeled with
+    /// [`MIGRATION_LABEL`], they then need to be signed and broadcast. Once they are confirmed,
+    /// the daemon may be switched over to the new descriptor.
+    pub fn create_migration(
+        &self,
+        descriptor: &descriptors::LianaDescriptor,
+        feerate_vb: u64,
+        max_inputs: Option<usize>,
+    ) -> Result<CreateMigrationResult, CommandError> {
+        let network = self.config.bitcoin_config.network;
+        if descriptor == &self.config.main_descriptor {
+            return Err(CommandError::MigrationToSameDescriptor);
+        }
+        if !descriptor.all_xpubs_net_is(network) {
+            return Err(CommandError::MigrationDescriptorNetwork(network));
+        }
+        let max_inputs = max_inputs.unwrap_or(DEFAULT_MIGRATION_MAX_INPUTS);
+        if max_inputs < 1 {
+            return Err(CommandError::InvalidMaxInputs(max_inputs));
+        }
+        if feerate_vb < 1 {
+            return Err(CommandError::InvalidFeerate(feerate_vb));
+        }
+        let mut tx_getter = DbTxGetter::new(&self.db);
+        let mut db_conn = self.db.connection();
+
+        // Spend the coins in a deterministic order, such as the same transactions are created
+        // for the same set of coins.
+        let reserved = reserved_coins(&mut db_conn);
+        let mut coins: Vec<Coin> = db_conn
+            .coins(&[CoinStatus::Unconfirmed, CoinStatus::Confirmed], &[])
+            .into_values()
+            .filter(|c| !c.is_frozen && !c.is_immature && !reserved.contains_key(&c.outpoint))
+            .collect();
+        if coins.is_empty() {
+            return Err(CommandError::NoCoinToMigrate);
+        }
+        coins.sort_unstable_by_key(|c| c.outpoint);
+
+        let locktime = self.anti_fee_sniping_locktime();
+        let mut psbts = Vec::with_capacity(coins.len() / max_inputs + 1);
+        for (i, batch) in coins.chunks(max_inputs).enumerate() {
+            let index = bip32::ChildNumber::from_normal_idx(i as u32)
+                .map_err(|_| CommandError::InvalidDerivationIndex)?;
+            let addr = descriptor
+                .receive_descriptor()
+                .derive(index, &self.secp)
+                .address(network);
+            let candidates: Vec<CandidateCoin> = batch
+                .iter()
+                .map(|c| {
+                    // Unconfirmed coins may only be spent along with their ancestors' info.
+                    let ancestor_info = if c.block_info.is_none() {
+                        self.bitcoin
+                            .mempool_entry(&c.outpoint.txid)
+                            .map(AncestorInfo::from)
+                    } else {
+                        None
+                    };
+                    coin_to_candidate(
+                        c,
+                        /*must_select=*/ true,
+                        /*sequence=*/ None,
+                        ancestor_info,
+                    )
+                })
+                .collect();
+            let CreateSpendRes { psbt, .. } = create_spend(
+                &self.config.main_descriptor,
+                &self.secp,
+                &mut tx_getter,
+                &[], // No destination, only the address of the new descriptor as change.
+                &candidates,
+                SpendTxFees::Regular(feerate_vb),
+                SpendOutputAddress { addr, info: None },
+                locktime,
+            )?;
+            psbts.push(psbt);
+        }
+
+        let mut labels = HashMap::with_capacity(psbts.len());
+        let mut events = Vec::with_capacity(psbts.len());
+        for psbt in &psbts {
+            let txid = psbt.unsigned_tx.txid();
+            db_conn.store_spend(psbt);
+            labels.insert(LabelItem::Txid(txid), Some(MIGRATION_LABEL.to_string()));
+            events.push(Event::SpendUpdated { txid });
+        }
+        db_conn.update_labels(&labels);
+        self.record_events(&mut db_conn, &events);
+
+        Ok(CreateMigrationResult { psbts })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetInfoDescriptors {
+    pub main: descriptors::LianaDescriptor,
+}
 
 /// Information about the daemon
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1129,6 +2603,25 @@ pub struct GetInfoResult {
     pub timestamp: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DescribePolicyResult {
+    #[serde(flatten)]
+    pub policy: descriptors::PolicyDescription,
+    /// The maximum size of the satisfaction of an input spent through the primary path, in
+    /// weight units.
+    pub primary_path_max_sat_weight: usize,
+    /// The maximum size of the satisfaction of an input spent through any path, in weight units.
+    pub max_sat_weight: usize,
+    /// The description of the policy as plain text.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EstimateFeeResult {
+    /// The estimated feerate in sats/vb.
+    pub feerate: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetAddressResult {
     #[serde(deserialize_with = "deser_addr_assume_checked")]
@@ -1150,6 +2643,20 @@ pub struct GetLabelsResult {
     pub labels: HashMap<String, String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportLabelsResult {
+    /// The labels as BIP329 JSON Lines.
+    pub labels: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportLabelsResult {
+    /// The number of labels set.
+    pub imported: usize,
+    /// The number of records for items which can't be labelled.
+    pub skipped: usize,
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 pub struct AddressInfo {
     index: u32,
@@ -1197,6 +2704,10 @@ pub struct ListCoinsEntry {
     pub is_immature: bool,
     /// Whether the coin deposit address was derived from the change descriptor.
     pub is_change: bool,
+    /// Whether the coin was frozen by the user, excluding it from automatic coin selection.
+    pub is_frozen: bool,
+    /// Txids of the stored Spend transactions spending this unspent coin, if any.
+    pub reserved_by: Vec<bitcoin::Txid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1204,6 +2715,131 @@ pub struct ListCoinsResult {
     pub coins: Vec<ListCoinsEntry>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RecoveryPathExpiry {
+    /// The relative timelock of the recovery path, in blocks.
+    pub timelock: u16,
+    /// Number of blocks to be mined before the recovery path becomes available. Zero if it
+    /// is already available.
+    pub blocks_remaining: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeRecoveryPathExpiry {
+    /// The relative timelock of the recovery path, in number of 512 seconds intervals.
+    pub timelock: u16,
+    /// Number of seconds the median time past of the tip must advance before the recovery path
+    /// becomes available. Zero if it is already available.
+    pub seconds_remaining: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ListExpiringCoinsEntry {
+    #[serde(
+        serialize_with = "ser_amount",
+        deserialize_with = "deser_amount_from_sats"
+    )]
+    pub amount: bitcoin::Amount,
+    pub outpoint: bitcoin::OutPoint,
+    pub block_height: i32,
+    /// Availability of each recovery path for this coin, by increasing timelock.
+    pub recovery_paths: Vec<RecoveryPathExpiry>,
+    /// Availability of each recovery path behind a relative timelock expressed in time for this
+    /// coin, by increasing timelock.
+    #[serde(default)]
+    pub time_recovery_paths: Vec<TimeRecoveryPathExpiry>,
+}
+
+/// A set of coins becoming spendable through a recovery path at the same block height or time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RecoveryTimelineUnlock {
+    /// The height of the first block in which these coins can be spent, for timelocks expressed
+    /// in blocks.
+    pub height: Option<i32>,
+    /// The (estimated, for timelocks expressed in blocks) time from which these coins can be spent.
+    /// `None` if they are already available or if it can't be estimated.
+    pub time: Option<u32>,
+    /// Whether these coins can be spent through this recovery path in the next block.
+    pub available: bool,
+    #[serde(
+        serialize_with = "ser_amount",
+        deserialize_with = "deser_amount_from_sats"
+    )]
+    pub amount: bitcoin::Amount,
+    pub coins: Vec<bitcoin::OutPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecoveryTimelinePath {
+    pub timelock: descriptors::RecoveryTimelock,
+    #[serde(flatten)]
+    pub path: descriptors::PathDescription,
+    /// The total amount of our confirmed coins.
+    #[serde(
+        serialize_with = "ser_amount",
+        deserialize_with = "deser_amount_from_sats"
+    )]
+    pub total_amount: bitcoin::Amount,
+    /// The coins grouped by the height or time from which they can be spent, those already
+    /// available first.
+    pub unlocks: Vec<RecoveryTimelineUnlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetRecoveryTimelineResult {
+    pub tip_height: i32,
+    /// The recovery paths, in the same order as in the description of the policy.
+    pub recovery_paths: Vec<RecoveryTimelinePath>,
+}
+
+impl ListExpiringCoinsEntry {
+    /// Number of blocks remaining until the first recovery path becomes available. For the
+    /// recovery paths behind a relative timelock expressed in time, this is estimated assuming a
+    /// block every 10 minutes. `None` if there is no relative recovery path for this coin.
+    pub fn blocks_remaining(&self) -> Option<u32> {
+        let blocks_remaining = self
+            .recovery_paths
+            .first()
+            .map(|path| path.blocks_remaining);
+        let estimate = self.time_recovery_paths.first().map(|path| {
+            let blocks = path.seconds_remaining / TARGET_BLOCK_INTERVAL_SECS;
+            if path.seconds_remaining % TARGET_BLOCK_INTERVAL_SECS > 0 {
+                blocks + 1
+            } else {
+                blocks
+            }
+        });
+        blocks_remaining.into_iter().chain(estimate).min()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListExpiringCoinsResult {
+    pub coins: Vec<ListExpiringCoinsEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WaitForEventsResult {
+    pub events: Vec<JournalEntry>,
+    /// The cursor to pass to get the events following these ones.
+    pub cursor: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RefreshCoinsResult {
+    /// One entry per refresh transaction, in the same format as for `createspend`.
+    pub spends: Vec<CreateSpendResult>,
+}
+
+/// What [`DaemonControl::auto_refresh_coins`] remembers from one call to the next.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoRefreshState {
+    // The unspent coins we already created a refresh transaction for.
+    refreshed: HashSet<bitcoin::OutPoint>,
+    // The sets of coins we couldn't afford to refresh at the last call.
+    unaffordable: HashSet<Vec<bitcoin::OutPoint>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum CreateSpendResult {
@@ -1242,16 +2878,119 @@ pub struct TransactionInfo {
     pub time: Option<u32>,
 }
 
+/// The direction of the funds moved by a transaction, from the point of view of the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryDirection {
+    /// The transaction increases our balance.
+    Incoming,
+    /// The transaction decreases our balance.
+    Outgoing,
+    /// The transaction only pays to ourselves. Our balance decreases by the fee.
+    SelfTransfer,
+}
+
+impl fmt::Display for HistoryDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Incoming => write!(f, "incoming"),
+            Self::Outgoing => write!(f, "outgoing"),
+            Self::SelfTransfer => write!(f, "self_transfer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub txid: bitcoin::Txid,
+    pub height: Option<i32>,
+    pub time: Option<u32>,
+    pub direction: HistoryDirection,
+    /// The change in our balance, fees included.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub net_amount: bitcoin::SignedAmount,
+    /// The fee paid by the transaction, if all its inputs are ours.
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub fee: Option<bitcoin::Amount>,
+    /// Our balance after this transaction.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub balance: bitcoin::SignedAmount,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportHistoryResult {
+    pub transactions: Vec<HistoryEntry>,
+}
+
+impl ExportHistoryResult {
+    /// The history as CSV, with a header line. Amounts are in satoshis and times are UNIX
+    /// timestamps.
+    pub fn to_csv(&self) -> String {
+        // Prefix a field with a single quote if it could be interpreted as a formula by a
+        // spreadsheet, and quote it if it contains a separator, a quote or a line break.
+        fn escape(field: &str) -> String {
+            let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                format!("'{}", field)
+            } else {
+                field.to_string()
+            };
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        }
+        fn opt<T: ToString>(field: Option<T>) -> String {
+            field.map(|f| f.to_string()).unwrap_or_default()
+        }
+
+        let mut csv = "txid,height,time,direction,net_amount,fee,balance,label\n".to_string();
+        for entry in &self.transactions {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{}\n",
+                entry.txid,
+                opt(entry.height),
+                opt(entry.time),
+                entry.direction,
+                entry.net_amount.to_sat(),
+                opt(entry.fee.map(|f| f.to_sat())),
+                entry.balance.to_sat(),
+                escape(entry.label.as_deref().unwrap_or_default()),
+            );
+        }
+        csv
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CreateRecoveryResult {
     #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
     pub psbt: Psbt,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CreateMigrationResult {
+    /// The migration transactions, in the order of the derivation index of their output.
+    #[serde(
+        serialize_with = "ser_vec_to_string",
+        deserialize_with = "deser_vec_fromstr"
+    )]
+    pub psbts: Vec<Psbt>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{bitcoin::Block, database::BlockInfo, spend::InsaneFeeInfo, testutils::*};
+    use crate::{
+        backup::RestoreError,
+        bitcoin::{Block, MempoolEntry, MempoolEntryFees},
+        config::RefreshPolicy,
+        database::BlockInfo,
+        descriptors::LianaDescriptor,
+        spend::InsaneFeeInfo,
+        testutils::*,
+    };
 
     use bitcoin::{
         bip32::{self, ChildNumber},
@@ -1259,7 +2998,7 @@ mod tests {
         locktime::absolute,
         Amount, OutPoint, ScriptBuf, Sequence, Transaction, Txid, Witness,
     };
-    use std::{collections::BTreeMap, str::FromStr};
+    use std::{collections::BTreeMap, str::FromStr, thread};
 
     #[test]
     fn getinfo() {
@@ -1269,6 +3008,74 @@ mod tests {
         ms.shutdown();
     }
 
+    #[test]
+    fn describe_policy() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let desc = &control.config.main_descriptor;
+
+        let res = control.describe_policy();
+        let primary_keys = match desc.policy().primary_path() {
+            descriptors::PathInfo::Single(key) => vec![key.clone()],
+            descriptors::PathInfo::Multi(_, keys) => keys.clone(),
+        };
+        assert_eq!(res.policy.primary_path.threshold, 1);
+        assert_eq!(
+            res.policy
+                .primary_path
+                .keys
+                .iter()
+                .map(|k| k.key.clone())
+                .collect::<Vec<_>>(),
+            primary_keys
+        );
+        assert_eq!(res.policy.recovery_paths.len(), 1);
+        let recov = &res.policy.recovery_paths[0];
+        assert_eq!(
+            recov.timelock,
+            descriptors::RecoveryTimelock::Relative(desc.first_timelock_value().unwrap())
+        );
+        assert_eq!(res.primary_path_max_sat_weight, desc.max_sat_weight(true));
+        assert_eq!(res.max_sat_weight, desc.max_sat_weight(false));
+        assert!(res
+            .text
+            .starts_with("Primary path: the following key can spend at any time.\n"));
+        assert!(res
+            .text
+            .contains("Recovery path #1: the following key can spend coins inactive for"));
+
+        // The JSON representation flattens the policy description.
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["recovery_paths"][0]["timelock"]["type"], "relative");
+        assert_eq!(json["recovery_paths"][0]["threshold"], 1);
+        assert!(json["primary_path"]["keys"][0]["fingerprint"].is_string());
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn estimate_fee() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+
+        // The confirmation target must be within bounds.
+        assert_eq!(
+            control.estimate_fee(0),
+            Err(CommandError::InvalidConfTarget(0))
+        );
+        assert_eq!(
+            control.estimate_fee(1009),
+            Err(CommandError::InvalidConfTarget(1009))
+        );
+
+        // Otherwise we get the estimate from the Bitcoin backend.
+        assert_eq!(control.estimate_fee(1).unwrap().feerate, 10);
+        assert_eq!(control.estimate_fee(6).unwrap().feerate, 2);
+        assert_eq!(control.estimate_fee(1008).unwrap().feerate, 2);
+
+        ms.shutdown();
+    }
+
     #[test]
     fn getnewaddress() {
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
@@ -1426,7 +3233,7 @@ mod tests {
         let dummy_value = 10_000;
         let mut destinations = <HashMap<bitcoin::Address<address::NetworkUnchecked>, u64>>::new();
         assert_eq!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Err(CommandError::NoOutpointForSelfSend)
         );
         destinations = [(dummy_addr.clone(), dummy_value)]
@@ -1435,18 +3242,18 @@ mod tests {
             .collect();
         // Insufficient funds for coin selection.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 0, None),
+            control.create_spend(&destinations, &[dummy_op], 0, None, false),
             Err(CommandError::InvalidFeerate(0))
         );
 
         // The coin doesn't exist. If we create a new unspent one at this outpoint with a much
         // higher value, we'll get a Spend transaction with a change output.
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
             Err(CommandError::UnknownOutpoint(dummy_op))
         );
         db_conn.new_unspent_coins(&[Coin {
@@ -1458,15 +3265,16 @@ mod tests {
             is_change: false,
             spend_txid: None,
             spend_block: None,
+            is_frozen: false,
         }]);
         // If we try to use coin selection, the unconfirmed non-change coin will not be used
         // as a candidate and so we get a coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             (psbt, warnings)
@@ -1496,7 +3304,7 @@ mod tests {
         // At 2sats/vb, it's twice that.
         assert_eq!(tx.output[1].value.to_sat(), 89_839);
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations, &[dummy_op], 2, None)
+            .create_spend(&destinations, &[dummy_op], 2, None, false)
             .unwrap()
         {
             psbt
@@ -1509,22 +3317,22 @@ mod tests {
         // A feerate of 555 won't trigger the sanity checks (they were previously not taking the
         // satisfaction size into account and overestimating the feerate).
         control
-            .create_spend(&destinations, &[dummy_op], 555, None)
+            .create_spend(&destinations, &[dummy_op], 555, None, false)
             .unwrap();
 
         // If we ask for a too high feerate, or a too large/too small output, it'll fail.
         assert!(matches!(
-            control.create_spend(&destinations, &[dummy_op], 10_000, None),
+            control.create_spend(&destinations, &[dummy_op], 10_000, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         *destinations.get_mut(&dummy_addr).unwrap() = 100_001;
         assert!(matches!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         *destinations.get_mut(&dummy_addr).unwrap() = 4_500;
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
             Err(CommandError::SpendCreation(
                 SpendCreationError::InvalidOutputValue(bitcoin::Amount::from_sat(4_500))
             ))
@@ -1536,7 +3344,7 @@ mod tests {
         let invalid_destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
             [(invalid_addr, dummy_value)].iter().cloned().collect();
         assert!(matches!(
-            control.create_spend(&invalid_destinations, &[dummy_op], 1, None),
+            control.create_spend(&invalid_destinations, &[dummy_op], 1, None, false),
             Err(CommandError::Address(
                 address::Error::NetworkValidation { .. }
             ))
@@ -1546,7 +3354,7 @@ mod tests {
         // won't create an output lower than 5k sats.
         *destinations.get_mut(&dummy_addr).unwrap() = 95_000;
         let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             (psbt, warnings)
@@ -1575,7 +3383,7 @@ mod tests {
         // Increase the target value by the change amount and the warning will disappear.
         *destinations.get_mut(&dummy_addr).unwrap() = 95_000 + 4_839;
         let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             (psbt, warnings)
@@ -1590,7 +3398,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 + 4_830 + /* fee for change output */ 43;
         let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             (psbt, warnings)
@@ -1605,7 +3413,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 + 4_839 + /* fee for change output */ 43 + 1;
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { missing: 1 }),
         );
 
@@ -1613,7 +3421,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             100_000 - /* fee without change */ 118 - /* extra fee for change output */ 43 - 1;
         let warnings = if let CreateSpendResult::Success { warnings, .. } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             warnings
@@ -1634,7 +3442,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 - /* fee without change */ 118 - /* extra fee for change output */ 43;
         let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             (psbt, warnings)
@@ -1650,7 +3458,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 - /* fee without change */ 118 - /* extra fee for change output */ 43 + 1;
         let warnings = if let CreateSpendResult::Success { warnings, .. } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, false)
             .unwrap()
         {
             warnings
@@ -1676,13 +3484,13 @@ mod tests {
             .unwrap(),
         )]);
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
             Err(CommandError::AlreadySpent(dummy_op))
         );
         // If we try to use coin selection, the spent coin will not be used as a candidate
         // and so we get a coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
 
@@ -1700,9 +3508,10 @@ mod tests {
             is_change: false,
             spend_txid: None,
             spend_block: None,
+            is_frozen: false,
         }]);
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op_dup], 1_001, None),
+            control.create_spend(&destinations, &[dummy_op_dup], 1_001, None, false),
             Err(CommandError::SpendCreation(SpendCreationError::InsaneFees(
                 InsaneFeeInfo::TooHighFeerate(1_001)
             )))
@@ -1722,18 +3531,19 @@ mod tests {
             is_change: true,
             spend_txid: None,
             spend_block: None,
+            is_frozen: false,
         };
         db_conn.new_unspent_coins(&[unconfirmed_coin]);
         // Coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // Set destination amount equal to value of confirmed coins.
         *destinations.get_mut(&dummy_addr).unwrap() = 80_000;
         // Coin selection error occurs due to insufficient funds to pay fee.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         let confirmed_op_2 = bitcoin::OutPoint {
@@ -1753,10 +3563,12 @@ mod tests {
             is_change: false,
             spend_txid: None,
             spend_block: None,
+            is_frozen: false,
         }]);
         // First, create a transaction using auto coin selection.
-        let psbt = if let CreateSpendResult::Success { psbt, .. } =
-            control.create_spend(&destinations, &[], 1, None).unwrap()
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(&destinations, &[], 1, None, false)
+            .unwrap()
         {
             psbt
         } else {
@@ -1781,7 +3593,13 @@ mod tests {
 
         // Create a second transaction using manual coin selection.
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations, &[confirmed_op_1, confirmed_op_2], 1, None)
+            .create_spend(
+                &destinations,
+                &[confirmed_op_1, confirmed_op_2],
+                1,
+                None,
+                false,
+            )
             .unwrap()
         {
             psbt
@@ -1814,7 +3632,7 @@ mod tests {
         unconfirmed_coin_2.is_immature = false; // (this is already the case)
         db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // 2. change and immature
@@ -1823,7 +3641,7 @@ mod tests {
         unconfirmed_coin_2.is_immature = true;
         db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // 3. not change and immature
@@ -1832,7 +3650,7 @@ mod tests {
         unconfirmed_coin_2.is_immature = true;
         db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, false),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
 
@@ -1847,90 +3665,1768 @@ mod tests {
             bitcoin::Network::Bitcoin,
         )
         .unwrap();
-        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(
-                &destinations,
-                &[confirmed_op_1, confirmed_op_2],
-                1,
-                Some(change_address.as_unchecked().clone()),
-            )
-            .unwrap()
-        {
-            psbt
-        } else {
-            panic!("expect successful spend creation")
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &destinations,
+                &[confirmed_op_1, confirmed_op_2],
+                1,
+                Some(change_address.as_unchecked().clone()),
+                false,
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx_manual = psbt.unsigned_tx;
+        // Now the outputs of each transaction are the same.
+        assert_eq!(tx_auto.output, tx_manual.output);
+        // Check again that inputs are still the same.
+        let mut auto_input = tx_auto.input;
+        let mut manual_input = tx_manual.input;
+        auto_input.sort();
+        manual_input.sort();
+        assert_eq!(auto_input, manual_input);
+
+        // Add a confirmed coin with a value near the dust limit and check that
+        // `InsufficientFunds` error is returned if feerate is too high.
+        let confirmed_op_3 = bitcoin::OutPoint {
+            txid: confirmed_op_2.txid,
+            vout: confirmed_op_2.vout + 10,
+        };
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: confirmed_op_3,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height: 174500,
+                time: 174500,
+            }),
+            amount: bitcoin::Amount::from_sat(5_250),
+            derivation_index: bip32::ChildNumber::from(56),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+        let empty_dest = &HashMap::<bitcoin::Address<address::NetworkUnchecked>, u64>::new();
+        assert!(matches!(
+            control.create_spend(empty_dest, &[confirmed_op_3], 5, None, false),
+            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        ));
+        // If we use a lower fee, the self-send will succeed.
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(empty_dest, &[confirmed_op_3], 1, None, false)
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx = psbt.unsigned_tx;
+        let tx_prev_outpoints = tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect::<Vec<OutPoint>>();
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx_prev_outpoints, vec![confirmed_op_3]);
+        assert_eq!(tx.output.len(), 1);
+
+        // Can't create a transaction that spends an immature coinbase deposit.
+        let imma_op = bitcoin::OutPoint::from_str(
+            "4753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: imma_op,
+            is_immature: true,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+        assert_eq!(
+            control.create_spend(&destinations, &[imma_op], 1_001, None, false),
+            Err(CommandError::ImmatureCoinbase(imma_op))
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn freeze_coins() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let dummy_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+
+        // We can't freeze a coin we don't know about.
+        assert_eq!(
+            control.freeze_coins(&[dummy_op]),
+            Err(CommandError::UnknownOutpoint(dummy_op))
+        );
+        assert_eq!(
+            control.unfreeze_coins(&[dummy_op]),
+            Err(CommandError::UnknownOutpoint(dummy_op))
+        );
+
+        // Add a confirmed coin. It can be selected automatically.
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: Some(BlockInfo { height: 1, time: 1 }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
+            [(dummy_addr, 10_000)].iter().cloned().collect();
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, false),
+            Ok(CreateSpendResult::Success { .. }),
+        ));
+        assert!(!control.list_coins(&[], &[]).coins[0].is_frozen);
+
+        // Once frozen, it's reported as such and isn't selected automatically anymore. But it can
+        // still be spent by selecting it explicitly.
+        control.freeze_coins(&[dummy_op]).unwrap();
+        assert!(control.list_coins(&[], &[]).coins[0].is_frozen);
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, false),
+            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        ));
+        assert!(matches!(
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
+            Ok(CreateSpendResult::Success { .. }),
+        ));
+
+        // Once unfrozen it can be selected automatically again.
+        control.unfreeze_coins(&[dummy_op]).unwrap();
+        assert!(!control.list_coins(&[], &[]).coins[0].is_frozen);
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, false),
+            Ok(CreateSpendResult::Success { .. }),
+        ));
+
+        // A spent coin can't be frozen.
+        db_conn.spend_coins(&[(
+            dummy_op,
+            bitcoin::Txid::from_str(
+                "ef78f79ba747813887747cf8582897a48f1a09f1ca04d2cd3d6fcfdcbb5e0797",
+            )
+            .unwrap(),
+        )]);
+        assert_eq!(
+            control.freeze_coins(&[dummy_op]),
+            Err(CommandError::AlreadySpent(dummy_op))
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn bip329_labels() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        let txid = bitcoin::Txid::from_str(
+            "ef78f79ba747813887747cf8582897a48f1a09f1ca04d2cd3d6fcfdcbb5e0797",
+        )
+        .unwrap();
+        let (op_a, op_b, op_unknown) = (
+            bitcoin::OutPoint::new(txid, 0),
+            bitcoin::OutPoint::new(txid, 1),
+            bitcoin::OutPoint::new(txid, 2),
+        );
+        let coin = |outpoint| Coin {
+            outpoint,
+            is_immature: false,
+            block_info: Some(BlockInfo { height: 1, time: 1 }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        db_conn.new_unspent_coins(&[coin(op_a), coin(op_b)]);
+        control.freeze_coins(&[op_b]).unwrap();
+        let addr = control.get_new_address().address;
+
+        // Nothing to export yet.
+        assert_eq!(control.export_labels().labels, "");
+
+        // Export labels on all kinds of items, with the wallet as origin. The spendable flag is
+        // set for our unspent coins.
+        let mut labels = HashMap::new();
+        labels.insert(LabelItem::Txid(txid), Some("tx".to_string()));
+        labels.insert(LabelItem::Address(addr.clone()), Some("addr".to_string()));
+        labels.insert(LabelItem::OutPoint(op_a), Some("coin a".to_string()));
+        labels.insert(LabelItem::OutPoint(op_b), Some("coin b".to_string()));
+        labels.insert(LabelItem::OutPoint(op_unknown), Some("unknown".to_string()));
+        control.update_labels(&labels);
+        let exported = control.export_labels().labels;
+        let records = bip329::from_jsonl(&exported).unwrap();
+        assert_eq!(records.len(), 5);
+        let origin = control.config.main_descriptor.keys_origin_descriptor();
+        for record in &records {
+            assert_eq!(record.origin.as_ref(), Some(&origin));
+            let item = record.item(bitcoin::Network::Bitcoin).unwrap().unwrap();
+            assert_eq!(labels[&item], record.label);
+            let spendable = match item {
+                LabelItem::OutPoint(op) if op == op_a => Some(true),
+                LabelItem::OutPoint(op) if op == op_b => Some(false),
+                _ => None,
+            };
+            assert_eq!(record.spendable, spendable, "{:?}", record);
+        }
+
+        // Import them on a fresh wallet along with records we can't use.
+        let ms_b = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control_b = &ms_b.control();
+        let mut db_conn_b = control_b.db().lock().unwrap().connection();
+        db_conn_b.new_unspent_coins(&[coin(op_a), coin(op_b)]);
+        control_b.freeze_coins(&[op_a]).unwrap();
+        let jsonl = format!(
+            "{}\n{}\n{}",
+            exported,
+            r#"{"type": "pubkey", "ref": "0283409659355b6d1cc3c32decd5d561abaac86c37a353b52895a5e6c196d6f448", "label": "Public Key"}"#,
+            r#"{"type": "input", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:0", "label": "Input"}"#,
+        );
+        let res = control_b.import_labels(&jsonl).unwrap();
+        assert_eq!((res.imported, res.skipped), (5, 2));
+        let items: HashSet<_> = labels.keys().cloned().collect();
+        assert_eq!(
+            control_b.get_labels(&items).labels,
+            control.get_labels(&items).labels
+        );
+        let coins = control_b.list_coins(&[], &[]).coins;
+        let frozen = |op| coins.iter().find(|c| c.outpoint == op).unwrap().is_frozen;
+        assert!(!frozen(op_a));
+        assert!(frozen(op_b));
+
+        // Invalid records are rejected, and so are labels too long for us.
+        assert!(matches!(
+            control_b.import_labels("{\"type\": \"tx\"}"),
+            Err(CommandError::Bip329(bip329::Bip329Error::InvalidRecord(
+                1,
+                _
+            )))
+        ));
+        assert!(matches!(
+            control_b.import_labels(r#"{"type": "tx", "ref": "aa", "label": "a"}"#),
+            Err(CommandError::Bip329(bip329::Bip329Error::InvalidReference(
+                bip329::LabelType::Tx,
+                _
+            )))
+        ));
+        let long_label = format!(
+            r#"{{"type": "tx", "ref": "{}", "label": "{}"}}"#,
+            txid,
+            "a".repeat(MAX_LABEL_LENGTH + 1)
+        );
+        assert_eq!(
+            control_b.import_labels(&long_label),
+            Err(CommandError::LabelTooLong(txid.to_string()))
+        );
+
+        ms.shutdown();
+        ms_b.shutdown();
+    }
+
+    #[test]
+    fn wallet_backup() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        let secp = bitcoin::secp256k1::Secp256k1::verification_only();
+
+        // Label an address and store a spend.
+        let addr = control.get_new_address().address;
+        let mut labels = HashMap::new();
+        labels.insert(LabelItem::Address(addr.clone()), Some("addr".to_string()));
+        control.update_labels(&labels);
+        db_conn.set_change_index(bip32::ChildNumber::from(7), &secp);
+        let psbt = Psbt::from_unsigned_tx(Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![TxIn {
+                previous_output: OutPoint::from_str(
+                    "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+                )
+                .unwrap(),
+                ..TxIn::default()
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(10_000),
+                script_pubkey: addr.script_pubkey(),
+            }],
+        })
+        .unwrap();
+        db_conn.store_spend(&psbt);
+
+        // Freeze one of two coins.
+        let coins: Vec<_> = (0..2)
+            .map(|vout| Coin {
+                outpoint: OutPoint::new(psbt.unsigned_tx.txid(), vout),
+                is_immature: false,
+                block_info: Some(BlockInfo { height: 1, time: 1 }),
+                amount: Amount::from_sat(10_000),
+                derivation_index: bip32::ChildNumber::from(0),
+                is_change: false,
+                spend_txid: None,
+                spend_block: None,
+                is_frozen: false,
+            })
+            .collect();
+        db_conn.new_unspent_coins(&coins);
+        db_conn.freeze_coins(&[coins[0].outpoint]);
+
+        // The backup contains all of it, and the key aliases if given.
+        let mut aliases = BTreeMap::new();
+        aliases.insert(
+            bip32::Fingerprint::from_str("8344c025").unwrap(),
+            "Alice".to_string(),
+        );
+        let backup = control.create_backup(Some(aliases));
+        assert_eq!(backup.version, BACKUP_VERSION);
+        assert_eq!(backup.main_descriptor, control.config.main_descriptor);
+        assert_eq!(backup.timestamp, db_conn.timestamp());
+        assert_eq!((backup.receive_index, backup.change_index), (1, 7));
+        assert_eq!(backup.labels.get(&addr.to_string()).unwrap(), "addr");
+        assert_eq!(backup.spend_txs, vec![psbt.clone()]);
+        assert_eq!(backup.frozen_coins, vec![coins[0].outpoint]);
+        assert_eq!(backup.key_aliases.as_ref().unwrap().len(), 1);
+        assert!(control.create_backup(None).key_aliases.is_none());
+
+        // It roundtrips through JSON.
+        let ser = serde_json::to_string(&backup).unwrap();
+        let backup: WalletBackup = serde_json::from_str(&ser).unwrap();
+        assert_eq!(backup, control.create_backup(backup.key_aliases.clone()));
+
+        // It can only be restored for the same network and descriptor, if we understand it.
+        let network = bitcoin::Network::Bitcoin;
+        backup.check(network, &backup.main_descriptor).unwrap();
+        assert_eq!(
+            backup.check(bitcoin::Network::Testnet, &backup.main_descriptor),
+            Err(RestoreError::NetworkMismatch {
+                backup: network,
+                config: bitcoin::Network::Testnet
+            })
+        );
+        let other_desc = LianaDescriptor::from_str("wsh(or_d(multi(1,[573fb35b/48'/1'/0'/2']tpubDFKp9T7WAYDcENSjoifkrpq1gMDF47KGJcJrpxzX23Qor8wuGbrEVs9utNq1MDS8E2WXJSBk1qoPQLpwyokW7DiUNPwFuxQkL7owNkLAb9W/<0;1>/*,[573fb35c/48'/1'/1'/2']tpubDFGezyzuHJPhdP3jHGW7v7Hwes4Hihqv5W2yyCmRY9VZJCRchETvxrMC8uECeJZdxQ14V4iD4DecoArkUSDwj8ogYE9WEv4MNZr12thNHCs/<0;1>/*),and_v(v:multi(2,[573fb35b/48'/1'/2'/2']tpubDDwxQauiaU964vPzt5Vd7jnDHEUtp2Vc34PaWpEXg5TQ3bRccxnc1MKKh88Hi7xiMeZo9Tm6fBcq4UGXqnDtGUniJLjqAD8SjQ8Eci3aSR7/<0;1>/*,[573fb35c/48'/1'/3'/2']tpubDE37XAVB5CQ1x85md3BQ5uHCoMwT5fgT8X13zzCUQ3x5o2jskYxKjj7Qcxt1Jpj4QB8tqspn2dooPCekRuQDYrDHov7J1ueUNu2wcvgRDxr/<0;1>/*),older(1000))))#fccaqlhh").unwrap();
+        assert_eq!(
+            backup.check(network, &other_desc),
+            Err(RestoreError::DescriptorMismatch)
+        );
+        let mut future_backup = backup.clone();
+        future_backup.version = BACKUP_VERSION + 1;
+        assert_eq!(
+            future_backup.check(network, &backup.main_descriptor),
+            Err(RestoreError::UnsupportedVersion(BACKUP_VERSION + 1))
+        );
+        let mut invalid_backup = backup.clone();
+        invalid_backup
+            .labels
+            .insert("not an item".to_string(), "label".to_string());
+        assert_eq!(
+            invalid_backup.check(network, &backup.main_descriptor),
+            Err(RestoreError::InvalidLabelItem("not an item".to_string()))
+        );
+
+        // Restoring it on a fresh database sets the indexes, labels and spends. The frozen coin is
+        // frozen again once it's found.
+        let db_b = DummyDatabase::new();
+        let mut db_conn_b = db_b.connection();
+        backup.restore_db(&mut db_conn_b, &secp).unwrap();
+        assert_eq!(db_conn_b.receive_index(), bip32::ChildNumber::from(1));
+        assert_eq!(db_conn_b.change_index(), bip32::ChildNumber::from(7));
+        assert_eq!(db_conn_b.list_labels(), db_conn.list_labels());
+        assert_eq!(db_conn_b.list_spend()[0].0, psbt);
+        db_conn_b.new_unspent_coins(&coins);
+        let coins_b = db_conn_b.coins_by_outpoints(&[coins[0].outpoint, coins[1].outpoint]);
+        assert!(coins_b[&coins[0].outpoint].is_frozen);
+        assert!(!coins_b[&coins[1].outpoint].is_frozen);
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn reserved_coins() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let dummy_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: Some(BlockInfo { height: 1, time: 1 }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+        assert!(control.list_coins(&[], &[]).coins[0].reserved_by.is_empty());
+
+        // Create a draft using the coin and store it. The coin is now reserved.
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
+            [(dummy_addr, 10_000)].iter().cloned().collect();
+        let psbt = match control.create_spend(&destinations, &[], 1, None, false) {
+            Ok(CreateSpendResult::Success { psbt, .. }) => psbt,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+        let txid = psbt.unsigned_tx.txid();
+        control.update_spend(psbt).unwrap();
+        assert_eq!(
+            control.list_coins(&[], &[]).coins[0].reserved_by,
+            vec![txid]
+        );
+
+        // Automatic coin selection won't use it anymore, unless we explicitly opt in. The coin
+        // can also still be selected manually.
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, false),
+            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        ));
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, true),
+            Ok(CreateSpendResult::Success { .. }),
+        ));
+        assert!(matches!(
+            control.create_spend(&destinations, &[dummy_op], 1, None, false),
+            Ok(CreateSpendResult::Success { .. }),
+        ));
+
+        // Deleting the draft releases the coin.
+        control.delete_spend(&txid);
+        assert!(control.list_coins(&[], &[]).coins[0].reserved_by.is_empty());
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, false),
+            Ok(CreateSpendResult::Success { .. }),
+        ));
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_cpfp() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let unconf_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
+        let conf_op = bitcoin::OutPoint::new(dummy_tx.txid(), 1);
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.create_cpfp(&[], 1),
+            Err(CommandError::NoOutpointForSelfSend)
+        );
+        assert_eq!(
+            control.create_cpfp(&[unconf_op], 0),
+            Err(CommandError::InvalidFeerate(0))
+        );
+        assert_eq!(
+            control.create_cpfp(&[unconf_op], 1),
+            Err(CommandError::UnknownOutpoint(unconf_op))
+        );
+
+        // Only unconfirmed coins can be bumped.
+        db_conn.new_unspent_coins(&[
+            Coin {
+                outpoint: unconf_op,
+                is_immature: false,
+                block_info: None,
+                amount: bitcoin::Amount::from_sat(100_000),
+                derivation_index: bip32::ChildNumber::from(13),
+                is_change: false,
+                spend_txid: None,
+                spend_block: None,
+                is_frozen: false,
+            },
+            Coin {
+                outpoint: conf_op,
+                is_immature: false,
+                block_info: Some(BlockInfo { height: 1, time: 1 }),
+                amount: bitcoin::Amount::from_sat(100_000),
+                derivation_index: bip32::ChildNumber::from(14),
+                is_change: false,
+                spend_txid: None,
+                spend_block: None,
+                is_frozen: false,
+            },
+        ]);
+        assert_eq!(
+            control.create_cpfp(&[unconf_op, conf_op], 1),
+            Err(CommandError::AlreadyConfirmed(conf_op))
+        );
+
+        // The parent transaction must be in mempool.
+        assert_eq!(
+            control.create_cpfp(&[unconf_op], 1),
+            Err(CommandError::NotInMempool(unconf_op))
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_cpfp_package_feerate() {
+        let parent_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let op = bitcoin::OutPoint::new(parent_tx.txid(), 0);
+        // The parent and its own unconfirmed ancestors weigh 300 vbytes and pay 1 sat/vb.
+        let (ancestor_vsize, ancestor_fee) = (300, 300);
+        let mut dummy_bitcoind = DummyBitcoind::new();
+        dummy_bitcoind.mempool_entries.insert(
+            op.txid,
+            MempoolEntry {
+                vsize: 150,
+                ancestor_vsize,
+                fees: MempoolEntryFees {
+                    base: Amount::from_sat(150),
+                    ancestor: Amount::from_sat(ancestor_fee),
+                    descendant: Amount::from_sat(150),
+                },
+            },
+        );
+        let ms = DummyLiana::new(dummy_bitcoind, DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[parent_tx]);
+        let coin_value = 100_000;
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: op,
+            is_immature: false,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(coin_value),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+
+        // The child spends the coin back to ourselves.
+        let feerate = 10;
+        let psbt = match control.create_cpfp(&[op], feerate).unwrap() {
+            CreateSpendResult::Success { psbt, .. } => psbt,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+        let tx = &psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.input[0].previous_output, op);
+        assert_eq!(tx.output.len(), 1);
+
+        // Its fee pays for the feerate deficit of the ancestors, such that the whole package has
+        // the target feerate. It doesn't overpay by more than one vbyte's worth of fee.
+        let child_fee = coin_value - tx.output[0].value.to_sat();
+        let child_vsize = control
+            .config
+            .main_descriptor
+            .unsigned_tx_max_vbytes(tx, true);
+        let package_fee = child_fee + ancestor_fee;
+        let package_vsize = child_vsize + ancestor_vsize;
+        assert!(child_fee > feerate * child_vsize);
+        assert!(package_fee >= feerate * package_vsize);
+        assert!(package_fee < feerate * (package_vsize + 1));
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn list_expiring_coins() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        assert!(control.list_expiring_coins(None).coins.is_empty());
+
+        // The dummy descriptor has a single recovery path with a 10_000 blocks timelock and the
+        // dummy chain tip is at height 100.
+        let old_op = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        let recent_op = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:1",
+        )
+        .unwrap();
+        let unconf_op = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:2",
+        )
+        .unwrap();
+        let coin = |outpoint, block_info| Coin {
+            outpoint,
+            is_immature: false,
+            block_info,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(
+                recent_op,
+                Some(BlockInfo {
+                    height: 90,
+                    time: 1,
+                }),
+            ),
+            coin(old_op, Some(BlockInfo { height: 1, time: 1 })),
+            coin(unconf_op, None),
+        ]);
+
+        // Unconfirmed coins aren't listed and the coin closest to expiry comes first.
+        let coins = control.list_expiring_coins(None).coins;
+        assert_eq!(coins.len(), 2);
+        assert_eq!(coins[0].outpoint, old_op);
+        assert_eq!(coins[0].block_height, 1);
+        assert_eq!(
+            coins[0].recovery_paths,
+            vec![RecoveryPathExpiry {
+                timelock: 10_000,
+                blocks_remaining: 9_900
+            }]
+        );
+        assert_eq!(coins[1].outpoint, recent_op);
+        assert_eq!(coins[1].blocks_remaining(), Some(9_989));
+
+        // Filter by the number of blocks until expiry.
+        let coins = control.list_expiring_coins(Some(9_900)).coins;
+        assert_eq!(coins.len(), 1);
+        assert_eq!(coins[0].outpoint, old_op);
+        assert!(control.list_expiring_coins(Some(9_899)).coins.is_empty());
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn refresh_coins() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ops: Vec<_> = (0..4)
+            .map(|vout| bitcoin::OutPoint::new(dummy_tx.txid(), vout))
+            .collect();
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.refresh_coins(10_000, 1, Some(0)),
+            Err(CommandError::InvalidMaxInputs(0))
+        );
+        assert_eq!(
+            control.refresh_coins(10_000, 0, None),
+            Err(CommandError::InvalidFeerate(0))
+        );
+        assert_eq!(
+            control.refresh_coins(10_000, 1, None).unwrap().spends,
+            vec![]
+        );
+
+        // The dummy descriptor has a single recovery path with a 10_000 blocks timelock and the
+        // dummy chain tip is at height 100. The last coin is frozen.
+        let coin = |outpoint, height| Coin {
+            outpoint,
+            is_immature: false,
+            block_info: Some(BlockInfo { height, time: 1 }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(ops[0], 90),
+            coin(ops[1], 2),
+            coin(ops[2], 1),
+            coin(ops[3], 1),
+        ]);
+        db_conn.freeze_coins(&[ops[3]]);
+
+        // Only the coins close enough to expiry are refreshed, those closest to expiry first.
+        let spent_ops = |res: RefreshCoinsResult| -> Vec<Vec<bitcoin::OutPoint>> {
+            res.spends
+                .into_iter()
+                .map(|spend| match spend {
+                    CreateSpendResult::Success { psbt, .. } => {
+                        assert_eq!(psbt.unsigned_tx.output.len(), 1);
+                        let mut ops: Vec<_> = psbt
+                            .unsigned_tx
+                            .input
+                            .iter()
+                            .map(|txin| txin.previous_output)
+                            .collect();
+                        ops.sort();
+                        ops
+                    }
+                    _ => panic!("expect successful spend creation"),
+                })
+                .collect()
+        };
+        assert_eq!(
+            control.refresh_coins(9_899, 1, None).unwrap().spends,
+            vec![]
+        );
+        assert_eq!(
+            spent_ops(control.refresh_coins(9_900, 1, None).unwrap()),
+            vec![vec![ops[2]]]
+        );
+        assert_eq!(
+            spent_ops(control.refresh_coins(9_901, 1, None).unwrap()),
+            vec![vec![ops[1], ops[2]]]
+        );
+
+        // The number of inputs per transaction can be limited. All the transactions send to the
+        // same new change address.
+        let change_index = db_conn.change_index();
+        let res = control.refresh_coins(10_000, 1, Some(2)).unwrap();
+        let spks: HashSet<_> = res
+            .spends
+            .iter()
+            .map(|spend| match spend {
+                CreateSpendResult::Success { psbt, .. } => {
+                    psbt.unsigned_tx.output[0].script_pubkey.clone()
+                }
+                _ => panic!("expect successful spend creation"),
+            })
+            .collect();
+        assert_eq!(spks.len(), 1);
+        assert_eq!(db_conn.change_index(), change_index.increment().unwrap());
+        assert_eq!(spent_ops(res), vec![vec![ops[1], ops[2]], vec![ops[0]]]);
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn auto_refresh_coins() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ops: Vec<_> = (0..3)
+            .map(|vout| bitcoin::OutPoint::new(dummy_tx.txid(), vout))
+            .collect();
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut control = ms.control().clone();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+        let mut state = AutoRefreshState::default();
+
+        // Without a refresh policy, nothing is ever refreshed.
+        assert_eq!(control.auto_refresh_coins(&mut state), Ok(vec![]));
+
+        // The dummy descriptor has a single recovery path with a 10_000 blocks timelock and the
+        // dummy chain tip is at height 100. Only the first two coins are within the margin.
+        control.config.refresh_policy = Some(RefreshPolicy {
+            margin_blocks: 9_901,
+            max_feerate: 5,
+            keychain: RefreshKeychain::Change,
+        });
+        let coin = |outpoint, height, amount| Coin {
+            outpoint,
+            is_immature: false,
+            block_info: Some(BlockInfo { height, time: 1 }),
+            amount: bitcoin::Amount::from_sat(amount),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(ops[0], 90, 100_000),
+            coin(ops[1], 2, 100_000),
+            coin(ops[2], 1, 100_000),
+        ]);
+
+        // A refresh transaction is created and stored for them.
+        let txids = control.auto_refresh_coins(&mut state).unwrap();
+        assert_eq!(txids.len(), 1);
+        let psbt = db_conn.spend_tx(&txids[0]).unwrap();
+        let mut spent: Vec<_> = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect();
+        spent.sort();
+        assert_eq!(spent, vec![ops[1], ops[2]]);
+
+        // They aren't refreshed twice, even if the user deletes the refresh transaction.
+        assert_eq!(control.auto_refresh_coins(&mut state), Ok(vec![]));
+        db_conn.delete_spend(&txids[0]);
+        assert_eq!(control.auto_refresh_coins(&mut state), Ok(vec![]));
+        assert!(db_conn.list_spend().is_empty());
+
+        // The coins we can't afford to refresh are remembered, to only warn about them once.
+        let mut state = AutoRefreshState::default();
+        db_conn.remove_coins(&[ops[1], ops[2]]);
+        db_conn.new_unspent_coins(&[coin(ops[1], 2, 500)]);
+        assert_eq!(control.auto_refresh_coins(&mut state), Ok(vec![]));
+        assert_eq!(
+            state.unaffordable,
+            vec![vec![ops[1]]].into_iter().collect::<HashSet<_>>()
+        );
+        assert!(state.refreshed.is_empty());
+
+        // When refreshing to the receive keychain, the receive index isn't used up by the
+        // transactions which couldn't be created.
+        control.config.refresh_policy = Some(RefreshPolicy {
+            margin_blocks: 9_901,
+            max_feerate: 5,
+            keychain: RefreshKeychain::Receive,
+        });
+        let receive_index = db_conn.receive_index();
+        assert_eq!(control.auto_refresh_coins(&mut state), Ok(vec![]));
+        assert_eq!(control.auto_refresh_coins(&mut state), Ok(vec![]));
+        assert_eq!(db_conn.receive_index(), receive_index);
+        // It is once one is.
+        db_conn.remove_coins(&[ops[1]]);
+        db_conn.new_unspent_coins(&[coin(ops[1], 2, 100_000)]);
+        let txids = control.auto_refresh_coins(&mut state).unwrap();
+        assert_eq!(txids.len(), 1);
+        let psbt = db_conn.spend_tx(&txids[0]).unwrap();
+        let receive_addr = control
+            .config
+            .main_descriptor
+            .receive_descriptor()
+            .derive(receive_index, &control.secp)
+            .address(control.config.bitcoin_config.network);
+        assert_eq!(
+            psbt.unsigned_tx.output[0].script_pubkey,
+            receive_addr.script_pubkey()
+        );
+        assert_eq!(db_conn.receive_index(), receive_index.increment().unwrap());
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_absolute_recovery() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let dummy_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut db_conn = ms.control().db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+        let addr = bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv")
+            .unwrap()
+            .assume_checked();
+
+        // The default descriptor has no recovery path behind an absolute timelock.
+        let control = ms.control();
+        assert_eq!(
+            control.create_absolute_recovery(addr.as_unchecked().clone(), 1, None),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // Use a descriptor with two of them: one available at the current height (100) and one
+        // not available yet.
+        let key = |s: &str| {
+            descriptors::PathInfo::Single(
+                miniscript::descriptor::DescriptorPublicKey::from_str(s).unwrap(),
+            )
+        };
+        let policy = descriptors::LianaPolicy::new_legacy(
+            key("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*"),
+            [(10_000, key("[aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_absolute_recovery_paths(
+            [
+                (100, key("[aabb0011]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*")),
+                (200, key("[aabb0022]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*")),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+        )
+        .unwrap();
+        let mut control = control.clone();
+        control.config.main_descriptor = LianaDescriptor::new(policy);
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.create_absolute_recovery(addr.as_unchecked().clone(), 0, None),
+            Err(CommandError::InvalidFeerate(0))
+        );
+        assert_eq!(
+            control.create_absolute_recovery(addr.as_unchecked().clone(), 1, Some(150)),
+            Err(CommandError::UnknownAbsoluteTimelock(150))
+        );
+
+        // No coin to sweep.
+        assert_eq!(
+            control.create_absolute_recovery(addr.as_unchecked().clone(), 1, None),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // The recovery path at height 100 is available, not the one at height 200.
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height: 10,
+                time: 1,
+            }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+        assert_eq!(
+            control.create_absolute_recovery(addr.as_unchecked().clone(), 1, Some(200)),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+        let psbt = control
+            .create_absolute_recovery(addr.as_unchecked().clone(), 1, None)
+            .unwrap()
+            .psbt;
+        let tx = &psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.input[0].previous_output, dummy_op);
+        assert!(tx.input[0].sequence.enables_absolute_lock_time());
+        assert!(!tx.input[0].sequence.is_relative_lock_time());
+        assert!(absolute::LockTime::from_consensus(100).is_implied_by(tx.lock_time));
+        assert_eq!(tx.output.len(), 1);
+        assert_eq!(tx.output[0].script_pubkey, addr.script_pubkey());
+        let spend_info = control
+            .config
+            .main_descriptor
+            .partial_spend_info(&psbt)
+            .unwrap();
+        assert!(spend_info.absolute_recovery_paths().contains_key(&100));
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_multi_path_recovery() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let dummy_txid = dummy_tx.txid();
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut db_conn = ms.control().db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+        let addr = bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv")
+            .unwrap()
+            .assume_checked();
+
+        // A descriptor with a recovery path after 50 blocks, one after 100 * 512 seconds and one
+        // from block height 90. The dummy backend's tip is at height 100.
+        let key = |s: &str| {
+            descriptors::PathInfo::Single(
+                miniscript::descriptor::DescriptorPublicKey::from_str(s).unwrap(),
+            )
+        };
+        let policy = descriptors::LianaPolicy::new_legacy(
+            key("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*"),
+            [(50, key("[aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_absolute_recovery_paths(
+            [(90, key("[aabb0011]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_time_recovery_paths(
+            [(100, key("[aabb0022]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        let mut control = ms.control().clone();
+        control.config.main_descriptor = LianaDescriptor::new(policy);
+        let fg = |s: &str| bip32::Fingerprint::from_str(s).unwrap();
+        let all_signers = [fg("aabbccdd"), fg("aabb0011"), fg("aabb0022")];
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.create_multi_path_recovery(addr.as_unchecked().clone(), 0, &all_signers),
+            Err(CommandError::InvalidFeerate(0))
+        );
+
+        // No coin to sweep.
+        assert_eq!(
+            control.create_multi_path_recovery(addr.as_unchecked().clone(), 1, &all_signers),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // A coin confirmed at height 10, for which all the recovery paths are available, one
+        // confirmed at height 30, for which the time one isn't yet, one confirmed at height 70
+        // for which only the absolute one is available and an unconfirmed one.
+        let coin = |vout: u32, height: Option<i32>| Coin {
+            outpoint: bitcoin::OutPoint::new(dummy_txid, vout),
+            is_immature: false,
+            block_info: height.map(|height| BlockInfo { height, time: 1 }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(0, Some(10)),
+            coin(1, Some(30)),
+            coin(2, Some(70)),
+            coin(3, None),
+        ]);
+        let recovery_psbt = |signers: &[bip32::Fingerprint]| {
+            control
+                .create_multi_path_recovery(addr.as_unchecked().clone(), 10, signers)
+                .map(|res| res.psbt)
+        };
+        let input_sequences = |psbt: &Psbt| {
+            psbt.unsigned_tx
+                .input
+                .iter()
+                .map(|txin| (txin.previous_output.vout, txin.sequence))
+                .collect::<HashMap<_, _>>()
+        };
+        let abs_seq = bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME;
+        let time_seq = bitcoin::Sequence::from_512_second_intervals(100);
+        let blocks_seq = bitcoin::Sequence::from_height(50);
+
+        // No recovery path can be used without signers.
+        assert_eq!(recovery_psbt(&[]), Err(CommandError::RecoveryNotAvailable));
+
+        // With all the signers, each coin is spent through the earliest path available for it.
+        let psbt = recovery_psbt(&all_signers).unwrap();
+        assert_eq!(
+            input_sequences(&psbt),
+            [(0, blocks_seq), (1, blocks_seq), (2, abs_seq)]
+                .iter()
+                .cloned()
+                .collect()
+        );
+        assert!(absolute::LockTime::from_consensus(90).is_implied_by(psbt.unsigned_tx.lock_time));
+        assert_eq!(psbt.unsigned_tx.output.len(), 1);
+        assert_eq!(
+            psbt.unsigned_tx.output[0].script_pubkey,
+            addr.script_pubkey()
+        );
+
+        // The fee accounts for the satisfaction weight of each input's spending path.
+        let desc = &control.config.main_descriptor;
+        let sat_weight = |tl| {
+            desc.spending_path_max_sat_weight(descriptors::SpendingPath::Recovery(tl))
+                .unwrap() as u64
+        };
+        let blocks_sat_weight = sat_weight(descriptors::RecoveryTimelock::Relative(50));
+        let abs_sat_weight = sat_weight(descriptors::RecoveryTimelock::Absolute(90));
+        assert_ne!(blocks_sat_weight, abs_sat_weight);
+        let tx_wu = psbt.unsigned_tx.weight().to_wu() + 2 * blocks_sat_weight + abs_sat_weight;
+        let fee = 300_000 - psbt.unsigned_tx.output[0].value.to_sat();
+        let min_fee = (tx_wu + 3) / 4 * 10;
+        assert!(fee >= min_fee && fee < min_fee + 10, "{} {}", fee, min_fee);
+
+        // Without the signer of the relative timelock in blocks, the time one is used for the
+        // oldest coin and the absolute one for the others.
+        let psbt = recovery_psbt(&[fg("aabb0011"), fg("aabb0022")]).unwrap();
+        assert_eq!(
+            input_sequences(&psbt),
+            [(0, time_seq), (1, abs_seq), (2, abs_seq)]
+                .iter()
+                .cloned()
+                .collect()
+        );
+
+        // Coins for which the paths usable by the signers aren't available are left out.
+        let psbt = recovery_psbt(&[fg("aabb0022")]).unwrap();
+        assert_eq!(
+            input_sequences(&psbt),
+            [(0, time_seq)].iter().cloned().collect()
+        );
+        let psbt = recovery_psbt(&[fg("aabbccdd")]).unwrap();
+        assert_eq!(
+            input_sequences(&psbt),
+            [(0, blocks_seq), (1, blocks_seq)].iter().cloned().collect()
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn get_recovery_timeline() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut db_conn = ms.control().db().lock().unwrap().connection();
+
+        // A descriptor with a recovery path after 50 blocks, one after 100 * 512 seconds and one
+        // from block height 150. The dummy backend's tip is at height 100.
+        let key = |s: &str| {
+            descriptors::PathInfo::Single(
+                miniscript::descriptor::DescriptorPublicKey::from_str(s).unwrap(),
+            )
+        };
+        let policy = descriptors::LianaPolicy::new_legacy(
+            key("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*"),
+            [(50, key("[aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_time_recovery_paths(
+            [(100, key("[aabb0011]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_absolute_recovery_paths(
+            [(150, key("[aabb0022]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        let mut control = ms.control().clone();
+        control.config.main_descriptor = LianaDescriptor::new(policy);
+
+        // Without coins, there is nothing to recover.
+        let timeline = control.get_recovery_timeline();
+        assert_eq!(timeline.tip_height, 100);
+        assert_eq!(
+            timeline
+                .recovery_paths
+                .iter()
+                .map(|p| (p.timelock, p.total_amount, p.unlocks.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    descriptors::RecoveryTimelock::Relative(50),
+                    bitcoin::Amount::from_sat(0),
+                    0
+                ),
+                (
+                    descriptors::RecoveryTimelock::RelativeTime(100),
+                    bitcoin::Amount::from_sat(0),
+                    0
+                ),
+                (
+                    descriptors::RecoveryTimelock::Absolute(150),
+                    bitcoin::Amount::from_sat(0),
+                    0
+                ),
+            ]
+        );
+
+        // One coin confirmed at height 10, two at height 60.
+        let coin = |vout: u32, height: i32| Coin {
+            outpoint: bitcoin::OutPoint::new(
+                bitcoin::Txid::from_str(
+                    "0c62a990d20d54429e70859292e82374ba6b1b951a3ab60f26bb65fee5724ff7",
+                )
+                .unwrap(),
+                vout,
+            ),
+            is_immature: false,
+            block_info: Some(BlockInfo { height, time: 1 }),
+            amount: bitcoin::Amount::from_sat(10_000 * (vout as u64 + 1)),
+            derivation_index: bip32::ChildNumber::from(vout),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        let coins = [coin(0, 10), coin(1, 60), coin(2, 60)];
+        db_conn.new_unspent_coins(&coins);
+        let timeline = control.get_recovery_timeline();
+        let paths = &timeline.recovery_paths;
+        assert_eq!(paths.len(), 3);
+        for path in paths {
+            assert_eq!(path.total_amount, bitcoin::Amount::from_sat(60_000));
+        }
+
+        // The first coin can be recovered after 50 blocks from the next block, the two others
+        // from block 110. The dummy backend doesn't give the tip time to estimate its date.
+        assert_eq!(
+            paths[0].unlocks,
+            vec![
+                RecoveryTimelineUnlock {
+                    height: Some(60),
+                    time: None,
+                    available: true,
+                    amount: bitcoin::Amount::from_sat(10_000),
+                    coins: vec![coins[0].outpoint],
+                },
+                RecoveryTimelineUnlock {
+                    height: Some(110),
+                    time: None,
+                    available: false,
+                    amount: bitcoin::Amount::from_sat(50_000),
+                    coins: vec![coins[1].outpoint, coins[2].outpoint],
+                },
+            ]
+        );
+
+        // The timelock in time of the first coin expired, the two others expire 100 * 512
+        // seconds after the median time past of block 59.
+        let genesis_time = control.bitcoin.genesis_block_timestamp();
+        assert_eq!(
+            paths[1].unlocks,
+            vec![
+                RecoveryTimelineUnlock {
+                    height: None,
+                    time: None,
+                    available: true,
+                    amount: bitcoin::Amount::from_sat(10_000),
+                    coins: vec![coins[0].outpoint],
+                },
+                RecoveryTimelineUnlock {
+                    height: None,
+                    time: Some(genesis_time + 59 * 600 + 100 * 512),
+                    available: false,
+                    amount: bitcoin::Amount::from_sat(50_000),
+                    coins: vec![coins[1].outpoint, coins[2].outpoint],
+                },
+            ]
+        );
+
+        // All coins can be recovered from block 151, the first one whose height is above the
+        // transaction's nLockTime.
+        assert_eq!(
+            paths[2].unlocks,
+            vec![RecoveryTimelineUnlock {
+                height: Some(151),
+                time: None,
+                available: false,
+                amount: bitcoin::Amount::from_sat(60_000),
+                coins: coins.iter().map(|c| c.outpoint).collect(),
+            }]
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_spend_through_path() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut db_conn = ms.control().db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+
+        // A descriptor with a recovery path after 50 blocks and one from block height 150, each
+        // with a key from a different signer. The dummy backend's tip is at height 100.
+        let key = |s: &str| {
+            descriptors::PathInfo::Single(
+                miniscript::descriptor::DescriptorPublicKey::from_str(s).unwrap(),
+            )
+        };
+        let policy = descriptors::LianaPolicy::new_legacy(
+            key("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*"),
+            [(50, key("[aabb0011]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_absolute_recovery_paths(
+            [(150, key("[aabb0022]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        let mut control = ms.control().clone();
+        control.config.main_descriptor = LianaDescriptor::new(policy);
+        let recovery_path =
+            descriptors::SpendingPath::Recovery(descriptors::RecoveryTimelock::Relative(50));
+
+        // A coin confirmed at height 10 and one at height 60. Only the first one can be spent
+        // through the recovery path in the next block.
+        let coin = |vout: u32, height: i32| Coin {
+            outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), vout),
+            is_immature: false,
+            block_info: Some(BlockInfo { height, time: 1 }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(vout),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        };
+        let (old_coin, recent_coin) = (coin(0, 10), coin(1, 60));
+        db_conn.new_unspent_coins(&[old_coin, recent_coin]);
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> = [(
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap(),
+            10_000,
+        )]
+        .iter()
+        .cloned()
+        .collect();
+        // The fingerprints of the keys the PSBT inputs have a BIP32 derivation for.
+        let fingerprints = |res: CreateSpendResult| match res {
+            CreateSpendResult::Success { psbt, .. } => (
+                psbt.unsigned_tx.input.clone(),
+                psbt.inputs
+                    .iter()
+                    .flat_map(|psbt_in| psbt_in.bip32_derivation.values().map(|(fg, _)| *fg))
+                    .collect::<HashSet<_>>(),
+            ),
+            CreateSpendResult::InsufficientFunds { .. } => panic!("Insufficient funds"),
+        };
+        let fg = |s: &str| bip32::Fingerprint::from_str(s).unwrap();
+
+        // The spending path must exist.
+        let unknown_path =
+            descriptors::SpendingPath::Recovery(descriptors::RecoveryTimelock::Relative(51));
+        assert_eq!(
+            control.create_spend_through_path(&destinations, &[], 1, None, false, unknown_path),
+            Err(CommandError::UnknownSpendingPath(unknown_path))
+        );
+
+        // Through the recovery path, only the old coin is selected. Its nSequence satisfies the
+        // timelock and only the derivation of the recovery key is kept.
+        let res = control
+            .create_spend_through_path(&destinations, &[], 1, None, false, recovery_path)
+            .unwrap();
+        let (inputs, fgs) = fingerprints(res);
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].previous_output, old_coin.outpoint);
+        assert_eq!(inputs[0].sequence, bitcoin::Sequence::from_height(50));
+        assert_eq!(fgs, [fg("aabb0011")].iter().cloned().collect());
+
+        // The recent coin can't be spent through it yet.
+        assert_eq!(
+            control.create_spend_through_path(
+                &destinations,
+                &[recent_coin.outpoint],
+                1,
+                None,
+                false,
+                recovery_path
+            ),
+            Err(CommandError::SpendingPathNotAvailable(recent_coin.outpoint))
+        );
+
+        // Neither is the recovery path behind the absolute timelock.
+        assert_eq!(
+            control.create_spend_through_path(
+                &destinations,
+                &[],
+                1,
+                None,
+                false,
+                descriptors::SpendingPath::Recovery(descriptors::RecoveryTimelock::Absolute(150))
+            ),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // Through the primary path any coin can be spent, and only the derivation of the primary
+        // key is kept. By default the derivations of all the keys are set.
+        let res = control
+            .create_spend_through_path(
+                &destinations,
+                &[recent_coin.outpoint],
+                1,
+                None,
+                false,
+                descriptors::SpendingPath::Primary,
+            )
+            .unwrap();
+        let (inputs, fgs) = fingerprints(res);
+        assert_eq!(
+            inputs[0].sequence,
+            bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME
+        );
+        assert_eq!(fgs, [fg("aabbccdd")].iter().cloned().collect());
+        let res = control
+            .create_spend(&destinations, &[recent_coin.outpoint], 1, None, false)
+            .unwrap();
+        assert_eq!(fingerprints(res).1.len(), 3);
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_recovery() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut db_conn = ms.control().db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+        let addr = bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv")
+            .unwrap()
+            .assume_checked();
+
+        // Use a descriptor with a recovery path after 50 blocks. The dummy backend's tip is at
+        // height 100.
+        let key = |s: &str| {
+            descriptors::PathInfo::Single(
+                miniscript::descriptor::DescriptorPublicKey::from_str(s).unwrap(),
+            )
+        };
+        let policy = descriptors::LianaPolicy::new_legacy(
+            key("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*"),
+            [(50, key("[aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        let mut control = ms.control().clone();
+        control.config.main_descriptor = LianaDescriptor::new(policy);
+
+        assert_eq!(
+            control.create_recovery(addr.as_unchecked().clone(), 0, None),
+            Err(CommandError::InvalidFeerate(0))
+        );
+        assert_eq!(
+            control.create_recovery(addr.as_unchecked().clone(), 1, None),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // The coin confirmed at height 50 can be swept at the next block, not the one confirmed
+        // at height 60.
+        let coins: Vec<_> = [50, 60]
+            .iter()
+            .enumerate()
+            .map(|(vout, height)| Coin {
+                outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), vout as u32),
+                is_immature: false,
+                block_info: Some(BlockInfo {
+                    height: *height,
+                    time: 1,
+                }),
+                amount: bitcoin::Amount::from_sat(100_000),
+                derivation_index: bip32::ChildNumber::from(13),
+                is_change: false,
+                spend_txid: None,
+                spend_block: None,
+                is_frozen: false,
+            })
+            .collect();
+        db_conn.new_unspent_coins(&coins);
+        for timelock in [None, Some(50)] {
+            let tx = control
+                .create_recovery(addr.as_unchecked().clone(), 1, timelock)
+                .unwrap()
+                .psbt
+                .unsigned_tx;
+            assert_eq!(tx.input.len(), 1);
+            assert_eq!(tx.input[0].previous_output, coins[0].outpoint);
+            assert_eq!(tx.input[0].sequence, bitcoin::Sequence::from_height(50));
+            assert_eq!(tx.output.len(), 1);
+            assert_eq!(tx.output[0].script_pubkey, addr.script_pubkey());
+        }
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_time_recovery() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
         };
-        let tx_manual = psbt.unsigned_tx;
-        // Now the outputs of each transaction are the same.
-        assert_eq!(tx_auto.output, tx_manual.output);
-        // Check again that inputs are still the same.
-        let mut auto_input = tx_auto.input;
-        let mut manual_input = tx_manual.input;
-        auto_input.sort();
-        manual_input.sort();
-        assert_eq!(auto_input, manual_input);
+        let dummy_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut db_conn = ms.control().db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+        let addr = bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv")
+            .unwrap()
+            .assume_checked();
 
-        // Add a confirmed coin with a value near the dust limit and check that
-        // `InsufficientFunds` error is returned if feerate is too high.
-        let confirmed_op_3 = bitcoin::OutPoint {
-            txid: confirmed_op_2.txid,
-            vout: confirmed_op_2.vout + 10,
+        // The default descriptor has no recovery path behind a relative timelock in time.
+        let control = ms.control();
+        assert_eq!(
+            control.create_time_recovery(addr.as_unchecked().clone(), 1, None),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // Use a descriptor with two of them. The dummy backend mines a block every 10 minutes
+        // and its tip is at height 100.
+        let key = |s: &str| {
+            descriptors::PathInfo::Single(
+                miniscript::descriptor::DescriptorPublicKey::from_str(s).unwrap(),
+            )
         };
+        let policy = descriptors::LianaPolicy::new_legacy(
+            key("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*"),
+            [(10_000, key("[aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*"))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_time_recovery_paths(
+            [
+                (100, key("[aabb0011]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*")),
+                (200, key("[aabb0022]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*")),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+        )
+        .unwrap();
+        let mut control = control.clone();
+        control.config.main_descriptor = LianaDescriptor::new(policy);
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.create_time_recovery(addr.as_unchecked().clone(), 0, None),
+            Err(CommandError::InvalidFeerate(0))
+        );
+        assert_eq!(
+            control.create_time_recovery(addr.as_unchecked().clone(), 1, Some(150)),
+            Err(CommandError::UnknownTimeTimelock(150))
+        );
+
+        // No coin to sweep.
+        assert_eq!(
+            control.create_time_recovery(addr.as_unchecked().clone(), 1, None),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+
+        // A coin confirmed at height 10. Its timelocks start at the median time past of block 9,
+        // which is 91 blocks (54_600 seconds) before the tip's. The recovery path after 100 * 512
+        // seconds is available for it, not the one after 200 * 512 seconds.
         db_conn.new_unspent_coins(&[Coin {
-            outpoint: confirmed_op_3,
+            outpoint: dummy_op,
             is_immature: false,
             block_info: Some(BlockInfo {
-                height: 174500,
-                time: 174500,
+                height: 10,
+                time: 1,
             }),
-            amount: bitcoin::Amount::from_sat(5_250),
-            derivation_index: bip32::ChildNumber::from(56),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
             is_change: false,
             spend_txid: None,
             spend_block: None,
+            is_frozen: false,
         }]);
-        let empty_dest = &HashMap::<bitcoin::Address<address::NetworkUnchecked>, u64>::new();
-        assert!(matches!(
-            control.create_spend(empty_dest, &[confirmed_op_3], 5, None),
-            Ok(CreateSpendResult::InsufficientFunds { .. }),
-        ));
-        // If we use a lower fee, the self-send will succeed.
-        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(empty_dest, &[confirmed_op_3], 1, None)
+        let expiring = control.list_expiring_coins(None).coins;
+        assert_eq!(expiring.len(), 1);
+        assert_eq!(
+            expiring[0].time_recovery_paths,
+            vec![
+                TimeRecoveryPathExpiry {
+                    timelock: 100,
+                    seconds_remaining: 0,
+                },
+                TimeRecoveryPathExpiry {
+                    timelock: 200,
+                    seconds_remaining: 200 * 512 - 54_600,
+                },
+            ]
+        );
+        // The time-based recovery path is available before the one in blocks.
+        assert_eq!(expiring[0].blocks_remaining(), Some(0));
+        assert_eq!(
+            control.create_time_recovery(addr.as_unchecked().clone(), 1, Some(200)),
+            Err(CommandError::RecoveryNotAvailable)
+        );
+        let psbt = control
+            .create_time_recovery(addr.as_unchecked().clone(), 1, None)
             .unwrap()
-        {
-            psbt
-        } else {
-            panic!("expect successful spend creation")
-        };
-        let tx = psbt.unsigned_tx;
-        let tx_prev_outpoints = tx
-            .input
-            .iter()
-            .map(|txin| txin.previous_output)
-            .collect::<Vec<OutPoint>>();
+            .psbt;
+        let tx = &psbt.unsigned_tx;
         assert_eq!(tx.input.len(), 1);
-        assert_eq!(tx_prev_outpoints, vec![confirmed_op_3]);
+        assert_eq!(tx.input[0].previous_output, dummy_op);
+        assert_eq!(
+            tx.input[0].sequence,
+            Sequence::from_512_second_intervals(100)
+        );
         assert_eq!(tx.output.len(), 1);
+        assert_eq!(tx.output[0].script_pubkey, addr.script_pubkey());
+        let spend_info = control
+            .config
+            .main_descriptor
+            .partial_spend_info(&psbt)
+            .unwrap();
+        assert!(spend_info.time_recovery_paths().contains_key(&100));
+        assert!(!spend_info.time_recovery_paths().contains_key(&200));
 
-        // Can't create a transaction that spends an immature coinbase deposit.
-        let imma_op = bitcoin::OutPoint::from_str(
-            "4753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
-        )
-        .unwrap();
-        db_conn.new_unspent_coins(&[Coin {
-            outpoint: imma_op,
-            is_immature: true,
-            block_info: None,
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_migration() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ops: Vec<_> = (0..4)
+            .map(|vout| bitcoin::OutPoint::new(dummy_tx.txid(), vout))
+            .collect();
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[dummy_tx]);
+        let new_desc = LianaDescriptor::from_str("wsh(or_d(pk([aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*),and_v(v:pkh([aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*),older(20000))))").unwrap();
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.create_migration(&control.config.main_descriptor, 1, None),
+            Err(CommandError::MigrationToSameDescriptor)
+        );
+        let testnet_desc = LianaDescriptor::from_str("wsh(andor(pk([aabbccdd]tpubDExU4YLJkyQ9RRbVScQq2brFxWWha7WmAUByPWyaWYwmcTv3Shx8aHp6mVwuE5n4TeM4z5DTWGf2YhNPmXtfvyr8cUDVvA3txdrFnFgNdF7/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
+        assert_eq!(
+            control.create_migration(&testnet_desc, 1, None),
+            Err(CommandError::MigrationDescriptorNetwork(
+                bitcoin::Network::Bitcoin
+            ))
+        );
+        assert_eq!(
+            control.create_migration(&new_desc, 1, Some(0)),
+            Err(CommandError::InvalidMaxInputs(0))
+        );
+        assert_eq!(
+            control.create_migration(&new_desc, 0, None),
+            Err(CommandError::InvalidFeerate(0))
+        );
+        assert_eq!(
+            control.create_migration(&new_desc, 1, None),
+            Err(CommandError::NoCoinToMigrate)
+        );
+
+        // The last coin is frozen, it isn't migrated.
+        let coin = |outpoint| Coin {
+            outpoint,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height: 10,
+                time: 1,
+            }),
             amount: bitcoin::Amount::from_sat(100_000),
             derivation_index: bip32::ChildNumber::from(13),
             is_change: false,
             spend_txid: None,
             spend_block: None,
-        }]);
+            is_frozen: false,
+        };
+        db_conn.new_unspent_coins(&[coin(ops[2]), coin(ops[0]), coin(ops[1]), coin(ops[3])]);
+        db_conn.freeze_coins(&[ops[3]]);
+
+        // The coins are sent to the receive addresses of the new descriptor, with a limited
+        // number of inputs per transaction.
+        let psbts = control
+            .create_migration(&new_desc, 1, Some(2))
+            .unwrap()
+            .psbts;
+        assert_eq!(psbts.len(), 2);
+        for (i, (psbt, spent_ops)) in psbts
+            .iter()
+            .zip(vec![vec![ops[0], ops[1]], vec![ops[2]]])
+            .enumerate()
+        {
+            let mut inputs: Vec<_> = psbt
+                .unsigned_tx
+                .input
+                .iter()
+                .map(|txin| txin.previous_output)
+                .collect();
+            inputs.sort();
+            assert_eq!(inputs, spent_ops);
+            assert_eq!(psbt.unsigned_tx.output.len(), 1);
+            let addr = new_desc
+                .receive_descriptor()
+                .derive(bip32::ChildNumber::from(i as u32), &control.secp)
+                .address(bitcoin::Network::Bitcoin);
+            assert_eq!(
+                psbt.unsigned_tx.output[0].script_pubkey,
+                addr.script_pubkey()
+            );
+        }
+
+        // The transactions are stored and labeled, so the coins aren't migrated twice.
+        let txids: HashSet<_> = psbts.iter().map(|psbt| psbt.unsigned_tx.txid()).collect();
+        let stored: HashSet<_> = db_conn
+            .list_spend()
+            .into_iter()
+            .map(|(psbt, _)| psbt.unsigned_tx.txid())
+            .collect();
+        assert_eq!(stored, txids);
+        let items: HashSet<_> = txids.iter().map(|txid| LabelItem::Txid(*txid)).collect();
+        let labels = db_conn.labels(&items);
+        assert_eq!(labels.len(), 2);
+        assert!(labels.values().all(|l| l == MIGRATION_LABEL));
         assert_eq!(
-            control.create_spend(&destinations, &[imma_op], 1_001, None),
-            Err(CommandError::ImmatureCoinbase(imma_op))
+            control.create_migration(&new_desc, 1, None),
+            Err(CommandError::NoCoinToMigrate)
         );
 
         ms.shutdown();
@@ -1972,6 +5468,7 @@ mod tests {
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             },
             Coin {
                 outpoint: dummy_op_b,
@@ -1982,6 +5479,7 @@ mod tests {
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             },
         ]);
 
@@ -2008,7 +5506,7 @@ mod tests {
                 .cloned()
                 .collect();
         let mut psbt_a = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_a, &[dummy_op_a], 1, None)
+            .create_spend(&destinations_a, &[dummy_op_a], 1, None, false)
             .unwrap()
         {
             psbt
@@ -2017,7 +5515,7 @@ mod tests {
         };
         let txid_a = psbt_a.unsigned_tx.txid();
         let psbt_b = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_b, &[dummy_op_b], 10, None)
+            .create_spend(&destinations_b, &[dummy_op_b], 10, None, false)
             .unwrap()
         {
             psbt
@@ -2026,7 +5524,7 @@ mod tests {
         };
         let txid_b = psbt_b.unsigned_tx.txid();
         let psbt_c = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_c, &[dummy_op_a, dummy_op_b], 100, None)
+            .create_spend(&destinations_c, &[dummy_op_a, dummy_op_b], 100, None, false)
             .unwrap()
         {
             psbt
@@ -2129,6 +5627,7 @@ mod tests {
                 height: 184500,
                 time: 184500,
             }),
+            is_frozen: false,
         }]);
         // The coin is spent so we cannot RBF.
         assert_eq!(
@@ -2240,6 +5739,7 @@ mod tests {
                 derivation_index: ChildNumber::from(0),
                 amount: bitcoin::Amount::from_sat(100_000_000),
                 spend_txid: Some(spend_tx.txid()),
+                is_frozen: false,
             },
             // Deposit 2
             Coin {
@@ -2254,6 +5754,7 @@ mod tests {
                 derivation_index: ChildNumber::from(1),
                 amount: bitcoin::Amount::from_sat(2000),
                 spend_txid: None,
+                is_frozen: false,
             },
             // This coin is a change output.
             Coin {
@@ -2265,6 +5766,7 @@ mod tests {
                 derivation_index: ChildNumber::from(2),
                 amount: bitcoin::Amount::from_sat(100_000_000 - 4000 - 1000),
                 spend_txid: None,
+                is_frozen: false,
             },
             // Deposit 3
             Coin {
@@ -2279,6 +5781,7 @@ mod tests {
                 derivation_index: ChildNumber::from(3),
                 amount: bitcoin::Amount::from_sat(3000),
                 spend_txid: None,
+                is_frozen: false,
             },
         ]);
 
@@ -2380,6 +5883,179 @@ mod tests {
         ms.shutdown();
     }
 
+    #[test]
+    fn export_history() {
+        let tx = |prev: OutPoint, values: &[u64]| Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: prev,
+                ..TxIn::default()
+            }],
+            output: values
+                .iter()
+                .map(|v| TxOut {
+                    script_pubkey: ScriptBuf::new(),
+                    value: Amount::from_sat(*v),
+                })
+                .collect(),
+        };
+        let coin = |outpoint, amount, height, spend: Option<(Txid, i32)>| Coin {
+            outpoint,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height,
+                time: height as u32,
+            }),
+            amount: Amount::from_sat(amount),
+            derivation_index: ChildNumber::from(0),
+            is_change: false,
+            spend_txid: spend.map(|(txid, _)| txid),
+            spend_block: spend.map(|(_, height)| BlockInfo {
+                height,
+                time: height as u32,
+            }),
+            is_frozen: false,
+        };
+
+        // A deposit, spent in block 2 to pay someone. The change is spent in the same block by a
+        // self transfer. Then another deposit in block 3.
+        let external_op = OutPoint::from_str(
+            "617eab1fc0b03ee7f82ba70166725291783461f1a0e7975eaf8b5f8f674234f3:0",
+        )
+        .unwrap();
+        let deposit_a = tx(external_op, &[100_000]);
+        let payment = tx(OutPoint::new(deposit_a.txid(), 0), &[40_000, 59_000]);
+        let self_transfer = tx(OutPoint::new(payment.txid(), 1), &[58_500]);
+        let deposit_b = tx(OutPoint::new(external_op.txid, 1), &[20_000, 1_000]);
+        let mut db = DummyDatabase::new();
+        db.insert_coins(vec![
+            coin(
+                OutPoint::new(deposit_a.txid(), 0),
+                100_000,
+                1,
+                Some((payment.txid(), 2)),
+            ),
+            coin(
+                OutPoint::new(payment.txid(), 1),
+                59_000,
+                2,
+                Some((self_transfer.txid(), 2)),
+            ),
+            coin(OutPoint::new(self_transfer.txid(), 0), 58_500, 2, None),
+            coin(OutPoint::new(deposit_b.txid(), 0), 20_000, 3, None),
+        ]);
+        let ms = DummyLiana::new(DummyBitcoind::new(), db);
+        let control = &ms.control();
+        let mut db_conn = control.db.connection();
+        db_conn.new_txs(&[
+            self_transfer.clone(),
+            deposit_b.clone(),
+            payment.clone(),
+            deposit_a.clone(),
+        ]);
+        let mut labels = HashMap::new();
+        labels.insert(
+            LabelItem::Txid(deposit_b.txid()),
+            Some("salary, \"march\"".to_string()),
+        );
+        control.update_labels(&labels);
+
+        // The whole history, in order, with the running balance.
+        let history = control.export_history(0, u32::MAX).transactions;
+        let summary: Vec<_> = history
+            .iter()
+            .map(|e| {
+                (
+                    e.txid,
+                    e.height,
+                    e.direction,
+                    e.net_amount.to_sat(),
+                    e.fee.map(|f| f.to_sat()),
+                    e.balance.to_sat(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    deposit_a.txid(),
+                    Some(1),
+                    HistoryDirection::Incoming,
+                    100_000,
+                    None,
+                    100_000
+                ),
+                (
+                    payment.txid(),
+                    Some(2),
+                    HistoryDirection::Outgoing,
+                    -41_000,
+                    Some(1_000),
+                    59_000
+                ),
+                (
+                    self_transfer.txid(),
+                    Some(2),
+                    HistoryDirection::SelfTransfer,
+                    -500,
+                    Some(500),
+                    58_500
+                ),
+                (
+                    deposit_b.txid(),
+                    Some(3),
+                    HistoryDirection::Incoming,
+                    20_000,
+                    None,
+                    78_500
+                ),
+            ]
+        );
+        assert_eq!(history[3].label.as_deref(), Some("salary, \"march\""));
+        assert_eq!(history[3].time, Some(3));
+
+        // Over a date range, the running balance accounts for the previous transactions.
+        let history = control.export_history(2, 2).transactions;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].txid, payment.txid());
+        assert_eq!(history[0].balance.to_sat(), 59_000);
+        assert_eq!(history[1].balance.to_sat(), 58_500);
+        assert!(control.export_history(4, 10).transactions.is_empty());
+
+        // As CSV.
+        let csv = control.export_history(3, 3).to_csv();
+        assert_eq!(
+            csv,
+            format!(
+                "txid,height,time,direction,net_amount,fee,balance,label\n\
+                {},3,3,incoming,20000,,78500,\"salary, \"\"march\"\"\"\n",
+                deposit_b.txid()
+            )
+        );
+
+        // A label which would be interpreted as a formula by a spreadsheet is escaped.
+        for (label, escaped) in [
+            ("=1+1", "'=1+1"),
+            ("+1", "'+1"),
+            ("-1", "'-1"),
+            ("@SUM(A1)", "'@SUM(A1)"),
+            (
+                "=HYPERLINK(\"a\",\"b\")",
+                "\"'=HYPERLINK(\"\"a\"\",\"\"b\"\")\"",
+            ),
+        ] {
+            let mut labels = HashMap::new();
+            labels.insert(LabelItem::Txid(deposit_b.txid()), Some(label.to_string()));
+            control.update_labels(&labels);
+            let csv = control.export_history(3, 3).to_csv();
+            assert!(csv.ends_with(&format!(",78500,{}\n", escaped)), "{}", csv);
+        }
+
+        ms.shutdown();
+    }
+
     #[test]
     fn list_transactions() {
         let outpoint = OutPoint::new(
@@ -2499,6 +6175,7 @@ mod tests {
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 }]);
             }
         }
@@ -2523,4 +6200,85 @@ mod tests {
 
         ms.shutdown();
     }
+
+    #[test]
+    fn wait_for_events() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = ms.control();
+        let txid =
+            Txid::from_str("0c62a990d20d54429e70859292e82374ba6b1b951a3ab60f26bb65fee5724ff7")
+                .unwrap();
+
+        // Arguments sanity checking.
+        assert_eq!(
+            control.wait_for_events(None, MAX_WAIT_FOR_EVENTS_TIMEOUT + 1),
+            Err(CommandError::InvalidTimeout(
+                MAX_WAIT_FOR_EVENTS_TIMEOUT + 1
+            ))
+        );
+
+        // The journal is empty, we time out without any event.
+        let res = control.wait_for_events(None, 0).unwrap();
+        assert!(res.events.is_empty());
+        assert_eq!(res.cursor, 0);
+
+        // Deleting a spend records an event, returned immediately.
+        control.delete_spend(&txid);
+        let res = control.wait_for_events(None, 0).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].event, Event::SpendDeleted { txid });
+        assert_eq!(res.cursor, res.events[0].id);
+
+        // Once we've seen it, we wait for the next one and get woken up when it's recorded.
+        let cursor = res.cursor;
+        let waiter = {
+            let control = control.clone();
+            thread::spawn(move || control.wait_for_events(Some(cursor), 60).unwrap())
+        };
+        thread::sleep(time::Duration::from_millis(100));
+        control.delete_spend(&txid);
+        let res = waiter.join().unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert!(res.events[0].id > cursor);
+        assert_eq!(res.cursor, res.events[0].id);
+
+        // Nothing new after this cursor.
+        let cursor = res.cursor;
+        let res = control.wait_for_events(Some(cursor), 0).unwrap();
+        assert!(res.events.is_empty());
+
+        // Only a limited number of calls may be waiting for events at the same time. Calls which
+        // don't need to wait are still served.
+        let waiters: Vec<_> = (0..MAX_EVENT_WAITERS)
+            .map(|_| {
+                let control = control.clone();
+                thread::spawn(move || control.wait_for_events(Some(cursor), 60).unwrap())
+            })
+            .collect();
+        thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(
+            control.wait_for_events(Some(cursor), 1),
+            Err(CommandError::TooManyEventWaiters)
+        );
+        assert!(control
+            .wait_for_events(Some(cursor), 0)
+            .unwrap()
+            .events
+            .is_empty());
+        assert_eq!(control.wait_for_events(None, 1).unwrap().events.len(), 2);
+        control.delete_spend(&txid);
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap().events.len(), 1);
+        }
+
+        // Once they returned, we can wait again.
+        let res = control.wait_for_events(None, 1).unwrap();
+        assert_eq!(res.events.len(), 3);
+        assert_eq!(
+            control.wait_for_events(Some(res.cursor), 1).unwrap().events,
+            vec![]
+        );
+
+        ms.shutdown();
+    }
 }
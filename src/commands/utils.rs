@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use miniscript::bitcoin::{self, consensus, hashes::hex::FromHex};
-use serde::{de, Deserialize, Deserializer, Serializer};
+use serde::{de, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
 
 pub fn deser_fromstr<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
@@ -20,6 +20,31 @@ pub fn ser_to_string<T: std::fmt::Display, S: Serializer>(
     s.serialize_str(&field.to_string())
 }
 
+/// Deserialize a list of items from their string representation.
+pub fn deser_vec_fromstr<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    <T as FromStr>::Err: std::fmt::Display,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|string| T::from_str(string).map_err(de::Error::custom))
+        .collect()
+}
+
+/// Serialize a list of items as their string representation.
+pub fn ser_vec_to_string<T: std::fmt::Display, S: Serializer>(
+    field: &[T],
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    let mut seq = s.serialize_seq(Some(field.len()))?;
+    for item in field {
+        seq.serialize_element(&item.to_string())?;
+    }
+    seq.end()
+}
+
 /// Deserialize an address from string, assuming the network was checked.
 pub fn deser_addr_assume_checked<'de, D>(deserializer: D) -> Result<bitcoin::Address, D::Error>
 where
@@ -64,6 +64,34 @@ where
     Ok(rpc_auth)
 }
 
+fn deserialize_http_rpc_auth<'de, D>(deserializer: D) -> Result<HttpRpcAuth, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    pub struct HttpRpcAuthHelper {
+        cookie_path: Option<PathBuf>,
+        auth: Option<String>,
+    }
+    let HttpRpcAuthHelper { cookie_path, auth } = HttpRpcAuthHelper::deserialize(deserializer)?;
+    let rpc_auth = match (cookie_path, auth) {
+        (Some(_), Some(_)) => {
+            return Err(de::Error::custom(
+                "must not set both `cookie_path` and `auth`",
+            ));
+        }
+        (Some(path), None) => HttpRpcAuth::CookieFile(path),
+        (None, Some(auth)) => auth
+            .split_once(':')
+            .ok_or(de::Error::custom("`auth` must be 'user:password'"))
+            .map(|(user, pass)| HttpRpcAuth::UserPass(user.to_string(), pass.to_string()))?,
+        (None, None) => {
+            return Err(de::Error::custom("must set either `cookie_path` or `auth`"));
+        }
+    };
+    Ok(rpc_auth)
+}
+
 fn serialize_userpass<S: Serializer>(
     user: &String,
     password: &String,
@@ -80,6 +108,10 @@ fn default_poll_interval() -> Duration {
     Duration::from_secs(30)
 }
 
+fn default_refresh_keychain() -> RefreshKeychain {
+    RefreshKeychain::Change
+}
+
 #[cfg(unix)]
 fn default_daemon() -> bool {
     false
@@ -126,6 +158,42 @@ pub struct BitcoindConfig {
     pub addr: SocketAddr,
 }
 
+/// Authentication options for our JSONRPC server over HTTP.
+#[derive(Clone, PartialEq, Eq, Serialize)]
+pub enum HttpRpcAuth {
+    /// Path at which to write a cookie file containing random credentials, at startup.
+    #[serde(rename = "cookie_path")]
+    CookieFile(PathBuf),
+    /// "USER:PASSWORD" for authentication.
+    #[serde(rename = "auth", serialize_with = "serialize_userpass")]
+    UserPass(String, String),
+}
+
+impl fmt::Debug for HttpRpcAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CookieFile(path) => path.fmt(f),
+            Self::UserPass(_, _) => write!(f, "REDACTED RPC CREDENTIALS"),
+        }
+    }
+}
+
+/// Settings for serving our JSONRPC API over HTTP, in addition to the Unix Domain Socket.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpRpcConfig {
+    /// The IP:port to listen on.
+    pub addr: SocketAddr,
+    /// Credentials clients must provide through HTTP basic authentication.
+    #[serde(flatten, deserialize_with = "deserialize_http_rpc_auth")]
+    pub auth: HttpRpcAuth,
+    /// Path to the PEM-encoded certificate chain to use for serving the API over TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_cert_path: Option<PathBuf>,
+    /// Path to the PEM-encoded private key of the certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_key_path: Option<PathBuf>,
+}
+
 /// Everything we need to know for talking to Electrum serenely.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ElectrumConfig {
@@ -148,6 +216,55 @@ pub struct BitcoinConfig {
     pub poll_interval_secs: Duration,
 }
 
+/// The keychain the refreshed coins are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefreshKeychain {
+    Receive,
+    Change,
+}
+
+/// Settings for automatically refreshing coins before a recovery path becomes available for them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RefreshPolicy {
+    /// Refresh the coins for which the first recovery path becomes available within this number
+    /// of blocks.
+    pub margin_blocks: u32,
+    /// The maximum feerate, in sats/vb, to use for the refresh transactions.
+    pub max_feerate: u64,
+    /// The keychain to send the refreshed coins to.
+    #[serde(default = "default_refresh_keychain")]
+    pub keychain: RefreshKeychain,
+}
+
+/// A command to run on wallet events, see [`crate::events::Event`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventHookConfig {
+    /// Path to the program to run. The event is passed on its standard input, as JSON.
+    pub command: PathBuf,
+    /// Arguments to pass to the program.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// An additional wallet served by this daemon, next to the one for the main descriptor.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WalletConfig {
+    /// The name used to select this wallet in the JSONRPC API.
+    pub name: String,
+    /// The descriptor to use for sending/receiving coins with this wallet.
+    #[serde(
+        deserialize_with = "deserialize_fromstr",
+        serialize_with = "serialize_to_string"
+    )]
+    pub main_descriptor: LianaDescriptor,
+    /// The creation date of this wallet, as a UNIX timestamp. When the wallet is first loaded by
+    /// the daemon, the block chain is rescanned from this date. If not set, coins received before
+    /// the wallet is first loaded won't be detected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u32>,
+}
+
 /// Static informations we require to operate
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -175,6 +292,18 @@ pub struct Config {
     /// Settings specific to the Bitcoin backend.
     #[serde(flatten)]
     pub bitcoin_backend: Option<BitcoinBackend>,
+    /// Settings for automatically creating transactions refreshing coins close to expiry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_policy: Option<RefreshPolicy>,
+    /// A command to run on wallet events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_hook: Option<EventHookConfig>,
+    /// Settings for serving the JSONRPC API over HTTP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_rpc_config: Option<HttpRpcConfig>,
+    /// Additional wallets to serve, each with its own descriptor.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wallets: Vec<WalletConfig>,
 }
 
 impl Config {
@@ -278,24 +407,89 @@ impl Config {
             Network::Bitcoin => Network::Bitcoin,
             _ => Network::Testnet,
         };
-        if !self.main_descriptor.all_xpubs_net_is(expected_network) {
+        if !self.main_descriptor.all_xpubs_net_is(expected_network)
+            || !self
+                .wallets
+                .iter()
+                .all(|w| w.main_descriptor.all_xpubs_net_is(expected_network))
+        {
             return Err(ConfigError::Unexpected(format!(
                 "Our bitcoin network is {} but one xpub is not for network {}",
                 self.bitcoin_config.network, expected_network
             )));
         }
 
+        // The additional wallets are watched through the same bitcoind watchonly wallet.
+        if !self.wallets.is_empty() {
+            if let Some(BitcoinBackend::Electrum(_)) = self.bitcoin_backend {
+                return Err(ConfigError::Unexpected(
+                    "Additional wallets are only supported with the bitcoind backend".to_string(),
+                ));
+            }
+        }
+        for (i, wallet) in self.wallets.iter().enumerate() {
+            if wallet.name.is_empty() {
+                return Err(ConfigError::Unexpected(
+                    "The name of a wallet must not be empty".to_string(),
+                ));
+            }
+            if wallet.main_descriptor == self.main_descriptor {
+                return Err(ConfigError::Unexpected(format!(
+                    "Wallet '{}' has the same descriptor as the main wallet",
+                    wallet.name
+                )));
+            }
+            for other in &self.wallets[..i] {
+                if other.name == wallet.name {
+                    return Err(ConfigError::Unexpected(format!(
+                        "Duplicate wallet name '{}'",
+                        wallet.name
+                    )));
+                }
+                if other.main_descriptor == wallet.main_descriptor {
+                    return Err(ConfigError::Unexpected(format!(
+                        "Wallets '{}' and '{}' have the same descriptor",
+                        other.name, wallet.name
+                    )));
+                }
+            }
+        }
+
         // TODO: check the semantics of the main descriptor
 
+        if let Some(policy) = &self.refresh_policy {
+            if policy.margin_blocks == 0 {
+                return Err(ConfigError::Unexpected(
+                    "The refresh policy safety margin must be at least one block".to_string(),
+                ));
+            }
+            if policy.max_feerate == 0 {
+                return Err(ConfigError::Unexpected(
+                    "The refresh policy maximum feerate must be at least 1 sat/vb".to_string(),
+                ));
+            }
+        }
+
+        if let Some(http_config) = &self.http_rpc_config {
+            if http_config.tls_cert_path.is_some() != http_config.tls_key_path.is_some() {
+                return Err(ConfigError::Unexpected(
+                    "Both a TLS certificate and its key must be set to serve the JSONRPC API over TLS".to_string(),
+                ));
+            }
+        }
+
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{net::SocketAddr, path::PathBuf, str::FromStr};
 
-    use super::{config_file_path, BitcoindConfig, BitcoindRpcAuth, Config};
+    use super::{
+        config_file_path, BitcoindConfig, BitcoindRpcAuth, Config, EventHookConfig, HttpRpcAuth,
+        HttpRpcConfig, RefreshKeychain, RefreshPolicy,
+    };
 
     // Test the format of the configuration file
     #[test]
@@ -377,6 +571,172 @@ mod tests {
         #[cfg(unix)] // On non-UNIX there is no 'daemon' member.
         assert_eq!(toml_str, serialized);
 
+        // A valid, round-tripping, config with a refresh policy
+        let toml_str = r#"
+            data_dir = '/home/wizardsardine/custom/folder/'
+            daemon = false
+            log_level = 'TRACE'
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'bitcoin'
+            poll_interval_secs = 18
+
+            [bitcoind_config]
+            cookie_path = '/home/user/.bitcoin/.cookie'
+            addr = '127.0.0.1:8332'
+
+            [refresh_policy]
+            margin_blocks = 1008
+            max_feerate = 20
+            keychain = 'receive'
+
+            [event_hook]
+            command = '/usr/local/bin/notify'
+            args = [
+                '--wallet',
+                'main',
+            ]
+            "#.trim_start().replace("            ", "");
+        let parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+        assert_eq!(
+            parsed.refresh_policy,
+            Some(RefreshPolicy {
+                margin_blocks: 1008,
+                max_feerate: 20,
+                keychain: RefreshKeychain::Receive,
+            })
+        );
+        assert_eq!(
+            parsed.event_hook,
+            Some(EventHookConfig {
+                command: PathBuf::from("/usr/local/bin/notify"),
+                args: vec!["--wallet".to_string(), "main".to_string()],
+            })
+        );
+        let serialized = toml::to_string_pretty(&parsed).expect("Serializing to toml");
+        #[cfg(unix)] // On non-UNIX there is no 'daemon' member.
+        assert_eq!(toml_str, serialized);
+
+        // The refresh policy keychain defaults to the change keychain.
+        let toml_str = r#"
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'bitcoin'
+
+            [refresh_policy]
+            margin_blocks = 1008
+            max_feerate = 20
+            "#.trim_start().replace("            ", "");
+        let parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+        assert_eq!(
+            parsed.refresh_policy.map(|p| p.keychain),
+            Some(RefreshKeychain::Change)
+        );
+
+        // A valid, round-tripping, config with an HTTP JSONRPC server.
+        let toml_str = r#"
+            data_dir = '/home/wizardsardine/custom/folder/'
+            daemon = false
+            log_level = 'TRACE'
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'bitcoin'
+            poll_interval_secs = 18
+
+            [bitcoind_config]
+            cookie_path = '/home/user/.bitcoin/.cookie'
+            addr = '127.0.0.1:8332'
+
+            [http_rpc_config]
+            addr = '0.0.0.0:9442'
+            auth = 'monitoring:my_password'
+            tls_cert_path = '/etc/liana/cert.pem'
+            tls_key_path = '/etc/liana/key.pem'
+            "#.trim_start().replace("            ", "");
+        let parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+        assert_eq!(
+            parsed.http_rpc_config,
+            Some(HttpRpcConfig {
+                addr: SocketAddr::from_str("0.0.0.0:9442").unwrap(),
+                auth: HttpRpcAuth::UserPass("monitoring".to_string(), "my_password".to_string()),
+                tls_cert_path: Some(PathBuf::from("/etc/liana/cert.pem")),
+                tls_key_path: Some(PathBuf::from("/etc/liana/key.pem")),
+            })
+        );
+        let serialized = toml::to_string_pretty(&parsed).expect("Serializing to toml");
+        #[cfg(unix)] // On non-UNIX there is no 'daemon' member.
+        assert_eq!(toml_str, serialized);
+
+        // The HTTP server may use a cookie file, and TLS is optional. But it needs credentials.
+        let toml_str = r#"
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'bitcoin'
+
+            [http_rpc_config]
+            addr = '127.0.0.1:9442'
+            cookie_path = '/home/user/.liana/.cookie'
+            "#.trim_start().replace("            ", "");
+        let parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+        let http_config = parsed.http_rpc_config.unwrap();
+        assert_eq!(
+            http_config.auth,
+            HttpRpcAuth::CookieFile(PathBuf::from("/home/user/.liana/.cookie"))
+        );
+        assert!(http_config.tls_cert_path.is_none() && http_config.tls_key_path.is_none());
+        let toml_str = toml_str.replace("cookie_path = '/home/user/.liana/.cookie'\n", "");
+        toml::from_str::<Config>(&toml_str).expect_err("No credentials");
+
+        // Additional wallets, selected by name in the JSONRPC API.
+        let toml_str = r#"
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'testnet'
+
+            [bitcoind_config]
+            cookie_path = '/home/user/.bitcoin/.cookie'
+            addr = '127.0.0.1:8332'
+
+            [[wallets]]
+            name = 'alice'
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDExU4YLJkyQ9RRbVScQq2brFxWWha7WmAUByPWyaWYwmcTv3Shx8aHp6mVwuE5n4TeM4z5DTWGf2YhNPmXtfvyr8cUDVvA3txdrFnFgNdF7/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))'
+            timestamp = 1700000000
+            "#.trim_start().replace("            ", "");
+        let mut parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+        assert_eq!(parsed.wallets.len(), 1);
+        assert_eq!(parsed.wallets[0].name, "alice");
+        assert_eq!(parsed.wallets[0].timestamp, Some(1_700_000_000));
+        parsed.check().expect("Valid config");
+        // Wallets must have distinct names and descriptors.
+        let mut wallet = parsed.wallets[0].clone();
+        wallet.main_descriptor = parsed.main_descriptor.clone();
+        wallet.name = "bob".to_string();
+        parsed.wallets.push(wallet.clone());
+        assert!(parsed
+            .check()
+            .unwrap_err()
+            .to_string()
+            .contains("same descriptor"));
+        wallet.main_descriptor = parsed.wallets[0].main_descriptor.clone();
+        *parsed.wallets.last_mut().unwrap() = wallet.clone();
+        assert!(parsed
+            .check()
+            .unwrap_err()
+            .to_string()
+            .contains("same descriptor"));
+        wallet.name = "alice".to_string();
+        *parsed.wallets.last_mut().unwrap() = wallet;
+        assert!(parsed
+            .check()
+            .unwrap_err()
+            .to_string()
+            .contains("Duplicate wallet name"));
+
         // Invalid desc checksum
         let toml_str = r#"
             daemon = false
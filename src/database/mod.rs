@@ -7,9 +7,10 @@ pub mod sqlite;
 use crate::{
     bitcoin::BlockChainTip,
     database::sqlite::{
-        schema::{DbBlockInfo, DbCoin, DbTip},
+        schema::{DbBlockInfo, DbCoin, DbLabelledKind, DbTip},
         SqliteConn, SqliteDb,
     },
+    events::{Event, JournalEntry},
 };
 
 use std::{
@@ -118,6 +119,16 @@ pub trait DatabaseConnection {
     /// Mark a set of coins as spent by a specified txid at a specified block time.
     fn confirm_spend(&mut self, outpoints: &[(bitcoin::OutPoint, bitcoin::Txid, i32, u32)]);
 
+    /// Mark a set of coins as frozen. Frozen coins are never selected automatically.
+    fn freeze_coins(&mut self, outpoints: &[bitcoin::OutPoint]);
+
+    /// Mark a set of coins as not being frozen anymore.
+    fn unfreeze_coins(&mut self, outpoints: &[bitcoin::OutPoint]);
+
+    /// Mark a set of coins, not yet in database, to be frozen once they are stored. This is used
+    /// when restoring a wallet, before its coins are found again.
+    fn freeze_coins_once_found(&mut self, outpoints: &[bitcoin::OutPoint]);
+
     /// Get specific coins from the database.
     fn coins_by_outpoints(
         &mut self,
@@ -141,6 +152,9 @@ pub trait DatabaseConnection {
 
     fn labels(&mut self, labels: &HashSet<LabelItem>) -> HashMap<String, String>;
 
+    /// Get all the labels, in the order they were first set.
+    fn list_labels(&mut self) -> Vec<(LabelItem, String)>;
+
     /// Mark the given tip as the new best seen block. Update stored data accordingly.
     fn rollback_tip(&mut self, new_tip: &BlockChainTip);
 
@@ -158,6 +172,14 @@ pub trait DatabaseConnection {
         &mut self,
         txids: &[bitcoin::Txid],
     ) -> Vec<(bitcoin::Transaction, Option<i32>, Option<u32>)>;
+
+    /// Record these events in the journal, in order. The journal may drop its oldest events to
+    /// make room for them.
+    fn store_events(&mut self, events: &[Event]);
+
+    /// Retrieve at most `limit` events from the journal, in order, recorded after the event with
+    /// the given id.
+    fn events(&mut self, after_id: u64, limit: u64) -> Vec<JournalEntry>;
 }
 
 impl DatabaseConnection for SqliteConn {
@@ -262,6 +284,18 @@ impl DatabaseConnection for SqliteConn {
         self.confirm_spend(outpoints)
     }
 
+    fn freeze_coins(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        self.set_coins_frozen(outpoints, true)
+    }
+
+    fn unfreeze_coins(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        self.set_coins_frozen(outpoints, false)
+    }
+
+    fn freeze_coins_once_found(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        self.set_coins_pending_frozen(outpoints)
+    }
+
     fn derivation_index_by_address(
         &mut self,
         address: &bitcoin::Address,
@@ -315,6 +349,27 @@ impl DatabaseConnection for SqliteConn {
         HashMap::from_iter(labels.into_iter().map(|label| (label.item, label.value)))
     }
 
+    fn list_labels(&mut self) -> Vec<(LabelItem, String)> {
+        self.db_list_labels()
+            .into_iter()
+            .map(|label| {
+                let item = match label.item_kind {
+                    DbLabelledKind::Address => bitcoin::Address::from_str(&label.item)
+                        .expect("We only store valid addresses")
+                        .assume_checked()
+                        .into(),
+                    DbLabelledKind::Txid => bitcoin::Txid::from_str(&label.item)
+                        .expect("We only store valid txids")
+                        .into(),
+                    DbLabelledKind::OutPoint => bitcoin::OutPoint::from_str(&label.item)
+                        .expect("We only store valid outpoints")
+                        .into(),
+                };
+                (item, label.value)
+            })
+            .collect()
+    }
+
     fn rollback_tip(&mut self, new_tip: &BlockChainTip) {
         self.rollback_tip(new_tip)
     }
@@ -346,6 +401,21 @@ impl DatabaseConnection for SqliteConn {
             })
             .collect()
     }
+
+    fn store_events(&mut self, events: &[Event]) {
+        self.store_events(events)
+    }
+
+    fn events(&mut self, after_id: u64, limit: u64) -> Vec<JournalEntry> {
+        self.db_events(after_id, limit)
+            .into_iter()
+            .map(|e| JournalEntry {
+                id: e.id as u64,
+                timestamp: e.timestamp,
+                event: e.event,
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -373,6 +443,8 @@ pub struct Coin {
     pub is_change: bool,
     pub spend_txid: Option<bitcoin::Txid>,
     pub spend_block: Option<BlockInfo>,
+    /// Whether this coin must be excluded from automatic coin selection.
+    pub is_frozen: bool,
 }
 
 impl std::convert::From<DbCoin> for Coin {
@@ -386,6 +458,7 @@ impl std::convert::From<DbCoin> for Coin {
             is_change,
             spend_txid,
             spend_block,
+            is_frozen,
             ..
         } = db_coin;
         Coin {
@@ -397,6 +470,7 @@ impl std::convert::From<DbCoin> for Coin {
             is_change,
             spend_txid,
             spend_block: spend_block.map(BlockInfo::from),
+            is_frozen,
         }
     }
 }
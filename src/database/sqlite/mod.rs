@@ -15,17 +15,18 @@ use crate::{
     database::{
         sqlite::{
             schema::{
-                DbAddress, DbCoin, DbLabel, DbLabelledKind, DbSpendTransaction, DbTip, DbWallet,
-                DbWalletTransaction, SCHEMA,
+                DbAddress, DbCoin, DbEvent, DbLabel, DbLabelledKind, DbSpendTransaction, DbTip,
+                DbWallet, DbWalletTransaction, SCHEMA,
             },
             utils::{
                 create_fresh_db, curr_timestamp, db_exec, db_query, db_tx_query, db_version,
-                maybe_apply_migration, LOOK_AHEAD_LIMIT,
+                insert_wallet, maybe_apply_migration, LOOK_AHEAD_LIMIT,
             },
         },
         Coin, CoinStatus, LabelItem,
     },
     descriptors::LianaDescriptor,
+    events::Event,
 };
 
 use std::{
@@ -43,7 +44,11 @@ use miniscript::bitcoin::{
     secp256k1,
 };
 
-const DB_VERSION: i64 = 5;
+const DB_VERSION: i64 = 9;
+
+/// The maximum number of events kept in the journal of a wallet. The oldest ones are removed as
+/// new ones are recorded.
+pub const MAX_JOURNAL_EVENTS: u64 = 10_000;
 
 /// Last database version for which Bitcoin transactions were not stored in database. In practice
 /// this meant we relied on the bitcoind watchonly wallet to store them for us.
@@ -120,6 +125,7 @@ pub struct FreshDbOptions {
     pub(self) main_descriptor: LianaDescriptor,
     pub(self) schema: &'static str,
     pub(self) version: i64,
+    pub(self) timestamp: Option<u32>,
 }
 
 impl FreshDbOptions {
@@ -132,13 +138,23 @@ impl FreshDbOptions {
             main_descriptor,
             schema: SCHEMA,
             version: DB_VERSION,
+            timestamp: None,
         }
     }
+
+    /// Record this creation date for the wallet instead of the current time. Used when restoring
+    /// an existing wallet.
+    pub fn with_timestamp(mut self, timestamp: u32) -> FreshDbOptions {
+        self.timestamp = Some(timestamp);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SqliteDb {
     db_path: path::PathBuf,
+    // The wallet the connections to this database are scoped to.
+    wallet_id: i64,
 }
 
 impl SqliteDb {
@@ -161,7 +177,63 @@ impl SqliteDb {
 
         log::info!("Checking if the database needs upgrading.");
 
-        Ok(SqliteDb { db_path })
+        Ok(SqliteDb {
+            db_path,
+            wallet_id: MAIN_WALLET_ID,
+        })
+    }
+
+    /// Get the database for the wallet with this main descriptor, inserting a fresh wallet if
+    /// there is none yet. The connections to the returned database are scoped to this wallet.
+    /// If a creation timestamp is given, a fresh wallet is inserted with an ongoing rescan from
+    /// this date.
+    /// NOTE: any migration must have been applied beforehand.
+    pub fn wallet(
+        &self,
+        bitcoind_network: bitcoin::Network,
+        main_descriptor: &LianaDescriptor,
+        creation_timestamp: Option<u32>,
+        secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    ) -> Result<SqliteDb, SqliteDbError> {
+        let mut conn = rusqlite::Connection::open(&self.db_path)?;
+        let desc_str = main_descriptor.to_string();
+        let existing_id = db_query(
+            &mut conn,
+            "SELECT id FROM wallets WHERE main_descriptor = ?1",
+            rusqlite::params![desc_str],
+            |row| row.get::<_, i64>(0),
+        )?
+        .pop();
+        let wallet_id = match existing_id {
+            Some(id) => id,
+            None => {
+                let mut wallet_id = None;
+                db_exec(&mut conn, |tx| {
+                    let id = insert_wallet(
+                        tx,
+                        bitcoind_network,
+                        main_descriptor,
+                        creation_timestamp.unwrap_or_else(curr_timestamp),
+                        secp,
+                    )?;
+                    if let Some(timestamp) = creation_timestamp {
+                        tx.execute(
+                            "UPDATE wallets SET rescan_timestamp = (?1) WHERE id = (?2)",
+                            rusqlite::params![timestamp, id],
+                        )?;
+                    }
+                    wallet_id = Some(id);
+                    Ok(())
+                })?;
+                log::info!("Inserted a fresh wallet for descriptor '{}'.", desc_str);
+                wallet_id.expect("Set if the database transaction succeeded")
+            }
+        };
+
+        Ok(SqliteDb {
+            db_path: self.db_path.clone(),
+            wallet_id,
+        })
     }
 
     /// If the database version is older than expected, migrate it to the current version. If
@@ -178,7 +250,10 @@ impl SqliteDb {
     pub fn connection(&self) -> Result<SqliteConn, SqliteDbError> {
         let conn = rusqlite::Connection::open(&self.db_path)?;
         conn.busy_timeout(std::time::Duration::from_secs(60))?;
-        Ok(SqliteConn { conn })
+        Ok(SqliteConn {
+            conn,
+            wallet_id: self.wallet_id,
+        })
     }
 
     /// Perform startup sanity checks.
@@ -213,11 +288,13 @@ impl SqliteDb {
     }
 }
 
-// We only support single wallet. The id of the wallet row is always 1.
-const WALLET_ID: i64 = 1;
+// The id of the row of the wallet created along with the database, for the main descriptor.
+const MAIN_WALLET_ID: i64 = 1;
 
 pub struct SqliteConn {
     conn: rusqlite::Connection,
+    // All the wallet data we query and store is for this wallet.
+    wallet_id: i64,
 }
 
 impl SqliteConn {
@@ -229,8 +306,8 @@ impl SqliteConn {
     pub fn db_tip(&mut self) -> DbTip {
         db_query(
             &mut self.conn,
-            "SELECT * FROM tip",
-            rusqlite::params![],
+            "SELECT network, blockheight, blockhash FROM tip WHERE wallet_id = ?1",
+            rusqlite::params![self.wallet_id],
             |row| row.try_into(),
         )
         .expect("Db must not fail")
@@ -242,8 +319,8 @@ impl SqliteConn {
     pub fn db_wallet(&mut self) -> DbWallet {
         db_query(
             &mut self.conn,
-            "SELECT * FROM wallets",
-            rusqlite::params![],
+            "SELECT * FROM wallets WHERE id = ?1",
+            rusqlite::params![self.wallet_id],
             |row| row.try_into(),
         )
         .expect("Db must not fail")
@@ -253,11 +330,12 @@ impl SqliteConn {
 
     /// Update the network tip.
     pub fn update_tip(&mut self, tip: &BlockChainTip) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             db_tx
                 .execute(
-                    "UPDATE tip SET blockheight = (?1), blockhash = (?2)",
-                    rusqlite::params![tip.height, tip.hash[..].to_vec()],
+                    "UPDATE tip SET blockheight = (?1), blockhash = (?2) WHERE wallet_id = (?3)",
+                    rusqlite::params![tip.height, tip.hash[..].to_vec(), wallet_id],
                 )
                 .map(|_| ())
         })
@@ -275,10 +353,11 @@ impl SqliteConn {
         secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     ) {
         let network = self.db_tip().network;
+        let wallet_id = self.wallet_id;
 
         db_exec(&mut self.conn, |db_tx| {
             let db_wallet: DbWallet =
-                db_tx_query(db_tx, "SELECT * FROM wallets", rusqlite::params![], |row| {
+                db_tx_query(db_tx, "SELECT * FROM wallets WHERE id = ?1", rusqlite::params![wallet_id], |row| {
                     row.try_into()
                 })?
                 .pop()
@@ -296,13 +375,13 @@ impl SqliteConn {
             let index_u32: u32 = index.into();
             if change {
                 db_tx.execute(
-                    "UPDATE wallets SET change_derivation_index = (?1)",
-                    rusqlite::params![index_u32],
+                    "UPDATE wallets SET change_derivation_index = (?1) WHERE id = (?2)",
+                    rusqlite::params![index_u32, wallet_id],
                 )?;
             } else {
                 db_tx.execute(
-                    "UPDATE wallets SET deposit_derivation_index = (?1)",
-                    rusqlite::params![index_u32],
+                    "UPDATE wallets SET deposit_derivation_index = (?1) WHERE id = (?2)",
+                    rusqlite::params![index_u32, wallet_id],
                 )?;
             }
 
@@ -322,8 +401,8 @@ impl SqliteConn {
                     let receive_addr = receive_desc.derive(la_index.into(), secp).address(network);
                     let change_addr = change_desc.derive(la_index.into(), secp).address(network);
                     db_tx.execute(
-                        "INSERT INTO addresses (receive_address, change_address, derivation_index) VALUES (?1, ?2, ?3)",
-                        rusqlite::params![receive_addr.to_string(), change_addr.to_string(), la_index],
+                        "INSERT INTO addresses (receive_address, change_address, derivation_index, wallet_id) VALUES (?1, ?2, ?3, ?4)",
+                        rusqlite::params![receive_addr.to_string(), change_addr.to_string(), la_index, wallet_id],
                     )?;
                 }
 
@@ -335,12 +414,12 @@ impl SqliteConn {
     }
 
     pub fn set_wallet_rescan_timestamp(&mut self, timestamp: u32) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
-            // NOTE: this will need to be updated if we ever implement multi-wallet support
             db_tx
                 .execute(
-                    "UPDATE wallets SET rescan_timestamp = (?1)",
-                    rusqlite::params![timestamp],
+                    "UPDATE wallets SET rescan_timestamp = (?1) WHERE id = (?2)",
+                    rusqlite::params![timestamp, wallet_id],
                 )
                 .map(|_| ())
         })
@@ -359,12 +438,12 @@ impl SqliteConn {
             db_wallet.timestamp,
         );
 
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
-            // NOTE: this will need to be updated if we ever implement multi-wallet support
             db_tx
                 .execute(
-                    "UPDATE wallets SET timestamp = (?1), rescan_timestamp = NULL",
-                    rusqlite::params![new_timestamp],
+                    "UPDATE wallets SET timestamp = (?1), rescan_timestamp = NULL WHERE id = (?2)",
+                    rusqlite::params![new_timestamp, wallet_id],
                 )
                 .map(|_| ())
         })
@@ -418,19 +497,20 @@ impl SqliteConn {
         } else {
             String::new()
         };
-        let where_clause = if !status_condition.is_empty() && !op_condition.is_empty() {
-            format!(" WHERE ({}) AND ({})", status_condition, op_condition)
-        } else if status_condition.is_empty() && !op_condition.is_empty() {
-            format!(" WHERE {}", op_condition)
-        } else if !status_condition.is_empty() && op_condition.is_empty() {
-            format!(" WHERE {}", status_condition)
-        } else {
-            String::new()
-        };
-        let query = format!("SELECT * FROM coins{}", where_clause);
-        db_query(&mut self.conn, &query, rusqlite::params![], |row| {
-            row.try_into()
-        })
+        let mut where_clause = "WHERE wallet_id = ?1".to_string();
+        if !status_condition.is_empty() {
+            where_clause += &format!(" AND ({})", status_condition);
+        }
+        if !op_condition.is_empty() {
+            where_clause += &format!(" AND ({})", op_condition);
+        }
+        let query = format!("SELECT * FROM coins {}", where_clause);
+        db_query(
+            &mut self.conn,
+            &query,
+            rusqlite::params![self.wallet_id],
+            |row| row.try_into(),
+        )
         .expect("Db must not fail")
     }
 
@@ -443,14 +523,17 @@ impl SqliteConn {
     /// Store new, unconfirmed and unspent, coins.
     /// Will panic if given a coin that is already in DB.
     pub fn new_unspent_coins<'a>(&mut self, coins: impl IntoIterator<Item = &'a Coin>) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for coin in coins {
                 let deriv_index: u32 = coin.derivation_index.into();
+                // The coin is frozen right away if it was marked to be once found.
                 db_tx.execute(
-                    "INSERT INTO coins (wallet_id, txid, vout, amount_sat, derivation_index, is_change, is_immature) \
-                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    "INSERT INTO coins (wallet_id, txid, vout, amount_sat, derivation_index, is_change, is_immature, is_frozen) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, \
+                         EXISTS (SELECT 1 FROM pending_frozen_coins WHERE wallet_id = ?1 AND txid = ?2 AND vout = ?3))",
                     rusqlite::params![
-                        WALLET_ID,
+                        wallet_id,
                         coin.outpoint.txid[..].to_vec(),
                         coin.outpoint.vout,
                         coin.amount.to_sat(),
@@ -459,6 +542,10 @@ impl SqliteConn {
                         coin.is_immature,
                     ],
                 )?;
+                db_tx.execute(
+                    "DELETE FROM pending_frozen_coins WHERE wallet_id = ?1 AND txid = ?2 AND vout = ?3",
+                    rusqlite::params![wallet_id, coin.outpoint.txid[..].to_vec(), coin.outpoint.vout],
+                )?;
             }
             Ok(())
         })
@@ -467,11 +554,12 @@ impl SqliteConn {
 
     /// Remove a set of coins from the database.
     pub fn remove_coins(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for outpoint in outpoints {
                 db_tx.execute(
-                    "DELETE FROM coins WHERE txid = ?1 AND vout = ?2",
-                    rusqlite::params![outpoint.txid[..].to_vec(), outpoint.vout,],
+                    "DELETE FROM coins WHERE txid = ?1 AND vout = ?2 AND wallet_id = ?3",
+                    rusqlite::params![outpoint.txid[..].to_vec(), outpoint.vout, wallet_id],
                 )?;
             }
 
@@ -488,11 +576,12 @@ impl SqliteConn {
         &mut self,
         outpoints: impl IntoIterator<Item = &'a (bitcoin::OutPoint, i32, u32)>,
     ) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for (outpoint, height, time) in outpoints {
                 db_tx.execute(
-                    "UPDATE coins SET blockheight = ?1, blocktime = ?2, is_immature = 0 WHERE txid = ?3 AND vout = ?4",
-                    rusqlite::params![height, time, outpoint.txid[..].to_vec(), outpoint.vout,],
+                    "UPDATE coins SET blockheight = ?1, blocktime = ?2, is_immature = 0 WHERE txid = ?3 AND vout = ?4 AND wallet_id = ?5",
+                    rusqlite::params![height, time, outpoint.txid[..].to_vec(), outpoint.vout, wallet_id],
                 )?;
             }
 
@@ -506,14 +595,16 @@ impl SqliteConn {
         &mut self,
         outpoints: impl IntoIterator<Item = &'a (bitcoin::OutPoint, bitcoin::Txid)>,
     ) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for (outpoint, spend_txid) in outpoints {
                 db_tx.execute(
-                    "UPDATE coins SET spend_txid = ?1 WHERE txid = ?2 AND vout = ?3",
+                    "UPDATE coins SET spend_txid = ?1 WHERE txid = ?2 AND vout = ?3 AND wallet_id = ?4",
                     rusqlite::params![
                         spend_txid[..].to_vec(),
                         outpoint.txid[..].to_vec(),
                         outpoint.vout,
+                        wallet_id,
                     ],
                 )?;
             }
@@ -528,13 +619,15 @@ impl SqliteConn {
         &mut self,
         outpoints: impl IntoIterator<Item = &'a bitcoin::OutPoint>,
     ) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for outpoint in outpoints {
                 db_tx.execute(
-                    "UPDATE coins SET spend_txid = NULL, spend_block_height = NULL, spend_block_time = NULL WHERE txid = ?1 AND vout = ?2",
+                    "UPDATE coins SET spend_txid = NULL, spend_block_height = NULL, spend_block_time = NULL WHERE txid = ?1 AND vout = ?2 AND wallet_id = ?3",
                     rusqlite::params![
                         outpoint.txid[..].to_vec(),
                         outpoint.vout,
+                        wallet_id,
                     ],
                 )?;
             }
@@ -544,22 +637,63 @@ impl SqliteConn {
         .expect("Database must be available")
     }
 
+    /// Mark a set of coins as frozen, or not, by the user.
+    pub fn set_coins_frozen<'a>(
+        &mut self,
+        outpoints: impl IntoIterator<Item = &'a bitcoin::OutPoint>,
+        is_frozen: bool,
+    ) {
+        let wallet_id = self.wallet_id;
+        db_exec(&mut self.conn, |db_tx| {
+            for outpoint in outpoints {
+                db_tx.execute(
+                    "UPDATE coins SET is_frozen = ?1 WHERE txid = ?2 AND vout = ?3 AND wallet_id = ?4",
+                    rusqlite::params![is_frozen, outpoint.txid[..].to_vec(), outpoint.vout, wallet_id],
+                )?;
+            }
+
+            Ok(())
+        })
+        .expect("Database must be available")
+    }
+
+    /// Mark a set of coins to be frozen once they are stored in database.
+    pub fn set_coins_pending_frozen<'a>(
+        &mut self,
+        outpoints: impl IntoIterator<Item = &'a bitcoin::OutPoint>,
+    ) {
+        let wallet_id = self.wallet_id;
+        db_exec(&mut self.conn, |db_tx| {
+            for outpoint in outpoints {
+                db_tx.execute(
+                    "INSERT OR IGNORE INTO pending_frozen_coins (wallet_id, txid, vout) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![wallet_id, outpoint.txid[..].to_vec(), outpoint.vout],
+                )?;
+            }
+
+            Ok(())
+        })
+        .expect("Database must be available")
+    }
+
     /// Mark the Spend transaction of a given set of coins as being confirmed at a given
     /// block.
     pub fn confirm_spend<'a>(
         &mut self,
         outpoints: impl IntoIterator<Item = &'a (bitcoin::OutPoint, bitcoin::Txid, i32, u32)>,
     ) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for (outpoint, spend_txid, height, time) in outpoints {
                 db_tx.execute(
-                    "UPDATE coins SET spend_txid = ?1, spend_block_height = ?2, spend_block_time = ?3 WHERE txid = ?4 AND vout = ?5",
+                    "UPDATE coins SET spend_txid = ?1, spend_block_height = ?2, spend_block_time = ?3 WHERE txid = ?4 AND vout = ?5 AND wallet_id = ?6",
                     rusqlite::params![
                         spend_txid[..].to_vec(),
                         height,
                         time,
                         outpoint.txid[..].to_vec(),
                         outpoint.vout,
+                        wallet_id,
                     ],
                 )?;
             }
@@ -572,8 +706,8 @@ impl SqliteConn {
     pub fn db_address(&mut self, address: &bitcoin::Address) -> Option<DbAddress> {
         db_query(
             &mut self.conn,
-            "SELECT * FROM addresses WHERE receive_address = ?1 OR change_address = ?1",
-            rusqlite::params![address.to_string()],
+            "SELECT * FROM addresses WHERE (receive_address = ?1 OR change_address = ?1) AND wallet_id = ?2",
+            rusqlite::params![address.to_string(), self.wallet_id],
             |row| row.try_into(),
         )
         .expect("Db must not fail")
@@ -587,8 +721,8 @@ impl SqliteConn {
     pub fn db_spend(&mut self, txid: &bitcoin::Txid) -> Option<DbSpendTransaction> {
         db_query(
             &mut self.conn,
-            "SELECT * FROM spend_transactions WHERE txid = ?1",
-            rusqlite::params![txid[..].to_vec()],
+            "SELECT * FROM spend_transactions WHERE txid = ?1 AND wallet_id = ?2",
+            rusqlite::params![txid[..].to_vec(), self.wallet_id],
             |row| row.try_into(),
         )
         .expect("Db must not fail")
@@ -599,11 +733,12 @@ impl SqliteConn {
     pub fn store_spend(&mut self, psbt: &Psbt) {
         let txid = &psbt.unsigned_tx.txid()[..].to_vec();
 
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             db_tx.execute(
-                "INSERT into spend_transactions (psbt, txid, updated_at) VALUES (?1, ?2, ?3) \
+                "INSERT into spend_transactions (psbt, txid, updated_at, wallet_id) VALUES (?1, ?2, ?3, ?4) \
                  ON CONFLICT DO UPDATE SET psbt=excluded.psbt",
-                rusqlite::params![psbt.serialize(), txid, curr_timestamp()],
+                rusqlite::params![psbt.serialize(), txid, curr_timestamp(), wallet_id],
             )?;
             Ok(())
         })
@@ -613,14 +748,15 @@ impl SqliteConn {
     pub fn list_spend(&mut self) -> Vec<DbSpendTransaction> {
         db_query(
             &mut self.conn,
-            "SELECT * FROM spend_transactions",
-            rusqlite::params![],
+            "SELECT * FROM spend_transactions WHERE wallet_id = ?1",
+            rusqlite::params![self.wallet_id],
             |row| row.try_into(),
         )
         .expect("Db must not fail")
     }
 
     pub fn update_labels(&mut self, items: &HashMap<LabelItem, Option<String>>) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             for (labelled, kind, value) in items
                 .iter()
@@ -635,12 +771,12 @@ impl SqliteConn {
                     db_tx.execute(
                         "INSERT INTO labels (wallet_id, item, item_kind, value) VALUES (?1, ?2, ?3, ?4) \
                         ON CONFLICT DO UPDATE SET value=excluded.value",
-                        rusqlite::params![WALLET_ID, labelled, kind as i64, value],
+                        rusqlite::params![wallet_id, labelled, kind as i64, value],
                     )?;
                 } else {
                     db_tx.execute(
                         "DELETE FROM labels WHERE wallet_id = ?1 AND item = ?2",
-                        rusqlite::params![WALLET_ID, labelled],
+                        rusqlite::params![wallet_id, labelled],
                     )?;
                 }
             }
@@ -651,16 +787,66 @@ impl SqliteConn {
 
     pub fn db_labels(&mut self, items: &HashSet<LabelItem>) -> Vec<DbLabel> {
         let query = format!(
-            "SELECT * FROM labels where item in ({})",
+            "SELECT * FROM labels where wallet_id = ?1 AND item in ({})",
             items
                 .iter()
                 .map(|a| format!("'{}'", a))
                 .collect::<Vec<String>>()
                 .join(",")
         );
-        db_query(&mut self.conn, &query, rusqlite::params![], |row| {
-            row.try_into()
+        db_query(
+            &mut self.conn,
+            &query,
+            rusqlite::params![self.wallet_id],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+    }
+
+    /// Get all the labels, in the order they were first set.
+    pub fn db_list_labels(&mut self) -> Vec<DbLabel> {
+        db_query(
+            &mut self.conn,
+            "SELECT * FROM labels WHERE wallet_id = ?1 ORDER BY id",
+            rusqlite::params![self.wallet_id],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+    }
+
+    /// Record these events in the journal, in this order.
+    pub fn store_events(&mut self, events: &[Event]) {
+        let wallet_id = self.wallet_id;
+        db_exec(&mut self.conn, |db_tx| {
+            let timestamp = curr_timestamp();
+            for event in events {
+                let event = serde_json::to_string(event).expect("Serialization can't fail");
+                db_tx.execute(
+                    "INSERT INTO events (timestamp, event, wallet_id) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![timestamp, event, wallet_id],
+                )?;
+            }
+            // Only keep the most recent events.
+            db_tx.execute(
+                "DELETE FROM events WHERE wallet_id = ?1 AND id <= ( \
+                    SELECT id FROM events WHERE wallet_id = ?1 ORDER BY id DESC LIMIT 1 OFFSET ?2 \
+                )",
+                rusqlite::params![wallet_id, MAX_JOURNAL_EVENTS],
+            )?;
+            Ok(())
         })
+        .expect("Db must not fail");
+    }
+
+    /// Retrieves at most `limit` events from the journal, in order, starting after the event with
+    /// the given id.
+    pub fn db_events(&mut self, after_id: u64, limit: u64) -> Vec<DbEvent> {
+        db_query(
+            &mut self.conn,
+            "SELECT * FROM events WHERE id > ?1 AND wallet_id = ?3 ORDER BY id LIMIT ?2",
+            rusqlite::params![after_id, limit, self.wallet_id],
+            |row| row.try_into(),
+        )
         .expect("Db must not fail")
     }
 
@@ -672,20 +858,22 @@ impl SqliteConn {
             "SELECT DISTINCT(txid) FROM ( \
                 SELECT * from ( \
                     SELECT txid, blocktime AS date FROM coins \
-                    WHERE blocktime >= (?1) \
+                    WHERE wallet_id = (?4) \
+                    AND blocktime >= (?1) \
                     AND blocktime <= (?2) \
                     ORDER BY blocktime \
                 ) \
                 UNION \
                 SELECT * FROM (
                     SELECT spend_txid AS txid, spend_block_time AS date FROM coins \
-                    WHERE spend_block_time >= (?1) \
+                    WHERE wallet_id = (?4) \
+                    AND spend_block_time >= (?1) \
                     AND spend_block_time <= (?2) \
                     ORDER BY spend_block_time \
                 ) \
                 ORDER BY date DESC LIMIT (?3) \
             )",
-            rusqlite::params![start, end, limit],
+            rusqlite::params![start, end, limit, self.wallet_id],
             |row| {
                 let txid: Vec<u8> = row.get(0)?;
                 let txid: bitcoin::Txid =
@@ -742,15 +930,16 @@ impl SqliteConn {
             INNER JOIN ( \
                 SELECT txid, blockheight, blocktime \
                 FROM coins \
-                WHERE wallet_id = {WALLET_ID} \
+                WHERE wallet_id = {wallet_id} \
                 UNION \
                 SELECT spend_txid, spend_block_height, spend_block_time \
                 FROM coins \
-                WHERE wallet_id = {WALLET_ID} \
+                WHERE wallet_id = {wallet_id} \
                 AND spend_txid IS NOT NULL \
             ) c ON t.txid = c.txid \
-            WHERE t.txid in ({})",
-            txids
+            WHERE t.txid in ({txids})",
+            wallet_id = self.wallet_id,
+            txids = txids
                 .iter()
                 .map(|txid| format!("x'{}'", FrontwardHexTxid(*txid)))
                 .collect::<Vec<_>>()
@@ -774,10 +963,11 @@ impl SqliteConn {
     }
 
     pub fn delete_spend(&mut self, txid: &bitcoin::Txid) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             db_tx.execute(
-                "DELETE FROM spend_transactions WHERE txid = ?1",
-                rusqlite::params![txid[..].to_vec()],
+                "DELETE FROM spend_transactions WHERE txid = ?1 AND wallet_id = ?2",
+                rusqlite::params![txid[..].to_vec(), wallet_id],
             )?;
             Ok(())
         })
@@ -796,18 +986,19 @@ impl SqliteConn {
     /// This will have to be updated if we are to add new fields based on block data
     /// in the database eventually.
     pub fn rollback_tip(&mut self, new_tip: &BlockChainTip) {
+        let wallet_id = self.wallet_id;
         db_exec(&mut self.conn, |db_tx| {
             db_tx.execute(
-                "UPDATE coins SET blockheight = NULL, blocktime = NULL, spend_block_height = NULL, spend_block_time = NULL WHERE blockheight > ?1",
-                rusqlite::params![new_tip.height],
+                "UPDATE coins SET blockheight = NULL, blocktime = NULL, spend_block_height = NULL, spend_block_time = NULL WHERE blockheight > ?1 AND wallet_id = ?2",
+                rusqlite::params![new_tip.height, wallet_id],
             )?;
             db_tx.execute(
-                "UPDATE coins SET spend_block_height = NULL, spend_block_time = NULL WHERE spend_block_height > ?1",
-                rusqlite::params![new_tip.height],
+                "UPDATE coins SET spend_block_height = NULL, spend_block_time = NULL WHERE spend_block_height > ?1 AND wallet_id = ?2",
+                rusqlite::params![new_tip.height, wallet_id],
             )?;
             db_tx.execute(
-                "UPDATE tip SET blockheight = (?1), blockhash = (?2)",
-                rusqlite::params![new_tip.height, new_tip.hash[..].to_vec()],
+                "UPDATE tip SET blockheight = (?1), blockhash = (?2) WHERE wallet_id = (?3)",
+                rusqlite::params![new_tip.height, new_tip.hash[..].to_vec(), wallet_id],
             )?;
             Ok(())
         })
@@ -1069,6 +1260,96 @@ CREATE TABLE labels (
     item TEXT UNIQUE NOT NULL,
     value TEXT NOT NULL
 );
+";
+
+    const V7_SCHEMA: &str = "
+CREATE TABLE version (
+    version INTEGER NOT NULL
+);
+
+/* About the Bitcoin network. */
+CREATE TABLE tip (
+    network TEXT NOT NULL,
+    blockheight INTEGER,
+    blockhash BLOB
+);
+
+/* This stores metadata about our wallet. We only support single wallet for
+ * now (and the foreseeable future).
+ *
+ * The 'timestamp' field is the creation date of the wallet. We guarantee to have seen all
+ * information related to our descriptor(s) that occured after this date.
+ * The optional 'rescan_timestamp' field is a the timestamp we need to rescan the chain
+ * for events related to our descriptor(s) from.
+ */
+CREATE TABLE wallets (
+    id INTEGER PRIMARY KEY NOT NULL,
+    timestamp INTEGER NOT NULL,
+    main_descriptor TEXT NOT NULL,
+    deposit_derivation_index INTEGER NOT NULL,
+    change_derivation_index INTEGER NOT NULL,
+    rescan_timestamp INTEGER
+);
+
+CREATE TABLE coins (
+    id INTEGER PRIMARY KEY NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    blockheight INTEGER,
+    blocktime INTEGER,
+    txid BLOB NOT NULL,
+    vout INTEGER NOT NULL,
+    amount_sat INTEGER NOT NULL,
+    derivation_index INTEGER NOT NULL,
+    is_change BOOLEAN NOT NULL CHECK (is_change IN (0,1)),
+    spend_txid BLOB,
+    spend_block_height INTEGER,
+    spend_block_time INTEGER,
+    is_immature BOOLEAN NOT NULL CHECK (is_immature IN (0,1)),
+    is_frozen BOOLEAN NOT NULL DEFAULT 0 CHECK (is_frozen IN (0,1)),
+    UNIQUE (txid, vout),
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT,
+    FOREIGN KEY (txid) REFERENCES transactions (txid)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT,
+    FOREIGN KEY (spend_txid) REFERENCES transactions (txid)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
+);
+
+CREATE TABLE addresses (
+    receive_address TEXT NOT NULL UNIQUE,
+    change_address TEXT NOT NULL UNIQUE,
+    derivation_index INTEGER NOT NULL UNIQUE
+);
+
+CREATE TABLE transactions (
+    id INTEGER PRIMARY KEY NOT NULL,
+    txid BLOB UNIQUE NOT NULL,
+    tx BLOB UNIQUE NOT NULL
+);
+
+CREATE TABLE spend_transactions (
+    id INTEGER PRIMARY KEY NOT NULL,
+    psbt BLOB UNIQUE NOT NULL,
+    txid BLOB UNIQUE NOT NULL,
+    updated_at INTEGER
+);
+
+CREATE TABLE labels (
+    id INTEGER PRIMARY KEY NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    item_kind INTEGER NOT NULL CHECK (item_kind IN (0,1,2)),
+    item TEXT UNIQUE NOT NULL,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    event TEXT NOT NULL
+);
 ";
 
     fn psbt_from_str(psbt_str: &str) -> Psbt {
@@ -1100,6 +1381,74 @@ CREATE TABLE labels (
         (tmp_dir, options, secp, db)
     }
 
+    // Create a database with a schema from before the tip and addresses were scoped to a wallet.
+    // Store coins in a database created with a legacy schema, which has no table of the coins
+    // to freeze once found.
+    fn new_legacy_unspent_coins(conn: &mut SqliteConn, coins: &[Coin]) {
+        db_exec(&mut conn.conn, |db_tx| {
+            for coin in coins {
+                let deriv_index: u32 = coin.derivation_index.into();
+                db_tx.execute(
+                    "INSERT INTO coins (wallet_id, txid, vout, amount_sat, derivation_index, is_change, is_immature) \
+                         VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        coin.outpoint.txid[..].to_vec(),
+                        coin.outpoint.vout,
+                        coin.amount.to_sat(),
+                        deriv_index,
+                        coin.is_change,
+                        coin.is_immature,
+                    ],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn create_legacy_db(
+        db_path: &path::Path,
+        options: FreshDbOptions,
+        secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    ) {
+        utils::create_db_file(db_path).unwrap();
+        let mut conn = rusqlite::Connection::open(db_path).unwrap();
+        db_exec(&mut conn, |tx| {
+            tx.execute_batch(options.schema)?;
+            tx.execute(
+                "INSERT INTO version (version) VALUES (?1)",
+                rusqlite::params![options.version],
+            )?;
+            tx.execute(
+                "INSERT INTO tip (network, blockheight, blockhash) VALUES (?1, NULL, NULL)",
+                rusqlite::params![options.bitcoind_network.to_string()],
+            )?;
+            tx.execute(
+                "INSERT INTO wallets (timestamp, main_descriptor, deposit_derivation_index, change_derivation_index) \
+                         VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![curr_timestamp(), options.main_descriptor.to_string(), 0, 0],
+            )?;
+            for index in 0..LOOK_AHEAD_LIMIT {
+                let receive_address = options
+                    .main_descriptor
+                    .receive_descriptor()
+                    .derive(index.into(), secp)
+                    .address(options.bitcoind_network);
+                let change_address = options
+                    .main_descriptor
+                    .change_descriptor()
+                    .derive(index.into(), secp)
+                    .address(options.bitcoind_network);
+                tx.execute(
+                    "INSERT INTO addresses (receive_address, change_address, derivation_index) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![receive_address.to_string(), change_address.to_string(), index],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
     #[test]
     fn db_startup_sanity_checks() {
         let tmp_dir = tmp_dir();
@@ -1202,16 +1551,221 @@ CREATE TABLE labels (
             let db_labels = conn.db_labels(&items);
             assert_eq!(db_labels[0].value, "hello again");
 
+            // We can list all the labels, in the order they were first set.
+            let outpoint =
+                LabelItem::from_str(&format!("{}:1", txid_str), bitcoin::Network::Bitcoin).unwrap();
+            let mut outpoint_labels = HashMap::new();
+            outpoint_labels.insert(outpoint, Some("coin".to_string()));
+            conn.update_labels(&outpoint_labels);
+            conn.update_labels(&txids_labels);
+            let all_labels: Vec<_> = conn
+                .db_list_labels()
+                .into_iter()
+                .map(|l| (l.item_kind, l.value))
+                .collect();
+            assert_eq!(
+                all_labels,
+                vec![
+                    (DbLabelledKind::Txid, "hello again".to_string()),
+                    (DbLabelledKind::OutPoint, "coin".to_string())
+                ]
+            );
+
             // Now delete the label by passing a None value.
             *txids_labels.get_mut(&txid).unwrap() = None;
             conn.update_labels(&txids_labels);
             let db_labels = conn.db_labels(&items);
             assert!(db_labels.is_empty());
+            assert_eq!(conn.db_list_labels().len(), 1);
         }
 
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
+    #[test]
+    fn db_events() {
+        let (tmp_dir, _, _, db) = dummy_db();
+
+        {
+            let mut conn = db.connection().unwrap();
+            assert!(conn.db_events(0, 100).is_empty());
+
+            let txid = bitcoin::Txid::from_str(
+                "0c62a990d20d54429e70859292e82374ba6b1b951a3ab60f26bb65fee5724ff7",
+            )
+            .unwrap();
+            let events = vec![
+                Event::SpendUpdated { txid },
+                Event::RescanProgress { progress: 0.5 },
+                Event::SpendDeleted { txid },
+            ];
+            conn.store_events(&events);
+
+            // They are returned in order, with increasing ids.
+            let db_events = conn.db_events(0, 100);
+            assert_eq!(
+                db_events
+                    .iter()
+                    .map(|e| e.event.clone())
+                    .collect::<Vec<_>>(),
+                events
+            );
+            assert!(db_events.windows(2).all(|w| w[0].id < w[1].id));
+
+            // We can query the events after a given one, and limit their number.
+            let after_first = conn.db_events(db_events[0].id as u64, 100);
+            assert_eq!(after_first, db_events[1..]);
+            let limited = conn.db_events(0, 2);
+            assert_eq!(limited, db_events[..2]);
+            assert!(conn.db_events(db_events[2].id as u64, 100).is_empty());
+
+            // Past the maximum number of events, the oldest ones are removed. The events after
+            // a removed one are returned from the oldest one kept.
+            let spend_updated = Event::SpendUpdated { txid };
+            conn.store_events(&vec![spend_updated; MAX_JOURNAL_EVENTS as usize - 2]);
+            let db_events_full = conn.db_events(0, MAX_JOURNAL_EVENTS + 1);
+            assert_eq!(db_events_full.len(), MAX_JOURNAL_EVENTS as usize);
+            assert_eq!(db_events_full[..2], db_events[1..]);
+            assert_eq!(conn.db_events(db_events[0].id as u64, 2), db_events[1..]);
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn db_multiple_wallets() {
+        let (tmp_dir, options, secp, db) = dummy_db();
+        let other_desc = LianaDescriptor::from_str("wsh(andor(pk([aabbccdd]tpubDExU4YLJkyQ9RRbVScQq2brFxWWha7WmAUByPWyaWYwmcTv3Shx8aHp6mVwuE5n4TeM4z5DTWGf2YhNPmXtfvyr8cUDVvA3txdrFnFgNdF7/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
+
+        // The wallet for the main descriptor is the one the database was created with. A fresh
+        // wallet is inserted for another descriptor, and retrieved from then on. It is created at
+        // the given timestamp and must be rescanned from there.
+        let main_db = db
+            .wallet(
+                options.bitcoind_network,
+                &options.main_descriptor,
+                Some(1_600_000_000),
+                &secp,
+            )
+            .unwrap();
+        let main_wallet = main_db.connection().unwrap().db_wallet();
+        assert_eq!(main_wallet.id, 1);
+        assert!(main_wallet.rescan_timestamp.is_none());
+        let other_db = db
+            .wallet(
+                options.bitcoind_network,
+                &other_desc,
+                Some(1_600_000_000),
+                &secp,
+            )
+            .unwrap();
+        let other_wallet = other_db.connection().unwrap().db_wallet();
+        assert_ne!(other_wallet.id, 1);
+        assert_eq!(other_wallet.timestamp, 1_600_000_000);
+        assert_eq!(other_wallet.rescan_timestamp, Some(1_600_000_000));
+        let other_db = db
+            .wallet(options.bitcoind_network, &other_desc, None, &secp)
+            .unwrap();
+        let other_id = other_wallet.id;
+        let other_wallet = other_db.connection().unwrap().db_wallet();
+        assert_eq!(other_wallet.id, other_id);
+        assert_eq!(other_wallet.rescan_timestamp, Some(1_600_000_000));
+        other_db
+            .sanity_check(options.bitcoind_network, &other_desc)
+            .unwrap();
+
+        let mut conn = db.connection().unwrap();
+        let mut other_conn = other_db.connection().unwrap();
+        assert_eq!(other_conn.db_wallet().main_descriptor, other_desc);
+
+        // Each wallet has its own tip.
+        let new_tip = BlockChainTip {
+            height: 746756,
+            hash: bitcoin::BlockHash::from_str(
+                "00000000000000000006d50e4c9fd269ddf690c94f422dff85e96f1a84b3a615",
+            )
+            .unwrap(),
+        };
+        conn.update_tip(&new_tip);
+        assert_eq!(conn.db_tip().block_height, Some(new_tip.height));
+        let other_tip = other_conn.db_tip();
+        assert!(other_tip.block_height.is_none() && other_tip.block_hash.is_none());
+        assert_eq!(other_tip.network, options.bitcoind_network);
+
+        // Each wallet has its own addresses and derivation indexes.
+        let other_addr = other_desc
+            .receive_descriptor()
+            .derive(5.into(), &secp)
+            .address(options.bitcoind_network);
+        assert!(conn.db_address(&other_addr).is_none());
+        assert_eq!(
+            other_conn.db_address(&other_addr).unwrap().derivation_index,
+            5.into()
+        );
+        other_conn.set_derivation_index(10.into(), false, &secp);
+        assert_eq!(other_conn.db_wallet().deposit_derivation_index, 10.into());
+        assert_eq!(conn.db_wallet().deposit_derivation_index, 0.into());
+        let other_addr = other_desc
+            .receive_descriptor()
+            .derive((10 + LOOK_AHEAD_LIMIT - 1).into(), &secp)
+            .address(options.bitcoind_network);
+        assert!(other_conn.db_address(&other_addr).is_some());
+
+        // Each wallet has its own coins.
+        let tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::from_height(0).unwrap(),
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+        other_conn.new_txs(&[tx.clone()]);
+        let outpoint = bitcoin::OutPoint::new(tx.txid(), 0);
+        other_conn.new_unspent_coins(&[Coin {
+            outpoint,
+            is_immature: false,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(98765),
+            derivation_index: 5.into(),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_frozen: false,
+        }]);
+        conn.confirm_coins(&[(outpoint, 746756, 1_700_000_000)]);
+        assert!(conn.coins(&[], &[]).is_empty());
+        let other_coins = other_conn.coins(&[], &[]);
+        assert_eq!(other_coins.len(), 1);
+        assert!(other_coins[0].block_info.is_none());
+        assert!(conn.list_wallet_transactions(&[tx.txid()]).is_empty());
+        assert_eq!(other_conn.list_wallet_transactions(&[tx.txid()]).len(), 1);
+
+        // Each wallet has its own spend transactions.
+        let psbt = psbt_from_str("cHNidP8BAIkCAAAAAWi3OFgkj1CqCDT3Swm8kbxZS9lxz4L3i4W2v9KGC7nqAQAAAAD9////AkANAwAAAAAAIgAg27lNc1rog+dOq80ohRuds4Hgg/RcpxVun2XwgpuLSrFYMwwAAAAAACIAIDyWveqaElWmFGkTbFojg1zXWHODtiipSNjfgi2DqBy9AAAAAAABAOoCAAAAAAEBsRWl70USoAFFozxc86pC7Dovttdg4kvja//3WMEJskEBAAAAAP7///8CWKmCIk4GAAAWABRKBWYWkCNS46jgF0r69Ehdnq+7T0BCDwAAAAAAIgAgTt5fs+CiB+FRzNC8lHcgWLH205sNjz1pT59ghXlG5tQCRzBEAiBXK9MF8z3bX/VnY2aefgBBmiAHPL4tyDbUOe7+KpYA4AIgL5kU0DFG8szKd+szRzz/OTUWJ0tZqij41h2eU9rSe1IBIQNBB1hy+jKsg1TihMT0dXw7etpu9TkO3NuvhBDFJlBj1cP2AQABAStAQg8AAAAAACIAIE7eX7PgogfhUczQvJR3IFix9tObDY89aU+fYIV5RubUIgICSKJsNs0zFJN58yd2aYQ+C3vhMbi0x7k0FV3wBhR4THlIMEUCIQCPWWWOhs2lThxOq/G8X2fYBRvM9MXSm7qPH+dRVYQZEwIgfut2vx3RvwZWcgEj4ohQJD5lNJlwOkA4PAiN1fjx6dABIgID3mvj1zerZKohOVhKCiskYk+3qrCum6PIwDhQ16ePACpHMEQCICZNR+0/1hPkrDQwPFmg5VjUHkh6aK9cXUu3kPbM8hirAiAyE/5NUXKfmFKij30isuyysJbq8HrURjivd+S9vdRGKQEBBZNSIQJIomw2zTMUk3nzJ3ZphD4Le+ExuLTHuTQVXfAGFHhMeSEC9OfCXl+sJOrxUFLBuMV4ZUlJYjuzNGZSld5ioY14y8FSrnNkUSED3mvj1zerZKohOVhKCiskYk+3qrCum6PIwDhQ16ePACohA+ECH+HlR+8Sf3pumaXH3IwSsoqSLCH7H1THiBP93z3ZUq9SsmgiBgJIomw2zTMUk3nzJ3ZphD4Le+ExuLTHuTQVXfAGFHhMeRxjat8/MAAAgAEAAIAAAACAAgAAgAAAAAABAAAAIgYC9OfCXl+sJOrxUFLBuMV4ZUlJYjuzNGZSld5ioY14y8Ec/9Y8jTAAAIABAACAAAAAgAIAAIAAAAAAAQAAACIGA95r49c3q2SqITlYSgorJGJPt6qwrpujyMA4UNenjwAqHGNq3z8wAACAAQAAgAEAAIACAACAAAAAAAEAAAAiBgPhAh/h5UfvEn96bpmlx9yMErKKkiwh+x9Ux4gT/d892Rz/1jyNMAAAgAEAAIABAACAAgAAgAAAAAABAAAAACICAlBQ7gGocg7eF3sXrCio+zusAC9+xfoyIV95AeR69DWvHGNq3z8wAACAAQAAgAEAAIACAACAAAAAAAMAAAAiAgMvVy984eg8Kgvj058PBHetFayWbRGb7L0DMnS9KHSJzBxjat8/MAAAgAEAAIAAAACAAgAAgAAAAAADAAAAIgIDSRIG1dn6njdjsDXenHa2lUvQHWGPLKBVrSzbQOhiIxgc/9Y8jTAAAIABAACAAAAAgAIAAIAAAAAAAwAAACICA0/epE59sVEj7Et0I4R9qJQNuX23RNvDZKCRL7eUps9FHP/WPI0wAACAAQAAgAEAAIACAACAAAAAAAMAAAAAIgICgldCOK6iHscv//2NipgaMABLV5TICU/zlP7HlQmlg08cY2rfPzAAAIABAACAAQAAgAIAAIABAAAAAQAAACICApb0p9rfpJshB3J186PGWrvzQdixcwQZWmebOUMdkquZHP/WPI0wAACAAQAAgAAAAIACAACAAQAAAAEAAAAiAgLY5q+unoDxC/HI5BaNiPq12ei1REZIcUAN304JfKXUwxz/1jyNMAAAgAEAAIABAACAAgAAgAEAAAABAAAAIgIDg6cUVCJB79cMcofiURHojxFARWyS4YEhJNRixuOZZRgcY2rfPzAAAIABAACAAAAAgAIAAIABAAAAAQAAAAA=");
+        conn.store_spend(&psbt);
+        assert_eq!(conn.list_spend().len(), 1);
+        assert!(other_conn.list_spend().is_empty());
+        assert!(other_conn.db_spend(&psbt.unsigned_tx.txid()).is_none());
+
+        // Each wallet has its own labels, and the same item may be labelled in both.
+        let item = LabelItem::Txid(tx.txid());
+        let mut labels = HashMap::new();
+        labels.insert(item.clone(), Some("main".to_string()));
+        conn.update_labels(&labels);
+        assert!(other_conn.db_list_labels().is_empty());
+        labels.insert(item.clone(), Some("other".to_string()));
+        other_conn.update_labels(&labels);
+        let items: HashSet<_> = vec![item].into_iter().collect();
+        assert_eq!(conn.db_labels(&items)[0].value, "main");
+        assert_eq!(other_conn.db_labels(&items)[0].value, "other");
+
+        // Each wallet has its own events.
+        other_conn.store_events(&[Event::RescanProgress { progress: 0.5 }]);
+        assert!(conn.db_events(0, 100).is_empty());
+        assert_eq!(other_conn.db_events(0, 100).len(), 1);
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
     #[test]
     fn db_coins() {
         let (tmp_dir, _, _, db) = dummy_db();
@@ -1243,6 +1797,7 @@ CREATE TABLE labels (
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             conn.new_unspent_coins(&[coin_a]);
             // We can query by status and/or outpoint.
@@ -1289,6 +1844,7 @@ CREATE TABLE labels (
                 is_change: true,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             conn.new_unspent_coins(&[coin_b]);
             // Both coins are unconfirmed.
@@ -1420,6 +1976,7 @@ CREATE TABLE labels (
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let outpoint_d = bitcoin::OutPoint::new(txs.get(4).unwrap().txid(), 43);
             let coin_d = Coin {
@@ -1431,6 +1988,7 @@ CREATE TABLE labels (
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             conn.new_unspent_coins(&[coin_c, coin_d]);
 
@@ -1543,6 +2101,7 @@ CREATE TABLE labels (
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             conn.new_unspent_coins(&[coin_a]);
             assert_eq!(conn.coins(&[], &[])[0].outpoint, coin_a.outpoint);
@@ -1585,6 +2144,7 @@ CREATE TABLE labels (
                 is_change: true,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             conn.new_unspent_coins(&[coin_b]);
             let outpoints: HashSet<bitcoin::OutPoint> = conn
@@ -1708,6 +2268,7 @@ CREATE TABLE labels (
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             conn.new_unspent_coins(&[coin_imma]);
             let outpoints: HashSet<bitcoin::OutPoint> = conn
@@ -1724,6 +2285,43 @@ CREATE TABLE labels (
             conn.confirm_coins(&[(coin_imma.outpoint, height, time)]);
             let coin = conn.db_coins(&[coin_imma.outpoint]).pop().unwrap();
             assert!(!coin.is_immature);
+
+            // New coins are not frozen. We can freeze and unfreeze them.
+            assert!(conn
+                .db_coins(&[coin_a.outpoint, coin_imma.outpoint])
+                .iter()
+                .all(|c| !c.is_frozen));
+            conn.set_coins_frozen(&[coin_a.outpoint, coin_imma.outpoint], true);
+            assert!(conn
+                .db_coins(&[coin_a.outpoint, coin_imma.outpoint])
+                .iter()
+                .all(|c| c.is_frozen));
+            conn.set_coins_frozen(&[coin_a.outpoint], false);
+            assert!(!conn.db_coins(&[coin_a.outpoint]).pop().unwrap().is_frozen);
+            assert!(
+                conn.db_coins(&[coin_imma.outpoint])
+                    .pop()
+                    .unwrap()
+                    .is_frozen
+            );
+
+            // A coin may be marked to be frozen before it's found.
+            let coin_c = Coin {
+                outpoint: bitcoin::OutPoint::new(txs.get(3).unwrap().txid(), 43),
+                ..coin_imma
+            };
+            let coin_d = Coin {
+                outpoint: bitcoin::OutPoint::new(txs.get(3).unwrap().txid(), 44),
+                ..coin_imma
+            };
+            conn.set_coins_pending_frozen(&[coin_c.outpoint]);
+            conn.new_unspent_coins(&[coin_c, coin_d]);
+            assert!(conn.db_coins(&[coin_c.outpoint]).pop().unwrap().is_frozen);
+            assert!(!conn.db_coins(&[coin_d.outpoint]).pop().unwrap().is_frozen);
+            // It's only frozen once, if it's found again after it was removed it isn't frozen.
+            conn.remove_coins(&[coin_c.outpoint]);
+            conn.new_unspent_coins(&[coin_c]);
+            assert!(!conn.db_coins(&[coin_c.outpoint]).pop().unwrap().is_frozen);
         }
 
         fs::remove_dir_all(tmp_dir).unwrap();
@@ -1879,6 +2477,7 @@ CREATE TABLE labels (
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(1).unwrap().txid(), 2),
@@ -1892,6 +2491,7 @@ CREATE TABLE labels (
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(2).unwrap().txid(), 3),
@@ -1908,6 +2508,7 @@ CREATE TABLE labels (
                         height: 101_199,
                         time: 1_231_678,
                     }),
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(4).unwrap().txid(), 4),
@@ -1921,6 +2522,7 @@ CREATE TABLE labels (
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(5).unwrap().txid(), 5),
@@ -1937,6 +2539,7 @@ CREATE TABLE labels (
                         height: 101_105,
                         time: 1_201_678,
                     }),
+                    is_frozen: false,
                 },
             ];
             conn.new_unspent_coins(&coins);
@@ -2082,6 +2685,7 @@ CREATE TABLE labels (
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(1).unwrap().txid(), 2),
@@ -2095,6 +2699,7 @@ CREATE TABLE labels (
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(2).unwrap().txid(), 3),
@@ -2111,6 +2716,7 @@ CREATE TABLE labels (
                         height: 101_199,
                         time: 1_123_000,
                     }),
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(4).unwrap().txid(), 4),
@@ -2124,6 +2730,7 @@ CREATE TABLE labels (
                     is_change: false,
                     spend_txid: None,
                     spend_block: None,
+                    is_frozen: false,
                 },
                 Coin {
                     outpoint: bitcoin::OutPoint::new(txs.get(5).unwrap().txid(), 5),
@@ -2140,6 +2747,7 @@ CREATE TABLE labels (
                         height: 101_105,
                         time: 1_126_000,
                     }),
+                    is_frozen: false,
                 },
             ];
             conn.new_unspent_coins(&coins);
@@ -2268,6 +2876,7 @@ CREATE TABLE labels (
                     } else {
                         None
                     },
+                    is_frozen: false,
                 })
                 .collect();
 
@@ -2384,7 +2993,7 @@ CREATE TABLE labels (
     }
 
     #[test]
-    fn v0_to_v5_migration() {
+    fn v0_to_v9_migration() {
         let secp = secp256k1::Secp256k1::verification_only();
 
         // Create a database with version 0, using the old schema.
@@ -2396,7 +3005,7 @@ CREATE TABLE labels (
         let mut options = dummy_options();
         options.schema = V0_SCHEMA;
         options.version = 0;
-        create_fresh_db(&db_path, options, &secp).unwrap();
+        create_legacy_db(&db_path, options, &secp);
 
         // Two PSBTs we'll insert in the DB before and after the migration. Note they are random
         // PSBTs taken from the descriptor unit tests, it doesn't matter.
@@ -2448,7 +3057,7 @@ CREATE TABLE labels (
                         "INSERT INTO coins (wallet_id, txid, vout, amount_sat, derivation_index, is_change) \
                              VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
                         rusqlite::params![
-                            WALLET_ID,
+                            MAIN_WALLET_ID,
                             outpoint.txid[..].to_vec(),
                             outpoint.vout,
                             amount.to_sat(),
@@ -2490,7 +3099,17 @@ CREATE TABLE labels (
         {
             let mut conn = db.connection().unwrap();
             let version = conn.db_version();
-            assert_eq!(version, 5);
+            assert_eq!(version, 9);
+
+            // The tip and addresses were attributed to the main wallet.
+            assert_eq!(conn.db_tip().network, bitcoin::Network::Bitcoin);
+            let addr = conn
+                .db_wallet()
+                .main_descriptor
+                .receive_descriptor()
+                .derive(0.into(), &secp)
+                .address(bitcoin::Network::Bitcoin);
+            assert!(conn.db_address(&addr).is_some());
         }
         // We should now be able to insert another PSBT, to query both, and the first PSBT must
         // have no associated timestamp.
@@ -2530,6 +3149,7 @@ CREATE TABLE labels (
                 is_change: false,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             }]);
             let coins = conn.coins(&[], &[]);
             assert_eq!(coins.len(), 3);
@@ -2556,7 +3176,7 @@ CREATE TABLE labels (
     }
 
     #[test]
-    fn v3_to_v5_migration() {
+    fn v3_to_v9_migration() {
         let secp = secp256k1::Secp256k1::verification_only();
 
         // Create a database with version 3, using the old schema.
@@ -2568,7 +3188,7 @@ CREATE TABLE labels (
         let mut options = dummy_options();
         options.schema = V3_SCHEMA;
         options.version = 3;
-        create_fresh_db(&db_path, options, &secp).unwrap();
+        create_legacy_db(&db_path, options, &secp);
 
         {
             let db = SqliteDb::new(db_path.clone(), None, &secp).unwrap();
@@ -2598,6 +3218,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let coin_b = Coin {
                 outpoint: bitcoin::OutPoint::new(bitcoin_txs.get(1).unwrap().txid(), 19234),
@@ -2608,6 +3229,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let coin_c = Coin {
                 outpoint: bitcoin::OutPoint::new(bitcoin_txs.get(2).unwrap().txid(), 932),
@@ -2618,6 +3240,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let coin_d = Coin {
                 outpoint: bitcoin::OutPoint::new(bitcoin_txs.get(3).unwrap().txid(), 1456),
@@ -2628,6 +3251,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let coin_e = Coin {
                 outpoint: bitcoin::OutPoint::new(bitcoin_txs.get(4).unwrap().txid(), 4633),
@@ -2638,6 +3262,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let coin_imma_a = Coin {
                 outpoint: bitcoin::OutPoint::new(bitcoin_txs.get(5).unwrap().txid(), 5),
@@ -2648,6 +3273,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             let coin_imma_b = Coin {
                 outpoint: bitcoin::OutPoint::new(bitcoin_txs.get(6).unwrap().txid(), 19234),
@@ -2658,6 +3284,7 @@ CREATE TABLE labels (
                 block_info: None,
                 spend_txid: None,
                 spend_block: None,
+                is_frozen: false,
             };
             // After the following operations, the state of the coins will be:
             // - coin_a is spent.
@@ -2667,15 +3294,18 @@ CREATE TABLE labels (
             // - coin_e is the unconfirmed output of coin_d's spend.
             // - coin_imma_a is confirmed.
             // - coin_imma_b is still immature.
-            conn.new_unspent_coins(&[
-                coin_a,
-                coin_b,
-                coin_c,
-                coin_d,
-                coin_e,
-                coin_imma_a,
-                coin_imma_b,
-            ]);
+            new_legacy_unspent_coins(
+                &mut conn,
+                &[
+                    coin_a,
+                    coin_b,
+                    coin_c,
+                    coin_d,
+                    coin_e,
+                    coin_imma_a,
+                    coin_imma_b,
+                ],
+            );
             conn.confirm_coins(&[
                 (coin_a.outpoint, 175500, 1755001001),
                 (coin_b.outpoint, 175502, 1755001032),
@@ -2693,14 +3323,34 @@ CREATE TABLE labels (
                 245500,
                 1755003000,
             )]);
+
+            // The current schema has more columns than the v3 one, so query the coins manually
+            // before the migration. A v3 coin can't be frozen.
+            let coins_pre: Vec<DbCoin> = db_query(
+                &mut conn.conn,
+                "SELECT *, 0 AS is_frozen FROM coins",
+                rusqlite::params![],
+                |row| row.try_into(),
+            )
+            .unwrap();
+            assert_eq!(coins_pre.len(), 7);
+
+            // Migrate the DB.
+            maybe_apply_migration(&db_path, &bitcoin_txs).unwrap();
+            assert_eq!(conn.db_version(), 9);
+            // Migrating twice will be a no-op. No need to pass `bitcoin_txs` second time.
+            maybe_apply_migration(&db_path, &[]).unwrap();
+            assert!(conn.db_version() == 9);
+
+            // The coins were all preserved, and none of them is frozen.
             assert_eq!(conn.coins(&[CoinStatus::Unconfirmed], &[]).len(), 2);
             assert_eq!(conn.coins(&[CoinStatus::Confirmed], &[]).len(), 2);
             assert_eq!(conn.coins(&[CoinStatus::Spending], &[]).len(), 2);
             assert_eq!(conn.coins(&[CoinStatus::Spent], &[]).len(), 1);
-            let coins_pre = conn.coins(&[], &[]);
-            assert_eq!(coins_pre.len(), 7);
+            let coins_post = conn.coins(&[], &[]);
+            assert_eq!(coins_post.len(), 7);
             assert_eq!(
-                coins_pre
+                coins_post
                     .iter()
                     .filter(|c| c.is_immature)
                     .collect::<Vec<_>>()
@@ -2708,29 +3358,36 @@ CREATE TABLE labels (
                 1
             );
             assert_eq!(
-                coins_pre
+                coins_post
                     .iter()
                     .filter(|c| c.is_change)
                     .collect::<Vec<_>>()
                     .len(),
                 2
             );
-
-            // Migrate the DB.
-            maybe_apply_migration(&db_path, &bitcoin_txs).unwrap();
-            assert_eq!(conn.db_version(), 5);
-            // Migrating twice will be a no-op. No need to pass `bitcoin_txs` second time.
-            maybe_apply_migration(&db_path, &[]).unwrap();
-            assert!(conn.db_version() == 5);
-            let coins_post = conn.coins(&[], &[]);
             assert_eq!(coins_pre, coins_post);
+            assert!(coins_post.iter().all(|c| !c.is_frozen));
+            let outpoints_post: HashSet<_> = coins_post.iter().map(|c| c.outpoint).collect();
+            let outpoints_pre: HashSet<_> = [
+                coin_a,
+                coin_b,
+                coin_c,
+                coin_d,
+                coin_e,
+                coin_imma_a,
+                coin_imma_b,
+            ]
+            .iter()
+            .map(|c| c.outpoint)
+            .collect();
+            assert_eq!(outpoints_pre, outpoints_post);
         }
 
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
     #[test]
-    fn v4_to_v5_migration() {
+    fn v4_to_v9_migration() {
         let secp = secp256k1::Secp256k1::verification_only();
 
         // Create a database with version 3, using the old schema.
@@ -2804,15 +3461,17 @@ CREATE TABLE labels (
                 } else {
                     None
                 },
+                is_frozen: false,
             })
             .collect();
 
+        create_legacy_db(&db_path, options, &secp);
         {
-            let db = SqliteDb::new(db_path.clone(), Some(options), &secp).unwrap();
+            let db = SqliteDb::new(db_path.clone(), None, &secp).unwrap();
             let mut conn = db.connection().unwrap();
 
             // Insert all these coins into database.
-            conn.new_unspent_coins(&coins);
+            new_legacy_unspent_coins(&mut conn, &coins);
 
             // Confirm those which are supposed to be.
             let confirmed_coins: Vec<_> = coins
@@ -2883,4 +3542,139 @@ CREATE TABLE labels (
 
         fs::remove_dir_all(tmp_dir).unwrap();
     }
+
+    #[test]
+    fn v7_to_v8_migration() {
+        let secp = secp256k1::Secp256k1::verification_only();
+
+        // Create a database with version 7, using the old schema.
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let db_path: path::PathBuf = [tmp_dir.as_path(), path::Path::new("lianad_v7.sqlite3")]
+            .iter()
+            .collect();
+        let mut options = dummy_options();
+        options.schema = V7_SCHEMA;
+        options.version = 7;
+        let main_descriptor = options.main_descriptor.clone();
+        let network = options.bitcoind_network;
+        create_legacy_db(&db_path, options, &secp);
+
+        // Fill the tables which get scoped to a wallet.
+        let tip = BlockChainTip {
+            height: 746756,
+            hash: bitcoin::BlockHash::from_str(
+                "00000000000000000006d50e4c9fd269ddf690c94f422dff85e96f1a84b3a615",
+            )
+            .unwrap(),
+        };
+        let psbt = psbt_from_str("cHNidP8BAIkCAAAAAWi3OFgkj1CqCDT3Swm8kbxZS9lxz4L3i4W2v9KGC7nqAQAAAAD9////AkANAwAAAAAAIgAg27lNc1rog+dOq80ohRuds4Hgg/RcpxVun2XwgpuLSrFYMwwAAAAAACIAIDyWveqaElWmFGkTbFojg1zXWHODtiipSNjfgi2DqBy9AAAAAAABAOoCAAAAAAEBsRWl70USoAFFozxc86pC7Dovttdg4kvja//3WMEJskEBAAAAAP7///8CWKmCIk4GAAAWABRKBWYWkCNS46jgF0r69Ehdnq+7T0BCDwAAAAAAIgAgTt5fs+CiB+FRzNC8lHcgWLH205sNjz1pT59ghXlG5tQCRzBEAiBXK9MF8z3bX/VnY2aefgBBmiAHPL4tyDbUOe7+KpYA4AIgL5kU0DFG8szKd+szRzz/OTUWJ0tZqij41h2eU9rSe1IBIQNBB1hy+jKsg1TihMT0dXw7etpu9TkO3NuvhBDFJlBj1cP2AQABAStAQg8AAAAAACIAIE7eX7PgogfhUczQvJR3IFix9tObDY89aU+fYIV5RubUIgICSKJsNs0zFJN58yd2aYQ+C3vhMbi0x7k0FV3wBhR4THlIMEUCIQCPWWWOhs2lThxOq/G8X2fYBRvM9MXSm7qPH+dRVYQZEwIgfut2vx3RvwZWcgEj4ohQJD5lNJlwOkA4PAiN1fjx6dABIgID3mvj1zerZKohOVhKCiskYk+3qrCum6PIwDhQ16ePACpHMEQCICZNR+0/1hPkrDQwPFmg5VjUHkh6aK9cXUu3kPbM8hirAiAyE/5NUXKfmFKij30isuyysJbq8HrURjivd+S9vdRGKQEBBZNSIQJIomw2zTMUk3nzJ3ZphD4Le+ExuLTHuTQVXfAGFHhMeSEC9OfCXl+sJOrxUFLBuMV4ZUlJYjuzNGZSld5ioY14y8FSrnNkUSED3mvj1zerZKohOVhKCiskYk+3qrCum6PIwDhQ16ePACohA+ECH+HlR+8Sf3pumaXH3IwSsoqSLCH7H1THiBP93z3ZUq9SsmgiBgJIomw2zTMUk3nzJ3ZphD4Le+ExuLTHuTQVXfAGFHhMeRxjat8/MAAAgAEAAIAAAACAAgAAgAAAAAABAAAAIgYC9OfCXl+sJOrxUFLBuMV4ZUlJYjuzNGZSld5ioY14y8Ec/9Y8jTAAAIABAACAAAAAgAIAAIAAAAAAAQAAACIGA95r49c3q2SqITlYSgorJGJPt6qwrpujyMA4UNenjwAqHGNq3z8wAACAAQAAgAEAAIACAACAAAAAAAEAAAAiBgPhAh/h5UfvEn96bpmlx9yMErKKkiwh+x9Ux4gT/d892Rz/1jyNMAAAgAEAAIABAACAAgAAgAAAAAABAAAAACICAlBQ7gGocg7eF3sXrCio+zusAC9+xfoyIV95AeR69DWvHGNq3z8wAACAAQAAgAEAAIACAACAAAAAAAMAAAAiAgMvVy984eg8Kgvj058PBHetFayWbRGb7L0DMnS9KHSJzBxjat8/MAAAgAEAAIAAAACAAgAAgAAAAAADAAAAIgIDSRIG1dn6njdjsDXenHa2lUvQHWGPLKBVrSzbQOhiIxgc/9Y8jTAAAIABAACAAAAAgAIAAIAAAAAAAwAAACICA0/epE59sVEj7Et0I4R9qJQNuX23RNvDZKCRL7eUps9FHP/WPI0wAACAAQAAgAEAAIACAACAAAAAAAMAAAAAIgICgldCOK6iHscv//2NipgaMABLV5TICU/zlP7HlQmlg08cY2rfPzAAAIABAACAAQAAgAIAAIABAAAAAQAAACICApb0p9rfpJshB3J186PGWrvzQdixcwQZWmebOUMdkquZHP/WPI0wAACAAQAAgAAAAIACAACAAQAAAAEAAAAiAgLY5q+unoDxC/HI5BaNiPq12ei1REZIcUAN304JfKXUwxz/1jyNMAAAgAEAAIABAACAAgAAgAEAAAABAAAAIgIDg6cUVCJB79cMcofiURHojxFARWyS4YEhJNRixuOZZRgcY2rfPzAAAIABAACAAAAAgAIAAIABAAAAAQAAAAA=");
+        let txid = psbt.unsigned_tx.txid();
+        let event = Event::SpendUpdated { txid };
+        {
+            let mut conn = rusqlite::Connection::open(&db_path).unwrap();
+            db_exec(&mut conn, |tx| {
+                tx.execute(
+                    "UPDATE tip SET blockheight = (?1), blockhash = (?2)",
+                    rusqlite::params![tip.height, tip.hash[..].to_vec()],
+                )?;
+                tx.execute(
+                    "INSERT INTO spend_transactions (psbt, txid, updated_at) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![psbt.serialize(), txid[..].to_vec(), 1_700_000_000],
+                )?;
+                tx.execute(
+                    "INSERT INTO labels (wallet_id, item_kind, item, value) VALUES (1, 2, ?1, ?2)",
+                    rusqlite::params![txid.to_string(), "spend"],
+                )?;
+                tx.execute(
+                    "INSERT INTO events (timestamp, event) VALUES (?1, ?2)",
+                    rusqlite::params![1_700_000_000, serde_json::to_string(&event).unwrap()],
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        }
+
+        maybe_apply_migration(&db_path, &[]).unwrap();
+
+        // All the existing data now belongs to the main wallet.
+        let db = SqliteDb::new(db_path.clone(), None, &secp).unwrap();
+        db.sanity_check(network, &main_descriptor).unwrap();
+        let mut conn = db.connection().unwrap();
+        assert_eq!(conn.db_tip().block_height, Some(tip.height));
+        assert_eq!(conn.db_tip().block_hash, Some(tip.hash));
+        let addr = main_descriptor
+            .receive_descriptor()
+            .derive(5.into(), &secp)
+            .address(network);
+        assert_eq!(conn.db_address(&addr).unwrap().derivation_index, 5.into());
+        let spends = conn.list_spend();
+        assert_eq!(spends.len(), 1);
+        assert_eq!(spends[0].psbt, psbt);
+        assert_eq!(spends[0].updated_at, Some(1_700_000_000));
+        let labels = conn.db_list_labels();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(
+            (
+                labels[0].wallet_id,
+                labels[0].item.as_str(),
+                labels[0].value.as_str()
+            ),
+            (1, txid.to_string().as_str(), "spend")
+        );
+        let events = conn.db_events(0, 100);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, event);
+        assert_eq!(events[0].timestamp, 1_700_000_000);
+
+        // None of it is visible to another wallet, which can use the same label item.
+        let other_desc = LianaDescriptor::from_str("wsh(andor(pk([aabbccdd]tpubDExU4YLJkyQ9RRbVScQq2brFxWWha7WmAUByPWyaWYwmcTv3Shx8aHp6mVwuE5n4TeM4z5DTWGf2YhNPmXtfvyr8cUDVvA3txdrFnFgNdF7/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
+        let other_db = db.wallet(network, &other_desc, None, &secp).unwrap();
+        let mut other_conn = other_db.connection().unwrap();
+        assert!(other_conn.db_tip().block_height.is_none());
+        assert!(other_conn.db_address(&addr).is_none());
+        assert!(other_conn.list_spend().is_empty());
+        assert!(other_conn.db_list_labels().is_empty());
+        assert!(other_conn.db_events(0, 100).is_empty());
+        let mut labels = HashMap::new();
+        labels.insert(LabelItem::Txid(txid), Some("other".to_string()));
+        other_conn.update_labels(&labels);
+        assert_eq!(other_conn.db_list_labels()[0].value, "other");
+        assert_eq!(conn.db_list_labels()[0].value, "spend");
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn v8_to_v9_migration() {
+        let (tmp_dir, options, secp, db) = dummy_db();
+        let db_path = db.db_path.clone();
+
+        // Turn the fresh database into a version 8 one, which has no pending frozen coins.
+        {
+            let mut conn = rusqlite::Connection::open(&db_path).unwrap();
+            db_exec(&mut conn, |tx| {
+                tx.execute_batch("DROP TABLE pending_frozen_coins; UPDATE version SET version = 8;")
+            })
+            .unwrap();
+        }
+        assert!(matches!(
+            db.sanity_check(options.bitcoind_network, &options.main_descriptor),
+            Err(SqliteDbError::UnsupportedVersion(8))
+        ));
+
+        maybe_apply_migration(&db_path, &[]).unwrap();
+        let db = SqliteDb::new(db_path, None, &secp).unwrap();
+        db.sanity_check(options.bitcoind_network, &options.main_descriptor)
+            .unwrap();
+        let mut conn = db.connection().unwrap();
+        assert_eq!(conn.db_version(), 9);
+        let outpoint = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        conn.set_coins_pending_frozen(&[outpoint]);
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
 }
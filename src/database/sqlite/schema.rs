@@ -1,4 +1,4 @@
-use crate::descriptors::LianaDescriptor;
+use crate::{descriptors::LianaDescriptor, events::Event};
 
 use std::{convert::TryFrom, str::FromStr};
 
@@ -9,15 +9,20 @@ CREATE TABLE version (
     version INTEGER NOT NULL
 );
 
-/* About the Bitcoin network. */
+/* About the Bitcoin network, and the last block each wallet was updated up to. */
 CREATE TABLE tip (
     network TEXT NOT NULL,
     blockheight INTEGER,
-    blockhash BLOB
+    blockhash BLOB,
+    wallet_id INTEGER NOT NULL UNIQUE,
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
 );
 
-/* This stores metadata about our wallet. We only support single wallet for
- * now (and the foreseeable future).
+/* This stores metadata about our wallets. The wallet with id 1 is the one for the main
+ * descriptor the database was created with. Additional wallets may be served by the same
+ * daemon, in which case all their data is scoped by the 'wallet_id' in the other tables.
  *
  * The 'timestamp' field is the creation date of the wallet. We guarantee to have seen all
  * information related to our descriptor(s) that occured after this date.
@@ -41,6 +46,9 @@ CREATE TABLE wallets (
  * The 'is_immature' field is for coinbase deposits that are not yet buried under 100
  * blocks. Note coinbase deposits can't technically be unconfirmed but we keep them
  * as such until they become mature.
+ *
+ * The 'is_frozen' field is set by the user for coins which must never be selected
+ * automatically when creating a transaction.
  */
 CREATE TABLE coins (
     id INTEGER PRIMARY KEY NOT NULL,
@@ -56,6 +64,7 @@ CREATE TABLE coins (
     spend_block_height INTEGER,
     spend_block_time INTEGER,
     is_immature BOOLEAN NOT NULL CHECK (is_immature IN (0,1)),
+    is_frozen BOOLEAN NOT NULL DEFAULT 0 CHECK (is_frozen IN (0,1)),
     UNIQUE (txid, vout),
     FOREIGN KEY (wallet_id) REFERENCES wallets (id)
         ON UPDATE RESTRICT
@@ -74,7 +83,12 @@ CREATE TABLE coins (
 CREATE TABLE addresses (
     receive_address TEXT NOT NULL UNIQUE,
     change_address TEXT NOT NULL UNIQUE,
-    derivation_index INTEGER NOT NULL UNIQUE
+    derivation_index INTEGER NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    UNIQUE (wallet_id, derivation_index),
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
 );
 
 /* Transactions for all wallets. */
@@ -89,7 +103,11 @@ CREATE TABLE spend_transactions (
     id INTEGER PRIMARY KEY NOT NULL,
     psbt BLOB UNIQUE NOT NULL,
     txid BLOB UNIQUE NOT NULL,
-    updated_at INTEGER
+    updated_at INTEGER,
+    wallet_id INTEGER NOT NULL,
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
 );
 
 /* Labels applied on addresses (0), outpoints (1), txids (2) */
@@ -97,8 +115,34 @@ CREATE TABLE labels (
     id INTEGER PRIMARY KEY NOT NULL,
     wallet_id INTEGER NOT NULL,
     item_kind INTEGER NOT NULL CHECK (item_kind IN (0,1,2)),
-    item TEXT UNIQUE NOT NULL,
-    value TEXT NOT NULL
+    item TEXT NOT NULL,
+    value TEXT NOT NULL,
+    UNIQUE (wallet_id, item)
+);
+
+/* Coins to mark as frozen once they are found. Used when restoring a wallet from a backup, as the
+ * coins it froze are only found again by rescanning the chain.
+ */
+CREATE TABLE pending_frozen_coins (
+    id INTEGER PRIMARY KEY NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    txid BLOB NOT NULL,
+    vout INTEGER NOT NULL,
+    UNIQUE (wallet_id, txid, vout),
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
+);
+
+/* The journal of the changes to the wallet state, as JSON-serialized events. */
+CREATE TABLE events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
 );
 ";
 
@@ -190,6 +234,8 @@ pub struct DbCoin {
     pub is_change: bool,
     pub spend_txid: Option<bitcoin::Txid>,
     pub spend_block: Option<DbBlockInfo>,
+    /// Whether this coin must be excluded from automatic coin selection.
+    pub is_frozen: bool,
 }
 
 impl TryFrom<&rusqlite::Row<'_>> for DbCoin {
@@ -229,6 +275,7 @@ impl TryFrom<&rusqlite::Row<'_>> for DbCoin {
         });
 
         let is_immature: bool = row.get(12)?;
+        let is_frozen: bool = row.get(13)?;
 
         Ok(DbCoin {
             id,
@@ -241,6 +288,7 @@ impl TryFrom<&rusqlite::Row<'_>> for DbCoin {
             is_change,
             spend_txid,
             spend_block,
+            is_frozen,
         })
     }
 }
@@ -360,6 +408,31 @@ impl TryFrom<&rusqlite::Row<'_>> for DbLabel {
     }
 }
 
+/// A row in the "events" table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbEvent {
+    pub id: i64,
+    pub timestamp: u32,
+    pub event: Event,
+}
+
+impl TryFrom<&rusqlite::Row<'_>> for DbEvent {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+        let id: i64 = row.get(0)?;
+        let timestamp: u32 = row.get(1)?;
+        let event: String = row.get(2)?;
+        let event: Event = serde_json::from_str(&event).expect("We only store valid events");
+
+        Ok(DbEvent {
+            id,
+            timestamp,
+            event,
+        })
+    }
+}
+
 /// A transaction together with its block info.
 #[derive(Clone, Debug, PartialEq)]
 pub struct DbWalletTransaction {
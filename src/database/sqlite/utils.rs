@@ -1,4 +1,7 @@
-use crate::database::sqlite::{FreshDbOptions, SqliteDbError, DB_VERSION};
+use crate::{
+    database::sqlite::{FreshDbOptions, SqliteDbError, DB_VERSION},
+    descriptors::LianaDescriptor,
+};
 
 use std::{convert::TryInto, fs, path, time};
 
@@ -82,35 +85,55 @@ pub fn create_db_file(db_path: &path::Path) -> Result<(), std::io::Error> {
     };
 }
 
-/// Create a fresh Liana database with the given schema.
-pub fn create_fresh_db(
-    db_path: &path::Path,
-    options: FreshDbOptions,
+/// Insert a fresh wallet for this descriptor, along with its tip and the addresses in the initial
+/// look-ahead window. Returns the id of the new wallet row.
+pub fn insert_wallet(
+    tx: &rusqlite::Transaction,
+    network: bitcoin::Network,
+    main_descriptor: &LianaDescriptor,
+    timestamp: u32,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
-) -> Result<(), SqliteDbError> {
-    create_db_file(db_path)?;
-
-    let timestamp = curr_timestamp();
-
-    // Fill the initial addresses. On a fresh database, the deposit_derivation_index is
+) -> rusqlite::Result<i64> {
+    tx.execute(
+        "INSERT INTO wallets (timestamp, main_descriptor, deposit_derivation_index, change_derivation_index) \
+                 VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![timestamp, main_descriptor.to_string(), 0, 0],
+    )?;
+    let wallet_id = tx.last_insert_rowid();
+    tx.execute(
+        "INSERT INTO tip (network, blockheight, blockhash, wallet_id) VALUES (?1, NULL, NULL, ?2)",
+        rusqlite::params![network.to_string(), wallet_id],
+    )?;
+
+    // Fill the initial addresses. On a fresh wallet, the deposit_derivation_index is
     // necessarily 0.
     let mut query = String::with_capacity(100 * LOOK_AHEAD_LIMIT as usize);
     for index in 0..LOOK_AHEAD_LIMIT {
-        let receive_address = options
-            .main_descriptor
+        let receive_address = main_descriptor
             .receive_descriptor()
             .derive(index.into(), secp)
-            .address(options.bitcoind_network);
-        let change_address = options
-            .main_descriptor
+            .address(network);
+        let change_address = main_descriptor
             .change_descriptor()
             .derive(index.into(), secp)
-            .address(options.bitcoind_network);
+            .address(network);
         query += &format!(
-            "INSERT INTO addresses (receive_address, change_address, derivation_index) VALUES (\"{}\", \"{}\", {});\n",
-            receive_address, change_address, index
+            "INSERT INTO addresses (receive_address, change_address, derivation_index, wallet_id) VALUES (\"{}\", \"{}\", {}, {});\n",
+            receive_address, change_address, index, wallet_id
         );
     }
+    tx.execute_batch(&query)?;
+
+    Ok(wallet_id)
+}
+
+/// Create a fresh Liana database with the given schema.
+pub fn create_fresh_db(
+    db_path: &path::Path,
+    options: FreshDbOptions,
+    secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+) -> Result<(), SqliteDbError> {
+    create_db_file(db_path)?;
 
     let mut conn = rusqlite::Connection::open(db_path)?;
     db_exec(&mut conn, |tx| {
@@ -119,16 +142,14 @@ pub fn create_fresh_db(
             "INSERT INTO version (version) VALUES (?1)",
             rusqlite::params![options.version],
         )?;
-        tx.execute(
-            "INSERT INTO tip (network, blockheight, blockhash) VALUES (?1, NULL, NULL)",
-            rusqlite::params![options.bitcoind_network.to_string()],
-        )?;
-        tx.execute(
-            "INSERT INTO wallets (timestamp, main_descriptor, deposit_derivation_index, change_derivation_index) \
-                     VALUES (?1, ?2, ?3, ?4)",
-            rusqlite::params![timestamp, options.main_descriptor.to_string(), 0, 0],
+        let timestamp = options.timestamp.unwrap_or_else(curr_timestamp);
+        insert_wallet(
+            tx,
+            options.bitcoind_network,
+            &options.main_descriptor,
+            timestamp,
+            secp,
         )?;
-        tx.execute_batch(&query)?;
 
         Ok(())
     })?;
@@ -294,6 +315,143 @@ fn migrate_v4_to_v5(
     Ok(())
 }
 
+// After Liana 6.0 we upgraded the schema to record whether a coin was frozen by the user.
+fn migrate_v5_to_v6(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |tx| {
+        tx.execute(
+            "ALTER TABLE coins ADD COLUMN is_frozen BOOLEAN NOT NULL DEFAULT 0 CHECK (is_frozen IN (0,1))",
+            rusqlite::params![],
+        )?;
+        tx.execute("UPDATE version SET version = 6", rusqlite::params![])?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+// Create the events journal table.
+fn migrate_v6_to_v7(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |tx| {
+        tx.execute(
+            "CREATE TABLE events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                event TEXT NOT NULL
+            )",
+            rusqlite::params![],
+        )?;
+        tx.execute("UPDATE version SET version = 7", rusqlite::params![])?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+// Scope the tip, addresses, spend transactions, labels and events to a wallet, to be able to serve
+// multiple wallets from a single database. The existing rows all belong to the main wallet.
+fn migrate_v7_to_v8(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |tx| {
+        tx.execute_batch(
+            "CREATE TABLE tip_new (
+                network TEXT NOT NULL,
+                blockheight INTEGER,
+                blockhash BLOB,
+                wallet_id INTEGER NOT NULL UNIQUE,
+                FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+                    ON UPDATE RESTRICT
+                    ON DELETE RESTRICT
+            );
+            INSERT INTO tip_new (network, blockheight, blockhash, wallet_id)
+                SELECT network, blockheight, blockhash, 1 FROM tip;
+            DROP TABLE tip;
+            ALTER TABLE tip_new RENAME TO tip;
+
+            CREATE TABLE addresses_new (
+                receive_address TEXT NOT NULL UNIQUE,
+                change_address TEXT NOT NULL UNIQUE,
+                derivation_index INTEGER NOT NULL,
+                wallet_id INTEGER NOT NULL,
+                UNIQUE (wallet_id, derivation_index),
+                FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+                    ON UPDATE RESTRICT
+                    ON DELETE RESTRICT
+            );
+            INSERT INTO addresses_new (receive_address, change_address, derivation_index, wallet_id)
+                SELECT receive_address, change_address, derivation_index, 1 FROM addresses;
+            DROP TABLE addresses;
+            ALTER TABLE addresses_new RENAME TO addresses;
+
+            CREATE TABLE spend_transactions_new (
+                id INTEGER PRIMARY KEY NOT NULL,
+                psbt BLOB UNIQUE NOT NULL,
+                txid BLOB UNIQUE NOT NULL,
+                updated_at INTEGER,
+                wallet_id INTEGER NOT NULL,
+                FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+                    ON UPDATE RESTRICT
+                    ON DELETE RESTRICT
+            );
+            INSERT INTO spend_transactions_new (id, psbt, txid, updated_at, wallet_id)
+                SELECT id, psbt, txid, updated_at, 1 FROM spend_transactions;
+            DROP TABLE spend_transactions;
+            ALTER TABLE spend_transactions_new RENAME TO spend_transactions;
+
+            CREATE TABLE labels_new (
+                id INTEGER PRIMARY KEY NOT NULL,
+                wallet_id INTEGER NOT NULL,
+                item_kind INTEGER NOT NULL CHECK (item_kind IN (0,1,2)),
+                item TEXT NOT NULL,
+                value TEXT NOT NULL,
+                UNIQUE (wallet_id, item)
+            );
+            INSERT INTO labels_new SELECT * FROM labels;
+            DROP TABLE labels;
+            ALTER TABLE labels_new RENAME TO labels;
+
+            CREATE TABLE events_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                event TEXT NOT NULL,
+                wallet_id INTEGER NOT NULL,
+                FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+                    ON UPDATE RESTRICT
+                    ON DELETE RESTRICT
+            );
+            INSERT INTO events_new (id, timestamp, event, wallet_id)
+                SELECT id, timestamp, event, 1 FROM events;
+            DROP TABLE events;
+            ALTER TABLE events_new RENAME TO events;
+
+            UPDATE version SET version = 8;",
+        )
+    })?;
+
+    Ok(())
+}
+
+// Create the table of the coins to freeze once they are found.
+fn migrate_v8_to_v9(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |tx| {
+        tx.execute(
+            "CREATE TABLE pending_frozen_coins (
+                id INTEGER PRIMARY KEY NOT NULL,
+                wallet_id INTEGER NOT NULL,
+                txid BLOB NOT NULL,
+                vout INTEGER NOT NULL,
+                UNIQUE (wallet_id, txid, vout),
+                FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+                    ON UPDATE RESTRICT
+                    ON DELETE RESTRICT
+            )",
+            rusqlite::params![],
+        )?;
+        tx.execute("UPDATE version SET version = 9", rusqlite::params![])?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
 /// Check the database version and if necessary apply the migrations to upgrade it to the current
 /// one. The `bitcoin_txs` parameter is here for the migration from versions 4 and earlier, which
 /// did not store the Bitcoin transactions in database, to versions 5 and later, which do. For a
@@ -342,6 +500,26 @@ pub fn maybe_apply_migration(
                 migrate_v4_to_v5(&mut conn, bitcoin_txs)?;
                 log::warn!("Migration from database version 4 to version 5 successful.");
             }
+            5 => {
+                log::warn!("Upgrading database from version 5 to version 6.");
+                migrate_v5_to_v6(&mut conn)?;
+                log::warn!("Migration from database version 5 to version 6 successful.");
+            }
+            6 => {
+                log::warn!("Upgrading database from version 6 to version 7.");
+                migrate_v6_to_v7(&mut conn)?;
+                log::warn!("Migration from database version 6 to version 7 successful.");
+            }
+            7 => {
+                log::warn!("Upgrading database from version 7 to version 8.");
+                migrate_v7_to_v8(&mut conn)?;
+                log::warn!("Migration from database version 7 to version 8 successful.");
+            }
+            8 => {
+                log::warn!("Upgrading database from version 8 to version 9.");
+                migrate_v8_to_v9(&mut conn)?;
+                log::warn!("Migration from database version 8 to version 9 successful.");
+            }
             _ => return Err(SqliteDbError::UnsupportedVersion(version)),
         }
     }
@@ -6,7 +6,7 @@ use miniscript::{
     },
     descriptor,
     policy::{Concrete as ConcretePolicy, Liftable, Semantic as SemanticPolicy},
-    ScriptContext,
+    AbsLockTime, ScriptContext,
 };
 
 use std::{
@@ -16,6 +16,8 @@ use std::{
     str::FromStr,
 };
 
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug)]
 pub enum LianaPolicyError {
     MissingRecoveryPath,
@@ -144,6 +146,33 @@ fn csv_check(csv_value: u32) -> Result<u16, LianaPolicyError> {
     }
 }
 
+// We require the time-based relative locktime to:
+//  - not be disabled
+//  - be in units of 512 seconds
+//  - be 'clean' / minimal, ie all bits without consensus meaning should be 0
+//  - be positive (Miniscript requires it not to be 0)
+//
+// This returns the number of 512 seconds intervals.
+fn csv_time_check(csv_value: u32) -> Result<u16, LianaPolicyError> {
+    let intervals = (csv_value & 0xFF_FF) as u16;
+    if intervals > 0 && Sequence::from_512_second_intervals(intervals).0 == csv_value {
+        Ok(intervals)
+    } else {
+        Err(LianaPolicyError::InsaneTimelock(csv_value))
+    }
+}
+
+// We require the absolute locktime to be a valid argument to OP_CHECKLOCKTIMEVERIFY, that is to
+// be positive (Miniscript requires it not to be 0) and to fit in 31 bits. It is a block height if
+// it is below 500_000_000, a UNIX timestamp otherwise.
+fn cltv_check(cltv_value: u32) -> Result<u32, LianaPolicyError> {
+    if cltv_value > 0 && cltv_value < 0x80_00_00_00 {
+        Ok(cltv_value)
+    } else {
+        Err(LianaPolicyError::InsaneTimelock(cltv_value))
+    }
+}
+
 // Get the fingerprint and the full derivation paths (path from the master fingerprint in the
 // origin, with the xpub derivation path appended) for a multipath xpub.
 fn key_origins(
@@ -169,6 +198,62 @@ fn key_origins(
     }
 }
 
+/// The timelock of a recovery path.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum RecoveryTimelock {
+    /// A relative timelock (`older()`), in number of blocks.
+    Relative(u16),
+    /// A relative timelock (`older()`), in number of 512 seconds intervals.
+    RelativeTime(u16),
+    /// An absolute timelock (`after()`), as a block height if below 500_000_000 or as a UNIX
+    /// timestamp otherwise.
+    Absolute(u32),
+}
+
+impl RecoveryTimelock {
+    /// The approximate duration of this timelock in days, rounded to one decimal. Assumes 10
+    /// minutes blocks for timelocks expressed in blocks. `None` for absolute timelocks.
+    pub fn approx_days(&self) -> Option<f64> {
+        let seconds = match *self {
+            RecoveryTimelock::Relative(blocks) => blocks as u64 * 600,
+            RecoveryTimelock::RelativeTime(intervals) => intervals as u64 * 512,
+            RecoveryTimelock::Absolute(_) => return None,
+        };
+        Some((seconds as f64 / 86_400.0 * 10.0).round() / 10.0)
+    }
+}
+
+impl fmt::Display for RecoveryTimelock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Relative(blocks) => write!(f, "relative timelock of {} blocks", blocks),
+            Self::RelativeTime(intervals) => {
+                write!(f, "relative timelock of {} times 512 seconds", intervals)
+            }
+            Self::Absolute(value) => write!(f, "absolute timelock of {}", value),
+        }
+    }
+}
+
+/// A spending path of a Liana policy.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash)]
+pub enum SpendingPath {
+    /// The primary path, which can be used at any time.
+    Primary,
+    /// The recovery path behind this timelock.
+    Recovery(RecoveryTimelock),
+}
+
+impl fmt::Display for SpendingPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Primary => write!(f, "primary path"),
+            Self::Recovery(timelock) => write!(f, "recovery path with a {}", timelock),
+        }
+    }
+}
+
 /// Information about a single spending path in the descriptor.
 #[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd, Hash)]
 pub enum PathInfo {
@@ -201,14 +286,25 @@ impl PathInfo {
 
     /// Get the information about the recovery spending path.
     /// Returns None if the policy does not describe the recovery spending path of a Liana
-    /// descriptor (that is, a set of keys after a timelock).
+    /// descriptor (that is, a set of keys after a relative or absolute timelock).
     pub fn from_recovery_path(
         policy: SemanticPolicy<descriptor::DescriptorPublicKey>,
-    ) -> Result<(u16, PathInfo), LianaPolicyError> {
+    ) -> Result<(RecoveryTimelock, PathInfo), LianaPolicyError> {
         // The recovery spending path must always be a policy of type `thresh(2, older(x), thresh(n, key1,
         // key2, ..))`. In the special case n == 1, it is only `thresh(2, older(x), key)`. In the
         // special case n == len(keys) (i.e. it's an N-of-N multisig), it is normalized as
-        // `thresh(n+1, older(x), key1, key2, ...)`.
+        // `thresh(n+1, older(x), key1, key2, ...)`. The same goes with an `after(x)` in place of
+        // the `older(x)`.
+        let timelock = |sub: &SemanticPolicy<_>| match sub {
+            SemanticPolicy::Older(val) if val.is_time_locked() => {
+                Some(csv_time_check(val.0).map(RecoveryTimelock::RelativeTime))
+            }
+            SemanticPolicy::Older(val) => Some(csv_check(val.0).map(RecoveryTimelock::Relative)),
+            SemanticPolicy::After(val) => {
+                Some(cltv_check(val.to_consensus_u32()).map(RecoveryTimelock::Absolute))
+            }
+            _ => None,
+        };
         let (k, subs) = match policy {
             SemanticPolicy::Threshold(k, subs) => (k, subs),
             _ => return Err(LianaPolicyError::IncompatibleDesc),
@@ -218,10 +314,7 @@ impl PathInfo {
             // of the same form as a primary path.
             let tl_value = subs
                 .iter()
-                .find_map(|s| match s {
-                    SemanticPolicy::Older(val) => Some(csv_check(val.0)),
-                    _ => None,
-                })
+                .find_map(timelock)
                 .ok_or(LianaPolicyError::IncompatibleDesc)??;
             let keys_sub = subs
                 .into_iter()
@@ -234,18 +327,18 @@ impl PathInfo {
             let mut tl_value = None;
             let mut keys = Vec::with_capacity(subs.len());
             for sub in subs {
-                match sub {
-                    SemanticPolicy::Key(key) => keys.push(key),
-                    SemanticPolicy::Older(val) => {
-                        if tl_value.is_some() {
-                            return Err(LianaPolicyError::IncompatibleDesc);
-                        }
-                        tl_value = Some(csv_check(val.0)?);
+                if let SemanticPolicy::Key(key) = sub {
+                    keys.push(key);
+                } else if let Some(tl) = timelock(&sub) {
+                    if tl_value.is_some() {
+                        return Err(LianaPolicyError::IncompatibleDesc);
                     }
-                    _ => return Err(LianaPolicyError::IncompatibleDesc),
+                    tl_value = Some(tl?);
+                } else {
+                    return Err(LianaPolicyError::IncompatibleDesc);
                 }
             }
-            assert!(keys.len() > 1); // At least 3 subs, only one of which may be older().
+            assert!(keys.len() > 1); // At least 3 subs, only one of which may be a timelock.
             Ok((
                 tl_value.ok_or(LianaPolicyError::IncompatibleDesc)?,
                 PathInfo::Multi(k - 1, keys),
@@ -302,6 +395,30 @@ impl PathInfo {
         }
     }
 
+    /// Describe the threshold and the keys of this spending path.
+    pub fn description(&self) -> PathDescription {
+        let (threshold, keys) = match self {
+            PathInfo::Single(key) => (1, vec![key]),
+            PathInfo::Multi(thresh, keys) => (*thresh, keys.iter().collect()),
+        };
+        let keys = keys
+            .into_iter()
+            .map(|key| {
+                let (fingerprint, origin_path) = match key {
+                    descriptor::DescriptorPublicKey::MultiXPub(ref xpub) => xpub.origin.clone(),
+                    _ => None,
+                }
+                .expect("Must be a multixpub with an origin.");
+                KeyDescription {
+                    fingerprint,
+                    origin_path,
+                    key: key.clone(),
+                }
+            })
+            .collect();
+        PathDescription { threshold, keys }
+    }
+
     /// Get the spend information for this descriptor based from the list of all pubkeys that
     /// signed the transaction.
     pub fn spend_info<'a>(
@@ -450,6 +567,12 @@ fn unspendable_internal_key(
 ///     - A directly available path with any number of keys checks; or
 ///     - One or more recovery paths with any number of keys checks, behind increasing relative
 ///     timelocks. No two recovery paths may have the same timelock.
+///     - Optionally, recovery paths with any number of keys checks behind relative timelocks
+///     expressed in time (in units of 512 seconds) instead of blocks. No two of them may have the
+///     same timelock.
+///     - Optionally, recovery paths with any number of keys checks behind absolute timelocks (a
+///     block height or a date). These are not reset when a coin is spent to a new address. No two
+///     of them may have the same timelock.
 /// A Liana policy can be created from some settings (the primary and recovery keys, the
 /// timelock(s)) and be used to derive a descriptor. It can also be inferred from a descriptor and
 /// be used to retrieve the settings.
@@ -459,6 +582,8 @@ fn unspendable_internal_key(
 pub struct LianaPolicy {
     pub(super) primary_path: PathInfo,
     pub(super) recovery_paths: BTreeMap<u16, PathInfo>,
+    pub(super) time_recovery_paths: BTreeMap<u16, PathInfo>,
+    pub(super) absolute_recovery_paths: BTreeMap<u32, PathInfo>,
     is_taproot: bool,
 }
 
@@ -467,9 +592,14 @@ impl LianaPolicy {
     fn _new(
         primary_path: PathInfo,
         recovery_paths: BTreeMap<u16, PathInfo>,
+        time_recovery_paths: BTreeMap<u16, PathInfo>,
+        absolute_recovery_paths: BTreeMap<u32, PathInfo>,
         is_taproot: bool,
     ) -> Result<LianaPolicy, LianaPolicyError> {
-        if recovery_paths.is_empty() {
+        if recovery_paths.is_empty()
+            && time_recovery_paths.is_empty()
+            && absolute_recovery_paths.is_empty()
+        {
             return Err(LianaPolicyError::MissingRecoveryPath);
         }
 
@@ -483,6 +613,14 @@ impl LianaPolicy {
         if recovery_paths.contains_key(&0) {
             return Err(LianaPolicyError::InsaneTimelock(0));
         }
+        if time_recovery_paths.contains_key(&0) {
+            return Err(LianaPolicyError::InsaneTimelock(
+                Sequence::from_512_second_intervals(0).0,
+            ));
+        }
+        for timelock in absolute_recovery_paths.keys() {
+            cltv_check(*timelock)?;
+        }
 
         // Check all keys are valid according to our standard (this checks all are multipath keys).
         // Note while the Miniscript compiler does check for duplicate, it does so at the
@@ -490,6 +628,8 @@ impl LianaPolicy {
         // ourselves here.
         let spending_paths = recovery_paths
             .values()
+            .chain(time_recovery_paths.values())
+            .chain(absolute_recovery_paths.values())
             .chain(std::iter::once(&primary_path));
         let mut key_checker = DescKeyChecker::new();
         for path in spending_paths {
@@ -521,6 +661,8 @@ impl LianaPolicy {
         let policy = LianaPolicy {
             primary_path,
             recovery_paths,
+            time_recovery_paths,
+            absolute_recovery_paths,
             is_taproot,
         };
         policy.clone().into_multipath_descriptor_fallible()?;
@@ -532,7 +674,13 @@ impl LianaPolicy {
         primary_path: PathInfo,
         recovery_paths: BTreeMap<u16, PathInfo>,
     ) -> Result<LianaPolicy, LianaPolicyError> {
-        Self::_new(primary_path, recovery_paths, /* is_taproot = */ true)
+        Self::_new(
+            primary_path,
+            recovery_paths,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            /* is_taproot = */ true,
+        )
     }
 
     /// Create a new Liana policy for use under a P2WSH context.
@@ -540,7 +688,63 @@ impl LianaPolicy {
         primary_path: PathInfo,
         recovery_paths: BTreeMap<u16, PathInfo>,
     ) -> Result<LianaPolicy, LianaPolicyError> {
-        Self::_new(primary_path, recovery_paths, /* is_taproot = */ false)
+        Self::_new(
+            primary_path,
+            recovery_paths,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            /* is_taproot = */ false,
+        )
+    }
+
+    /// Create a new Liana policy with recovery paths behind any kind of timelock: relative
+    /// timelocks in blocks, relative timelocks in number of 512 seconds intervals and absolute
+    /// timelocks. Any of these mappings may be empty, as long as there is at least one recovery
+    /// path.
+    pub fn new_with_recovery_paths(
+        primary_path: PathInfo,
+        recovery_paths: BTreeMap<u16, PathInfo>,
+        time_recovery_paths: BTreeMap<u16, PathInfo>,
+        absolute_recovery_paths: BTreeMap<u32, PathInfo>,
+        is_taproot: bool,
+    ) -> Result<LianaPolicy, LianaPolicyError> {
+        Self::_new(
+            primary_path,
+            recovery_paths,
+            time_recovery_paths,
+            absolute_recovery_paths,
+            is_taproot,
+        )
+    }
+
+    /// Add recovery paths behind absolute timelocks to this policy. They are keyed by the value of
+    /// their timelock: a block height if below 500_000_000, a UNIX timestamp otherwise.
+    pub fn with_absolute_recovery_paths(
+        self,
+        absolute_recovery_paths: BTreeMap<u32, PathInfo>,
+    ) -> Result<LianaPolicy, LianaPolicyError> {
+        Self::_new(
+            self.primary_path,
+            self.recovery_paths,
+            self.time_recovery_paths,
+            absolute_recovery_paths,
+            self.is_taproot,
+        )
+    }
+
+    /// Add recovery paths behind relative timelocks expressed in time to this policy. They are
+    /// keyed by the value of their timelock, in number of 512 seconds intervals.
+    pub fn with_time_recovery_paths(
+        self,
+        time_recovery_paths: BTreeMap<u16, PathInfo>,
+    ) -> Result<LianaPolicy, LianaPolicyError> {
+        Self::_new(
+            self.primary_path,
+            self.recovery_paths,
+            time_recovery_paths,
+            self.absolute_recovery_paths,
+            self.is_taproot,
+        )
     }
 
     /// Create a Liana policy from a descriptor. This will check the descriptor is correctly formed
@@ -600,7 +804,9 @@ impl LianaPolicy {
 
         // Fetch all spending paths' semantic policies. The primary path is identified as the only
         // one that isn't timelocked.
-        let (mut primary_path, mut recovery_paths) = (None::<PathInfo>, BTreeMap::new());
+        let (mut primary_path, mut recovery_paths, mut time_recovery_paths) =
+            (None::<PathInfo>, BTreeMap::new(), BTreeMap::new());
+        let mut absolute_recovery_paths = BTreeMap::new();
         for sub in subs {
             // This is a (multi)key check. It must be the primary path.
             if is_single_key_or_multisig(&sub) {
@@ -620,35 +826,64 @@ impl LianaPolicy {
             } else {
                 // If it's not a simple (multi)key check, it must be (one of) the timelocked
                 // recovery path(s).
-                let (timelock, path_info) = PathInfo::from_recovery_path(sub)?;
-                if recovery_paths.contains_key(&timelock) {
+                let duplicate = match PathInfo::from_recovery_path(sub)? {
+                    (RecoveryTimelock::Relative(timelock), path_info) => {
+                        recovery_paths.insert(timelock, path_info).is_some()
+                    }
+                    (RecoveryTimelock::RelativeTime(timelock), path_info) => {
+                        time_recovery_paths.insert(timelock, path_info).is_some()
+                    }
+                    (RecoveryTimelock::Absolute(timelock), path_info) => absolute_recovery_paths
+                        .insert(timelock, path_info)
+                        .is_some(),
+                };
+                if duplicate {
                     return Err(LianaPolicyError::IncompatibleDesc);
                 }
-                recovery_paths.insert(timelock, path_info);
             }
         }
 
         // Use the constructor for sanity checking the keys and the Miniscript policy. Note this
-        // makes sure the recovery paths mapping isn't empty, too.
+        // makes sure there is at least one recovery path, too.
         let prim_path = primary_path.ok_or(LianaPolicyError::IncompatibleDesc)?;
-        LianaPolicy::_new(prim_path, recovery_paths, is_taproot)
+        LianaPolicy::_new(
+            prim_path,
+            recovery_paths,
+            time_recovery_paths,
+            absolute_recovery_paths,
+            is_taproot,
+        )
     }
 
     pub fn primary_path(&self) -> &PathInfo {
         &self.primary_path
     }
 
-    /// Timelocks and path info of the recovery paths. Note we guarantee this mapping is never
-    /// empty, as there is always at least one recovery path.
+    /// Timelocks and path info of the recovery paths behind a relative timelock in blocks. This may
+    /// be empty if the policy only has recovery paths behind other kinds of timelocks, but there is
+    /// always at least one recovery path.
     pub fn recovery_paths(&self) -> &BTreeMap<u16, PathInfo> {
-        assert!(!self.recovery_paths.is_empty());
         &self.recovery_paths
     }
 
+    /// Timelocks and path info of the recovery paths behind a relative timelock expressed in time.
+    /// The timelock is in number of 512 seconds intervals. This may be empty.
+    pub fn time_recovery_paths(&self) -> &BTreeMap<u16, PathInfo> {
+        &self.time_recovery_paths
+    }
+
+    /// Timelocks and path info of the recovery paths behind an absolute timelock. The timelock is
+    /// a block height if below 500_000_000, a UNIX timestamp otherwise. This may be empty.
+    pub fn absolute_recovery_paths(&self) -> &BTreeMap<u32, PathInfo> {
+        &self.absolute_recovery_paths
+    }
+
     fn into_policy(self) -> miniscript::policy::Concrete<descriptor::DescriptorPublicKey> {
         let LianaPolicy {
             primary_path,
             recovery_paths,
+            time_recovery_paths,
+            absolute_recovery_paths,
             ..
         } = self;
 
@@ -656,16 +891,42 @@ impl LianaPolicy {
         let primary_keys = primary_path.into_ms_policy();
 
         // Incrementally create the top-level policy using all recovery paths.
-        assert!(!recovery_paths.is_empty());
-        recovery_paths
+        assert!(
+            !recovery_paths.is_empty()
+                || !time_recovery_paths.is_empty()
+                || !absolute_recovery_paths.is_empty()
+        );
+        let relative_paths = recovery_paths.into_iter().map(|(timelock, path_info)| {
+            (
+                ConcretePolicy::Older(Sequence::from_height(timelock)),
+                path_info,
+            )
+        });
+        let time_paths = time_recovery_paths
             .into_iter()
-            .fold(primary_keys, |tl_policy, (timelock, path_info)| {
-                let timelock = ConcretePolicy::Older(Sequence::from_height(timelock));
+            .map(|(timelock, path_info)| {
+                (
+                    ConcretePolicy::Older(Sequence::from_512_second_intervals(timelock)),
+                    path_info,
+                )
+            });
+        let absolute_paths = absolute_recovery_paths
+            .into_iter()
+            .map(|(timelock, path_info)| {
+                (
+                    ConcretePolicy::After(AbsLockTime::from_consensus(timelock)),
+                    path_info,
+                )
+            });
+        relative_paths.chain(time_paths).chain(absolute_paths).fold(
+            primary_keys,
+            |tl_policy, (timelock, path_info)| {
                 let keys = path_info.into_ms_policy();
                 let recovery_branch = ConcretePolicy::And(vec![keys.into(), timelock.into()]);
                 // We assume the larger the timelock the less likely a branch would be used.
                 ConcretePolicy::Or(vec![(99, tl_policy.into()), (1, recovery_branch.into())])
-            })
+            },
+        )
     }
 
     fn into_multipath_descriptor_fallible(
@@ -680,6 +941,13 @@ impl LianaPolicy {
             // vice-versa. So we use a dummy internal key. If it ends up as the internal key in the
             // compiled descriptor, we replace it with a deterministically computed unspendable
             // internal key.
+            //
+            // Note a multi-key primary path is always compiled to a `multi_a` leaf and never to a
+            // MuSig2 aggregate internal key. This is not supported by our dependencies yet: our
+            // version of Miniscript can't parse `musig()` key expressions (BIP390), our version of
+            // libsecp256k1 bindings doesn't expose the MuSig2 module and our version of the
+            // bitcoin crate doesn't have the PSBT fields for the nonce and partial signature
+            // exchange (BIP373). We won't roll our own MuSig2 implementation in the meantime.
             let dummy_internal_key =
                 descriptor::DescriptorPublicKey::XPub(descriptor::DescriptorXKey::<bip32::Xpub> {
                     origin: None,
@@ -729,6 +997,50 @@ impl LianaPolicy {
         }
     }
 
+    /// Get the information about the given spending path, if it is part of this policy.
+    pub fn spending_path(&self, path: SpendingPath) -> Option<&PathInfo> {
+        match path {
+            SpendingPath::Primary => Some(&self.primary_path),
+            SpendingPath::Recovery(RecoveryTimelock::Relative(tl)) => self.recovery_paths.get(&tl),
+            SpendingPath::Recovery(RecoveryTimelock::RelativeTime(tl)) => {
+                self.time_recovery_paths.get(&tl)
+            }
+            SpendingPath::Recovery(RecoveryTimelock::Absolute(tl)) => {
+                self.absolute_recovery_paths.get(&tl)
+            }
+        }
+    }
+
+    /// Describe this spending policy: the threshold and keys of the primary path, then those of
+    /// each recovery path along with its timelock. Recovery paths are ordered by kind of timelock
+    /// (relative in blocks, relative in time, absolute) then by timelock value.
+    pub fn description(&self) -> PolicyDescription {
+        let recovery_paths = self
+            .recovery_paths
+            .iter()
+            .map(|(tl, path)| (RecoveryTimelock::Relative(*tl), path))
+            .chain(
+                self.time_recovery_paths
+                    .iter()
+                    .map(|(tl, path)| (RecoveryTimelock::RelativeTime(*tl), path)),
+            )
+            .chain(
+                self.absolute_recovery_paths
+                    .iter()
+                    .map(|(tl, path)| (RecoveryTimelock::Absolute(*tl), path)),
+            )
+            .map(|(timelock, path)| RecoveryPathDescription {
+                timelock,
+                approx_days: timelock.approx_days(),
+                path: path.description(),
+            })
+            .collect();
+        PolicyDescription {
+            primary_path: self.primary_path.description(),
+            recovery_paths,
+        }
+    }
+
     /// Create a descriptor from this spending policy with multipath key expressions. Note this
     /// involves a Miniscript policy compilation: this function is **not deterministic**. If you
     /// are inferring a `LianaPolicy` from a descriptor, generating a descriptor from this
@@ -741,6 +1053,105 @@ impl LianaPolicy {
     }
 }
 
+/// A key in a spending path, as described by [`PathInfo::description`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyDescription {
+    /// The fingerprint of the master extended key of the signer.
+    pub fingerprint: bip32::Fingerprint,
+    /// The derivation path from the master extended key to the extended key in the descriptor.
+    pub origin_path: bip32::DerivationPath,
+    /// The key expression as it appears in the descriptor.
+    pub key: descriptor::DescriptorPublicKey,
+}
+
+/// The threshold and keys of a spending path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathDescription {
+    /// The number of signatures required to spend through this path.
+    pub threshold: usize,
+    pub keys: Vec<KeyDescription>,
+}
+
+impl PathDescription {
+    fn signers(&self) -> String {
+        if self.keys.len() == 1 {
+            "the following key".to_string()
+        } else {
+            format!(
+                "{} of the following {} keys",
+                self.threshold,
+                self.keys.len()
+            )
+        }
+    }
+
+    fn fmt_keys(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for key in &self.keys {
+            writeln!(f, "  - {}", key.key)?;
+        }
+        Ok(())
+    }
+}
+
+/// A recovery path and its timelock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryPathDescription {
+    pub timelock: RecoveryTimelock,
+    /// The approximate duration of the timelock in days. `None` for absolute timelocks.
+    pub approx_days: Option<f64>,
+    #[serde(flatten)]
+    pub path: PathDescription,
+}
+
+/// A description of a Liana spending policy, as returned by [`LianaPolicy::description`]. Its
+/// `Display` implementation renders it as plain text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyDescription {
+    pub primary_path: PathDescription,
+    pub recovery_paths: Vec<RecoveryPathDescription>,
+}
+
+impl fmt::Display for PolicyDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Primary path: {} can spend at any time.",
+            self.primary_path.signers()
+        )?;
+        self.primary_path.fmt_keys(f)?;
+        for (i, recov) in self.recovery_paths.iter().enumerate() {
+            write!(
+                f,
+                "Recovery path #{}: {} can spend ",
+                i + 1,
+                recov.path.signers()
+            )?;
+            match recov.timelock {
+                RecoveryTimelock::Relative(blocks) => {
+                    write!(f, "coins inactive for {} blocks", blocks)?
+                }
+                RecoveryTimelock::RelativeTime(intervals) => {
+                    write!(f, "coins inactive for {} seconds", intervals as u32 * 512)?
+                }
+                RecoveryTimelock::Absolute(value)
+                    if value < bitcoin::absolute::LOCK_TIME_THRESHOLD =>
+                {
+                    write!(f, "coins from block height {}", value)?
+                }
+                RecoveryTimelock::Absolute(value) => {
+                    write!(f, "coins from UNIX timestamp {}", value)?
+                }
+            }
+            if let Some(days) = recov.approx_days {
+                write!(f, " (~{} days)", days)?;
+            }
+            writeln!(f, ".")?;
+            recov.path.fmt_keys(f)?;
+        }
+        Ok(())
+    }
+}
+
 /// Partial spend information for a specific spending path within a descriptor.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct PathSpendInfo {
@@ -761,6 +1172,12 @@ pub struct PartialSpendInfo {
     /// Number of signatures present for the recovery path, only present for the recovery paths
     /// that are available.
     pub(super) recovery_paths: BTreeMap<u16, PathSpendInfo>,
+    /// Number of signatures present for the recovery paths behind a relative timelock expressed
+    /// in time, only present for those that are available.
+    pub(super) time_recovery_paths: BTreeMap<u16, PathSpendInfo>,
+    /// Number of signatures present for the recovery paths behind an absolute timelock, only
+    /// present for those that are available.
+    pub(super) absolute_recovery_paths: BTreeMap<u32, PathSpendInfo>,
 }
 
 impl PartialSpendInfo {
@@ -774,6 +1191,18 @@ impl PartialSpendInfo {
     pub fn recovery_paths(&self) -> &BTreeMap<u16, PathSpendInfo> {
         &self.recovery_paths
     }
+
+    /// Get the number of signatures present for each recovery path behind a relative timelock
+    /// expressed in time. Only present for available paths.
+    pub fn time_recovery_paths(&self) -> &BTreeMap<u16, PathSpendInfo> {
+        &self.time_recovery_paths
+    }
+
+    /// Get the number of signatures present for each recovery path behind an absolute timelock.
+    /// Only present for available paths.
+    pub fn absolute_recovery_paths(&self) -> &BTreeMap<u32, PathSpendInfo> {
+        &self.absolute_recovery_paths
+    }
 }
 
 #[cfg(test)]
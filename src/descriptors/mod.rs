@@ -222,15 +222,10 @@ impl LianaDescriptor {
             .expect("We never create a Liana descriptor with an invalid Liana policy.")
     }
 
-    /// Get the value (in blocks) of the smallest relative timelock of the recovery paths.
-    pub fn first_timelock_value(&self) -> u16 {
-        *self
-            .policy()
-            .recovery_paths
-            .iter()
-            .next()
-            .expect("There is always at least one recovery path")
-            .0
+    /// Get the value (in blocks) of the smallest relative timelock of the recovery paths. `None` if
+    /// there is no recovery path behind a relative timelock in blocks.
+    pub fn first_timelock_value(&self) -> Option<u16> {
+        self.policy().recovery_paths.keys().next().copied()
     }
 
     /// Get the maximum size difference of a transaction input spending a Script derived from this
@@ -239,46 +234,8 @@ impl LianaDescriptor {
     /// size of the witness stack length varint.
     pub fn max_sat_weight(&self, use_primary_path: bool) -> usize {
         if use_primary_path {
-            // Get the keys from the primary path, to get a satisfaction size estimation only
-            // considering those.
-            let keys = self
-                .policy()
-                .primary_path
-                .thresh_origins()
-                .1
-                .into_iter()
-                .fold(BTreeSet::new(), |mut keys, (fg, der_paths)| {
-                    for der_path in der_paths {
-                        keys.insert(((fg, der_path), CanSign::default()));
-                    }
-                    keys
-                });
-            let assets = Assets {
-                keys,
-                ..Default::default()
-            };
-
-            // Unfortunately rust-miniscript satisfaction size estimation is inconsistent. For
-            // Taproot it considers the whole witness (including the control block size + the
-            // script size) but under P2WSH it does not consider the witscript! Therefore we
-            // manually add the size of the witscript, but only under P2WSH by the mean of the
-            // `explicit_script()` helper.
-            let der_desc = self
-                .receive_desc
-                .0
-                .at_derivation_index(0)
-                .expect("unhardened index");
-            let witscript_size = der_desc
-                .explicit_script()
-                .map(|s| varint_len(s.len()) + s.len())
-                .unwrap_or(0);
-
-            // Finally, compute the satisfaction template for the primary path and get its size.
-            der_desc
-                .plan(&assets)
-                .expect("Always satisfiable")
-                .witness_size()
-                + witscript_size
+            self.spending_path_max_sat_weight(SpendingPath::Primary)
+                .expect("The primary path always exists")
         } else {
             // We add one to account for the witness stack size, as the values above give the
             // difference in size for a satisfied input that was *already* in a transaction
@@ -293,6 +250,69 @@ impl LianaDescriptor {
         }
     }
 
+    /// Get the maximum size in WU of a satisfaction for this descriptor through the given
+    /// spending path. Returns `None` if there is no such spending path in the policy.
+    pub fn spending_path_max_sat_weight(&self, spending_path: SpendingPath) -> Option<usize> {
+        let policy = self.policy();
+        let path_info = policy.spending_path(spending_path)?;
+
+        // Get the keys from this spending path, to get a satisfaction size estimation only
+        // considering those.
+        let keys = path_info.thresh_origins().1.into_iter().fold(
+            BTreeSet::new(),
+            |mut keys, (fg, der_paths)| {
+                for der_path in der_paths {
+                    keys.insert(((fg, der_path), CanSign::default()));
+                }
+                keys
+            },
+        );
+        let (absolute_timelock, relative_timelock) = match spending_path {
+            SpendingPath::Primary => (None, None),
+            SpendingPath::Recovery(RecoveryTimelock::Relative(blocks)) => {
+                (None, Some(bitcoin::Sequence::from_height(blocks)))
+            }
+            SpendingPath::Recovery(RecoveryTimelock::RelativeTime(intervals)) => (
+                None,
+                Some(bitcoin::Sequence::from_512_second_intervals(intervals)),
+            ),
+            SpendingPath::Recovery(RecoveryTimelock::Absolute(value)) => (
+                Some(bitcoin::absolute::LockTime::from_consensus(value)),
+                None,
+            ),
+        };
+        let assets = Assets {
+            keys,
+            absolute_timelock,
+            relative_timelock,
+            ..Default::default()
+        };
+
+        // Unfortunately rust-miniscript satisfaction size estimation is inconsistent. For
+        // Taproot it considers the whole witness (including the control block size + the
+        // script size) but under P2WSH it does not consider the witscript! Therefore we
+        // manually add the size of the witscript, but only under P2WSH by the mean of the
+        // `explicit_script()` helper.
+        let der_desc = self
+            .receive_desc
+            .0
+            .at_derivation_index(0)
+            .expect("unhardened index");
+        let witscript_size = der_desc
+            .explicit_script()
+            .map(|s| varint_len(s.len()) + s.len())
+            .unwrap_or(0);
+
+        // Finally, compute the satisfaction template for this path and get its size.
+        Some(
+            der_desc
+                .plan(&assets)
+                .expect("Always satisfiable")
+                .witness_size()
+                + witscript_size,
+        )
+    }
+
     /// Get the maximum size difference of a transaction input spending a Script derived from this
     /// descriptor before and after satisfaction. The returned value is in (rounded up) virtual
     /// bytes.
@@ -318,14 +338,66 @@ impl LianaDescriptor {
         matches!(self.multi_desc, descriptor::Descriptor::Tr(..))
     }
 
+    /// The descriptor with each key replaced by its origin, as used to identify a wallet in
+    /// the "origin" field of BIP329 labels. For instance `wsh(or_d(pk([aabbccdd/48'/0'/0'/2']),..))`.
+    pub fn keys_origin_descriptor(&self) -> String {
+        struct OriginTranslator;
+        impl Translator<descriptor::DescriptorPublicKey, String, ()> for OriginTranslator {
+            fn pk(&mut self, pk: &descriptor::DescriptorPublicKey) -> Result<String, ()> {
+                let origin_path = match pk {
+                    descriptor::DescriptorPublicKey::Single(key) => key.origin.as_ref(),
+                    descriptor::DescriptorPublicKey::XPub(xkey) => xkey.origin.as_ref(),
+                    descriptor::DescriptorPublicKey::MultiXPub(xkey) => xkey.origin.as_ref(),
+                }
+                .map(|(_, path)| {
+                    path.into_iter()
+                        .map(|child| format!("/{}", child))
+                        .collect::<String>()
+                })
+                .unwrap_or_default();
+                Ok(format!("[{}{}]", pk.master_fingerprint(), origin_path))
+            }
+
+            fn sha256(&mut self, hash: &bitcoin::hashes::sha256::Hash) -> Result<String, ()> {
+                Ok(hash.to_string())
+            }
+
+            fn hash256(&mut self, hash: &miniscript::hash256::Hash) -> Result<String, ()> {
+                Ok(hash.to_string())
+            }
+
+            fn ripemd160(&mut self, hash: &bitcoin::hashes::ripemd160::Hash) -> Result<String, ()> {
+                Ok(hash.to_string())
+            }
+
+            fn hash160(&mut self, hash: &bitcoin::hashes::hash160::Hash) -> Result<String, ()> {
+                Ok(hash.to_string())
+            }
+        }
+
+        let desc = self
+            .multi_desc
+            .translate_pk(&mut OriginTranslator)
+            .expect("Our translator never fails.");
+        // Drop the checksum, it would be meaningless.
+        desc.to_string()
+            .split('#')
+            .next()
+            .expect("Always at least one element")
+            .to_string()
+    }
+
     /// Get some information about a PSBT input spending Liana coins.
     /// This analysis assumes that:
     /// - The PSBT input actually spend a Liana coin for this descriptor. Otherwise the analysis will be off.
     /// - The signatures contained in the PSBT input are valid for this script.
+    ///
+    /// The `lock_time` is the one of the transaction spending the input.
     pub fn partial_spend_info_txin(
         &self,
         psbt_in: &PsbtIn,
         txin: &bitcoin::TxIn,
+        lock_time: bitcoin::absolute::LockTime,
     ) -> PartialSpendInfo {
         let is_taproot = self.is_taproot();
         // Get the origin ECDSA or Schnorr signatures, depending on the descriptor type.
@@ -362,7 +434,8 @@ impl LianaDescriptor {
 
         // Determine the structure of the descriptor. Then compute the spend info for the primary
         // and recovery paths. Only provide the spend info for the recovery path if it is available
-        // (ie if the nSequence is >= to the chosen CSV value).
+        // (ie if the nSequence is >= to the chosen CSV value, or the nLockTime is >= to the chosen
+        // CLTV value).
         let desc_info = self.policy();
         let primary_path = desc_info.primary_path.spend_info(pubkeys_signed.clone());
         let recovery_paths = desc_info
@@ -376,10 +449,39 @@ impl LianaDescriptor {
                 }
             })
             .collect();
+        let time_recovery_paths = desc_info
+            .time_recovery_paths
+            .iter()
+            .filter_map(
+                |(timelock, path_info)| match txin.sequence.to_relative_lock_time() {
+                    Some(bitcoin::relative::LockTime::Time(time)) if time.value() >= *timelock => {
+                        Some((*timelock, path_info.spend_info(pubkeys_signed.clone())))
+                    }
+                    _ => None,
+                },
+            )
+            .collect();
+        let absolute_recovery_paths = desc_info
+            .absolute_recovery_paths
+            .iter()
+            .filter_map(|(timelock, path_info)| {
+                let timelock = bitcoin::absolute::LockTime::from_consensus(*timelock);
+                if txin.sequence.enables_absolute_lock_time() && timelock.is_implied_by(lock_time) {
+                    Some((
+                        timelock.to_consensus_u32(),
+                        path_info.spend_info(pubkeys_signed.clone()),
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect();
 
         PartialSpendInfo {
             primary_path,
             recovery_paths,
+            time_recovery_paths,
+            absolute_recovery_paths,
         }
     }
 
@@ -414,12 +516,13 @@ impl LianaDescriptor {
                 .expect("We checked at least one is present."),
             txins.next().expect("We checked at least one is present."),
         );
-        let spend_info = self.partial_spend_info_txin(first_psbt_in, first_txin);
+        let lock_time = psbt.unsigned_tx.lock_time;
+        let spend_info = self.partial_spend_info_txin(first_psbt_in, first_txin, lock_time);
         for (psbt_in, txin) in psbt_ins.zip(txins) {
             // TODO: maybe it's better to not error if one of the input has more, or different
             // signatures? Instead of erroring we could ignore the superfluous data?
             if txin.sequence != first_txin.sequence
-                || spend_info != self.partial_spend_info_txin(psbt_in, txin)
+                || spend_info != self.partial_spend_info_txin(psbt_in, txin, lock_time)
             {
                 return Err(LianaDescError::InconsistentPsbt);
             }
@@ -1096,13 +1199,13 @@ mod tests {
         LianaDescriptor::from_str("wsh(or_i(pk([abcdef01]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),pk([abcdef01]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap_err();
 
         let desc = LianaDescriptor::from_str("wsh(andor(pk([abcdef01]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(1),pk([abcdef01]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
-        assert_eq!(desc.first_timelock_value(), 1);
+        assert_eq!(desc.first_timelock_value(), Some(1));
 
         let desc = LianaDescriptor::from_str("wsh(andor(pk([abcdef01]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(42000),pk([abcdef01]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
-        assert_eq!(desc.first_timelock_value(), 42000);
+        assert_eq!(desc.first_timelock_value(), Some(42000));
 
         let desc = LianaDescriptor::from_str("wsh(andor(pk([abcdef01]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(65535),pk([abcdef01]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
-        assert_eq!(desc.first_timelock_value(), 0xffff);
+        assert_eq!(desc.first_timelock_value(), Some(0xffff));
     }
 
     #[test]
@@ -1318,6 +1421,327 @@ mod tests {
         roundtrip("tr(xpub661MyMwAqRbcGqmqNapgQ9kqrLcDeZLHPktzsBcZXTtNx7aEay8NKQPizKcpu2fUejNbZzhZQaZLeDWL3nt8zg9QbFLBUTRQu4qqcSzeEmF/<0;1>/*,{{and_v(v:multi_a(1,[b4e32970]xpub661MyMwAqRbcEbs6ohRoUqTckEfLeT3vB2EsuWuckrEuDSKqdFXV6so8xJb4kvA4ZxT6hCydyFKsKwJrDm2LgSfTCphVqZgQbLzF49KwaXc/<0;1>/*,[c318e87f]xpub661MyMwAqRbcG2qnrFJ2MhKFSHehbVkK38gFfG7zXwasN51dKrL4kffj1HRd2zFhAZeQsjYKS8YaiN4sC4gVPHR28qXdQf7pf7nbYoefg6T/<0;1>/*),older(1678)),{and_v(v:pk([6c0d38a3]xpub661MyMwAqRbcF87hAvenL8GHW7qxhtn8Y9zHVkQbuTsd6RVtWkhBY5gh6m4Rua9ENmYDx7jTb8kbiyVB9iaLAbyRudxPFVTFoGPp6rTqoZn/<0;1>/*),older(42)),and_v(v:multi_a(2,[2e1370a6]xpub661MyMwAqRbcGRzCgSNLW7VFUFdwvC1dFXmKgWbZwQERj2QfNQuy5diCQSHNXuQYSS9FwXykLeWKtnZ5yRJ4ZHZzYqWf13FUY4PbDpBhipr/<0;1>/*,[fae2633e]xpub661MyMwAqRbcG9qKwZ7F363Mx3Ai3H2aMXAWTjvYCZrH4wqDEDLnsVghWFrwTKwpDGGzsSDCL7vPTiaiY7DhhdV2bY6RdPNGd7bF9om1MFz/<0;1>/*,[2ae87e33]xpub661MyMwAqRbcGw8ZvGfdLEjhCk4YC9hZrrUceKipiH32ANDMQccYFqq91kH8RpcwGiPnCbUWFo1S6ZGY2GxbVdJFsMYXqzpL1byJ1D3G2Mh/<0;1>/*),older(43))}},multi_a(2,[40f48611]xpub661MyMwAqRbcGUkDb45NBcMYwaaSE3fhsMNwvdf2psYhrqhFmRJY9n8irJuEB3juhK5LQPBiiqdr2gixMmC7Nmtg3Mwu4C5wbeagaAzbb9W/<0;1>/*,[a2bdfbe5]xpub661MyMwAqRbcH228eUBaJvc7Va1y7cGyEH9DZ5vPneKgZDX8eMsSd8PHS3uRYCFySyHPy3VfGfS8vKb5FzcS2MbNorNVv2c3Hn7AvVJJZ73/<0;1>/*,[028ece7a]xpub661MyMwAqRbcG9W1pZzs7rvWVtHeW1anzABj8iQRBbnz8yLf7vgUmYkVsydLf1hLffibgfzUjTBcrNCDKaBNnuqLtsp1xyiLSZJyLDtEjkF/<0;1>/*)})#xgzxdvrv");
     }
 
+    #[test]
+    fn absolute_recovery_path() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let owner_key = PathInfo::Single(random_desc_key(&secp));
+        let heir_key = PathInfo::Single(random_desc_key(&secp));
+        let lawyer_key = PathInfo::Single(random_desc_key(&secp));
+
+        // A recovery path behind an absolute timelock must have a sane timelock.
+        let new_policy = |is_taproot: bool, abs_timelock: u32| {
+            let policy = if is_taproot {
+                LianaPolicy::new(
+                    owner_key.clone(),
+                    [(52560, heir_key.clone())].iter().cloned().collect(),
+                )
+            } else {
+                LianaPolicy::new_legacy(
+                    owner_key.clone(),
+                    [(52560, heir_key.clone())].iter().cloned().collect(),
+                )
+            };
+            policy.unwrap().with_absolute_recovery_paths(
+                [(abs_timelock, lawyer_key.clone())]
+                    .iter()
+                    .cloned()
+                    .collect(),
+            )
+        };
+        assert!(new_policy(false, 0).is_err());
+        assert!(new_policy(false, 0x8000_0000).is_err());
+
+        for is_taproot in [false, true] {
+            // The absolute timelock survives a roundtrip through the descriptor string.
+            let policy = new_policy(is_taproot, 840_000).unwrap();
+            let desc = LianaDescriptor::new(policy.clone());
+            let desc_str = desc.to_string();
+            assert!(desc_str.contains("after(840000)"));
+            let desc = LianaDescriptor::from_str(&desc_str).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(
+                desc.policy()
+                    .absolute_recovery_paths()
+                    .keys()
+                    .collect::<Vec<_>>(),
+                vec![&840_000]
+            );
+            assert_eq!(desc.first_timelock_value(), Some(52560));
+
+            // The recovery path is only reported as available if the transaction's nLockTime is
+            // at least the timelock and enables it.
+            let new_psbt = |sequence: Sequence, lock_time: u32| {
+                let tx = bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::from_consensus(lock_time),
+                    input: vec![bitcoin::TxIn {
+                        sequence,
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: bitcoin::Amount::from_sat(10_000),
+                        script_pubkey: bitcoin::ScriptBuf::new(),
+                    }],
+                };
+                Psbt::from_unsigned_tx(tx).unwrap()
+            };
+            let psbt = new_psbt(Sequence::ENABLE_RBF_NO_LOCKTIME, 840_000);
+            let info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(info.absolute_recovery_paths().contains_key(&840_000));
+            assert!(info.recovery_paths().is_empty());
+            let psbt = new_psbt(Sequence::ENABLE_RBF_NO_LOCKTIME, 839_999);
+            let info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(info.absolute_recovery_paths().is_empty());
+            let psbt = new_psbt(Sequence::MAX, 840_000);
+            let info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(info.absolute_recovery_paths().is_empty());
+        }
+    }
+
+    #[test]
+    fn policy_description() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let owner_keys = vec![random_desc_key(&secp), random_desc_key(&secp)];
+        let heir_key = random_desc_key(&secp);
+        let lawyer_key = random_desc_key(&secp);
+        let notary_key = random_desc_key(&secp);
+
+        let policy = LianaPolicy::new(
+            PathInfo::Multi(2, owner_keys.clone()),
+            [(52560, PathInfo::Single(heir_key.clone()))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_time_recovery_paths(
+            [(675, PathInfo::Single(lawyer_key.clone()))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap()
+        .with_absolute_recovery_paths(
+            [(900_000, PathInfo::Single(notary_key.clone()))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        let desc = policy.description();
+
+        assert_eq!(desc.primary_path.threshold, 2);
+        assert_eq!(
+            desc.primary_path
+                .keys
+                .iter()
+                .map(|k| k.key.clone())
+                .collect::<Vec<_>>(),
+            owner_keys
+        );
+        assert_eq!(
+            desc.primary_path.keys[0].fingerprint,
+            owner_keys[0].master_fingerprint()
+        );
+        assert_eq!(
+            desc.recovery_paths
+                .iter()
+                .map(|p| (p.timelock, p.approx_days, p.path.keys[0].key.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    RecoveryTimelock::Relative(52560),
+                    Some(365.0),
+                    heir_key.clone()
+                ),
+                (
+                    RecoveryTimelock::RelativeTime(675),
+                    Some(4.0),
+                    lawyer_key.clone()
+                ),
+                (
+                    RecoveryTimelock::Absolute(900_000),
+                    None,
+                    notary_key.clone()
+                ),
+            ]
+        );
+
+        let text = desc.to_string();
+        assert_eq!(
+            text,
+            format!(
+                "Primary path: 2 of the following 2 keys can spend at any time.\n  - {}\n  - {}\n\
+                 Recovery path #1: the following key can spend coins inactive for 52560 blocks (~365 days).\n  - {}\n\
+                 Recovery path #2: the following key can spend coins inactive for 345600 seconds (~4 days).\n  - {}\n\
+                 Recovery path #3: the following key can spend coins from block height 900000.\n  - {}\n",
+                owner_keys[0], owner_keys[1], heir_key, lawyer_key, notary_key
+            )
+        );
+    }
+
+    #[test]
+    fn time_recovery_path() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let owner_key = PathInfo::Single(random_desc_key(&secp));
+        let heir_key = PathInfo::Single(random_desc_key(&secp));
+        let lawyer_key = PathInfo::Single(random_desc_key(&secp));
+
+        for is_taproot in [false, true] {
+            let recovery_paths: BTreeMap<_, _> =
+                [(52560, heir_key.clone())].iter().cloned().collect();
+            let policy = if is_taproot {
+                LianaPolicy::new(owner_key.clone(), recovery_paths)
+            } else {
+                LianaPolicy::new_legacy(owner_key.clone(), recovery_paths)
+            }
+            .unwrap();
+            // A timelock of 0 is refused.
+            assert!(policy
+                .clone()
+                .with_time_recovery_paths([(0, lawyer_key.clone())].iter().cloned().collect())
+                .is_err());
+            // About 6 months.
+            let policy = policy
+                .with_time_recovery_paths([(30_000, lawyer_key.clone())].iter().cloned().collect())
+                .unwrap();
+
+            // The timelock survives a roundtrip through the descriptor string.
+            let desc = LianaDescriptor::new(policy.clone());
+            let desc_str = desc.to_string();
+            let sequence = Sequence::from_512_second_intervals(30_000);
+            assert!(desc_str.contains(&format!("older({})", sequence.0)));
+            let desc = LianaDescriptor::from_str(&desc_str).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(
+                desc.policy()
+                    .time_recovery_paths()
+                    .keys()
+                    .collect::<Vec<_>>(),
+                vec![&30_000]
+            );
+            assert_eq!(desc.first_timelock_value(), Some(52560));
+
+            // The recovery path is only reported as available if the input's nSequence is set
+            // to a time-based relative timelock of at least this duration.
+            let new_psbt = |sequence: Sequence| {
+                let tx = bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::ZERO,
+                    input: vec![bitcoin::TxIn {
+                        sequence,
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: bitcoin::Amount::from_sat(10_000),
+                        script_pubkey: bitcoin::ScriptBuf::new(),
+                    }],
+                };
+                Psbt::from_unsigned_tx(tx).unwrap()
+            };
+            let info = desc.partial_spend_info(&new_psbt(sequence)).unwrap();
+            assert!(info.time_recovery_paths().contains_key(&30_000));
+            assert!(info.recovery_paths().is_empty());
+            let info = desc
+                .partial_spend_info(&new_psbt(Sequence::from_512_second_intervals(29_999)))
+                .unwrap();
+            assert!(info.time_recovery_paths().is_empty());
+            // A timelock in blocks with the same value doesn't unlock it.
+            let info = desc
+                .partial_spend_info(&new_psbt(Sequence::from_height(60_000)))
+                .unwrap();
+            assert!(info.time_recovery_paths().is_empty());
+            assert!(info.recovery_paths().contains_key(&52560));
+        }
+
+        // A time-based relative timelock with bits without consensus meaning set is refused.
+        let desc_str = LianaDescriptor::new(
+            LianaPolicy::new_legacy(owner_key, [(52560, heir_key)].iter().cloned().collect())
+                .unwrap()
+                .with_time_recovery_paths([(30_000, lawyer_key)].iter().cloned().collect())
+                .unwrap(),
+        )
+        .to_string();
+        let sequence = Sequence::from_512_second_intervals(30_000).0;
+        let desc_str = desc_str.split('#').next().unwrap().replace(
+            &format!("older({})", sequence),
+            &format!("older({})", sequence | 1 << 16),
+        );
+        assert!(LianaDescriptor::from_str(&desc_str).is_err());
+    }
+
+    #[test]
+    fn recovery_paths_without_relative_blocks() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let owner_key = PathInfo::Single(random_desc_key(&secp));
+        let heir_key = PathInfo::Single(random_desc_key(&secp));
+        let lawyer_key = PathInfo::Single(random_desc_key(&secp));
+
+        for is_taproot in [false, true] {
+            // There must be at least one recovery path, of any kind.
+            assert!(matches!(
+                LianaPolicy::new_with_recovery_paths(
+                    owner_key.clone(),
+                    BTreeMap::new(),
+                    BTreeMap::new(),
+                    BTreeMap::new(),
+                    is_taproot,
+                ),
+                Err(LianaPolicyError::MissingRecoveryPath)
+            ));
+
+            // A policy with a single recovery path behind an absolute timelock.
+            let policy = LianaPolicy::new_with_recovery_paths(
+                owner_key.clone(),
+                BTreeMap::new(),
+                BTreeMap::new(),
+                [(840_000, heir_key.clone())].iter().cloned().collect(),
+                is_taproot,
+            )
+            .unwrap();
+            let desc = LianaDescriptor::new(policy.clone());
+            let desc_str = desc.to_string();
+            assert!(desc_str.contains("after(840000)"));
+            assert!(!desc_str.contains("older("));
+            let desc = LianaDescriptor::from_str(&desc_str).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert!(desc.policy().recovery_paths().is_empty());
+            assert_eq!(desc.first_timelock_value(), None);
+
+            // A policy with recovery paths behind a relative timelock in time and an absolute
+            // timelock.
+            let policy = LianaPolicy::new_with_recovery_paths(
+                owner_key.clone(),
+                BTreeMap::new(),
+                [(30_000, heir_key.clone())].iter().cloned().collect(),
+                [(840_000, lawyer_key.clone())].iter().cloned().collect(),
+                is_taproot,
+            )
+            .unwrap();
+            let desc = LianaDescriptor::new(policy.clone());
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert!(desc.policy().recovery_paths().is_empty());
+            assert_eq!(desc.first_timelock_value(), None);
+            assert_eq!(desc.policy().description().recovery_paths.len(), 2);
+        }
+    }
+
+    #[test]
+    fn keys_origin_descriptor() {
+        let desc = LianaDescriptor::from_str("wsh(or_d(multi(3,[aabbccdd]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*,[aabb0011/10/4893]xpub6Bw79HbNSeS2xXw1sngPE3ehnk1U3iSPCgLYzC9LpN8m9nDuaKLZvkg8QXxL5pDmEmQtYscmUD8B9MkAAZbh6vxPzNXMaLfGQ9Sb3z85qhR/<0;1>/*,[aabb0022]xpub67zuTXF9Ln4731avKTBSawoVVNRuMfmRvkL7kLUaLBRqma9ZqdHBJg9qx8cPUm3oNQMiXT4TmGovXNoQPuwg17RFcVJ8YrnbcooN7pxVJqC/<0;1>/*),and_v(v:pk([aabbccdd/48'/1'/0'/2']xpub69cP4Y7S9TWcbSNxmk6CEDBsoaqr3ZEdjHuZcHxEFFKGh569RsJNr2V27XGhsbH9FXgWUEmKXRN7c5wQfq2VPjt31xP9VsYnVUyU8HcVevm/<0;1>/*),older(26352))))").unwrap();
+        assert_eq!(
+            desc.keys_origin_descriptor(),
+            "wsh(or_d(multi(3,[aabbccdd],[aabb0011/10/4893],[aabb0022]),and_v(v:pk([aabbccdd/48'/1'/0'/2']),older(26352))))"
+        );
+
+        let desc = LianaDescriptor::from_str("tr([8344c025]xpub661MyMwAqRbcG2SYC6YSRsUGvcSxXEZm1kjiQRTEaAqart1PQk1N1hVTTEsGfaBx6xQ5gDYXXtbourodE6ZE5qZTnaMgmehNs8GGEEY9YK6/<0;1>/*,and_v(v:pk([158fd0ef]xpub661MyMwAqRbcF2KsCnvJ4mqWXXrwd3799wCyQrLk2iNDC6CfK8UcfnABdeTpXyoJnBhRTybmtBLDAuTuHye1eQMq43BSLtR2miA6t9KqmWU/<0;1>/*),older(4242)))").unwrap();
+        assert_eq!(
+            desc.keys_origin_descriptor(),
+            "tr([8344c025],and_v(v:pk([158fd0ef]),older(4242)))"
+        );
+    }
+
     fn psbt_from_str(psbt_str: &str) -> Psbt {
         Psbt::from_str(psbt_str).unwrap()
     }
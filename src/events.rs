@@ -0,0 +1,432 @@
+//! # Wallet events
+//!
+//! Changes to the state of the wallet, as recorded in the events journal, and the hook command
+//! notified about them.
+
+use crate::{
+    commands::utils::{deser_addr_assume_checked, ser_to_string},
+    config::EventHookConfig,
+};
+
+use std::{
+    io::{self, Read, Write},
+    process,
+    sync::{self, atomic, mpsc},
+    thread, time,
+};
+
+use miniscript::bitcoin::{self, bip32};
+use serde::{Deserialize, Serialize};
+
+/// A change to the state of the wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A new coin was received. It may not be confirmed yet.
+    Received {
+        outpoint: bitcoin::OutPoint,
+        /// Value of the coin in satoshis.
+        amount: u64,
+        #[serde(
+            serialize_with = "ser_to_string",
+            deserialize_with = "deser_addr_assume_checked"
+        )]
+        address: bitcoin::Address,
+        derivation_index: bip32::ChildNumber,
+        is_change: bool,
+    },
+    /// The transaction creating a coin was included in a block.
+    Confirmed {
+        outpoint: bitcoin::OutPoint,
+        height: i32,
+        time: u32,
+    },
+    /// The transaction creating a coin was dropped from the mempool or reorged out of the chain.
+    Expired { outpoint: bitcoin::OutPoint },
+    /// A coin is being spent by an unconfirmed transaction.
+    Spending {
+        outpoint: bitcoin::OutPoint,
+        txid: bitcoin::Txid,
+    },
+    /// The transaction spending a coin was included in a block.
+    Spent {
+        outpoint: bitcoin::OutPoint,
+        txid: bitcoin::Txid,
+        height: i32,
+        time: u32,
+    },
+    /// The transaction spending a coin was dropped from the mempool or reorged out of the chain.
+    SpendExpired { outpoint: bitcoin::OutPoint },
+    /// Our tip was rolled back to this block, due to a reorg or a rescan.
+    Rollback {
+        height: i32,
+        hash: bitcoin::BlockHash,
+    },
+    /// Progress of an ongoing rescan, as a percentage between 0 and 1.
+    RescanProgress { progress: f64 },
+    /// A rescan of the block chain from this date completed.
+    RescanComplete { timestamp: u32 },
+    /// A Spend transaction was stored, or updated.
+    SpendUpdated { txid: bitcoin::Txid },
+    /// A stored Spend transaction was deleted.
+    SpendDeleted { txid: bitcoin::Txid },
+}
+
+/// An event recorded in the events journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Identifier of the event in the journal, strictly increasing.
+    pub id: u64,
+    /// Time at which the event was recorded.
+    pub timestamp: u32,
+    pub event: Event,
+}
+
+/// Lets threads wait for new events to be recorded in the journal.
+#[derive(Debug, Clone, Default)]
+pub struct EventsNotifier {
+    generation: sync::Arc<(sync::Mutex<u64>, sync::Condvar)>,
+    // The number of threads currently registered as waiting for new events.
+    waiters: sync::Arc<atomic::AtomicUsize>,
+}
+
+/// A registration as waiting for new events, see [`EventsNotifier::register_waiter`]. Dropping it
+/// unregisters.
+pub struct EventsWaiter(sync::Arc<atomic::AtomicUsize>);
+
+impl Drop for EventsWaiter {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, atomic::Ordering::Relaxed);
+    }
+}
+
+impl EventsNotifier {
+    /// The number of times new events were notified about so far.
+    pub fn generation(&self) -> u64 {
+        *self.generation.0.lock().unwrap()
+    }
+
+    /// Register as waiting for new events, unless there are already `max_waiters` registered.
+    pub fn register_waiter(&self, max_waiters: usize) -> Option<EventsWaiter> {
+        self.waiters
+            .fetch_update(atomic::Ordering::Relaxed, atomic::Ordering::Relaxed, |n| {
+                if n < max_waiters {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| EventsWaiter(self.waiters.clone()))
+    }
+
+    /// Wake up the waiters, new events were recorded in the journal.
+    pub fn notify(&self) {
+        let (generation, condvar) = &*self.generation;
+        *generation.lock().unwrap() += 1;
+        condvar.notify_all();
+    }
+
+    /// Wait until new events are notified after the given generation, or the timeout expires.
+    pub fn wait(&self, generation: u64, timeout: time::Duration) {
+        let (curr_generation, condvar) = &*self.generation;
+        let _ = condvar
+            .wait_timeout_while(curr_generation.lock().unwrap(), timeout, |curr| {
+                *curr == generation
+            })
+            .unwrap();
+    }
+}
+
+// How many events may be waiting for the hook command to be run. Past this, events are dropped.
+const EVENT_HOOK_QUEUE_SIZE: usize = 1_000;
+
+// For how long a run of the hook command may last before it is killed.
+const EVENT_HOOK_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+// For how long to wait for the queued events to be notified when stopping. Past this, the
+// remaining events are dropped and the running command killed.
+const EVENT_HOOK_STOP_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+// How much of the standard error of a failed hook command to report.
+const EVENT_HOOK_MAX_STDERR_SIZE: u64 = 4 * 1024;
+
+// How often to check whether the hook command exited.
+const EVENT_HOOK_POLL_INTERVAL: time::Duration = time::Duration::from_millis(20);
+
+/// Runs the configured hook command for each event, in order, in a dedicated thread.
+///
+/// The event is passed as a JSON object on the standard input of the command. For the events of
+/// an additional wallet, the name of the wallet is set in the `LIANA_WALLET` environment variable.
+/// If the command can't keep up, the events which don't fit in the queue are not passed to it (they
+/// are still recorded in the journal).
+pub struct EventHook {
+    sender: mpsc::SyncSender<(Option<String>, Event)>,
+    thread: thread::JoinHandle<()>,
+    // Set to stop running the hook command without waiting for the queued events.
+    abort: sync::Arc<atomic::AtomicBool>,
+}
+
+impl EventHook {
+    pub fn new(config: EventHookConfig) -> EventHook {
+        let (sender, receiver) =
+            mpsc::sync_channel::<(Option<String>, Event)>(EVENT_HOOK_QUEUE_SIZE);
+        let abort = sync::Arc::new(atomic::AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name("Event hook".to_string())
+            .spawn({
+                let abort = abort.clone();
+                move || {
+                    // Stops once the sender is dropped along with the hook.
+                    for (wallet, event) in receiver {
+                        if abort.load(atomic::Ordering::Relaxed) {
+                            return;
+                        }
+                        if let Err(e) = run_hook(&config, wallet.as_deref(), &event, &abort) {
+                            log::error!(
+                                "Error running hook command '{}': '{}'.",
+                                config.command.display(),
+                                e
+                            );
+                        }
+                    }
+                }
+            })
+            .expect("Spawning the event hook thread must never fail.");
+        EventHook {
+            sender,
+            thread,
+            abort,
+        }
+    }
+
+    /// Queue these events of the given wallet for the hook command to be notified about. The
+    /// wallet is `None` for the main one.
+    pub fn notify(&self, wallet: Option<&str>, events: &[Event]) {
+        for event in events {
+            match self
+                .sender
+                .try_send((wallet.map(|w| w.to_string()), event.clone()))
+            {
+                Ok(()) => {}
+                Err(mpsc::TrySendError::Full((_, event))) => {
+                    log::error!(
+                        "Event hook queue is full, the hook command won't be run for event '{:?}'.",
+                        event
+                    );
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => {
+                    log::error!("Event hook thread is gone, could not send event.");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Wait for the hook command to be run for the queued events, and stop the hook thread. If
+    /// this takes too long, the remaining events are dropped and the running command is killed.
+    pub fn stop(self) {
+        let EventHook {
+            sender,
+            thread,
+            abort,
+        } = self;
+        drop(sender);
+        let deadline = time::Instant::now() + EVENT_HOOK_STOP_TIMEOUT;
+        while !thread.is_finished() && time::Instant::now() < deadline {
+            thread::sleep(EVENT_HOOK_POLL_INTERVAL);
+        }
+        if !thread.is_finished() {
+            log::error!(
+                "Timed out waiting for the hook command to be run for the queued events. Dropping \
+                 the remaining ones."
+            );
+            abort.store(true, atomic::Ordering::Relaxed);
+        }
+        if thread.join().is_err() {
+            log::error!("Event hook thread panicked.");
+        }
+    }
+}
+
+// Run the hook command for this event and wait for its completion. The command is killed if it
+// takes too long, or if `abort` is set.
+fn run_hook(
+    config: &EventHookConfig,
+    wallet: Option<&str>,
+    event: &Event,
+    abort: &atomic::AtomicBool,
+) -> Result<(), String> {
+    let event = serde_json::to_vec(event).map_err(|e| e.to_string())?;
+    let mut command = process::Command::new(&config.command);
+    if let Some(wallet) = wallet {
+        command.env("LIANA_WALLET", wallet);
+    }
+    let mut child = command
+        .args(&config.args)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The command may not read its standard input, ignore a broken pipe.
+        if let Err(e) = stdin.write_all(&event).and_then(|_| stdin.write_all(b"\n")) {
+            log::debug!("Error writing event to hook command's stdin: '{}'.", e);
+        }
+    }
+    // Read its standard error in the background, not to block the command if it fills the pipe. A
+    // process it started in the background may keep the pipe open, don't wait for it either.
+    let stderr = child.stderr.take().map(|stderr| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stderr = stderr.take(EVENT_HOOK_MAX_STDERR_SIZE);
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            let _ = sender.send(buf);
+            let _ = io::copy(&mut stderr.into_inner(), &mut io::sink());
+        });
+        receiver
+    });
+
+    let deadline = time::Instant::now() + EVENT_HOOK_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if abort.load(atomic::Ordering::Relaxed) || time::Instant::now() >= deadline {
+            // It may have exited in the meantime, in which case killing it fails.
+            let _ = child.kill();
+            child.wait().map_err(|e| e.to_string())?;
+            return Err(if abort.load(atomic::Ordering::Relaxed) {
+                "killed upon shutdown".to_string()
+            } else {
+                format!(
+                    "killed after running for more than {} seconds",
+                    EVENT_HOOK_TIMEOUT.as_secs()
+                )
+            });
+        }
+        thread::sleep(EVENT_HOOK_POLL_INTERVAL);
+    };
+    if !status.success() {
+        let stderr = stderr
+            .and_then(|receiver| receiver.recv_timeout(EVENT_HOOK_POLL_INTERVAL).ok())
+            .unwrap_or_default();
+        return Err(format!(
+            "{}, stderr: '{}'",
+            status,
+            String::from_utf8_lossy(&stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn event_json() {
+        let outpoint = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:1",
+        )
+        .unwrap();
+        let event = Event::Confirmed {
+            outpoint,
+            height: 100,
+            time: 1_700_000_000,
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "confirmed",
+                "outpoint": "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:1",
+                "height": 100,
+                "time": 1_700_000_000,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&Event::RescanComplete { timestamp: 42 }).unwrap(),
+            serde_json::json!({"type": "rescan_complete", "timestamp": 42})
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_stop() {
+        let tmp_dir = crate::testutils::tmp_dir();
+        std::fs::create_dir_all(&tmp_dir).unwrap();
+        let events_path = tmp_dir.join("events.jsonl");
+        let hook = EventHook::new(EventHookConfig {
+            command: "/bin/sh".into(),
+            args: vec![
+                "-c".to_string(),
+                format!("sleep 0.1 && cat >> '{}'", events_path.display()),
+            ],
+        });
+
+        // Stopping the hook waits for the command to be run for all the queued events.
+        let events: Vec<_> = (0..3)
+            .map(|progress| Event::RescanProgress {
+                progress: progress as f64 / 10.0,
+            })
+            .collect();
+        hook.notify(None, &events);
+        hook.stop();
+        let written: Vec<Event> = std::fs::read_to_string(&events_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(written, events);
+
+        std::fs::remove_dir_all(&tmp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_stop_timeout() {
+        let tmp_dir = crate::testutils::tmp_dir();
+        std::fs::create_dir_all(&tmp_dir).unwrap();
+        let events_path = tmp_dir.join("events.jsonl");
+        let hook = EventHook::new(EventHookConfig {
+            command: "/bin/sh".into(),
+            args: vec![
+                "-c".to_string(),
+                format!("cat >> '{}' && sleep 1000", events_path.display()),
+            ],
+        });
+
+        // A hanging command doesn't prevent stopping the hook. It is killed and the remaining
+        // events are dropped.
+        let events: Vec<_> = (0..3)
+            .map(|progress| Event::RescanProgress {
+                progress: progress as f64 / 10.0,
+            })
+            .collect();
+        hook.notify(None, &events);
+        let start = time::Instant::now();
+        hook.stop();
+        assert!(start.elapsed() < EVENT_HOOK_STOP_TIMEOUT + time::Duration::from_secs(5));
+        let written: Vec<Event> = std::fs::read_to_string(&events_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(written, events[..1]);
+
+        // A run of the command is also killed if we are told to abort.
+        let abort = atomic::AtomicBool::new(true);
+        let config = EventHookConfig {
+            command: "/bin/sh".into(),
+            args: vec!["-c".to_string(), "sleep 1000".to_string()],
+        };
+        let start = time::Instant::now();
+        assert!(run_hook(&config, None, &events[0], &abort).is_err());
+        assert!(start.elapsed() < time::Duration::from_secs(5));
+
+        std::fs::remove_dir_all(&tmp_dir).unwrap();
+    }
+}
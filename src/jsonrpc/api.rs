@@ -1,16 +1,63 @@
 use crate::{
-    commands::{CoinStatus, LabelItem},
+    commands::{CoinStatus, LabelItem, DEFAULT_WAIT_FOR_EVENTS_TIMEOUT, MAX_LABEL_LENGTH},
+    descriptors::{LianaDescriptor, RecoveryTimelock, SpendingPath},
     jsonrpc::{Error, Params, Request, Response},
     DaemonControl,
 };
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryInto,
     str::FromStr,
 };
 
-use miniscript::bitcoin::{self, psbt::Psbt, Txid};
+use miniscript::bitcoin::{self, bip32, psbt::Psbt, Txid};
+
+/// Get the feerate to use for a transaction, either as given by the 'feerate' parameter or as
+/// estimated from the 'conf_target' parameter. At most one of them may be provided.
+fn get_feerate(
+    control: &DaemonControl,
+    params: &Params,
+    feerate_index: usize,
+    conf_target_index: usize,
+) -> Result<Option<u64>, Error> {
+    let feerate: Option<u64> = params
+        .get(feerate_index, "feerate")
+        .filter(|f| !f.is_null())
+        .map(|f| {
+            f.as_u64()
+                .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))
+        })
+        .transpose()?;
+    let conf_target: Option<u16> = params
+        .get(conf_target_index, "conf_target")
+        .filter(|t| !t.is_null())
+        .map(|t| {
+            t.as_u64()
+                .and_then(|t| t.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'conf_target' parameter."))
+        })
+        .transpose()?;
+    match (feerate, conf_target) {
+        (Some(_), Some(_)) => Err(Error::invalid_params(
+            "Only one of 'feerate' and 'conf_target' parameters may be provided.",
+        )),
+        (Some(feerate), None) => Ok(Some(feerate)),
+        (None, Some(conf_target)) => Ok(Some(control.estimate_fee(conf_target)?.feerate)),
+        (None, None) => Ok(None),
+    }
+}
+
+fn estimate_fee(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let conf_target: u16 = params
+        .get(0, "conf_target")
+        .ok_or_else(|| Error::invalid_params("Missing 'conf_target' parameter."))?
+        .as_u64()
+        .and_then(|t| t.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'conf_target' parameter."))?;
+    let res = control.estimate_fee(conf_target)?;
+    Ok(serde_json::json!(&res))
+}
 
 fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
     let destinations = params
@@ -41,13 +88,11 @@ fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
                 .collect::<Option<Vec<bitcoin::OutPoint>>>()
         })
         .ok_or_else(|| Error::invalid_params("Invalid 'outpoints' parameter."))?;
-    let feerate: u64 = params
-        .get(2, "feerate")
-        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))?
-        .as_u64()
-        .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
+    let feerate: u64 = get_feerate(control, &params, 2, 4)?
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' or 'conf_target' parameter."))?;
     let change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>> = params
         .get(3, "change_address")
+        .filter(|addr| !addr.is_null())
         .map(|addr| {
             let addr_str = addr.as_str().ok_or_else(|| {
                 Error::invalid_params("Invalid 'change_address' parameter: must be a string.")
@@ -57,8 +102,46 @@ fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
             })
         })
         .transpose()?;
+    let include_reserved = params
+        .get(5, "include_reserved")
+        .filter(|r| !r.is_null())
+        .map(|r| {
+            r.as_bool()
+                .ok_or_else(|| Error::invalid_params("Invalid 'include_reserved' parameter."))
+        })
+        .transpose()?
+        .unwrap_or(false);
+    // Either "primary" or a recovery timelock as described by 'describepolicy'.
+    let spending_path = params
+        .get(6, "spending_path")
+        .filter(|p| !p.is_null())
+        .map(|p| {
+            if p.as_str() == Some("primary") {
+                return Ok(SpendingPath::Primary);
+            }
+            serde_json::from_value::<RecoveryTimelock>(p.clone())
+                .map(SpendingPath::Recovery)
+                .map_err(|_| Error::invalid_params("Invalid 'spending_path' parameter."))
+        })
+        .transpose()?;
 
-    let res = control.create_spend(&destinations, &outpoints, feerate, change_address)?;
+    let res = match spending_path {
+        Some(spending_path) => control.create_spend_through_path(
+            &destinations,
+            &outpoints,
+            feerate,
+            change_address,
+            include_reserved,
+            spending_path,
+        )?,
+        None => control.create_spend(
+            &destinations,
+            &outpoints,
+            feerate,
+            change_address,
+            include_reserved,
+        )?,
+    };
     Ok(serde_json::json!(&res))
 }
 
@@ -110,19 +193,19 @@ fn rbf_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value
         .ok_or_else(|| Error::invalid_params("Missing 'is_cancel' parameter."))?
         .as_bool()
         .ok_or_else(|| Error::invalid_params("Invalid 'is_cancel' parameter."))?;
-    let feerate_vb: Option<u64> = if let Some(feerate) = params.get(2, "feerate") {
-        Some(
-            feerate
-                .as_u64()
-                .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?,
-        )
-    } else {
-        None
-    };
+    let feerate_vb = get_feerate(control, &params, 2, 3)?;
     let res = control.rbf_psbt(&txid, is_cancel, feerate_vb)?;
     Ok(serde_json::json!(&res))
 }
 
+fn create_cpfp(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let outpoints = get_outpoints(&params)?;
+    let feerate: u64 = get_feerate(control, &params, 1, 2)?
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' or 'conf_target' parameter."))?;
+    let res = control.create_cpfp(&outpoints, feerate)?;
+    Ok(serde_json::json!(&res))
+}
+
 fn list_coins(control: &DaemonControl, params: Option<Params>) -> Result<serde_json::Value, Error> {
     let statuses_arg = params
         .as_ref()
@@ -172,6 +255,55 @@ fn list_coins(control: &DaemonControl, params: Option<Params>) -> Result<serde_j
     Ok(serde_json::json!(&res))
 }
 
+fn list_expiring_coins(
+    control: &DaemonControl,
+    params: Option<Params>,
+) -> Result<serde_json::Value, Error> {
+    let max_blocks = params
+        .as_ref()
+        .and_then(|p| p.get(0, "max_blocks"))
+        .filter(|b| !b.is_null())
+        .map(|b| {
+            b.as_u64()
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'max_blocks' parameter."))
+        })
+        .transpose()?;
+    let res = control.list_expiring_coins(max_blocks);
+    Ok(serde_json::json!(&res))
+}
+
+fn get_outpoints(params: &Params) -> Result<Vec<bitcoin::OutPoint>, Error> {
+    params
+        .get(0, "outpoints")
+        .ok_or_else(|| Error::invalid_params("Missing 'outpoints' parameter."))?
+        .as_array()
+        .and_then(|arr| {
+            arr.iter()
+                .map(|entry| {
+                    entry
+                        .as_str()
+                        .and_then(|e| bitcoin::OutPoint::from_str(e).ok())
+                })
+                .collect::<Option<Vec<bitcoin::OutPoint>>>()
+        })
+        .ok_or_else(|| Error::invalid_params("Invalid 'outpoints' parameter."))
+}
+
+fn freeze_coins(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let outpoints = get_outpoints(&params)?;
+    control.freeze_coins(&outpoints)?;
+
+    Ok(serde_json::json!({}))
+}
+
+fn unfreeze_coins(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let outpoints = get_outpoints(&params)?;
+    control.unfreeze_coins(&outpoints)?;
+
+    Ok(serde_json::json!({}))
+}
+
 fn get_opt_u32<Q>(params: &Option<Params>, index: usize, name: &Q) -> Result<Option<u32>, Error>
 where
     String: std::borrow::Borrow<Q>,
@@ -285,21 +417,148 @@ fn create_recovery(control: &DaemonControl, params: Params) -> Result<serde_json
         .as_str()
         .and_then(|s| bitcoin::Address::from_str(s).ok())
         .ok_or_else(|| Error::invalid_params("Invalid 'address' parameter."))?;
-    let feerate: u64 = params
-        .get(1, "feerate")
-        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))?
-        .as_u64()
-        .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
+    let feerate: u64 = get_feerate(control, &params, 1, 3)?
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' or 'conf_target' parameter."))?;
     let timelock: Option<u16> = params
         .get(2, "timelock")
+        .filter(|tl| !tl.is_null())
         .map(|tl| {
             tl.as_u64()
                 .and_then(|tl| tl.try_into().ok())
                 .ok_or_else(|| Error::invalid_params("Invalid 'timelock' parameter."))
         })
         .transpose()?;
+    let absolute_timelock: Option<u32> = params
+        .get(4, "absolute_timelock")
+        .filter(|tl| !tl.is_null())
+        .map(|tl| {
+            tl.as_u64()
+                .and_then(|tl| tl.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'absolute_timelock' parameter."))
+        })
+        .transpose()?;
+    let time_timelock: Option<u16> = params
+        .get(5, "time_timelock")
+        .filter(|tl| !tl.is_null())
+        .map(|tl| {
+            tl.as_u64()
+                .and_then(|tl| tl.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'time_timelock' parameter."))
+        })
+        .transpose()?;
+    let signers: Option<Vec<bip32::Fingerprint>> = params
+        .get(6, "signers")
+        .filter(|s| !s.is_null())
+        .map(|signers| {
+            signers
+                .as_array()
+                .ok_or_else(|| Error::invalid_params("Invalid 'signers' parameter."))?
+                .iter()
+                .map(|fg| {
+                    fg.as_str()
+                        .and_then(|fg| bip32::Fingerprint::from_str(fg).ok())
+                        .ok_or_else(|| {
+                            Error::invalid_params(format!(
+                                "Invalid value {} in 'signers' parameter: must be a fingerprint",
+                                fg
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        })
+        .transpose()?;
+
+    let res = match (timelock, absolute_timelock, time_timelock, signers) {
+        (timelock, None, None, None) => control.create_recovery(address, feerate, timelock)?,
+        (None, Some(absolute_timelock), None, None) => {
+            control.create_absolute_recovery(address, feerate, Some(absolute_timelock))?
+        }
+        (None, None, Some(time_timelock), None) => {
+            control.create_time_recovery(address, feerate, Some(time_timelock))?
+        }
+        (None, None, None, Some(signers)) => {
+            control.create_multi_path_recovery(address, feerate, &signers)?
+        }
+        _ => {
+            return Err(Error::invalid_params(
+                "Only one of 'timelock', 'absolute_timelock', 'time_timelock' and 'signers' parameters may be provided.",
+            ))
+        }
+    };
+    Ok(serde_json::json!(&res))
+}
+
+fn refresh_coins(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let max_blocks: u32 = params
+        .get(0, "max_blocks")
+        .ok_or_else(|| Error::invalid_params("Missing 'max_blocks' parameter."))?
+        .as_u64()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'max_blocks' parameter."))?;
+    let feerate: u64 = get_feerate(control, &params, 1, 3)?
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' or 'conf_target' parameter."))?;
+    let max_inputs: Option<usize> = params
+        .get(2, "max_inputs")
+        .filter(|m| !m.is_null())
+        .map(|m| {
+            m.as_u64()
+                .and_then(|m| m.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'max_inputs' parameter."))
+        })
+        .transpose()?;
+
+    let res = control.refresh_coins(max_blocks, feerate, max_inputs)?;
+    Ok(serde_json::json!(&res))
+}
+
+fn create_migration(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let descriptor = params
+        .get(0, "descriptor")
+        .ok_or_else(|| Error::invalid_params("Missing 'descriptor' parameter."))?
+        .as_str()
+        .and_then(|s| LianaDescriptor::from_str(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'descriptor' parameter."))?;
+    let feerate: u64 = get_feerate(control, &params, 1, 3)?
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' or 'conf_target' parameter."))?;
+    let max_inputs: Option<usize> = params
+        .get(2, "max_inputs")
+        .filter(|m| !m.is_null())
+        .map(|m| {
+            m.as_u64()
+                .and_then(|m| m.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'max_inputs' parameter."))
+        })
+        .transpose()?;
 
-    let res = control.create_recovery(address, feerate, timelock)?;
+    let res = control.create_migration(&descriptor, feerate, max_inputs)?;
+    Ok(serde_json::json!(&res))
+}
+
+fn wait_for_events(
+    control: &DaemonControl,
+    params: Option<Params>,
+) -> Result<serde_json::Value, Error> {
+    let cursor = params
+        .as_ref()
+        .and_then(|p| p.get(0, "cursor"))
+        .filter(|c| !c.is_null())
+        .map(|c| {
+            c.as_u64()
+                .ok_or_else(|| Error::invalid_params("Invalid 'cursor' parameter."))
+        })
+        .transpose()?;
+    let timeout = params
+        .as_ref()
+        .and_then(|p| p.get(1, "timeout"))
+        .filter(|t| !t.is_null())
+        .map(|t| {
+            t.as_u64()
+                .ok_or_else(|| Error::invalid_params("Invalid 'timeout' parameter."))
+        })
+        .transpose()?
+        .unwrap_or(DEFAULT_WAIT_FOR_EVENTS_TIMEOUT);
+
+    let res = control.wait_for_events(cursor, timeout)?;
     Ok(serde_json::json!(&res))
 }
 
@@ -314,10 +573,10 @@ fn update_labels(control: &DaemonControl, params: Params) -> Result<serde_json::
     {
         let value = value.as_str().map(|s| s.to_string());
         if let Some(value) = &value {
-            if value.len() > 100 {
+            if value.len() > MAX_LABEL_LENGTH {
                 return Err(Error::invalid_params(format!(
-                    "Invalid 'labels.{}' value length: must be less or equal than 100 characters",
-                    item
+                    "Invalid 'labels.{}' value length: must be less or equal than {} characters",
+                    item, MAX_LABEL_LENGTH
                 )));
             }
         }
@@ -364,8 +623,108 @@ fn get_labels(control: &DaemonControl, params: Params) -> Result<serde_json::Val
     Ok(serde_json::json!(control.get_labels(&items)))
 }
 
+fn import_labels(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let labels = params
+        .get(0, "labels")
+        .ok_or_else(|| Error::invalid_params("Missing 'labels' parameter."))?
+        .as_str()
+        .ok_or_else(|| Error::invalid_params("Invalid 'labels' parameter."))?;
+
+    let res = control.import_labels(labels)?;
+    Ok(serde_json::json!(&res))
+}
+
+fn export_history(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let start: u32 = params
+        .get(0, "start")
+        .ok_or_else(|| Error::invalid_params("Missing 'start' parameter."))?
+        .as_i64()
+        .and_then(|i| i.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'start' parameter."))?;
+
+    let end: u32 = params
+        .get(1, "end")
+        .ok_or_else(|| Error::invalid_params("Missing 'end' parameter."))?
+        .as_i64()
+        .and_then(|i| i.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'end' parameter."))?;
+    if start > end {
+        return Err(Error::invalid_params(
+            "The 'start' parameter must not be after the 'end' parameter.",
+        ));
+    }
+
+    let csv = match params.get(2, "format").filter(|v| !v.is_null()) {
+        None => false,
+        Some(format) => match format.as_str() {
+            Some("json") => false,
+            Some("csv") => true,
+            _ => {
+                return Err(Error::invalid_params(
+                    "Invalid 'format' parameter: must be 'json' or 'csv'.",
+                ))
+            }
+        },
+    };
+
+    let res = control.export_history(start, end);
+    if csv {
+        Ok(serde_json::json!({ "csv": res.to_csv() }))
+    } else {
+        Ok(serde_json::json!(&res))
+    }
+}
+
+fn create_backup(
+    control: &DaemonControl,
+    params: Option<Params>,
+) -> Result<serde_json::Value, Error> {
+    let key_aliases = params
+        .as_ref()
+        .and_then(|p| p.get(0, "key_aliases"))
+        .filter(|v| !v.is_null())
+        .map(|aliases| {
+            aliases
+                .as_object()
+                .ok_or_else(|| Error::invalid_params("Invalid 'key_aliases' parameter."))?
+                .iter()
+                .map(|(fg, alias)| {
+                    let fg = bip32::Fingerprint::from_str(fg).map_err(|_| {
+                        Error::invalid_params(format!(
+                            "Invalid 'key_aliases.{}' parameter: must be a fingerprint",
+                            fg
+                        ))
+                    })?;
+                    let alias = alias.as_str().ok_or_else(|| {
+                        Error::invalid_params(format!("Invalid 'key_aliases.{}' value.", fg))
+                    })?;
+                    Ok((fg, alias.to_string()))
+                })
+                .collect::<Result<BTreeMap<_, _>, Error>>()
+        })
+        .transpose()?;
+
+    let res = control.create_backup(key_aliases);
+    Ok(serde_json::json!(&res))
+}
+
 /// Handle an incoming JSONRPC2 request.
 pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Response, Error> {
+    // A request for an additional wallet is processed with the control of this wallet.
+    if let Some(name) = req.wallet.as_deref() {
+        let mut wallet_control = control
+            .wallet(name)
+            .cloned()
+            .ok_or_else(|| Error::invalid_params(format!("Unknown wallet '{}'.", name)))?;
+        return handle_request(
+            &mut wallet_control,
+            Request {
+                wallet: None,
+                ..req
+            },
+        );
+    }
+
     let result = match req.method.as_str() {
         "broadcastspend" => {
             let params = req
@@ -373,12 +732,28 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
                 .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?;
             broadcast_spend(control, params)?
         }
+        "createbackup" => {
+            let params = req.params;
+            create_backup(control, params)?
+        }
+        "createmigration" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params("Missing 'descriptor' and 'feerate' parameters.")
+            })?;
+            create_migration(control, params)?
+        }
         "createrecovery" => {
             let params = req.params.ok_or_else(|| {
                 Error::invalid_params("Missing 'address' and 'feerate' parameters.")
             })?;
             create_recovery(control, params)?
         }
+        "createcpfp" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params("Missing 'outpoints' and 'feerate' parameters.")
+            })?;
+            create_cpfp(control, params)?
+        }
         "createspend" => {
             let params = req.params.ok_or_else(|| {
                 Error::invalid_params(
@@ -387,24 +762,61 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             create_spend(control, params)?
         }
+        "estimatefee" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'conf_target' parameter."))?;
+            estimate_fee(control, params)?
+        }
         "delspendtx" => {
             let params = req
                 .params
                 .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?;
             delete_spend(control, params)?
         }
+        "freezecoins" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'outpoints' parameter."))?;
+            freeze_coins(control, params)?
+        }
+        "unfreezecoins" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'outpoints' parameter."))?;
+            unfreeze_coins(control, params)?
+        }
         "rbfpsbt" => {
             let params = req.params.ok_or_else(|| {
                 Error::invalid_params("Missing 'txid', 'feerate' and 'is_cancel' parameters.")
             })?;
             rbf_psbt(control, params)?
         }
+        "exporthistory" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'start' and 'end' parameters."))?;
+            export_history(control, params)?
+        }
+        "describepolicy" => serde_json::json!(&control.describe_policy()),
+        "exportlabels" => serde_json::json!(&control.export_labels()),
         "getinfo" => serde_json::json!(&control.get_info()),
+        "getrecoverytimeline" => serde_json::json!(&control.get_recovery_timeline()),
         "getnewaddress" => serde_json::json!(&control.get_new_address()),
         "listcoins" => {
             let params = req.params;
             list_coins(control, params)?
         }
+        "listexpiringcoins" => {
+            let params = req.params;
+            list_expiring_coins(control, params)?
+        }
+        "importlabels" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'labels' parameter."))?;
+            import_labels(control, params)?
+        }
         "listaddresses" => {
             let params = req.params;
             list_addresses(control, params)?
@@ -426,6 +838,12 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             list_transactions(control, params)?
         }
+        "refreshcoins" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params("Missing 'max_blocks' and 'feerate' parameters.")
+            })?;
+            refresh_coins(control, params)?
+        }
         "startrescan" => {
             let params = req
                 .params
@@ -451,6 +869,10 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
                 .ok_or_else(|| Error::invalid_params("Missing 'items' parameter."))?;
             get_labels(control, params)?
         }
+        "waitforevents" => {
+            let params = req.params;
+            wait_for_events(control, params)?
+        }
         _ => {
             return Err(Error::method_not_found());
         }
@@ -0,0 +1,782 @@
+//! JSONRPC2 server over HTTP
+//!
+//! This module implements serving the JSONRPC2 API over HTTP, optionally over TLS, for clients
+//! which can't access our Unix Domain Socket. Requests are POSTed as the body of the HTTP request
+//! and clients must authenticate using HTTP basic authentication.
+
+use crate::{
+    config::{HttpRpcAuth, HttpRpcConfig},
+    jsonrpc::server::{process_message, MAX_CONNECTIONS},
+    random, DaemonControl,
+};
+
+use std::{
+    cell, error, fmt, fs,
+    io::{self, BufRead, Write},
+    net,
+    os::unix::fs::OpenOptionsExt,
+    path, rc,
+    sync::{self, atomic},
+    thread, time,
+};
+
+use miniscript::bitcoin::{
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    hex::DisplayHex,
+};
+
+// Maximum size of the request line and headers of an HTTP request.
+const MAX_HEADERS_SIZE: u64 = 8 * 1024;
+
+// Maximum size of the body of an HTTP request.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+// For how long a client may take to send a whole request, including the time the connection
+// stays idle before it. This bounds the request as a whole rather than each read, so that a client
+// trickling bytes can't hold a connection forever.
+const REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(60);
+
+// The user name in the credentials written to the cookie file.
+const COOKIE_USER: &str = "__cookie__";
+
+#[derive(Debug)]
+pub enum HttpServerError {
+    Io(io::Error),
+    Tls(String),
+    Cookie(path::PathBuf, String),
+    InsecureAddress(net::SocketAddr),
+}
+
+impl fmt::Display for HttpServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: '{}'", e),
+            Self::Tls(e) => write!(f, "Error setting up TLS: '{}'", e),
+            Self::Cookie(path, e) => write!(
+                f,
+                "Error writing cookie file at '{}': '{}'",
+                path.display(),
+                e
+            ),
+            Self::InsecureAddress(addr) => write!(
+                f,
+                "Refusing to serve the HTTP JSONRPC API on non-loopback address {} without TLS: \
+                 credentials and requests would be sent in clear over the network.",
+                addr
+            ),
+        }
+    }
+}
+
+impl error::Error for HttpServerError {}
+
+impl From<io::Error> for HttpServerError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A bound HTTP JSONRPC server, ready to serve requests.
+pub struct HttpServer {
+    listener: net::TcpListener,
+    tls_config: Option<sync::Arc<rustls::ServerConfig>>,
+    // The credentials clients must provide, as "user:password".
+    credentials: String,
+    // The cookie file we wrote at startup, to be removed when shutting down.
+    cookie_path: Option<path::PathBuf>,
+}
+
+impl HttpServer {
+    /// The address we are listening on.
+    pub fn local_addr(&self) -> Result<net::SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+}
+
+// Load the TLS certificate chain and private key to serve the API with.
+fn tls_config(
+    cert_path: &path::Path,
+    key_path: &path::Path,
+) -> Result<rustls::ServerConfig, HttpServerError> {
+    let certs = {
+        let mut reader = io::BufReader::new(fs::File::open(cert_path)?);
+        rustls_pemfile::certs(&mut reader)?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect::<Vec<_>>()
+    };
+    if certs.is_empty() {
+        return Err(HttpServerError::Tls(format!(
+            "No certificate found in '{}'",
+            cert_path.display()
+        )));
+    }
+    let key = {
+        let mut reader = io::BufReader::new(fs::File::open(key_path)?);
+        rustls_pemfile::read_all(&mut reader)?
+            .into_iter()
+            .find_map(|item| match item {
+                rustls_pemfile::Item::RSAKey(key)
+                | rustls_pemfile::Item::PKCS8Key(key)
+                | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                HttpServerError::Tls(format!("No private key found in '{}'", key_path.display()))
+            })?
+    };
+
+    rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| HttpServerError::Tls(e.to_string()))
+}
+
+// Generate random credentials and write them to the cookie file, readable only by the user.
+fn write_cookie(cookie_path: &path::Path) -> Result<String, HttpServerError> {
+    let password = random::random_bytes()
+        .map_err(|e| HttpServerError::Cookie(cookie_path.to_path_buf(), e.to_string()))?
+        .to_lower_hex_string();
+    let credentials = format!("{}:{}", COOKIE_USER, password);
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(cookie_path)
+        .and_then(|mut file| file.write_all(credentials.as_bytes()))
+        .map_err(|e| HttpServerError::Cookie(cookie_path.to_path_buf(), e.to_string()))?;
+    Ok(credentials)
+}
+
+/// Bind to the configured address, and set up TLS and the credentials.
+pub fn httpserver_setup(config: &HttpRpcConfig) -> Result<HttpServer, HttpServerError> {
+    log::debug!("Binding HTTP JSONRPC server at {}", config.addr);
+    let tls_config = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => Some(sync::Arc::new(tls_config(cert_path, key_path)?)),
+        _ => None,
+    };
+    // Clients always send their credentials in the basic authentication header, never let them go
+    // in clear over the network.
+    if tls_config.is_none() && !config.addr.ip().is_loopback() {
+        return Err(HttpServerError::InsecureAddress(config.addr));
+    }
+    let (credentials, cookie_path) = match &config.auth {
+        HttpRpcAuth::UserPass(user, password) => (format!("{}:{}", user, password), None),
+        HttpRpcAuth::CookieFile(path) => (write_cookie(path)?, Some(path.clone())),
+    };
+    let listener = net::TcpListener::bind(config.addr)?;
+
+    Ok(HttpServer {
+        listener,
+        tls_config,
+        credentials,
+        cookie_path,
+    })
+}
+
+// The parsed request line and headers of an HTTP request.
+#[derive(Debug, PartialEq, Eq)]
+struct HttpRequestHead {
+    method: String,
+    authorization: Option<String>,
+    keep_alive: bool,
+    content_length: Option<usize>,
+}
+
+// An HTTP request we can't serve, as the status code and reason to respond with.
+#[derive(Debug)]
+enum HttpError {
+    Io(io::Error),
+    Status(u16, &'static str),
+}
+
+impl From<io::Error> for HttpError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+// Read a line of the request head, without the line terminator. The `remaining` parameter is the
+// number of bytes we may still read for the request head.
+fn read_head_line(reader: &mut impl BufRead, remaining: &mut u64) -> Result<String, HttpError> {
+    let mut line = Vec::new();
+    let read = io::Read::take(&mut *reader, *remaining).read_until(b'\n', &mut line)?;
+    *remaining -= read as u64;
+    if line.last() != Some(&b'\n') {
+        return Err(if *remaining == 0 {
+            HttpError::Status(431, "Request Header Fields Too Large")
+        } else {
+            io::Error::from(io::ErrorKind::UnexpectedEof).into()
+        });
+    }
+    String::from_utf8(line)
+        .map(|line| line.trim_end_matches(&['\r', '\n'][..]).to_string())
+        .map_err(|_| HttpError::Status(400, "Bad Request"))
+}
+
+// Read the request line and headers of an HTTP request from the stream. Returns `None` if the
+// connection was closed.
+fn read_request_head(reader: &mut impl BufRead) -> Result<Option<HttpRequestHead>, HttpError> {
+    // Tolerate leading empty lines, and detect a closed connection.
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let mut remaining = MAX_HEADERS_SIZE;
+    let mut request_line = String::new();
+    while request_line.is_empty() {
+        request_line = read_head_line(reader, &mut remaining)?;
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(_), Some(version), None) => (method.to_string(), version),
+        _ => return Err(HttpError::Status(400, "Bad Request")),
+    };
+    // HTTP/1.1 connections are persistent by default, HTTP/1.0 ones aren't.
+    let mut keep_alive = match version {
+        "HTTP/1.1" => true,
+        "HTTP/1.0" => false,
+        _ => return Err(HttpError::Status(505, "HTTP Version Not Supported")),
+    };
+
+    let mut authorization = None;
+    let mut content_length = None;
+    loop {
+        let line = read_head_line(reader, &mut remaining)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or(HttpError::Status(400, "Bad Request"))?;
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            let length: usize = value
+                .parse()
+                .map_err(|_| HttpError::Status(400, "Bad Request"))?;
+            content_length = Some(length);
+        } else if name.eq_ignore_ascii_case("connection") {
+            if value.eq_ignore_ascii_case("close") {
+                keep_alive = false;
+            } else if value.eq_ignore_ascii_case("keep-alive") {
+                keep_alive = true;
+            }
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            // We don't support chunked requests.
+            return Err(HttpError::Status(411, "Length Required"));
+        }
+    }
+
+    Ok(Some(HttpRequestHead {
+        method,
+        authorization,
+        keep_alive,
+        content_length,
+    }))
+}
+
+// Read the body of the request whose head was just read from the stream.
+fn read_request_body(
+    reader: &mut impl BufRead,
+    head: &HttpRequestHead,
+) -> Result<Vec<u8>, HttpError> {
+    let length = head
+        .content_length
+        .ok_or(HttpError::Status(411, "Length Required"))?;
+    if length > MAX_BODY_SIZE {
+        return Err(HttpError::Status(413, "Payload Too Large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+// Write an HTTP response with the given status and body.
+fn write_response(
+    stream: &mut impl Write,
+    status: (u16, &str),
+    headers: &[(&str, &str)],
+    body: &[u8],
+    keep_alive: bool,
+) -> Result<(), io::Error> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: {}\r\n",
+        status.0,
+        status.1,
+        body.len(),
+        if keep_alive { "keep-alive" } else { "close" }
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+// Compare two byte strings in constant time (for a given length).
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Whether this Authorization header value carries these basic authentication credentials.
+fn is_authorized(authorization: Option<&str>, credentials: &str) -> bool {
+    let (scheme, encoded) = match authorization.and_then(|auth| auth.split_once(' ')) {
+        Some(auth) => auth,
+        None => return false,
+    };
+    let decoded = match BASE64.decode(encoded.trim()) {
+        Ok(decoded) => decoded,
+        Err(_) => return false,
+    };
+    scheme.eq_ignore_ascii_case("basic") && constant_time_eq(&decoded, credentials.as_bytes())
+}
+
+// A TCP stream whose reads fail once a deadline is reached.
+struct DeadlineStream {
+    stream: net::TcpStream,
+    deadline: rc::Rc<cell::Cell<time::Instant>>,
+}
+
+impl io::Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeout = self
+            .deadline
+            .get()
+            .checked_duration_since(time::Instant::now())
+            .filter(|timeout| !timeout.is_zero())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::TimedOut))?;
+        self.stream.set_read_timeout(Some(timeout))?;
+        io::Read::read(&mut self.stream, buf)
+    }
+}
+
+impl Write for DeadlineStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+// Handle all HTTP requests from this connection. The deadline for reading each request is set
+// as soon as the previous one was answered.
+fn connection_handler(
+    mut control: DaemonControl,
+    stream: impl io::Read + Write,
+    deadline: &cell::Cell<time::Instant>,
+    credentials: &str,
+    shutdown: &atomic::AtomicBool,
+) -> Result<(), io::Error> {
+    let mut reader = io::BufReader::new(stream);
+
+    while !shutdown.load(atomic::Ordering::Relaxed) {
+        deadline.set(time::Instant::now() + REQUEST_TIMEOUT);
+        let head = match read_request_head(&mut reader) {
+            Ok(Some(head)) => head,
+            // Connection closed.
+            Ok(None) => return Ok(()),
+            Err(HttpError::Io(e)) => return Err(e),
+            Err(HttpError::Status(code, reason)) => {
+                return write_response(reader.get_mut(), (code, reason), &[], &[], false);
+            }
+        };
+        // We don't read the body of the requests we reject. If they had one, we can't tell where
+        // the next request starts and must close the connection.
+        let rejected_keep_alive = head.keep_alive && head.content_length.unwrap_or(0) == 0;
+
+        if head.method != "POST" {
+            write_response(
+                reader.get_mut(),
+                (405, "Method Not Allowed"),
+                &[("Allow", "POST")],
+                &[],
+                rejected_keep_alive,
+            )?;
+            if !rejected_keep_alive {
+                return Ok(());
+            }
+            continue;
+        }
+        // Check the credentials before reading (and allocating for) the body.
+        if !is_authorized(head.authorization.as_deref(), credentials) {
+            log::debug!("Unauthorized HTTP JSONRPC request.");
+            write_response(
+                reader.get_mut(),
+                (401, "Unauthorized"),
+                &[("WWW-Authenticate", "Basic realm=\"lianad\"")],
+                &[],
+                rejected_keep_alive,
+            )?;
+            if !rejected_keep_alive {
+                return Ok(());
+            }
+            continue;
+        }
+
+        let body = match read_request_body(&mut reader, &head) {
+            Ok(body) => body,
+            Err(HttpError::Io(e)) => return Err(e),
+            Err(HttpError::Status(code, reason)) => {
+                return write_response(reader.get_mut(), (code, reason), &[], &[], false);
+            }
+        };
+        let reply = process_message(&mut control, &body, shutdown);
+        let resp_body = serde_json::to_vec(&reply)?;
+        write_response(
+            reader.get_mut(),
+            (200, "OK"),
+            &[("Content-Type", "application/json")],
+            &resp_body,
+            head.keep_alive,
+        )?;
+        if !head.keep_alive {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+// Set up the connection with this client and serve its requests.
+fn serve_connection(
+    control: DaemonControl,
+    stream: net::TcpStream,
+    tls_config: Option<sync::Arc<rustls::ServerConfig>>,
+    credentials: &str,
+    shutdown: &atomic::AtomicBool,
+) -> Result<(), io::Error> {
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    // The TLS handshake is part of the first request.
+    let deadline = rc::Rc::new(cell::Cell::new(time::Instant::now() + REQUEST_TIMEOUT));
+    let stream = DeadlineStream {
+        stream,
+        deadline: deadline.clone(),
+    };
+    if let Some(tls_config) = tls_config {
+        let tls_conn = rustls::ServerConnection::new(tls_config)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let stream = rustls::StreamOwned::new(tls_conn, stream);
+        connection_handler(control, stream, &deadline, credentials, shutdown)
+    } else {
+        connection_handler(control, stream, &deadline, credentials, shutdown)
+    }
+}
+
+/// The main event loop of the HTTP server. Wait for connections, and treat requests sent through
+/// them.
+pub fn httpserver_loop(
+    server: HttpServer,
+    daemon_control: DaemonControl,
+    shutdown: sync::Arc<atomic::AtomicBool>,
+) -> Result<(), io::Error> {
+    let HttpServer {
+        listener,
+        tls_config,
+        credentials,
+        cookie_path,
+    } = server;
+    let credentials = sync::Arc::new(credentials);
+    // As for the Unix Domain Socket server, treat each connection in its own thread.
+    let connections_counter = sync::Arc::from(atomic::AtomicU32::new(0));
+
+    listener.set_nonblocking(true)?;
+    while !shutdown.load(atomic::Ordering::Relaxed) {
+        let (connection, addr) = match listener.accept() {
+            Ok(c) => c,
+            Err(_) => {
+                thread::sleep(time::Duration::from_millis(100));
+                continue;
+            }
+        };
+        log::trace!("New HTTP JSONRPC connection from {}", addr);
+
+        // Don't wait for a slot to free up, this would stop us from noticing a shutdown request.
+        if connections_counter.load(atomic::Ordering::Relaxed) >= MAX_CONNECTIONS {
+            log::debug!(
+                "Too many HTTP JSONRPC connections, closing the one from {}.",
+                addr
+            );
+            continue;
+        }
+        connections_counter.fetch_add(1, atomic::Ordering::Relaxed);
+
+        let handler_id = connections_counter.load(atomic::Ordering::Relaxed);
+        thread::Builder::new()
+            .name(format!("liana-http-jsonrpc-{}", handler_id))
+            .spawn({
+                let control = daemon_control.clone();
+                let tls_config = tls_config.clone();
+                let credentials = credentials.clone();
+                let counter = connections_counter.clone();
+                let shutdown = shutdown.clone();
+
+                move || {
+                    if let Err(e) =
+                        serve_connection(control, connection, tls_config, &credentials, &shutdown)
+                    {
+                        log::debug!(
+                            "Error while handling HTTP connection from {}: '{}'",
+                            addr,
+                            e
+                        );
+                    } else {
+                        log::trace!("HTTP connection from {} terminated without error.", addr);
+                    }
+                    counter.fetch_sub(1, atomic::Ordering::Relaxed);
+                }
+            })?;
+    }
+
+    if let Some(cookie_path) = cookie_path {
+        if let Err(e) = fs::remove_file(&cookie_path) {
+            log::error!(
+                "Error removing cookie file at '{}': '{}'",
+                cookie_path.display(),
+                e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    use std::{env, io::Read, net::SocketAddr, process, str::FromStr};
+
+    #[test]
+    fn request_read() {
+        let req = b"POST / HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic Zm9vOmJhcg==\r\nContent-Length: 4\r\n\r\nbody";
+        let mut reader = &req[..];
+        let head = read_request_head(&mut reader).unwrap().unwrap();
+        assert_eq!(
+            head,
+            HttpRequestHead {
+                method: "POST".to_string(),
+                authorization: Some("Basic Zm9vOmJhcg==".to_string()),
+                keep_alive: true,
+                content_length: Some(4),
+            }
+        );
+        // The body isn't read along with the head.
+        assert_eq!(reader, b"body");
+        assert_eq!(read_request_body(&mut reader, &head).unwrap(), b"body");
+
+        // Two requests on the same connection, the second one closing it.
+        let reqs = b"POST / HTTP/1.1\r\ncontent-length: 2\r\n\r\n{}GET / HTTP/1.1\r\nConnection: close\r\n\r\n";
+        let mut reader = &reqs[..];
+        let head = read_request_head(&mut reader).unwrap().unwrap();
+        let body = read_request_body(&mut reader, &head).unwrap();
+        assert_eq!((head.method.as_str(), body), ("POST", b"{}".to_vec()));
+        let head = read_request_head(&mut reader).unwrap().unwrap();
+        assert_eq!((head.method.as_str(), head.keep_alive), ("GET", false));
+        assert!(read_request_head(&mut reader).unwrap().is_none());
+
+        // HTTP/1.0 connections are closed by default.
+        let req = b"POST / HTTP/1.0\r\nContent-Length: 0\r\n\r\n";
+        assert!(
+            !read_request_head(&mut &req[..])
+                .unwrap()
+                .unwrap()
+                .keep_alive
+        );
+
+        // A POST request must have a content length.
+        let req = b"POST / HTTP/1.1\r\n\r\n";
+        let mut reader = &req[..];
+        let head = read_request_head(&mut reader).unwrap().unwrap();
+        assert!(matches!(
+            read_request_body(&mut reader, &head),
+            Err(HttpError::Status(411, _))
+        ));
+
+        // Too large requests are rejected.
+        let req = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        );
+        let mut reader = req.as_bytes();
+        let head = read_request_head(&mut reader).unwrap().unwrap();
+        assert!(matches!(
+            read_request_body(&mut reader, &head),
+            Err(HttpError::Status(413, _))
+        ));
+        let req = format!("POST / HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(10_000));
+        assert!(matches!(
+            read_request_head(&mut req.as_bytes()),
+            Err(HttpError::Status(431, _))
+        ));
+
+        // Garbage.
+        let req = b"hello\r\n\r\n";
+        assert!(matches!(
+            read_request_head(&mut &req[..]),
+            Err(HttpError::Status(400, _))
+        ));
+    }
+
+    #[test]
+    fn basic_auth() {
+        assert!(is_authorized(Some("Basic Zm9vOmJhcg=="), "foo:bar"));
+        assert!(is_authorized(Some("basic Zm9vOmJhcg=="), "foo:bar"));
+        assert!(!is_authorized(Some("Basic Zm9vOmJheg=="), "foo:bar"));
+        assert!(!is_authorized(Some("Bearer Zm9vOmJhcg=="), "foo:bar"));
+        assert!(!is_authorized(Some("Basic !!!"), "foo:bar"));
+        assert!(!is_authorized(None, "foo:bar"));
+    }
+
+    #[test]
+    fn request_deadline() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let deadline = rc::Rc::new(cell::Cell::new(
+            time::Instant::now() + time::Duration::from_millis(200),
+        ));
+        let mut stream = DeadlineStream {
+            stream,
+            deadline: deadline.clone(),
+        };
+
+        // Data sent before the deadline is read.
+        (&client).write_all(b"a").unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(stream.read(&mut buf).unwrap(), 1);
+
+        // A read waiting for more data doesn't go past the deadline, and no read is attempted
+        // once it is reached even if there is data to be read.
+        let start = time::Instant::now();
+        assert!(stream.read(&mut buf).is_err());
+        assert!(start.elapsed() < REQUEST_TIMEOUT);
+        (&client).write_all(b"b").unwrap();
+        assert_eq!(
+            stream.read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+    }
+
+    #[test]
+    fn insecure_address() {
+        let config = HttpRpcConfig {
+            addr: SocketAddr::from_str("0.0.0.0:0").unwrap(),
+            auth: HttpRpcAuth::UserPass("foo".to_string(), "bar".to_string()),
+            tls_cert_path: None,
+            tls_key_path: None,
+        };
+        assert!(matches!(
+            httpserver_setup(&config),
+            Err(HttpServerError::InsecureAddress(_))
+        ));
+    }
+
+    // Send a request over a new connection and get the raw response.
+    fn http_request(addr: SocketAddr, req: &[u8]) -> String {
+        let mut stream = net::TcpStream::connect(addr).unwrap();
+        stream.write_all(req).unwrap();
+        let mut resp = String::new();
+        stream.read_to_string(&mut resp).unwrap();
+        resp
+    }
+
+    #[test]
+    fn http_server() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let cookie_path = env::temp_dir().join(format!(
+            "lianad-http-cookie-{}-{:?}",
+            process::id(),
+            thread::current().id()
+        ));
+        let config = HttpRpcConfig {
+            addr: SocketAddr::from_str("127.0.0.1:0").unwrap(),
+            auth: HttpRpcAuth::CookieFile(cookie_path.clone()),
+            tls_cert_path: None,
+            tls_key_path: None,
+        };
+        let server = httpserver_setup(&config).unwrap();
+        let addr = server.local_addr().unwrap();
+        let shutdown = sync::Arc::new(atomic::AtomicBool::new(false));
+        let server_thread = thread::spawn({
+            let control = ms.control().clone();
+            let shutdown = shutdown.clone();
+            move || httpserver_loop(server, control, shutdown)
+        });
+
+        // The cookie file contains the credentials to use.
+        let cookie = fs::read_to_string(&cookie_path).unwrap();
+        assert!(cookie.starts_with("__cookie__:"));
+        let auth = BASE64.encode(&cookie);
+
+        // Requests are rejected without the right credentials.
+        let body = r#"{"jsonrpc": "2.0", "id": 0, "method": "getinfo"}"#;
+        let resp = http_request(
+            addr,
+            format!(
+                "POST / HTTP/1.1\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        );
+        assert!(
+            resp.starts_with("HTTP/1.1 401 Unauthorized\r\n"),
+            "{}",
+            resp
+        );
+        // The credentials are checked before the body is read: we get a response right away even
+        // though we never send the announced body, and the connection is closed.
+        let resp = http_request(
+            addr,
+            format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY_SIZE
+            )
+            .as_bytes(),
+        );
+        assert!(
+            resp.starts_with("HTTP/1.1 401 Unauthorized\r\n"),
+            "{}",
+            resp
+        );
+        assert!(resp.contains("Connection: close\r\n"));
+        let resp = http_request(
+            addr,
+            b"GET / HTTP/1.1\r\nConnection: close\r\nAuthorization: Basic Zm9vOmJhcg==\r\n\r\n",
+        );
+        assert!(resp.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+
+        // With the right credentials, we get a JSONRPC response.
+        let resp = http_request(
+            addr,
+            format!(
+                "POST / HTTP/1.1\r\nConnection: close\r\nAuthorization: Basic {}\r\nContent-Length: {}\r\n\r\n{}",
+                auth,
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        );
+        assert!(resp.starts_with("HTTP/1.1 200 OK\r\n"), "{}", resp);
+        let resp_body = resp.split("\r\n\r\n").nth(1).unwrap();
+        let resp: serde_json::Value = serde_json::from_str(resp_body).unwrap();
+        assert_eq!(resp["id"], 0);
+        assert_eq!(resp["result"]["network"], "bitcoin");
+
+        // The cookie file is removed on shutdown.
+        shutdown.store(true, atomic::Ordering::Relaxed);
+        server_thread.join().unwrap().unwrap();
+        assert!(!cookie_path.exists());
+
+        ms.shutdown();
+    }
+}
@@ -1,4 +1,5 @@
 mod api;
+pub mod http;
 pub mod server;
 
 use crate::commands;
@@ -49,14 +50,25 @@ pub struct Request {
     pub params: Option<Params>,
     /// Request identifier.
     pub id: ReqId,
+    /// The name of the additional wallet this request is for. If not set, the request is for
+    /// the main wallet. This is an extension to JSONRPC2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
 }
 
 /// A failure to broadcast a transaction to the P2P network.
 const BROADCAST_ERROR: i64 = 1_000;
 
+/// Too many requests are already waiting for new events.
+const TOO_MANY_EVENT_WAITERS_ERROR: i64 = 1_001;
+
 /// JSONRPC2 error codes. See https://www.jsonrpc.org/specification#error_object.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ErrorCode {
+    /// Invalid JSON was received.
+    ParseError,
+    /// The JSON sent is not a valid Request object.
+    InvalidRequest,
     /// The method does not exist / is not available.
     MethodNotFound,
     /// Invalid method parameter(s).
@@ -70,6 +82,8 @@ pub enum ErrorCode {
 impl From<&ErrorCode> for i64 {
     fn from(code: &ErrorCode) -> i64 {
         match code {
+            ErrorCode::ParseError => -32700,
+            ErrorCode::InvalidRequest => -32600,
             ErrorCode::MethodNotFound => -32601,
             ErrorCode::InvalidParams => -32602,
             ErrorCode::InternalError => -32603,
@@ -81,6 +95,8 @@ impl From<&ErrorCode> for i64 {
 impl From<i64> for ErrorCode {
     fn from(code: i64) -> ErrorCode {
         match code {
+            -32700 => ErrorCode::ParseError,
+            -32600 => ErrorCode::InvalidRequest,
             -32601 => ErrorCode::MethodNotFound,
             -32602 => ErrorCode::InvalidParams,
             -32603 => ErrorCode::InternalError,
@@ -127,6 +143,20 @@ impl Error {
         }
     }
 
+    pub fn parse_error(message: impl Into<String>) -> Error {
+        Error::new(
+            ErrorCode::ParseError,
+            format!("Parse error: {}", message.into()),
+        )
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Error {
+        Error::new(
+            ErrorCode::InvalidRequest,
+            format!("Invalid request: {}", message.into()),
+        )
+    }
+
     pub fn method_not_found() -> Error {
         Error::new(ErrorCode::MethodNotFound, "Method not found")
     }
@@ -166,15 +196,34 @@ impl From<commands::CommandError> for Error {
             | commands::CommandError::InvalidDerivationIndex
             | commands::CommandError::RbfError(..)
             | commands::CommandError::EmptyFilterList
+            | commands::CommandError::AlreadyConfirmed(..)
+            | commands::CommandError::NotInMempool(..)
+            | commands::CommandError::InvalidConfTarget(..)
+            | commands::CommandError::InvalidMaxInputs(..)
+            | commands::CommandError::InvalidTimeout(..)
+            | commands::CommandError::Bip329(..)
+            | commands::CommandError::LabelTooLong(..)
+            | commands::CommandError::MigrationToSameDescriptor
+            | commands::CommandError::MigrationDescriptorNetwork(..)
+            | commands::CommandError::NoCoinToMigrate
+            | commands::CommandError::UnknownAbsoluteTimelock(..)
+            | commands::CommandError::UnknownTimeTimelock(..)
+            | commands::CommandError::UnknownSpendingPath(..)
+            | commands::CommandError::SpendingPathNotAvailable(..)
             | commands::CommandError::RecoveryNotAvailable => {
                 Error::new(ErrorCode::InvalidParams, e.to_string())
             }
-            commands::CommandError::RescanTrigger(..) => {
+            commands::CommandError::RescanTrigger(..)
+            | commands::CommandError::FeeEstimationUnavailable(..) => {
                 Error::new(ErrorCode::InternalError, e.to_string())
             }
             commands::CommandError::TxBroadcast(_) => {
                 Error::new(ErrorCode::ServerError(BROADCAST_ERROR), e.to_string())
             }
+            commands::CommandError::TooManyEventWaiters => Error::new(
+                ErrorCode::ServerError(TOO_MANY_EVENT_WAITERS_ERROR),
+                e.to_string(),
+            ),
         }
     }
 }
@@ -191,12 +240,12 @@ pub struct Response {
     /// Required on error. Must not exist on success.
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<Error>,
-    /// Request identifier.
-    id: ReqId,
+    /// Request identifier. Null if it could not be determined (invalid JSON or request).
+    id: Option<ReqId>,
 }
 
 impl Response {
-    fn new(id: ReqId, result: Option<serde_json::Value>, error: Option<Error>) -> Response {
+    fn new(id: Option<ReqId>, result: Option<serde_json::Value>, error: Option<Error>) -> Response {
         Response {
             jsonrpc: "2.0".to_string(),
             result,
@@ -206,10 +255,15 @@ impl Response {
     }
 
     pub fn success(id: ReqId, result: serde_json::Value) -> Response {
-        Response::new(id, Some(result), None)
+        Response::new(Some(id), Some(result), None)
     }
 
     pub fn error(id: ReqId, error: Error) -> Response {
-        Response::new(id, None, Some(error))
+        Response::new(Some(id), None, Some(error))
+    }
+
+    /// An error response to a message whose request identifier could not be determined.
+    pub fn error_null_id(error: Error) -> Response {
+        Response::new(None, None, Some(error))
     }
 }
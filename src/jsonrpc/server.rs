@@ -4,7 +4,7 @@
 //! JSONRPC2 requests on a Unix Domain Socket.
 
 use crate::{
-    jsonrpc::{api, Request, Response},
+    jsonrpc::{api, Error, ReqId, Request, Response},
     DaemonControl,
 };
 
@@ -17,9 +17,9 @@ use std::{
 };
 
 // Maximum number of concurrent RPC connections we may accept.
-const MAX_CONNECTIONS: u32 = 16;
+pub(super) const MAX_CONNECTIONS: u32 = 16;
 
-// Read a command from the stream.
+// Read a message from the stream.
 //
 // In order to both treat commands separately (respond as soon as we read one), and support
 // multiple commands in a single read or in multiple parts, we are given the context as writable
@@ -31,12 +31,14 @@ const MAX_CONNECTIONS: u32 = 16;
 //   for the separator character in the parts of the buffer with dummy values.
 //   - `cursor`: The index at which we checked for the separator character (`\n`). Used to not
 //   check twice for it on the same buffer chunk.
+//
+// The message is returned as is, it's up to the caller to parse it as a JSONRPC2 request or batch.
 fn read_command(
     stream: &mut dyn io::Read,
     buf: &mut Vec<u8>,
     end: &mut usize,
     cursor: &mut usize,
-) -> Result<Option<Request>, io::Error> {
+) -> Result<Option<Vec<u8>>, io::Error> {
     assert!(!buf.is_empty());
 
     loop {
@@ -48,19 +50,13 @@ fn read_command(
             String::from_utf8_lossy(&buf[*cursor..*end])
         );
         if let Some(pos) = pos {
-            log::trace!(
-                "Parsing Request from: {:?}",
-                String::from_utf8_lossy(&buf[..*cursor + pos])
-            );
-            // TODO: don't return an io::Error here, instead try to parse a Request. Failing that,
-            // try to parse a serde_json::Value. Then return accordingly a JSONRPC "malformed
-            // request" or "invalid JSON" error.
-            let req: Request = serde_json::from_slice(&buf[..*cursor + pos])?;
+            let msg = buf[..*cursor + pos].to_vec();
+            log::trace!("Read message: {:?}", String::from_utf8_lossy(&msg));
             *buf = buf[pos + 1..].to_vec(); // FIXME: can we avoid reallocating here?
             *cursor = 0;
             *end -= pos + 1;
 
-            return Ok(Some(req));
+            return Ok(Some(msg));
         }
 
         // If nothing can be gathered from the buffer, continue reading.
@@ -79,6 +75,88 @@ fn read_command(
     }
 }
 
+// Process a request, and stop the daemon if we were told to.
+fn process_request(
+    control: &mut DaemonControl,
+    req: Request,
+    shutdown: &atomic::AtomicBool,
+) -> Response {
+    let req_id = req.id.clone();
+    if &req.method == "stop" {
+        shutdown.store(true, atomic::Ordering::Relaxed);
+        log::info!("Stopping the liana daemon.");
+    }
+
+    log::trace!("JSONRPC request: {:?}", serde_json::to_string(&req));
+    let response = api::handle_request(control, req).unwrap_or_else(|e| Response::error(req_id, e));
+    log::trace!("JSONRPC response: {:?}", serde_json::to_string(&response));
+    response
+}
+
+/// The reply to a message: a single response, or an array of responses to a batch of requests.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(untagged)]
+pub(super) enum Reply {
+    Single(Response),
+    Batch(Vec<Response>),
+}
+
+// Check a JSON value is a valid request, or get the error response to send back.
+fn parse_request(value: serde_json::Value) -> Result<Request, Response> {
+    // Try to salvage the id to reply with, even if the request is otherwise invalid.
+    let id = value
+        .get("id")
+        .and_then(|id| serde_json::from_value::<ReqId>(id.clone()).ok());
+    let error = match serde_json::from_value::<Request>(value) {
+        Ok(req) if req.jsonrpc == "2.0" => return Ok(req),
+        Ok(_) => Error::invalid_request("'jsonrpc' must be \"2.0\""),
+        Err(e) => Error::invalid_request(e.to_string()),
+    };
+    Err(match id {
+        Some(id) => Response::error(id, error),
+        None => Response::error_null_id(error),
+    })
+}
+
+/// Process a message containing either a single request or a batch of requests. Shared by all
+/// our transports.
+pub(super) fn process_message(
+    control: &mut DaemonControl,
+    msg: &[u8],
+    shutdown: &atomic::AtomicBool,
+) -> Reply {
+    let value: serde_json::Value = match serde_json::from_slice(msg) {
+        Ok(v) => v,
+        Err(e) => {
+            log::debug!("Invalid JSON received: '{}'.", e);
+            return Reply::Single(Response::error_null_id(Error::parse_error(e.to_string())));
+        }
+    };
+
+    match value {
+        serde_json::Value::Array(values) => {
+            if values.is_empty() {
+                return Reply::Single(Response::error_null_id(Error::invalid_request(
+                    "empty batch",
+                )));
+            }
+            Reply::Batch(
+                values
+                    .into_iter()
+                    .map(|value| match parse_request(value) {
+                        Ok(req) => process_request(control, req, shutdown),
+                        Err(resp) => resp,
+                    })
+                    .collect(),
+            )
+        }
+        value => Reply::Single(match parse_request(value) {
+            Ok(req) => process_request(control, req, shutdown),
+            Err(resp) => resp,
+        }),
+    }
+}
+
 // Handle all messages from this connection.
 fn connection_handler(
     mut control: DaemonControl,
@@ -90,25 +168,16 @@ fn connection_handler(
     let mut cursor = 0;
 
     while !shutdown.load(atomic::Ordering::Relaxed) {
-        let req = match read_command(&mut stream, &mut buf, &mut end, &mut cursor)? {
-            Some(req) => req,
+        let msg = match read_command(&mut stream, &mut buf, &mut end, &mut cursor)? {
+            Some(msg) => msg,
             None => {
                 // Connection closed.
                 return Ok(());
             }
         };
 
-        let req_id = req.id.clone();
-        if &req.method == "stop" {
-            shutdown.store(true, atomic::Ordering::Relaxed);
-            log::info!("Stopping the liana daemon.");
-        }
-
-        log::trace!("JSONRPC request: {:?}", serde_json::to_string(&req));
-        let response =
-            api::handle_request(&mut control, req).unwrap_or_else(|e| Response::error(req_id, e));
-        log::trace!("JSONRPC response: {:?}", serde_json::to_string(&response));
-        if let Err(e) = serde_json::to_writer(&stream, &response) {
+        let reply = process_message(&mut control, &msg, &shutdown);
+        if let Err(e) = serde_json::to_writer(&stream, &reply) {
             log::error!("Error writing response: '{}'", e);
             return Ok(());
         }
@@ -211,11 +280,12 @@ pub fn rpcserver_setup(socket_path: &path::Path) -> Result<net::UnixListener, io
 mod tests {
     use super::*;
     use crate::{
+        descriptors::LianaDescriptor,
         jsonrpc::{Params, ReqId},
         testutils::*,
     };
 
-    use std::{env, fs, io::Write, process};
+    use std::{env, fs, io::Write, process, str::FromStr};
 
     fn read_one_command(socket_path: &path::Path) -> thread::JoinHandle<Option<Request>> {
         let listener = rpcserver_setup(socket_path).unwrap();
@@ -224,7 +294,9 @@ mod tests {
             let mut buf = vec![0; 32];
             let mut end = 0;
             let mut cursor = 0;
-            read_command(&mut conn, &mut buf, &mut end, &mut cursor).unwrap()
+            read_command(&mut conn, &mut buf, &mut end, &mut cursor)
+                .unwrap()
+                .map(|msg| serde_json::from_slice(&msg).unwrap())
         })
     }
 
@@ -239,8 +311,8 @@ mod tests {
 
             loop {
                 match read_command(&mut conn, &mut buf, &mut end, &mut cursor).unwrap() {
-                    Some(req) => {
-                        reqs.push(req);
+                    Some(msg) => {
+                        reqs.push(serde_json::from_slice(&msg).unwrap());
                     }
                     None => return reqs,
                 }
@@ -388,6 +460,7 @@ mod tests {
             method: "dummy".to_string(),
             params: Some(Params::Map(params)),
             id: ReqId::Num(0),
+            wallet: None,
         };
         write_messages(&socket_path, &[&serde_json::to_vec(&req).unwrap(), b"\n"]);
         let read_req = t.join().unwrap().unwrap();
@@ -396,6 +469,98 @@ mod tests {
         fs::remove_file(&socket_path).unwrap();
     }
 
+    #[test]
+    fn message_processing() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut control = ms.control().clone();
+        let other_desc = LianaDescriptor::from_str("wsh(andor(pk([aabbccdd]tpubDExU4YLJkyQ9RRbVScQq2brFxWWha7WmAUByPWyaWYwmcTv3Shx8aHp6mVwuE5n4TeM4z5DTWGf2YhNPmXtfvyr8cUDVvA3txdrFnFgNdF7/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))").unwrap();
+        let mut other_control = control.clone();
+        other_control.config.main_descriptor = other_desc.clone();
+        control.wallets = sync::Arc::new(
+            vec![("other".to_string(), other_control)]
+                .into_iter()
+                .collect(),
+        );
+        let shutdown = atomic::AtomicBool::new(false);
+        let mut process = |msg: &[u8]| {
+            serde_json::to_value(process_message(&mut control, msg, &shutdown)).unwrap()
+        };
+
+        // Invalid JSON gets a parse error with a null id.
+        let reply = process(br#"{"jsonrpc": "2.0", "id": 0, "method": "getinfo""#);
+        assert_eq!(reply["error"]["code"], -32700);
+        assert!(reply["id"].is_null());
+        assert!(reply.get("result").is_none());
+
+        // Valid JSON which isn't a valid request gets an invalid request error. The id is kept
+        // whenever it can be determined.
+        let reply = process(br#"{"jsonrpc": "2.0", "id": 12, "meth": "getinfo"}"#);
+        assert_eq!(reply["error"]["code"], -32600);
+        assert_eq!(reply["id"], 12);
+        let reply = process(br#"{"jsonrpc": "1.0", "id": "a", "method": "getinfo"}"#);
+        assert_eq!(reply["error"]["code"], -32600);
+        assert_eq!(reply["id"], "a");
+        let reply = process(br#"42"#);
+        assert_eq!(reply["error"]["code"], -32600);
+        assert!(reply["id"].is_null());
+
+        // An empty batch is an invalid request.
+        let reply = process(br#"[]"#);
+        assert_eq!(reply["error"]["code"], -32600);
+        assert!(reply["id"].is_null());
+
+        // A single request gets a single response.
+        let reply = process(br#"{"jsonrpc": "2.0", "id": 0, "method": "getinfo"}"#);
+        assert_eq!(reply["id"], 0);
+        assert!(reply["result"]["version"].is_string());
+
+        // A batch gets an array of responses, one per request, in order. Invalid requests within
+        // the batch don't prevent the others from being processed.
+        let reply = process(
+            br#"[
+                {"jsonrpc": "2.0", "id": 1, "method": "getinfo"},
+                {"jsonrpc": "2.0", "id": 2, "method": "listcoins"},
+                {"jsonrpc": "2.0", "id": 3, "method": "listspendtxs"},
+                {"jsonrpc": "2.0", "id": 4, "method": "unknownmethod"},
+                {"foo": "bar"},
+                1
+            ]"#,
+        );
+        let responses = reply.as_array().unwrap();
+        assert_eq!(responses.len(), 6);
+        for (i, resp) in responses[..3].iter().enumerate() {
+            assert_eq!(resp["id"], i + 1);
+            assert!(resp.get("error").is_none(), "{}", resp);
+            assert!(resp["result"].is_object());
+        }
+        assert_eq!(responses[3]["id"], 4);
+        assert_eq!(responses[3]["error"]["code"], -32601);
+        for resp in &responses[4..] {
+            assert!(resp["id"].is_null());
+            assert_eq!(resp["error"]["code"], -32600);
+        }
+
+        // A request may be directed to an additional wallet by name.
+        let reply =
+            process(br#"{"jsonrpc": "2.0", "id": 5, "method": "getinfo", "wallet": "other"}"#);
+        assert_eq!(reply["id"], 5);
+        assert_eq!(
+            reply["result"]["descriptors"]["main"]["multi_desc"],
+            other_desc.to_string()
+        );
+        let reply = process(br#"{"jsonrpc": "2.0", "id": 6, "method": "getinfo"}"#);
+        assert_ne!(
+            reply["result"]["descriptors"]["main"]["multi_desc"],
+            other_desc.to_string()
+        );
+        let reply =
+            process(br#"{"jsonrpc": "2.0", "id": 7, "method": "getinfo", "wallet": "unknown"}"#);
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["error"]["code"], -32602);
+
+        ms.shutdown();
+    }
+
     // TODO: debug on MacOS
     #[cfg(not(target_os = "macos"))]
     #[test]
@@ -419,6 +584,7 @@ mod tests {
             method: "stop".to_string(),
             params: None,
             id: ReqId::Num(0),
+            wallet: None,
         };
         write_messages(
             &socket_path,
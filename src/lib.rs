@@ -1,3 +1,5 @@
+pub mod backup;
+pub mod bip329;
 mod bitcoin;
 pub mod commands;
 pub mod config;
@@ -5,6 +7,7 @@ pub mod config;
 mod daemonize;
 mod database;
 pub mod descriptors;
+pub mod events;
 #[cfg(feature = "daemon")]
 mod jsonrpc;
 pub mod random;
@@ -23,14 +26,19 @@ pub use crate::bitcoin::{
     electrum::{Electrum, ElectrumError},
 };
 #[cfg(feature = "daemon")]
-use crate::jsonrpc::server::{rpcserver_loop, rpcserver_setup};
+use crate::jsonrpc::{
+    http::{httpserver_loop, httpserver_setup, HttpServerError},
+    server::{rpcserver_loop, rpcserver_setup},
+};
 use crate::{
+    backup::{RestoreError, WalletBackup},
     bitcoin::{poller, BitcoinInterface},
     config::Config,
     database::{
         sqlite::{FreshDbOptions, SqliteDb, SqliteDbError, MAX_DB_VERSION_NO_TX_DB},
         DatabaseInterface,
     },
+    events::EventsNotifier,
 };
 
 use std::{
@@ -107,6 +115,11 @@ pub enum StartupError {
     Daemonization(&'static str),
     #[cfg(windows)]
     NoWatchonlyInDatadir,
+    #[cfg(feature = "daemon")]
+    HttpServer(HttpServerError),
+    Restore(RestoreError),
+    /// Additional wallets are configured but we were given a custom database interface.
+    WalletsWithCustomDb,
 }
 
 impl fmt::Display for StartupError {
@@ -142,6 +155,13 @@ impl fmt::Display for StartupError {
             Self::Electrum(e) => write!(f, "Error setting up Electrum interface: '{}'.", e),
             #[cfg(unix)]
             Self::Daemonization(e) => write!(f, "Error when daemonizing: '{}'.", e),
+            #[cfg(feature = "daemon")]
+            Self::HttpServer(e) => write!(f, "Error setting up the HTTP JSONRPC server: '{}'.", e),
+            Self::Restore(e) => write!(f, "Error restoring wallet from backup: '{}'.", e),
+            Self::WalletsWithCustomDb => write!(
+                f,
+                "Additional wallets are only supported with the default SQLite database."
+            ),
             #[cfg(windows)]
             Self::NoWatchonlyInDatadir => {
                 write!(
@@ -177,6 +197,19 @@ impl From<BitcoindError> for StartupError {
     }
 }
 
+#[cfg(feature = "daemon")]
+impl From<HttpServerError> for StartupError {
+    fn from(e: HttpServerError) -> Self {
+        Self::HttpServer(e)
+    }
+}
+
+impl From<RestoreError> for StartupError {
+    fn from(e: RestoreError) -> Self {
+        Self::Restore(e)
+    }
+}
+
 fn create_datadir(datadir_path: &path::Path) -> Result<(), StartupError> {
     #[cfg(unix)]
     return {
@@ -199,23 +232,65 @@ fn create_datadir(datadir_path: &path::Path) -> Result<(), StartupError> {
     };
 }
 
-// Connect to the SQLite database. Create it if starting fresh, and do some sanity checks.
-// If all went well, returns the interface to the SQLite database.
+// Removes the data directory it was created with when dropped, unless it was unset. If the
+// watchonly wallet was already created in it, it is unloaded from bitcoind beforehand as bitcoind
+// would otherwise keep it loaded and refuse to create it anew.
+struct RestoreDatadirGuard {
+    data_dir: Option<path::PathBuf>,
+    bitcoind: Option<sync::Arc<sync::Mutex<BitcoinD>>>,
+}
+
+impl Drop for RestoreDatadirGuard {
+    fn drop(&mut self) {
+        if let Some(data_dir) = self.data_dir.take() {
+            if let Some(bitcoind) = self.bitcoind.take() {
+                log::info!("Unloading the watchonly wallet after failing to restore the wallet.");
+                let res = match bitcoind.lock() {
+                    Ok(bitcoind) => bitcoind.unload_watchonly_wallet(),
+                    Err(poisoned) => poisoned.into_inner().unload_watchonly_wallet(),
+                };
+                if let Err(e) = res {
+                    log::error!("Error unloading the watchonly wallet: '{}'", e);
+                }
+            }
+            log::info!(
+                "Removing data directory '{}' after failing to restore the wallet.",
+                data_dir.display()
+            );
+            if let Err(e) = fs::remove_dir_all(&data_dir) {
+                log::error!(
+                    "Error removing data directory '{}': '{}'",
+                    data_dir.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+// Connect to the SQLite database. Create it if starting fresh, and do some sanity checks. A fresh
+// database records the given wallet creation date if any (when restoring a wallet), or the current
+// time. If all went well, returns the interface to the SQLite database.
 fn setup_sqlite(
     config: &Config,
     data_dir: &path::Path,
     fresh_data_dir: bool,
+    creation_timestamp: Option<u32>,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
-    bitcoind: &Option<BitcoinD>,
+    bitcoind: Option<&BitcoinD>,
 ) -> Result<SqliteDb, StartupError> {
     let db_path: path::PathBuf = [data_dir, path::Path::new("lianad.sqlite3")]
         .iter()
         .collect();
     let options = if fresh_data_dir {
-        Some(FreshDbOptions::new(
+        let options = FreshDbOptions::new(
             config.bitcoin_config.network,
             config.main_descriptor.clone(),
-        ))
+        );
+        Some(match creation_timestamp {
+            Some(timestamp) => options.with_timestamp(timestamp),
+            None => options,
+        })
     } else {
         None
     };
@@ -226,7 +301,7 @@ fn setup_sqlite(
     if !fresh_data_dir {
         let mut conn = sqlite.connection()?;
         let wallet_txs = if conn.db_version() <= MAX_DB_VERSION_NO_TX_DB {
-            let bit = bitcoind.as_ref().ok_or(StartupError::DbMigrateBitcoinTxs(
+            let bit = bitcoind.ok_or(StartupError::DbMigrateBitcoinTxs(
                 "a connection to a Bitcoin backend is required",
             ))?;
             let coins = conn.db_coins(&[]);
@@ -284,7 +359,11 @@ fn setup_bitcoind(
     let bitcoind = BitcoinD::new(bitcoind_config, wo_path_str)?;
     bitcoind.node_sanity_checks(
         config.bitcoin_config.network,
-        config.main_descriptor.is_taproot(),
+        config.main_descriptor.is_taproot()
+            || config
+                .wallets
+                .iter()
+                .any(|w| w.main_descriptor.is_taproot()),
     )?;
     if fresh_data_dir {
         log::info!("Creating a new watchonly wallet on bitcoind.");
@@ -299,6 +378,17 @@ fn setup_bitcoind(
     log::info!("Loading our watchonly wallet on bitcoind.");
     bitcoind.maybe_load_watchonly_wallet()?;
     bitcoind.wallet_sanity_checks(&config.main_descriptor)?;
+    // The additional wallets are watched through the same watchonly wallet. Their descriptors are
+    // imported the first time they are configured, rescanning from their creation date if any.
+    let wallets_descs: Vec<_> = config
+        .wallets
+        .iter()
+        .map(|w| (&w.main_descriptor, w.timestamp))
+        .collect();
+    bitcoind.maybe_import_descriptors(&wallets_descs)?;
+    for wallet in &config.wallets {
+        bitcoind.wallet_sanity_checks(&wallet.main_descriptor)?;
+    }
     log::info!("Watchonly wallet loaded on bitcoind and sanity checked.");
 
     Ok(bitcoind)
@@ -376,6 +466,11 @@ pub struct DaemonControl {
     // FIXME: Should we require Sync on DatabaseInterface rather than using a Mutex?
     db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
     secp: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    // Notified whenever new events are recorded in the journal.
+    events_notifier: EventsNotifier,
+    // The controls for the additional wallets served by this daemon, by name. Only set on the
+    // control of the main wallet.
+    wallets: sync::Arc<collections::BTreeMap<String, DaemonControl>>,
 }
 
 impl DaemonControl {
@@ -392,9 +487,16 @@ impl DaemonControl {
             poller_sender,
             db,
             secp,
+            events_notifier: EventsNotifier::default(),
+            wallets: Default::default(),
         }
     }
 
+    /// Get the control for the additional wallet with this name, if there is one.
+    pub fn wallet(&self, name: &str) -> Option<&DaemonControl> {
+        self.wallets.get(name)
+    }
+
     // Useful for unit test to directly mess up with the DB
     #[cfg(test)]
     pub fn db(&self) -> sync::Arc<sync::Mutex<dyn DatabaseInterface>> {
@@ -416,6 +518,7 @@ pub enum DaemonHandle {
         poller_handle: thread::JoinHandle<()>,
         rpcserver_shutdown: sync::Arc<sync::atomic::AtomicBool>,
         rpcserver_handle: thread::JoinHandle<Result<(), io::Error>>,
+        httpserver_handle: Option<thread::JoinHandle<Result<(), io::Error>>>,
     },
 }
 
@@ -435,53 +538,118 @@ impl DaemonHandle {
         bitcoin: Option<impl BitcoinInterface + 'static>,
         db: Option<impl DatabaseInterface + 'static>,
         #[cfg(feature = "daemon")] with_rpc_server: bool,
+    ) -> Result<Self, StartupError> {
+        Self::start_inner(
+            config,
+            bitcoin,
+            db,
+            None,
+            #[cfg(feature = "daemon")]
+            with_rpc_server,
+        )
+    }
+
+    fn start_inner(
+        config: Config,
+        bitcoin: Option<impl BitcoinInterface + 'static>,
+        db: Option<impl DatabaseInterface + 'static>,
+        backup: Option<&WalletBackup>,
+        #[cfg(feature = "daemon")] with_rpc_server: bool,
     ) -> Result<Self, StartupError> {
         #[cfg(not(test))]
         setup_panic_hook();
 
         let secp = secp256k1::Secp256k1::verification_only();
 
-        // First, check the data directory
+        // First, check the data directory. A backup may only be restored to a fresh one.
         let mut data_dir = config
             .data_dir()
             .ok_or(StartupError::DefaultDataDirNotFound)?;
         data_dir.push(config.bitcoin_config.network.to_string());
         let fresh_data_dir = !data_dir.as_path().exists();
+        if let Some(backup) = backup {
+            backup.check(config.bitcoin_config.network, &config.main_descriptor)?;
+            if !fresh_data_dir {
+                return Err(RestoreError::ExistingWallet(data_dir).into());
+            }
+        }
         if fresh_data_dir {
             create_datadir(&data_dir)?;
             log::info!("Created a new data directory at '{}'", data_dir.display());
         }
+        // If the restore fails, don't leave a half-restored wallet behind as it would prevent from
+        // retrying.
+        let mut restore_guard = backup.map(|_| RestoreDatadirGuard {
+            data_dir: Some(data_dir.clone()),
+            bitcoind: None,
+        });
 
         // Set up the connection to bitcoind (if using it) first as we may need it for the database
         // migration when setting up SQLite below.
         let bitcoind = if bitcoin.is_none() {
             if let Some(config::BitcoinBackend::Bitcoind(_)) = &config.bitcoin_backend {
-                Some(setup_bitcoind(&config, &data_dir, fresh_data_dir)?)
+                Some(sync::Arc::new(sync::Mutex::new(setup_bitcoind(
+                    &config,
+                    &data_dir,
+                    fresh_data_dir,
+                )?)))
             } else {
                 None
             }
         } else {
             None
         };
+        if let Some(guard) = restore_guard.as_mut() {
+            guard.bitcoind = bitcoind.clone();
+        }
 
         // Then set up the database backend.
-        let db = match db {
-            Some(db) => sync::Arc::from(sync::Mutex::from(db)),
-            None => sync::Arc::from(sync::Mutex::from(setup_sqlite(
-                &config,
-                &data_dir,
-                fresh_data_dir,
-                &secp,
-                &bitcoind,
-            )?)) as sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+        let (db, sqlite) = match db {
+            Some(db) => (
+                sync::Arc::from(sync::Mutex::from(db))
+                    as sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+                None,
+            ),
+            None => {
+                let sqlite = setup_sqlite(
+                    &config,
+                    &data_dir,
+                    fresh_data_dir,
+                    backup.map(|b| b.timestamp),
+                    &secp,
+                    bitcoind.as_ref().map(|b| b.lock().unwrap()).as_deref(),
+                )?;
+                (
+                    sync::Arc::from(sync::Mutex::from(sqlite.clone()))
+                        as sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+                    Some(sqlite),
+                )
+            }
         };
 
+        // The data of the additional wallets is stored in the same database, each with its own
+        // wallet row.
+        let wallets_dbs = config
+            .wallets
+            .iter()
+            .map(|wallet| {
+                let sqlite = sqlite.as_ref().ok_or(StartupError::WalletsWithCustomDb)?;
+                let wallet_db = sqlite.wallet(
+                    config.bitcoin_config.network,
+                    &wallet.main_descriptor,
+                    wallet.timestamp,
+                    &secp,
+                )?;
+                wallet_db.sanity_check(config.bitcoin_config.network, &wallet.main_descriptor)?;
+                Ok((wallet.clone(), wallet_db))
+            })
+            .collect::<Result<Vec<_>, StartupError>>()?;
+
         // Finally set up the Bitcoin backend.
         let bit = match (bitcoin, &config.bitcoin_backend) {
             (Some(bit), _) => sync::Arc::from(sync::Mutex::from(bit)),
-            (None, Some(config::BitcoinBackend::Bitcoind(..))) => sync::Arc::from(
-                sync::Mutex::from(bitcoind.expect("bitcoind must have been set already")),
-            )
+            (None, Some(config::BitcoinBackend::Bitcoind(..))) => bitcoind
+                .expect("bitcoind must have been set already")
                 as sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
             (None, Some(config::BitcoinBackend::Electrum(..))) => {
                 sync::Arc::from(sync::Mutex::from(setup_electrum(&config, db.clone())?))
@@ -489,6 +657,24 @@ impl DaemonHandle {
             (None, None) => Err(StartupError::MissingBitcoinBackendConfig)?,
         };
 
+        // If restoring from a backup, fill the fresh database and rescan the chain from the wallet
+        // creation date. Only record the rescan once it was started on the backend, as the poller
+        // would otherwise consider it completed.
+        if let Some(backup) = backup {
+            log::info!("Restoring wallet from backup.");
+            let mut db_conn = db.connection();
+            backup.restore_db(&mut db_conn, &secp)?;
+            bit.lock()
+                .unwrap()
+                .start_rescan(&config.main_descriptor, backup.timestamp)
+                .map_err(RestoreError::Rescan)?;
+            db_conn.set_rescan(backup.timestamp);
+            log::info!("Wallet restored. Rescanning the chain from its creation date.");
+        }
+        if let Some(guard) = restore_guard.as_mut() {
+            guard.data_dir = None;
+        }
+
         // If we are on a UNIX system and they told us to daemonize, do it now.
         // NOTE: it's safe to daemonize now, as we don't carry any open DB connection
         // https://www.sqlite.org/howtocorrupt.html#_carrying_an_open_database_connection_across_a_fork_
@@ -503,30 +689,101 @@ impl DaemonHandle {
             }
         }
 
-        // Start the poller thread. Keep the thread handle to be able to check if it crashed. Store
-        // an atomic to be able to stop it.
-        let mut bitcoin_poller =
-            poller::Poller::new(bit.clone(), db.clone(), config.main_descriptor.clone());
+        // Create the API the external world will use to talk to us, either directly through the Rust
+        // structure or through the JSONRPC server we may setup below.
         let (poller_sender, poller_receiver) = mpsc::sync_channel(0);
+        let poll_interval = config.bitcoin_config.poll_interval_secs;
+        let main_descriptor = config.main_descriptor.clone();
+        let event_hook = config.event_hook.clone();
+        let mut control =
+            DaemonControl::new(config, bit.clone(), poller_sender.clone(), db.clone(), secp);
+
+        // Each additional wallet gets its own control, sharing our Bitcoin backend and poller.
+        let wallets_controls: Vec<_> = wallets_dbs
+            .into_iter()
+            .map(|(wallet, wallet_db)| {
+                let mut config = control.config.clone();
+                config.main_descriptor = wallet.main_descriptor;
+                config.wallets = Vec::new();
+                let mut wallet_control = DaemonControl::new(
+                    config,
+                    bit.clone(),
+                    poller_sender.clone(),
+                    sync::Arc::from(sync::Mutex::from(wallet_db)),
+                    control.secp.clone(),
+                );
+                wallet_control.events_notifier = control.events_notifier.clone();
+                (wallet.name, wallet_control)
+            })
+            .collect();
+        control.wallets = sync::Arc::new(wallets_controls.iter().cloned().collect());
+
+        // Start the poller thread. Keep the thread handle to be able to check if it crashed. Store
+        // an atomic to be able to stop it. If coins must be refreshed automatically, the poller
+        // needs access to the API to create the refresh transactions.
+        let refresh_control = control
+            .config
+            .refresh_policy
+            .as_ref()
+            .map(|_| control.clone());
+        let mut bitcoin_poller = poller::Poller::new(
+            bit,
+            db,
+            main_descriptor,
+            refresh_control,
+            event_hook,
+            control.events_notifier.clone(),
+        );
+        for (name, wallet_control) in wallets_controls {
+            let refresh_control = wallet_control
+                .config
+                .refresh_policy
+                .as_ref()
+                .map(|_| wallet_control.clone());
+            bitcoin_poller.add_wallet(
+                name,
+                wallet_control.db.clone(),
+                wallet_control.config.main_descriptor.clone(),
+                refresh_control,
+            );
+        }
         let poller_handle = thread::Builder::new()
             .name("Bitcoin Network poller".to_string())
-            .spawn({
-                let poll_interval = config.bitcoin_config.poll_interval_secs;
-                move || {
-                    log::info!("Bitcoin poller started.");
-                    bitcoin_poller.poll_forever(poll_interval, poller_receiver);
-                    log::info!("Bitcoin poller stopped.");
-                }
+            .spawn(move || {
+                log::info!("Bitcoin poller started.");
+                bitcoin_poller.poll_forever(poll_interval, poller_receiver);
+                log::info!("Bitcoin poller stopped.");
             })
             .expect("Spawning the poller thread must never fail.");
 
-        // Create the API the external world will use to talk to us, either directly through the Rust
-        // structure or through the JSONRPC server we may setup below.
-        let control = DaemonControl::new(config, bit, poller_sender.clone(), db, secp);
-
         #[cfg(feature = "daemon")]
         if with_rpc_server {
             let rpcserver_shutdown = sync::Arc::from(sync::atomic::AtomicBool::from(false));
+
+            // If configured, also serve the API over HTTP. Bind it right away so a misconfiguration
+            // is reported at startup. It is stopped along with the Unix Domain Socket server.
+            let httpserver = control
+                .config
+                .http_rpc_config
+                .as_ref()
+                .map(httpserver_setup)
+                .transpose()?;
+            let httpserver_handle = httpserver.map(|server| {
+                thread::Builder::new()
+                    .name("HTTP JSONRPC server".to_string())
+                    .spawn({
+                        let control = control.clone();
+                        let shutdown = rpcserver_shutdown.clone();
+                        move || {
+                            log::info!("HTTP JSONRPC server started on {}.", server.local_addr()?);
+                            httpserver_loop(server, control, shutdown)?;
+                            log::info!("HTTP JSONRPC server stopped.");
+                            Ok(())
+                        }
+                    })
+                    .expect("Spawning the HTTP server thread should never fail.")
+            });
+
             let rpcserver_handle = thread::Builder::new()
                 .name("Bitcoin Network poller".to_string())
                 .spawn({
@@ -549,6 +806,7 @@ impl DaemonHandle {
                 poller_handle,
                 rpcserver_shutdown,
                 rpcserver_handle,
+                httpserver_handle,
             });
         }
 
@@ -574,6 +832,26 @@ impl DaemonHandle {
         )
     }
 
+    /// Start the Liana daemon with the default Bitcoin and database interfaces, restoring the
+    /// wallet from the given backup. The data directory must not exist yet and the backup must be
+    /// for the network and descriptor in the configuration. The wallet is created with the backup's
+    /// creation date. If the restore fails, the watchonly wallet is unloaded from bitcoind and the
+    /// data directory removed so it can be retried.
+    pub fn restore_default(
+        config: Config,
+        backup: &WalletBackup,
+        #[cfg(feature = "daemon")] with_rpc_server: bool,
+    ) -> Result<DaemonHandle, StartupError> {
+        Self::start_inner(
+            config,
+            Option::<BitcoinD>::None,
+            Option::<SqliteDb>::None,
+            Some(backup),
+            #[cfg(feature = "daemon")]
+            with_rpc_server,
+        )
+    }
+
     /// Check whether the daemon is still up and running. This needs to be regularly polled to
     /// check for internal errors. If this returns `false`, collect the error using the `stop`
     /// method.
@@ -586,8 +864,16 @@ impl DaemonHandle {
             Self::Server {
                 ref poller_handle,
                 ref rpcserver_handle,
+                ref httpserver_handle,
                 ..
-            } => !poller_handle.is_finished() && !rpcserver_handle.is_finished(),
+            } => {
+                !poller_handle.is_finished()
+                    && !rpcserver_handle.is_finished()
+                    && !httpserver_handle
+                        .as_ref()
+                        .map(|h| h.is_finished())
+                        .unwrap_or(false)
+            }
         }
     }
 
@@ -611,6 +897,7 @@ impl DaemonHandle {
                 poller_handle,
                 rpcserver_shutdown,
                 rpcserver_handle,
+                httpserver_handle,
             } => {
                 poller_sender
                     .send(poller::PollerMessage::Shutdown)
@@ -619,6 +906,11 @@ impl DaemonHandle {
                 rpcserver_handle
                     .join()
                     .expect("Poller thread must not panic")?;
+                if let Some(httpserver_handle) = httpserver_handle {
+                    httpserver_handle
+                        .join()
+                        .expect("HTTP server thread must not panic")?;
+                }
                 poller_handle.join().expect("Poller thread must not panic");
                 Ok(())
             }
@@ -662,6 +954,35 @@ mod tests {
         }
     }
 
+    // Read a request and respond to it with this JSONRPC result, or error if `is_error` is set.
+    // Returns the part of the request read, which includes its body.
+    fn complete_request(server: &net::TcpListener, resp: &str, is_error: bool) -> String {
+        let (mut stream, _) = server.accept().unwrap();
+        stream
+            .set_read_timeout(Some(time::Duration::from_secs(5)))
+            .unwrap();
+        let mut req = Vec::new();
+        {
+            let mut reader = BufReader::new(&mut stream);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.starts_with("Authorization") {
+                    reader.read_until(b'}', &mut req).unwrap();
+                    break;
+                }
+            }
+        }
+        let resp = format!(
+            "HTTP/1.1 200\n\r\n{{\"jsonrpc\":\"2.0\",\"id\":1,\"{}\":{}}}\n",
+            if is_error { "error" } else { "result" },
+            resp
+        );
+        stream.write_all(resp.as_bytes()).unwrap();
+        stream.flush().unwrap();
+        String::from_utf8(req).unwrap()
+    }
+
     // Respond to the two "echo" sent at startup to sanity check the connection
     fn complete_sanity_check(server: &net::TcpListener) {
         let echo_resp =
@@ -855,6 +1176,10 @@ mod tests {
         let config = Config {
             bitcoin_config,
             bitcoin_backend: Some(config::BitcoinBackend::Bitcoind(bitcoind_config)),
+            refresh_policy: None,
+            event_hook: None,
+            http_rpc_config: None,
+            wallets: Vec::new(),
             data_dir: Some(data_dir),
             #[cfg(unix)]
             daemon: false,
@@ -912,4 +1237,182 @@ mod tests {
 
         fs::remove_dir_all(&tmp_dir).unwrap();
     }
+
+    #[test]
+    fn failed_restore_unloads_watchonly() {
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let data_dir: path::PathBuf = [tmp_dir.as_path(), path::Path::new("datadir")]
+            .iter()
+            .collect();
+        let wo_path: path::PathBuf = [
+            data_dir.as_path(),
+            path::Path::new("bitcoin"),
+            path::Path::new("lianad_watchonly_wallet"),
+        ]
+        .iter()
+        .collect();
+        let wo_path = wo_path.to_str().unwrap().to_string();
+
+        // Configure a dummy bitcoind
+        let network = bitcoin::Network::Bitcoin;
+        let cookie: path::PathBuf = [
+            tmp_dir.as_path(),
+            path::Path::new(&format!(
+                "dummy_bitcoind_{:?}.cookie",
+                thread::current().id()
+            )),
+        ]
+        .iter()
+        .collect();
+        fs::write(&cookie, [0; 32]).unwrap();
+        let addr: net::SocketAddr =
+            net::SocketAddrV4::new(net::Ipv4Addr::new(127, 0, 0, 1), 0).into();
+        let server = net::TcpListener::bind(addr).unwrap();
+        let addr = server.local_addr().unwrap();
+        let desc_str = "wsh(andor(pk([aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*),older(10000),pk([aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*)))#3xh8xmhn";
+        let desc = LianaDescriptor::from_str(desc_str).unwrap();
+        let receive_desc = desc.receive_descriptor().clone();
+        let change_desc = desc.change_descriptor().clone();
+        let config = Config {
+            bitcoin_config: BitcoinConfig {
+                network,
+                poll_interval_secs: time::Duration::from_secs(2),
+            },
+            bitcoin_backend: Some(config::BitcoinBackend::Bitcoind(BitcoindConfig {
+                addr,
+                rpc_auth: BitcoindRpcAuth::CookieFile(cookie),
+            })),
+            refresh_policy: None,
+            event_hook: None,
+            http_rpc_config: None,
+            wallets: Vec::new(),
+            data_dir: Some(data_dir.clone()),
+            #[cfg(unix)]
+            daemon: false,
+            log_level: log::LevelFilter::Debug,
+            main_descriptor: desc.clone(),
+        };
+        let backup = WalletBackup {
+            version: backup::BACKUP_VERSION,
+            network,
+            main_descriptor: desc,
+            timestamp: 1_600_000_000,
+            receive_index: 0,
+            change_index: 0,
+            labels: Default::default(),
+            spend_txs: Vec::new(),
+            frozen_coins: Vec::new(),
+            key_aliases: None,
+        };
+
+        // Restore in a new thread so the current one acts as the bitcoind server.
+        let t = thread::spawn({
+            let data_dir = data_dir.clone();
+            move || {
+                let res = DaemonHandle::restore_default(
+                    config,
+                    &backup,
+                    #[cfg(feature = "daemon")]
+                    false,
+                );
+                assert!(matches!(
+                    res,
+                    Err(StartupError::Restore(RestoreError::Rescan(_)))
+                ));
+                assert!(!data_dir.join(network.to_string()).exists());
+            }
+        });
+        complete_sanity_check(&server);
+        complete_version_check(&server);
+        complete_network_check(&server);
+        complete_wallet_creation(&server);
+        complete_wallet_loading(&server);
+        complete_wallet_check(&server, &wo_path);
+        complete_desc_check(&server, &receive_desc.to_string(), &change_desc.to_string());
+
+        // The watchonly wallet was created and loaded, and the database set up. Make the rescan
+        // fail to start as the blocks before the creation date aren't available.
+        let genesis_hash = "\"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f\"";
+        complete_request(&server, "{\"descriptors\":[]}", false);
+        complete_request(&server, "{\"pruneheight\":1}", false);
+        complete_request(
+            &server,
+            &format!("{{\"bestblockhash\":{},\"blocks\":0}}", genesis_hash),
+            false,
+        );
+        complete_request(&server, genesis_hash, false);
+        complete_request(
+            &server,
+            "{\"code\":-5,\"message\":\"Block not found\"}",
+            true,
+        );
+
+        // The watchonly wallet is unloaded before the data directory is removed, so the restore
+        // can be retried without restarting bitcoind.
+        let req = complete_request(&server, "{\"name\":\"dummy\"}", false);
+        assert!(req.contains("\"method\":\"unloadwallet\""), "{}", req);
+        assert!(req.contains(&wo_path), "{}", req);
+        t.join().unwrap();
+
+        fs::remove_dir_all(&tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn failed_restore_cleanup() {
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let data_dir: path::PathBuf = [tmp_dir.as_path(), path::Path::new("datadir")]
+            .iter()
+            .collect();
+        let network = bitcoin::Network::Bitcoin;
+        let desc_str = "wsh(andor(pk([aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*),older(10000),pk([aabbccdd]xpub68JJTXc1MWK8PEQozKsRatrUHXKFNkD1Cb1BuQU9Xr5moCv87anqGyXLyUd4KpnDyZgo3gz4aN1r3NiaoweFW8UutBsBbgKHzaD5HkTkifK/<0;1>/*)))#3xh8xmhn";
+        let desc = LianaDescriptor::from_str(desc_str).unwrap();
+        let config = Config {
+            bitcoin_config: BitcoinConfig {
+                network,
+                poll_interval_secs: time::Duration::from_secs(2),
+            },
+            bitcoin_backend: None,
+            refresh_policy: None,
+            event_hook: None,
+            http_rpc_config: None,
+            wallets: Vec::new(),
+            data_dir: Some(data_dir.clone()),
+            #[cfg(unix)]
+            daemon: false,
+            log_level: log::LevelFilter::Debug,
+            main_descriptor: desc.clone(),
+        };
+        let backup = WalletBackup {
+            version: backup::BACKUP_VERSION,
+            network,
+            main_descriptor: desc,
+            timestamp: 1_600_000_000,
+            receive_index: 0,
+            change_index: 0,
+            labels: Default::default(),
+            spend_txs: Vec::new(),
+            frozen_coins: Vec::new(),
+            key_aliases: None,
+        };
+
+        // The dummy Bitcoin interface fails to start the rescan, after the data directory and the
+        // database were created. The data directory is removed so the restore can be retried.
+        let res = DaemonHandle::start_inner(
+            config,
+            Some(DummyBitcoind::new()),
+            Option::<SqliteDb>::None,
+            Some(&backup),
+            #[cfg(feature = "daemon")]
+            false,
+        );
+        assert!(matches!(
+            res,
+            Err(StartupError::Restore(RestoreError::Rescan(_)))
+        ));
+        assert!(!data_dir.join(network.to_string()).exists());
+
+        fs::remove_dir_all(&tmp_dir).unwrap();
+    }
 }
@@ -23,6 +23,12 @@ use miniscript::bitcoin::{
     secp256k1, sighash,
 };
 
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use zeroize::Zeroizing;
+
 /// An error related to using a signer.
 #[derive(Debug)]
 pub enum SignerError {
@@ -30,6 +36,8 @@ pub enum SignerError {
     Mnemonic(bip39::Error),
     Bip32(Bip32Error),
     MnemonicStorage(io::Error),
+    MnemonicDecryption,
+    PassphraseStorage,
     InsanePsbt,
     IncompletePsbt,
 }
@@ -41,6 +49,14 @@ impl fmt::Display for SignerError {
             Self::Mnemonic(s) => write!(f, "Error when working with mnemonics: {}", s),
             Self::Bip32(e) => write!(f, "BIP32 error: {}", e),
             Self::MnemonicStorage(e) => write!(f, "BIP39 mnemonic storage error: {}", e),
+            Self::MnemonicDecryption => write!(
+                f,
+                "Could not decrypt the BIP39 mnemonic: wrong passphrase or corrupted data."
+            ),
+            Self::PassphraseStorage => write!(
+                f,
+                "The BIP39 passphrase of a signer is only stored encrypted, if explicitly requested."
+            ),
             Self::InsanePsbt => write!(f, "Information contained in the PSBT is wrong."),
             Self::IncompletePsbt => write!(
                 f,
@@ -54,14 +70,158 @@ impl error::Error for SignerError {}
 
 pub const MNEMONICS_FOLDER_NAME: &str = "mnemonics";
 
-// TODO: zeroize, mlock, etc.. For now we don't even encrypt the seed on disk so that'd be
-// overkill.
-/// A signer that keeps the key on the laptop. Based on BIP39.
+// The extensions of the files storing a mnemonic, in plaintext or encrypted.
+const PLAINTEXT_EXTENSION: &str = "txt";
+const ENCRYPTED_EXTENSION: &str = "enc";
+
+// The parameters of the scrypt key derivation for encrypting a mnemonic. With a block size of 8,
+// a cost of 2^17 uses 128MiB of memory.
+#[cfg(not(test))]
+const SCRYPT_LOG_N: u8 = 17;
+#[cfg(test)]
+const SCRYPT_LOG_N: u8 = 10;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+// The format of an encrypted mnemonic is:
+// version (1) || fingerprint (4) || scrypt log_n (1) || scrypt r (4) || scrypt p (4) || salt (16)
+//  || nonce (24) || ciphertext
+// The header, everything but the ciphertext, is authenticated along with it.
+const ENCRYPTED_MNEMONIC_VERSION: u8 = 0;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = 1 + 4 + 1 + 4 + 4 + SALT_LEN + NONCE_LEN;
+
+// Derive the key to encrypt a mnemonic with from the passphrase.
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+) -> Result<Zeroizing<[u8; 32]>, SignerError> {
+    let params =
+        scrypt::Params::new(log_n, r, p, 32).map_err(|_| SignerError::MnemonicDecryption)?;
+    let mut key = Zeroizing::new([0; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, key.as_mut())
+        .expect("Valid output length");
+    Ok(key)
+}
+
+// Encrypt the mnemonic of the signer with this fingerprint using the passphrase.
+fn encrypt_mnemonic(
+    mnemonic: &str,
+    fingerprint: bip32::Fingerprint,
+    passphrase: &str,
+) -> Result<Vec<u8>, SignerError> {
+    let salt = random::random_bytes().map_err(SignerError::Randomness)?;
+    let nonce = random::random_bytes().map_err(SignerError::Randomness)?;
+    let mut data = Vec::with_capacity(HEADER_LEN + mnemonic.len() + 16);
+    data.push(ENCRYPTED_MNEMONIC_VERSION);
+    data.extend_from_slice(fingerprint.as_bytes());
+    data.push(SCRYPT_LOG_N);
+    data.extend_from_slice(&SCRYPT_R.to_be_bytes());
+    data.extend_from_slice(&SCRYPT_P.to_be_bytes());
+    data.extend_from_slice(&salt[..SALT_LEN]);
+    data.extend_from_slice(&nonce[..NONCE_LEN]);
+
+    let key = derive_key(
+        passphrase,
+        &salt[..SALT_LEN],
+        SCRYPT_LOG_N,
+        SCRYPT_R,
+        SCRYPT_P,
+    )?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce[..NONCE_LEN]),
+            Payload {
+                msg: mnemonic.as_bytes(),
+                aad: &data,
+            },
+        )
+        .expect("Encryption of a mnemonic never fails");
+    data.extend_from_slice(&ciphertext);
+
+    Ok(data)
+}
+
+// Get the fingerprint of the signer from an encrypted mnemonic.
+fn encrypted_mnemonic_fingerprint(data: &[u8]) -> Result<bip32::Fingerprint, SignerError> {
+    if data.len() < HEADER_LEN || data[0] != ENCRYPTED_MNEMONIC_VERSION {
+        return Err(SignerError::MnemonicDecryption);
+    }
+    let fg: [u8; 4] = data[1..5].try_into().expect("4 bytes");
+    Ok(fg.into())
+}
+
+// Decrypt a mnemonic encrypted using this passphrase.
+fn decrypt_mnemonic(data: &[u8], passphrase: &str) -> Result<Zeroizing<String>, SignerError> {
+    if data.len() < HEADER_LEN || data[0] != ENCRYPTED_MNEMONIC_VERSION {
+        return Err(SignerError::MnemonicDecryption);
+    }
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    let log_n = header[5];
+    let r = u32::from_be_bytes(header[6..10].try_into().expect("4 bytes"));
+    let p = u32::from_be_bytes(header[10..14].try_into().expect("4 bytes"));
+    let salt = &header[14..14 + SALT_LEN];
+    let nonce = &header[14 + SALT_LEN..];
+    // The header isn't authenticated yet. Don't let it make us derive the key with arbitrary
+    // (and arbitrarily expensive) parameters: only the ones we encrypt with are valid.
+    if (log_n, r, p) != (SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P) {
+        return Err(SignerError::MnemonicDecryption);
+    }
+
+    let key = derive_key(passphrase, salt, log_n, r, p)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let plaintext = cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| SignerError::MnemonicDecryption)?;
+
+    String::from_utf8(plaintext)
+        .map(Zeroizing::new)
+        .map_err(|_| SignerError::MnemonicDecryption)
+}
+
+// TODO: mlock, etc..
+/// A signer that keeps the key on the laptop. Based on BIP39, optionally with a passphrase.
 pub struct HotSigner {
     mnemonic: bip39::Mnemonic,
+    passphrase: Zeroizing<String>,
     master_xpriv: bip32::Xpriv,
 }
 
+/// A hot signer whose mnemonic is stored encrypted in the data directory. It must be unlocked
+/// using the passphrase it was encrypted with before it can be used.
+pub struct LockedHotSigner {
+    fingerprint: bip32::Fingerprint,
+    encrypted_mnemonic: Vec<u8>,
+}
+
+impl LockedHotSigner {
+    /// Get the fingerprint of the master xpub for this signer.
+    pub fn fingerprint(&self) -> bip32::Fingerprint {
+        self.fingerprint
+    }
+
+    /// Decrypt the mnemonic of this signer using the passphrase it was stored with.
+    pub fn unlock(
+        &self,
+        network: bitcoin::Network,
+        passphrase: &str,
+    ) -> Result<HotSigner, SignerError> {
+        let content = decrypt_mnemonic(&self.encrypted_mnemonic, passphrase)?;
+        HotSigner::from_encrypted_storage_str(network, &content)
+    }
+}
+
 // TODO: instead of copying them here we could have a util module with those helpers.
 // Create a directory with no permission for group and other users.
 fn create_dir(path: &path::Path) -> io::Result<()> {
@@ -103,27 +263,82 @@ impl HotSigner {
     fn from_mnemonic(
         network: bitcoin::Network,
         mnemonic: bip39::Mnemonic,
+        passphrase: &str,
     ) -> Result<Self, SignerError> {
-        let master_xpriv =
-            bip32::Xpriv::new_master(network, &mnemonic.to_seed("")).map_err(SignerError::Bip32)?;
+        let master_xpriv = bip32::Xpriv::new_master(network, &mnemonic.to_seed(passphrase))
+            .map_err(SignerError::Bip32)?;
         Ok(Self {
             mnemonic,
+            passphrase: Zeroizing::new(passphrase.to_string()),
             master_xpriv,
         })
     }
 
     /// Create a new hot signer from random bytes. Uses a 12-words mnemonics without a passphrase.
     pub fn generate(network: bitcoin::Network) -> Result<Self, SignerError> {
-        // We want a 12-words mnemonic so we only use 16 of the 32 bytes.
-        let random_32bytes = random::random_bytes().map_err(SignerError::Randomness)?;
-        let mnemonic =
-            bip39::Mnemonic::from_entropy(&random_32bytes[..16]).map_err(SignerError::Mnemonic)?;
-        Self::from_mnemonic(network, mnemonic)
+        Self::generate_with_word_count(network, 12)
+    }
+
+    /// Create a new hot signer from random bytes, with a mnemonic of the given number of words
+    /// (12, 15, 18, 21 or 24) and without a passphrase.
+    pub fn generate_with_word_count(
+        network: bitcoin::Network,
+        word_count: usize,
+    ) -> Result<Self, SignerError> {
+        if ![12, 15, 18, 21, 24].contains(&word_count) {
+            return Err(SignerError::Mnemonic(bip39::Error::BadWordCount(
+                word_count,
+            )));
+        }
+        // Each 3 words encode 32 bits of entropy, so a 24-words mnemonic uses all 32 bytes.
+        let random_32bytes =
+            Zeroizing::new(random::random_bytes().map_err(SignerError::Randomness)?);
+        let mnemonic = bip39::Mnemonic::from_entropy(&random_32bytes[..word_count / 3 * 4])
+            .map_err(SignerError::Mnemonic)?;
+        Self::from_mnemonic(network, mnemonic, "")
     }
 
     pub fn from_str(network: bitcoin::Network, s: &str) -> Result<Self, SignerError> {
+        Self::from_str_with_passphrase(network, s, "")
+    }
+
+    /// Create a hot signer from a BIP39 mnemonic and passphrase (sometimes called the "25th
+    /// word"). An empty passphrase is the same as no passphrase.
+    pub fn from_str_with_passphrase(
+        network: bitcoin::Network,
+        s: &str,
+        passphrase: &str,
+    ) -> Result<Self, SignerError> {
         let mnemonic = bip39::Mnemonic::from_str(s).map_err(SignerError::Mnemonic)?;
-        Self::from_mnemonic(network, mnemonic)
+        Self::from_mnemonic(network, mnemonic, passphrase)
+    }
+
+    // Read a hot signer from the content of a plaintext mnemonic file. It only ever contains the
+    // mnemonic words, possibly surrounded by whitespace (such as a trailing newline).
+    fn from_plaintext_storage_str(network: bitcoin::Network, s: &str) -> Result<Self, SignerError> {
+        Self::from_str(network, s.trim())
+    }
+
+    // The content of an encrypted mnemonic file: the mnemonic words and, if storing it was
+    // requested, the BIP39 passphrase on the following line.
+    fn encrypted_storage_str(&self, with_passphrase: bool) -> Zeroizing<String> {
+        let mut content = Zeroizing::new(self.mnemonic_str());
+        if with_passphrase && !self.passphrase.is_empty() {
+            content.push('\n');
+            content.push_str(&self.passphrase);
+        }
+        content
+    }
+
+    // Read a hot signer from the decrypted content of an encrypted mnemonic file. Everything after
+    // the first line is the passphrase, so as to not alter one which would contain a newline.
+    fn from_encrypted_storage_str(network: bitcoin::Network, s: &str) -> Result<Self, SignerError> {
+        match s.split_once('\n') {
+            Some((mnemonic, passphrase)) => {
+                Self::from_str_with_passphrase(network, mnemonic, passphrase)
+            }
+            None => Self::from_str(network, s),
+        }
     }
 
     fn mnemonics_folder(datadir_root: &path::Path, network: bitcoin::Network) -> path::PathBuf {
@@ -136,34 +351,85 @@ impl HotSigner {
         .collect()
     }
 
+    // Read the paths to all the mnemonics files from the datadir for the given network.
+    fn mnemonics_paths(
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+    ) -> Result<Vec<path::PathBuf>, SignerError> {
+        fs::read_dir(Self::mnemonics_folder(datadir_root, network))
+            .map_err(SignerError::MnemonicStorage)?
+            .map(|entry| {
+                entry
+                    .map(|e| e.path())
+                    .map_err(SignerError::MnemonicStorage)
+            })
+            .collect()
+    }
+
     /// Read all the mnemonics from the datadir for the given network.
+    ///
+    /// The mnemonics stored encrypted are only read if a `passphrase` is provided, in which case
+    /// they must all have been encrypted with it. To unlock them individually, use
+    /// [`HotSigner::locked_from_datadir`] instead.
     pub fn from_datadir(
         datadir_root: &path::Path,
         network: bitcoin::Network,
+        passphrase: Option<&str>,
     ) -> Result<Vec<Self>, SignerError> {
         let mut signers = Vec::new();
 
-        let mnemonic_paths = fs::read_dir(Self::mnemonics_folder(datadir_root, network))
-            .map_err(SignerError::MnemonicStorage)?;
-        for entry in mnemonic_paths {
-            let mnemonic = fs::read_to_string(entry.map_err(SignerError::MnemonicStorage)?.path())
-                .map_err(SignerError::MnemonicStorage)?;
-            signers.push(Self::from_str(network, &mnemonic)?);
+        for path in Self::mnemonics_paths(datadir_root, network)? {
+            if path.extension() == Some(ENCRYPTED_EXTENSION.as_ref()) {
+                if let Some(passphrase) = passphrase {
+                    let data = fs::read(path).map_err(SignerError::MnemonicStorage)?;
+                    let content = decrypt_mnemonic(&data, passphrase)?;
+                    signers.push(Self::from_encrypted_storage_str(network, &content)?);
+                }
+            } else {
+                let mnemonic =
+                    Zeroizing::new(fs::read_to_string(path).map_err(SignerError::MnemonicStorage)?);
+                signers.push(Self::from_plaintext_storage_str(network, &mnemonic)?);
+            }
+        }
+
+        Ok(signers)
+    }
+
+    /// Read all the mnemonics stored encrypted from the datadir for the given network, to be
+    /// unlocked using their passphrase.
+    pub fn locked_from_datadir(
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+    ) -> Result<Vec<LockedHotSigner>, SignerError> {
+        let mut signers = Vec::new();
+
+        for path in Self::mnemonics_paths(datadir_root, network)? {
+            if path.extension() == Some(ENCRYPTED_EXTENSION.as_ref()) {
+                let encrypted_mnemonic = fs::read(path).map_err(SignerError::MnemonicStorage)?;
+                signers.push(LockedHotSigner {
+                    fingerprint: encrypted_mnemonic_fingerprint(&encrypted_mnemonic)?,
+                    encrypted_mnemonic,
+                });
+            }
         }
 
         Ok(signers)
     }
 
     /// The BIP39 mnemonics from which the master key of this signer is derived.
-    pub fn words(&self) -> [&'static str; 12] {
-        let words: Vec<&'static str> = self.mnemonic.word_iter().collect();
-        words.try_into().expect("Always 12 words")
+    pub fn words(&self) -> Vec<&'static str> {
+        self.mnemonic.word_iter().collect()
+    }
+
+    /// Whether the master key of this signer is derived using a BIP39 passphrase.
+    pub fn has_passphrase(&self) -> bool {
+        !self.passphrase.is_empty()
     }
 
     /// The BIP39 mnemonic words as a string.
     pub fn mnemonic_str(&self) -> String {
-        let mut mnemonic_str = String::with_capacity(12 * 7);
         let words = self.words();
+        let mut mnemonic_str = String::with_capacity(words.len() * 7);
 
         for (i, word) in words.iter().enumerate() {
             mnemonic_str += word;
@@ -186,24 +452,83 @@ impl HotSigner {
     /// Store the mnemonic in a file within the given "data directory".
     /// The file is stored within a "mnemonics" folder, with the filename set to the fingerprint of
     /// the master xpub corresponding to this mnemonic.
+    ///
+    /// If a `passphrase` is provided the mnemonic is encrypted using a key derived from it, and
+    /// the signer will need to be unlocked with it when read back. Otherwise it is stored in
+    /// plaintext.
+    ///
+    /// The BIP39 passphrase of the signer is never stored by this method, as it would undo the
+    /// protection it provides. Storing a signer with a BIP39 passphrase is refused, see
+    /// [`HotSigner::store_with_bip39_passphrase`] to explicitly store it encrypted instead.
     pub fn store(
         &self,
         datadir_root: &path::Path,
         network: bitcoin::Network,
         secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+        passphrase: Option<&str>,
+    ) -> Result<(), SignerError> {
+        if self.has_passphrase() {
+            return Err(SignerError::PassphraseStorage);
+        }
+        self.store_inner(datadir_root, network, secp, passphrase, false)
+    }
+
+    /// Store the mnemonic along with the BIP39 passphrase of the signer, encrypted using a key
+    /// derived from the given `passphrase`. See [`HotSigner::store`].
+    ///
+    /// Anyone who can decrypt the file gets both the mnemonic and its BIP39 passphrase, so only
+    /// use this if the BIP39 passphrase isn't meant to protect against such an access.
+    pub fn store_with_bip39_passphrase(
+        &self,
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+        passphrase: &str,
+    ) -> Result<(), SignerError> {
+        self.store_inner(datadir_root, network, secp, Some(passphrase), true)
+    }
+
+    fn store_inner(
+        &self,
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+        passphrase: Option<&str>,
+        with_bip39_passphrase: bool,
     ) -> Result<(), SignerError> {
         let mut mnemonics_folder = Self::mnemonics_folder(datadir_root, network);
         if !mnemonics_folder.exists() {
             create_dir(&mnemonics_folder).map_err(SignerError::MnemonicStorage)?;
         }
 
-        // This will fail if a file with this fingerprint exists already.
-        mnemonics_folder.push(format!("mnemonic-{:x}.txt", self.fingerprint(secp)));
-        let mnemonic_path = mnemonics_folder;
+        // This will fail if a file with this fingerprint exists already, whether the mnemonic it
+        // contains is encrypted or not.
+        let fingerprint = self.fingerprint(secp);
+        mnemonics_folder.push(format!("mnemonic-{:x}", fingerprint));
+        let (extension, other_extension) = if passphrase.is_some() {
+            (ENCRYPTED_EXTENSION, PLAINTEXT_EXTENSION)
+        } else {
+            (PLAINTEXT_EXTENSION, ENCRYPTED_EXTENSION)
+        };
+        if mnemonics_folder.with_extension(other_extension).exists() {
+            return Err(SignerError::MnemonicStorage(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "The mnemonic is already stored.",
+            )));
+        }
+        let mnemonic_path = mnemonics_folder.with_extension(extension);
+        let content = match passphrase {
+            Some(passphrase) => encrypt_mnemonic(
+                &self.encrypted_storage_str(with_bip39_passphrase),
+                fingerprint,
+                passphrase,
+            )?,
+            None => self.mnemonic_str().into_bytes(),
+        };
         let mut mnemonic_file =
             create_file(&mnemonic_path).map_err(SignerError::MnemonicStorage)?;
         mnemonic_file
-            .write_all(self.mnemonic_str().as_bytes())
+            .write_all(&content)
             .map_err(SignerError::MnemonicStorage)?;
 
         Ok(())
@@ -454,11 +779,11 @@ mod tests {
         let words_set: HashSet<_> = (0..10)
             .map(|_| {
                 let signer = HotSigner::generate(network).unwrap();
-                signer.store(&tmp_dir, network, &secp).unwrap();
+                signer.store(&tmp_dir, network, &secp, None).unwrap();
                 signer.words()
             })
             .collect();
-        let words_read: HashSet<_> = HotSigner::from_datadir(&tmp_dir, network)
+        let words_read: HashSet<_> = HotSigner::from_datadir(&tmp_dir, network, None)
             .unwrap()
             .into_iter()
             .map(|signer| signer.words())
@@ -468,6 +793,238 @@ mod tests {
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
+    #[test]
+    fn hot_signer_word_count_and_passphrase() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let network = bitcoin::Network::Bitcoin;
+
+        // Mnemonics of 12 words by default, up to 24 words.
+        assert_eq!(HotSigner::generate(network).unwrap().words().len(), 12);
+        for word_count in &[12, 15, 18, 21, 24] {
+            let signer = HotSigner::generate_with_word_count(network, *word_count).unwrap();
+            assert_eq!(signer.words().len(), *word_count);
+            assert!(!signer.has_passphrase());
+        }
+        for word_count in &[0, 13, 27] {
+            assert!(matches!(
+                HotSigner::generate_with_word_count(network, *word_count),
+                Err(SignerError::Mnemonic(_))
+            ));
+        }
+
+        // Test vectors from BIP39, for 12 and 24 words with the "TREZOR" passphrase.
+        let signer = HotSigner::from_str_with_passphrase(
+            network,
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "TREZOR",
+        )
+        .unwrap();
+        assert!(signer.has_passphrase());
+        assert_eq!(signer.master_xpriv, bip32::Xpriv::from_str("xprv9s21ZrQH143K3h3fDYiay8mocZ3afhfULfb5GX8kCBdno77K4HiA15Tg23wpbeF1pLfs1c5SPmYHrEpTuuRhxMwvKDwqdKiGJS9XFKzUsAF").unwrap());
+        let signer = HotSigner::from_str_with_passphrase(
+            network,
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+            "TREZOR",
+        )
+        .unwrap();
+        assert_eq!(signer.words().len(), 24);
+        assert_eq!(signer.master_xpriv, bip32::Xpriv::from_str("xprv9s21ZrQH143K32qBagUJAMU2LsHg3ka7jqMcV98Y7gVeVyNStwYS3U7yVVoDZ4btbRNf4h6ibWpY22iRmXq35qgLs79f312g2kj5539ebPM").unwrap());
+
+        // An empty passphrase is the same as none.
+        let mnemonic = signer.mnemonic_str();
+        assert_eq!(
+            HotSigner::from_str_with_passphrase(network, &mnemonic, "")
+                .unwrap()
+                .fingerprint(&secp),
+            HotSigner::from_str(network, &mnemonic)
+                .unwrap()
+                .fingerprint(&secp)
+        );
+
+        // The passphrase is never stored in plaintext, nor encrypted unless explicitly requested.
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let other_signer = HotSigner::from_str_with_passphrase(
+            network,
+            &HotSigner::generate_with_word_count(network, 24)
+                .unwrap()
+                .mnemonic_str(),
+            "first line\nsecond line",
+        )
+        .unwrap();
+        for s in &[&signer, &other_signer] {
+            assert!(matches!(
+                s.store(&tmp_dir, network, &secp, None),
+                Err(SignerError::PassphraseStorage)
+            ));
+            assert!(matches!(
+                s.store(&tmp_dir, network, &secp, Some("pass")),
+                Err(SignerError::PassphraseStorage)
+            ));
+        }
+        assert!(!HotSigner::mnemonics_folder(&tmp_dir, network).exists());
+
+        // When explicitly requested it is stored encrypted, along with the mnemonic. It may
+        // contain a newline.
+        for s in &[&signer, &other_signer] {
+            s.store_with_bip39_passphrase(&tmp_dir, network, &secp, "pass")
+                .unwrap();
+        }
+        let fingerprints: HashSet<_> = HotSigner::from_datadir(&tmp_dir, network, Some("pass"))
+            .unwrap()
+            .into_iter()
+            .map(|s| {
+                assert!(s.has_passphrase());
+                s.fingerprint(&secp)
+            })
+            .collect();
+        assert_eq!(
+            fingerprints,
+            [other_signer.fingerprint(&secp), signer.fingerprint(&secp)]
+                .iter()
+                .cloned()
+                .collect()
+        );
+        assert!(HotSigner::from_datadir(&tmp_dir, network, None)
+            .unwrap()
+            .is_empty());
+        for path in HotSigner::mnemonics_paths(&tmp_dir, network).unwrap() {
+            let content = String::from_utf8_lossy(&fs::read(&path).unwrap()).to_string();
+            assert!(!content.contains("TREZOR") && !content.contains("first line"));
+        }
+        fs::remove_dir_all(&tmp_dir).unwrap();
+
+        // A plaintext mnemonic file only contains the words. Surrounding whitespace is ignored
+        // and anything else is an error, instead of silently deriving a different key.
+        let mnemonics_folder = HotSigner::mnemonics_folder(&tmp_dir, network);
+        fs::create_dir_all(&mnemonics_folder).unwrap();
+        fs::write(
+            mnemonics_folder.join("mnemonic-a.txt"),
+            format!("{}\n\n", mnemonic),
+        )
+        .unwrap();
+        let signers = HotSigner::from_datadir(&tmp_dir, network, None).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert!(!signers[0].has_passphrase());
+        assert_eq!(
+            signers[0].fingerprint(&secp),
+            HotSigner::from_str(network, &mnemonic)
+                .unwrap()
+                .fingerprint(&secp)
+        );
+        fs::write(
+            mnemonics_folder.join("mnemonic-b.txt"),
+            format!("{}\nTREZOR", mnemonic),
+        )
+        .unwrap();
+        assert!(matches!(
+            HotSigner::from_datadir(&tmp_dir, network, None),
+            Err(SignerError::Mnemonic(_))
+        ));
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn hot_signer_encrypted_storage() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let network = bitcoin::Network::Bitcoin;
+        let passphrase = "correct horse battery staple";
+
+        // Store a signer in plaintext and a few encrypted ones.
+        let plain_signer = HotSigner::generate(network).unwrap();
+        plain_signer.store(&tmp_dir, network, &secp, None).unwrap();
+        let encrypted_words: HashSet<_> = (0..3)
+            .map(|_| {
+                let signer = HotSigner::generate(network).unwrap();
+                signer
+                    .store(&tmp_dir, network, &secp, Some(passphrase))
+                    .unwrap();
+                signer.words()
+            })
+            .collect();
+
+        // The mnemonics can't be read from the encrypted files.
+        for path in HotSigner::mnemonics_paths(&tmp_dir, network).unwrap() {
+            if path.extension() == Some(ENCRYPTED_EXTENSION.as_ref()) {
+                let content = String::from_utf8_lossy(&fs::read(&path).unwrap()).to_string();
+                assert!(encrypted_words
+                    .iter()
+                    .all(|words| !content.contains(&words.join(" "))));
+            }
+        }
+
+        // A signer can't be stored twice, even with a different encryption.
+        assert!(matches!(
+            plain_signer.store(&tmp_dir, network, &secp, Some(passphrase)),
+            Err(SignerError::MnemonicStorage(_))
+        ));
+
+        // Without a passphrase, only the plaintext signer is read.
+        let signers = HotSigner::from_datadir(&tmp_dir, network, None).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers[0].words(), plain_signer.words());
+
+        // The encrypted ones can be unlocked individually, but not with a wrong passphrase.
+        let locked_signers = HotSigner::locked_from_datadir(&tmp_dir, network).unwrap();
+        assert_eq!(locked_signers.len(), 3);
+        for locked in &locked_signers {
+            assert!(matches!(
+                locked.unlock(network, "incorrect horse battery staple"),
+                Err(SignerError::MnemonicDecryption)
+            ));
+            let signer = locked.unlock(network, passphrase).unwrap();
+            assert_eq!(signer.fingerprint(&secp), locked.fingerprint());
+            assert!(encrypted_words.contains(&signer.words()));
+        }
+
+        // With the passphrase, all signers are read.
+        let words_read: HashSet<_> = HotSigner::from_datadir(&tmp_dir, network, Some(passphrase))
+            .unwrap()
+            .into_iter()
+            .map(|signer| signer.words())
+            .collect();
+        let mut words_set = encrypted_words.clone();
+        words_set.insert(plain_signer.words());
+        assert_eq!(words_read, words_set);
+        assert!(matches!(
+            HotSigner::from_datadir(&tmp_dir, network, Some("wrong")),
+            Err(SignerError::MnemonicDecryption)
+        ));
+
+        // Tampering with the header is detected.
+        let mut data = encrypt_mnemonic(
+            &plain_signer.mnemonic_str(),
+            plain_signer.fingerprint(&secp),
+            passphrase,
+        )
+        .unwrap();
+        assert_eq!(
+            decrypt_mnemonic(&data, passphrase).unwrap().as_str(),
+            plain_signer.mnemonic_str()
+        );
+        data[1] ^= 1;
+        assert!(matches!(
+            decrypt_mnemonic(&data, passphrase),
+            Err(SignerError::MnemonicDecryption)
+        ));
+        data[1] ^= 1;
+
+        // Scrypt parameters other than ours are refused before deriving the key.
+        for (index, value) in &[(5, 30), (5, SCRYPT_LOG_N - 1), (9, 0xff), (13, 2)] {
+            let mut data = data.clone();
+            data[*index] = *value;
+            assert!(matches!(
+                decrypt_mnemonic(&data, passphrase),
+                Err(SignerError::MnemonicDecryption)
+            ));
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
     #[test]
     fn hot_signer_sign_p2wsh() {
         let secp = secp256k1::Secp256k1::new();
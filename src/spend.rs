@@ -101,15 +101,13 @@ fn check_output_value(value: bitcoin::Amount) -> Result<(), SpendCreationError>
 
 // Apply some sanity checks on a created transaction's PSBT.
 // TODO: add more sanity checks from revault_tx
-fn sanity_check_psbt(
-    spent_desc: &descriptors::LianaDescriptor,
-    psbt: &Psbt,
-    use_primary_path: bool,
-) -> Result<(), SpendCreationError> {
+// `input_sat_weights` is the maximum satisfaction weight of each input, in order.
+fn sanity_check_psbt(psbt: &Psbt, input_sat_weights: &[usize]) -> Result<(), SpendCreationError> {
     let tx = &psbt.unsigned_tx;
 
     // Must have as many in/out in the PSBT and Bitcoin tx.
     if psbt.inputs.len() != tx.input.len()
+        || input_sat_weights.len() != tx.input.len()
         || psbt.outputs.len() != tx.output.len()
         || tx.output.is_empty()
     {
@@ -143,7 +141,18 @@ fn sanity_check_psbt(
     }
 
     // Check the feerate isn't insane.
-    let tx_vb = spent_desc.unsigned_tx_max_vbytes(tx, use_primary_path);
+    // Add weights together before converting to vbytes to avoid rounding up multiple times.
+    let tx_wu = tx.weight().to_wu()
+        + input_sat_weights
+            .iter()
+            .map(|w| -> u64 { (*w).try_into().expect("Weight must fit in a u64") })
+            .sum::<u64>();
+    let witness_factor: u64 = WITNESS_SCALE_FACTOR.try_into().unwrap();
+    let tx_vb = tx_wu
+        .checked_add(witness_factor.checked_sub(1).unwrap())
+        .unwrap()
+        .checked_div(witness_factor)
+        .unwrap();
     let feerate_sats_vb = abs_fee
         .checked_div(tx_vb)
         .ok_or(SpendCreationError::InsaneFees(
@@ -201,10 +210,15 @@ pub struct CandidateCoin {
     pub is_change: bool,
     /// Whether or not this coin must be selected by the coin selection algorithm.
     pub must_select: bool,
-    /// The nSequence field to set for an input spending this coin.
+    /// The nSequence field to set for an input spending this coin. Only set it for spending
+    /// through a recovery path.
     pub sequence: Option<bitcoin::Sequence>,
     /// Information about in-mempool ancestors of the coin.
     pub ancestor_info: Option<AncestorInfo>,
+    /// The maximum satisfaction weight of an input spending this coin. Only set it when spending
+    /// coins through different paths in the same transaction, otherwise the maximum satisfaction
+    /// weight of the spending path used by all the inputs applies.
+    pub sat_weight: Option<usize>,
 }
 
 /// A coin selection result.
@@ -276,7 +290,8 @@ impl bdk_coin_select::BnbMetric for LowestFeeChangeCondition {
 /// Otherwise, it should be `None`.
 ///
 /// `max_sat_weight` is the maximum weight difference of an input in the
-/// transaction before and after satisfaction.
+/// transaction before and after satisfaction. It is only used for candidates
+/// which don't have their own satisfaction weight set.
 ///
 /// `must_have_change` indicates whether the transaction must have a change output.
 /// If `true`, the returned change amount will be positive.
@@ -344,7 +359,14 @@ fn select_coins_for_spend(
                 // Store the extra weight for this candidate for use later on.
                 // At the same time, make sure there are no duplicate outpoints.
                 assert!(added_weights.insert(cand.outpoint, extra).is_none());
-                max_input_weight
+                let input_weight = cand
+                    .sat_weight
+                    .map(|w| {
+                        let w: u32 = w.try_into().expect("Weight must fit in a u32");
+                        TXIN_BASE_WEIGHT + w
+                    })
+                    .unwrap_or(max_input_weight);
+                input_weight
                     .checked_add(extra)
                     .expect("effective weight must fit in u32")
             },
@@ -706,13 +728,16 @@ pub fn create_spend(
         value: bitcoin::Amount::MAX,
         script_pubkey: change_addr.addr.script_pubkey(),
     };
-    // If no candidates have relative locktime, then we should use the primary spending path.
+    // If no candidates have relative locktime, then we should use the primary spending path. The
+    // same goes if no candidate has its nSequence explicitly set for spending through a recovery
+    // path behind an absolute timelock.
     // Note we set this value before actually selecting the coins, but we expect either all
     // candidates or none to have relative locktime sequence so this is fine.
     let use_primary_path = !candidate_coins
         .iter()
         .filter_map(|cand| cand.sequence)
-        .any(|seq| seq.is_relative_lock_time());
+        .any(|seq| seq.is_relative_lock_time() || seq.enables_absolute_lock_time());
+    let max_sat_weight = main_descriptor.max_sat_weight(use_primary_path);
     // Now select the coins necessary using the provided candidates and determine whether
     // there is any leftover to create a change output.
     let CoinSelectionRes {
@@ -733,10 +758,7 @@ pub fn create_spend(
             fr
         }
         .into();
-        let max_sat_wu = main_descriptor
-            .max_sat_weight(use_primary_path)
-            .try_into()
-            .expect("Weight must fit in a u32");
+        let max_sat_wu = max_sat_weight.try_into().expect("Weight must fit in a u32");
         select_coins_for_spend(
             candidate_coins,
             tx.clone(),
@@ -786,6 +808,7 @@ pub fn create_spend(
 
     // Iterate through selected coins and add necessary information to the PSBT inputs.
     let mut psbt_ins = Vec::with_capacity(selected.len());
+    let mut input_sat_weights = Vec::with_capacity(selected.len());
     for cand in &selected {
         let sequence = cand
             .sequence
@@ -809,6 +832,7 @@ pub fn create_spend(
             psbt_in.non_witness_utxo = tx_getter.get_tx(&cand.outpoint.txid);
         }
         psbt_ins.push(psbt_in);
+        input_sat_weights.push(cand.sat_weight.unwrap_or(max_sat_weight));
     }
 
     // Finally, create the PSBT with all inputs and outputs, sanity check it and return it.
@@ -821,7 +845,7 @@ pub fn create_spend(
         inputs: psbt_ins,
         outputs: psbt_outs,
     };
-    sanity_check_psbt(main_descriptor, &psbt, use_primary_path)?;
+    sanity_check_psbt(&psbt, &input_sat_weights)?;
     // TODO: maybe check for common standardness rules (max size, ..)?
 
     Ok(CreateSpendRes {
@@ -2,7 +2,9 @@ use crate::{
     bitcoin::{BitcoinInterface, Block, BlockChainTip, MempoolEntry, SyncProgress, UTxO},
     config::{BitcoinConfig, Config},
     database::{BlockInfo, Coin, CoinStatus, DatabaseConnection, DatabaseInterface, LabelItem},
-    descriptors, DaemonControl, DaemonHandle,
+    descriptors,
+    events::{Event, JournalEntry},
+    DaemonControl, DaemonHandle,
 };
 
 use std::convert::TryInto;
@@ -21,6 +23,7 @@ use miniscript::{
 
 pub struct DummyBitcoind {
     pub txs: HashMap<Txid, (Transaction, Option<Block>)>,
+    pub mempool_entries: HashMap<Txid, MempoolEntry>,
 }
 
 impl DummyBitcoind {}
@@ -29,6 +32,7 @@ impl DummyBitcoind {
     pub fn new() -> Self {
         Self {
             txs: HashMap::new(),
+            mempool_entries: HashMap::new(),
         }
     }
 }
@@ -110,7 +114,7 @@ impl BitcoinInterface for DummyBitcoind {
     }
 
     fn start_rescan(&mut self, _: &descriptors::LianaDescriptor, _: u32) -> Result<(), String> {
-        todo!()
+        Err("Rescans aren't supported by the dummy Bitcoin interface.".to_string())
     }
 
     fn rescan_progress(&self) -> Option<f64> {
@@ -125,6 +129,13 @@ impl BitcoinInterface for DummyBitcoind {
         None
     }
 
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        // A block every 10 minutes since the genesis block, with the tip at height 100.
+        (0..=100)
+            .contains(&height)
+            .then(|| self.genesis_block_timestamp() + height as u32 * 600)
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -136,8 +147,13 @@ impl BitcoinInterface for DummyBitcoind {
         Vec::new()
     }
 
-    fn mempool_entry(&self, _: &bitcoin::Txid) -> Option<MempoolEntry> {
-        None
+    fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
+        self.mempool_entries.get(txid).cloned()
+    }
+
+    fn estimate_feerate(&self, conf_target: u16) -> Option<u64> {
+        // Mimic a fee market where the estimate decreases with the confirmation target.
+        Some(if conf_target <= 2 { 10 } else { 2 })
     }
 }
 
@@ -148,6 +164,9 @@ struct DummyDbState {
     coins: HashMap<bitcoin::OutPoint, Coin>,
     txs: HashMap<bitcoin::Txid, bitcoin::Transaction>,
     spend_txs: HashMap<bitcoin::Txid, (Psbt, Option<u32>)>,
+    events: Vec<JournalEntry>,
+    labels: Vec<(LabelItem, String)>,
+    pending_frozen: HashSet<bitcoin::OutPoint>,
     timestamp: u32,
 }
 
@@ -180,6 +199,9 @@ impl DummyDatabase {
                 coins: HashMap::new(),
                 txs: HashMap::new(),
                 spend_txs: HashMap::new(),
+                events: Vec::new(),
+                labels: Vec::new(),
+                pending_frozen: HashSet::new(),
                 timestamp: now,
             })),
         }
@@ -283,8 +305,11 @@ impl DatabaseConnection for DummyDatabase {
     }
 
     fn new_unspent_coins<'a>(&mut self, coins: &[Coin]) {
+        let mut db = self.db.write().unwrap();
         for coin in coins {
-            self.db.write().unwrap().coins.insert(coin.outpoint, *coin);
+            let mut coin = *coin;
+            coin.is_frozen |= db.pending_frozen.remove(&coin.outpoint);
+            db.coins.insert(coin.outpoint, coin);
         }
     }
 
@@ -340,6 +365,38 @@ impl DatabaseConnection for DummyDatabase {
         }
     }
 
+    fn freeze_coins(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        for op in outpoints {
+            self.db
+                .write()
+                .unwrap()
+                .coins
+                .get_mut(op)
+                .unwrap()
+                .is_frozen = true;
+        }
+    }
+
+    fn unfreeze_coins(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        for op in outpoints {
+            self.db
+                .write()
+                .unwrap()
+                .coins
+                .get_mut(op)
+                .unwrap()
+                .is_frozen = false;
+        }
+    }
+
+    fn freeze_coins_once_found(&mut self, outpoints: &[bitcoin::OutPoint]) {
+        self.db
+            .write()
+            .unwrap()
+            .pending_frozen
+            .extend(outpoints.iter().copied());
+    }
+
     fn derivation_index_by_address(
         &mut self,
         _: &bitcoin::Address,
@@ -411,12 +468,33 @@ impl DatabaseConnection for DummyDatabase {
         todo!()
     }
 
-    fn update_labels(&mut self, _items: &HashMap<LabelItem, Option<String>>) {
-        todo!()
+    fn update_labels(&mut self, items: &HashMap<LabelItem, Option<String>>) {
+        let labels = &mut self.db.write().unwrap().labels;
+        for (item, value) in items {
+            match (labels.iter().position(|(i, _)| i == item), value) {
+                (Some(pos), Some(value)) => labels[pos].1 = value.clone(),
+                (Some(pos), None) => {
+                    labels.remove(pos);
+                }
+                (None, Some(value)) => labels.push((item.clone(), value.clone())),
+                (None, None) => {}
+            }
+        }
     }
 
-    fn labels(&mut self, _items: &HashSet<LabelItem>) -> HashMap<String, String> {
-        todo!()
+    fn labels(&mut self, items: &HashSet<LabelItem>) -> HashMap<String, String> {
+        self.db
+            .read()
+            .unwrap()
+            .labels
+            .iter()
+            .filter(|(item, _)| items.contains(item))
+            .map(|(item, value)| (item.to_string(), value.clone()))
+            .collect()
+    }
+
+    fn list_labels(&mut self) -> Vec<(LabelItem, String)> {
+        self.db.read().unwrap().labels.clone()
     }
 
     fn list_txids(&mut self, start: u32, end: u32, limit: u64) -> Vec<bitcoin::Txid> {
@@ -489,6 +567,31 @@ impl DatabaseConnection for DummyDatabase {
         }
         wallet_txs
     }
+
+    fn store_events(&mut self, events: &[Event]) {
+        let mut db = self.db.write().unwrap();
+        let timestamp = db.timestamp;
+        for event in events {
+            let id = db.events.len() as u64 + 1;
+            db.events.push(JournalEntry {
+                id,
+                timestamp,
+                event: event.clone(),
+            });
+        }
+    }
+
+    fn events(&mut self, after_id: u64, limit: u64) -> Vec<JournalEntry> {
+        self.db
+            .read()
+            .unwrap()
+            .events
+            .iter()
+            .filter(|e| e.id > after_id)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
 }
 
 pub struct DummyLiana {
@@ -543,6 +646,10 @@ impl DummyLiana {
         let config = Config {
             bitcoin_config,
             bitcoin_backend: None,
+            refresh_policy: None,
+            event_hook: None,
+            http_rpc_config: None,
+            wallets: Vec::new(),
             data_dir: Some(data_dir),
             #[cfg(unix)]
             daemon: false,
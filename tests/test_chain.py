@@ -1,4 +1,5 @@
 import copy
+import json
 
 from fixtures import *
 from test_framework.utils import (
@@ -522,3 +523,75 @@ def test_spend_replacement(lianad, bitcoind):
     assert (
         lianad.rpc.listcoins([], [first_outpoints[0]])["coins"][0]["spend_info"] is None
     )
+
+
+def test_backup_restore(lianad, bitcoind):
+    """Test we can restore a wallet from a backup on a fresh data directory."""
+    initial_tip = bitcoind.rpc.getblockheader(bitcoind.rpc.getbestblockhash())
+
+    # Get a coin, label it and store a Spend transaction.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.5)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    coin = lianad.rpc.listcoins()["coins"][0]
+    labels = {addr: "deposit address", txid: "deposit", coin["outpoint"]: "coin"}
+    lianad.rpc.updatelabels(labels)
+    destinations = {bitcoind.rpc.getnewaddress(): 100_000}
+    res = lianad.rpc.createspend(destinations, [coin["outpoint"]], 2)
+    lianad.rpc.updatespend(res["psbt"])
+    for _ in range(3):
+        lianad.rpc.getnewaddress()
+
+    # Back it all up, along with the key aliases.
+    fingerprint = xpub_fingerprint(lianad.signer.primary_hd)
+    backup = lianad.rpc.createbackup({fingerprint: "Alice"})
+    assert backup["version"] == 1
+    assert backup["network"] == "regtest"
+    assert backup["main_descriptor"] == lianad.rpc.getinfo()["descriptors"]["main"]
+    assert backup["receive_index"] == 4
+    assert backup["labels"] == labels
+    assert backup["spend_txs"] == [res["psbt"]]
+    assert backup["key_aliases"] == {fingerprint: "Alice"}
+    assert "key_aliases" not in lianad.rpc.createbackup()
+
+    # Advance the blocktime by >2h in median-time past for rescan
+    added_time = 60 * 60 * 3
+    bitcoind.rpc.setmocktime(initial_tip["time"] + added_time)
+    bitcoind.generate_block(12)
+
+    # Lose our state and restore the backup on the fresh data directory.
+    backup_path = os.path.join(lianad.datadir, "backup.json")
+    with open(backup_path, "w") as f:
+        json.dump(backup, f)
+    lianad.stop()
+    dir_path = os.path.join(lianad.datadir, "regtest")
+    shutil.rmtree(dir_path)
+    if BITCOIN_BACKEND_TYPE is BitcoinBackendType.Bitcoind:
+        wallet_path = os.path.join(dir_path, "lianad_watchonly_wallet")
+        bitcoind.node_rpc.unloadwallet(wallet_path)
+    cmd_line = lianad.cmd_line
+    lianad.cmd_line = cmd_line + ["--restore-backup", backup_path]
+    lianad.start()
+    lianad.cmd_line = cmd_line
+
+    # The restored wallet has the creation date of the backed up one from the start.
+    assert lianad.rpc.getinfo()["timestamp"] == backup["timestamp"]
+
+    # The chain is rescanned and all our state is back.
+    wait_for(lambda: lianad.rpc.getinfo()["rescan_progress"] is None)
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) == 1)
+    assert lianad.rpc.listcoins()["coins"][0]["outpoint"] == coin["outpoint"]
+    assert lianad.rpc.getlabels(list(labels))["labels"] == labels
+    spend_txs = lianad.rpc.listspendtxs()["spend_txs"]
+    assert [s["psbt"] for s in spend_txs] == [res["psbt"]]
+    new_addr = lianad.rpc.getnewaddress()["address"]
+    assert lianad.rpc.listaddresses(4, 1)["addresses"][0]["receive"] == new_addr
+
+    # We can't restore a backup on an existing wallet.
+    lianad.stop()
+    lianad.cmd_line = cmd_line + ["--restore-backup", backup_path]
+    with pytest.raises(Exception):
+        lianad.start()
+    lianad.cmd_line = cmd_line
+    lianad.start()
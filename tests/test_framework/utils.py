@@ -212,10 +212,12 @@ class UnixSocket(object):
 
 
 class UnixDomainSocketRpc(object):
-    def __init__(self, socket_path, logger=logging):
+    def __init__(self, socket_path, logger=logging, wallet=None):
         self.socket_path = socket_path
         self.logger = logger
         self.next_id = 0
+        # The name of the additional wallet to direct the requests to, if any.
+        self.wallet = wallet
 
     def _readobj(self, sock):
         """Read a JSON object"""
@@ -254,14 +256,15 @@ class UnixDomainSocketRpc(object):
 
         # FIXME: we open a new socket for every readobj call...
         sock = UnixSocket(self.socket_path)
-        msg = json.dumps(
-            {
-                "jsonrpc": "2.0",
-                "id": 0,
-                "method": method,
-                "params": params,
-            }
-        )
+        req = {
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        }
+        if self.wallet is not None:
+            req["wallet"] = self.wallet
+        msg = json.dumps(req)
         sock.sendall(msg.encode() + b"\n")
         this_id = self.next_id
         resp = self._readobj(sock)
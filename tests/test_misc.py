@@ -1,7 +1,15 @@
+import base64
+import json
 import logging
+import os
 import pytest
 import shutil
+import socket
+import ssl
+import subprocess
 import time
+import urllib.error
+import urllib.request
 
 from fixtures import *
 from test_framework.authproxy import JSONRPCException
@@ -11,6 +19,8 @@ from test_framework.utils import (
     BITCOIN_BACKEND_TYPE,
     wait_for,
     RpcError,
+    UnixDomainSocketRpc,
+    sign_and_broadcast_psbt,
     OLD_LIANAD_PATH,
     LIANAD_PATH,
     COIN,
@@ -375,3 +385,339 @@ def test_retry_on_workqueue_exceeded(lianad, bitcoind, executor):
     # We should have retried the request to bitcoind, which should now succeed along with the call.
     # This just checks the response we get is sane, nothing particular with this field.
     assert "block_height" in f_liana.result(TIMEOUT)
+
+
+def test_refresh_policy(lianad, bitcoind):
+    """Test the automatic creation of refresh transactions for coins close to expiry."""
+    # Configure the daemon to refresh the coins within 5 blocks of their first recovery
+    # path (we use a csv of 10 in the fixture).
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("[refresh_policy]\n")
+        f.write("margin_blocks = 5\n")
+        f.write("max_feerate = 2\n")
+    lianad.start()
+
+    # Receive a coin. It isn't refreshed until it enters the safety margin.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.1)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    coin = lianad.rpc.listcoins(["confirmed"])["coins"][0]
+    bitcoind.generate_block(3)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+    assert lianad.rpc.listexpiringcoins()["coins"][0]["recovery_paths"][0][
+        "blocks_remaining"
+    ] == 6
+    assert lianad.rpc.listspendtxs()["spend_txs"] == []
+
+    # Once it does, a refresh transaction is stored.
+    bitcoind.generate_block(1)
+    wait_for(lambda: len(lianad.rpc.listspendtxs()["spend_txs"]) == 1)
+    psbt = PSBT.from_base64(lianad.rpc.listspendtxs()["spend_txs"][0]["psbt"])
+    assert [f"{i.prevout.hash:064x}:{i.prevout.n}" for i in psbt.tx.vin] == [
+        coin["outpoint"]
+    ]
+    assert len(psbt.tx.vout) == 1
+    wait_for(
+        lambda: lianad.rpc.listcoins([], [coin["outpoint"]])["coins"][0]["reserved_by"]
+        == [psbt.tx.txid().hex()]
+    )
+
+    # The coin is reserved by the stored transaction and isn't refreshed twice.
+    bitcoind.generate_block(1)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+    time.sleep(2)
+    assert len(lianad.rpc.listspendtxs()["spend_txs"]) == 1
+
+    # If the refresh transaction is deleted, it isn't created again.
+    lianad.rpc.delspendtx(psbt.tx.txid().hex())
+    bitcoind.generate_block(1)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+    time.sleep(2)
+    assert lianad.rpc.listspendtxs()["spend_txs"] == []
+
+
+def test_event_hook(lianad, bitcoind):
+    """Test the hook command is run with the JSON of the events on its stdin."""
+    events_file = os.path.join(lianad.datadir, "events.jsonl")
+    hook_path = os.path.join(lianad.datadir, "hook.sh")
+    with open(hook_path, "w") as f:
+        f.write(f"#!/bin/sh\ncat >> {events_file}\n")
+    os.chmod(hook_path, 0o755)
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("[event_hook]\n")
+        f.write(f"command = '{hook_path}'\n")
+    lianad.start()
+
+    def events():
+        if not os.path.exists(events_file):
+            return []
+        with open(events_file) as f:
+            return [json.loads(line) for line in f.readlines()]
+
+    # Receive a coin, get it confirmed then spend it.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.1)
+    wait_for(lambda: len(events()) == 1)
+    received = events()[0]
+    assert received["type"] == "received"
+    assert received["outpoint"].startswith(txid)
+    assert received["amount"] == 10_000_000
+    assert received["address"] == addr
+    assert not received["is_change"]
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(events()) == 2)
+    confirmed = events()[1]
+    assert confirmed["type"] == "confirmed"
+    assert confirmed["outpoint"] == received["outpoint"]
+    assert confirmed["height"] == bitcoind.rpc.getblockcount()
+
+    res = lianad.rpc.createspend({bitcoind.rpc.getnewaddress(): 100_000}, [], 2)
+    spend_psbt = PSBT.from_base64(res["psbt"])
+    spend_txid = sign_and_broadcast_psbt(lianad, spend_psbt)
+    wait_for(lambda: any(e["type"] == "spending" for e in events()))
+    spending = next(e for e in events() if e["type"] == "spending")
+    assert spending["outpoint"] == received["outpoint"]
+    assert spending["txid"] == spend_txid
+    bitcoind.generate_block(1, wait_for_mempool=spend_txid)
+    wait_for(lambda: any(e["type"] == "spent" for e in events()))
+    spent = next(e for e in events() if e["type"] == "spent")
+    assert spent["outpoint"] == received["outpoint"]
+    assert spent["txid"] == spend_txid
+    assert spent["height"] == bitcoind.rpc.getblockcount()
+
+
+
+@pytest.mark.skipif(
+    BITCOIN_BACKEND_TYPE is not BitcoinBackendType.Bitcoind,
+    reason="Additional wallets are only supported with the bitcoind backend.",
+)
+def test_multiple_wallets(lianad, bitcoind):
+    """Test serving an additional wallet from the same daemon."""
+    signer = SingleSigner(is_taproot=USE_TAPROOT)
+    desc = single_key_desc(
+        xpub_fingerprint(signer.primary_hd),
+        signer.primary_hd.get_xpub(),
+        xpub_fingerprint(signer.recovery_hd),
+        signer.recovery_hd.get_xpub(),
+        10,
+        is_taproot=USE_TAPROOT,
+    )
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("[[wallets]]\n")
+        f.write("name = 'savings'\n")
+        f.write(f"main_descriptor = '{desc}'\n")
+    lianad.start()
+    savings = UnixDomainSocketRpc(lianad.rpc.socket_path, wallet="savings")
+    assert savings.getinfo()["descriptors"]["main"]["multi_desc"].startswith(desc)
+    assert savings.getinfo()["descriptors"] != lianad.rpc.getinfo()["descriptors"]
+
+    # Each wallet has its own addresses and only sees its own coins.
+    addr = savings.getnewaddress()["address"]
+    assert addr != lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.2)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(savings.listcoins(["confirmed"])["coins"]) == 1)
+    assert savings.listcoins()["coins"][0]["amount"] == 20_000_000
+    assert lianad.rpc.listcoins()["coins"] == []
+    wait_for(
+        lambda: savings.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+
+    # An unknown wallet is refused.
+    with pytest.raises(RpcError, match="Unknown wallet"):
+        UnixDomainSocketRpc(lianad.rpc.socket_path, wallet="unknown").getinfo()
+
+    # The additional wallet is still there after a restart.
+    lianad.stop()
+    lianad.start()
+    assert len(savings.listcoins()["coins"]) == 1
+    assert lianad.rpc.listcoins()["coins"] == []
+
+    # A wallet configured with its creation date finds the coins it received since then, even
+    # before it was served by the daemon.
+    desc = single_key_desc(
+        xpub_fingerprint(signer.primary_hd),
+        signer.primary_hd.get_xpub(),
+        xpub_fingerprint(signer.recovery_hd),
+        signer.recovery_hd.get_xpub(),
+        11,
+        is_taproot=USE_TAPROOT,
+    )
+    timestamp = bitcoind.rpc.getblockheader(bitcoind.rpc.getbestblockhash())["time"]
+    receive_desc = Descriptor.from_str(desc).singlepath_descriptors()[0]
+    addr = bitcoind.rpc.deriveaddresses(str(receive_desc), [0, 0])[0]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.3)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("[[wallets]]\n")
+        f.write("name = 'vault'\n")
+        f.write(f"main_descriptor = '{desc}'\n")
+        f.write(f"timestamp = {timestamp}\n")
+    lianad.start()
+    vault = UnixDomainSocketRpc(lianad.rpc.socket_path, wallet="vault")
+    assert vault.getinfo()["timestamp"] == timestamp
+    wait_for(lambda: len(vault.listcoins(["confirmed"])["coins"]) == 1)
+    assert vault.listcoins()["coins"][0]["amount"] == 30_000_000
+    assert len(savings.listcoins()["coins"]) == 1
+
+
+def test_descriptor_migration(lianad, bitcoind):
+    """Test moving all the coins of the wallet to a new descriptor."""
+    signer = SingleSigner(is_taproot=USE_TAPROOT)
+    new_desc = single_key_desc(
+        xpub_fingerprint(signer.primary_hd),
+        signer.primary_hd.get_xpub(),
+        xpub_fingerprint(signer.recovery_hd),
+        signer.recovery_hd.get_xpub(),
+        20,
+        is_taproot=USE_TAPROOT,
+    )
+    with pytest.raises(RpcError, match="No coin to migrate"):
+        lianad.rpc.createmigration(new_desc, 2)
+    with pytest.raises(RpcError, match="must be different from the current one"):
+        lianad.rpc.createmigration(str(lianad.multi_desc), 2)
+
+    # Receive three coins, migrate them in two transactions.
+    txids = [
+        bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.1)
+        for _ in range(3)
+    ]
+    bitcoind.generate_block(1, wait_for_mempool=txids)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 3)
+    psbts = [
+        PSBT.from_base64(psbt)
+        for psbt in lianad.rpc.createmigration(new_desc, 2, 2)["psbts"]
+    ]
+    assert sorted(len(psbt.tx.vin) for psbt in psbts) == [1, 2]
+    receive_desc, _ = Descriptor.from_str(new_desc).singlepath_descriptors()
+    for i, psbt in enumerate(psbts):
+        assert len(psbt.tx.vout) == 1
+        desc = Descriptor.from_str(str(receive_desc))
+        desc.derive(i)
+        assert psbt.tx.vout[0].scriptPubKey == desc.script_pubkey
+
+    # They are stored and labeled, and the coins aren't migrated twice.
+    spend_txids = [psbt.tx.txid().hex() for psbt in psbts]
+    stored = [
+        PSBT.from_base64(s["psbt"]).tx.txid().hex()
+        for s in lianad.rpc.listspendtxs()["spend_txs"]
+    ]
+    assert sorted(stored) == sorted(spend_txids)
+    labels = lianad.rpc.getlabels(spend_txids)["labels"]
+    assert all(labels[txid] == "Migration to a new descriptor" for txid in spend_txids)
+    with pytest.raises(RpcError, match="No coin to migrate"):
+        lianad.rpc.createmigration(new_desc, 2)
+
+    # Once broadcast and confirmed, no coin is left in the wallet.
+    for psbt in psbts:
+        sign_and_broadcast_psbt(lianad, psbt)
+    bitcoind.generate_block(1, wait_for_mempool=spend_txids)
+    wait_for(
+        lambda: len(lianad.rpc.listcoins(["unconfirmed", "confirmed"])["coins"]) == 0
+    )
+
+
+def http_rpc(url, credentials, method, params=None, context=None):
+    """Send a JSONRPC request to the HTTP server of lianad."""
+    body = json.dumps(
+        {"jsonrpc": "2.0", "id": 0, "method": method, "params": params or []}
+    ).encode()
+    auth = base64.b64encode(credentials.encode()).decode()
+    req = urllib.request.Request(
+        url, data=body, headers={"Authorization": f"Basic {auth}"}
+    )
+    with urllib.request.urlopen(req, timeout=TIMEOUT, context=context) as resp:
+        return json.loads(resp.read())
+
+
+def test_http_rpc(lianad, bitcoind):
+    """Test serving the JSONRPC API over HTTP, with basic and cookie authentication and TLS."""
+    with socket.socket() as s:
+        s.bind(("127.0.0.1", 0))
+        port = s.getsockname()[1]
+    url = f"http://127.0.0.1:{port}"
+    conf = open(lianad.conf_file).read()
+
+    # With user and password credentials.
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("[http_rpc_config]\n")
+        f.write(f"addr = '127.0.0.1:{port}'\n")
+        f.write("auth = 'monitoring:my_password'\n")
+    lianad.start()
+    res = http_rpc(url, "monitoring:my_password", "getinfo")
+    assert res["result"] == lianad.rpc.getinfo()
+    addr = http_rpc(url, "monitoring:my_password", "getnewaddress")["result"]["address"]
+    assert lianad.rpc.listaddresses(1, 1)["addresses"][0]["receive"] == addr
+    res = http_rpc(url, "monitoring:my_password", "listcoins", [["invalid"]])
+    assert res["error"]["code"] == -32602
+    with pytest.raises(urllib.error.HTTPError, match="401"):
+        http_rpc(url, "monitoring:wrong_password", "getinfo")
+
+    # With a cookie file.
+    cookie_path = os.path.join(lianad.datadir, "http_cookie")
+    lianad.stop()
+    with open(lianad.conf_file, "w") as f:
+        f.write(conf)
+        f.write("[http_rpc_config]\n")
+        f.write(f"addr = '127.0.0.1:{port}'\n")
+        f.write(f"cookie_path = '{cookie_path}'\n")
+    lianad.start()
+    cookie = open(cookie_path).read()
+    assert cookie.startswith("__cookie__:")
+    assert http_rpc(url, cookie, "getinfo")["result"] == lianad.rpc.getinfo()
+    with pytest.raises(urllib.error.HTTPError, match="401"):
+        http_rpc(url, "__cookie__:wrong", "getinfo")
+
+    # Over TLS, with a self-signed certificate.
+    if shutil.which("openssl") is None:
+        return
+    cert_path = os.path.join(lianad.datadir, "cert.pem")
+    key_path = os.path.join(lianad.datadir, "key.pem")
+    subprocess.check_call(
+        [
+            "openssl",
+            "req",
+            "-x509",
+            "-newkey",
+            "rsa:2048",
+            "-nodes",
+            "-keyout",
+            key_path,
+            "-out",
+            cert_path,
+            "-days",
+            "1",
+            "-subj",
+            "/CN=localhost",
+        ],
+        stdout=subprocess.DEVNULL,
+        stderr=subprocess.DEVNULL,
+    )
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write(f"tls_cert_path = '{cert_path}'\n")
+        f.write(f"tls_key_path = '{key_path}'\n")
+    lianad.start()
+    cookie = open(cookie_path).read()
+    context = ssl.create_default_context(cafile=cert_path)
+    context.check_hostname = False
+    res = http_rpc(f"https://127.0.0.1:{port}", cookie, "getinfo", context=context)
+    assert res["result"] == lianad.rpc.getinfo()
+
+    # The stop command works over HTTP too.
+    http_rpc(f"https://127.0.0.1:{port}", cookie, "stop", context=context)
+    lianad.proc.wait(TIMEOUT)
+    assert not os.path.exists(cookie_path)
+    lianad.start()
@@ -1,3 +1,4 @@
+import json
 import pytest
 import random
 import re
@@ -13,6 +14,7 @@ from test_framework.utils import (
     wait_for,
     COIN,
     RpcError,
+    UnixSocket,
     get_txid,
     spend_coins,
     sign_and_broadcast,
@@ -33,6 +35,21 @@ def test_getinfo(lianad):
     assert res["rescan_progress"] is None
 
 
+def test_describepolicy(lianad):
+    res = lianad.rpc.describepolicy()
+    # A single key can always spend, another one after 10 blocks.
+    assert res["primary_path"]["threshold"] == 1
+    assert len(res["primary_path"]["keys"]) == 1
+    assert len(res["recovery_paths"]) == 1
+    recov = res["recovery_paths"][0]
+    assert recov["timelock"] == {"type": "relative", "value": 10}
+    assert recov["threshold"] == 1
+    assert recov["approx_days"] == 0.1
+    assert res["max_sat_weight"] >= res["primary_path_max_sat_weight"] > 0
+    assert res["text"].startswith("Primary path: the following key can spend at any")
+    assert "coins inactive for 10 blocks" in res["text"]
+
+
 def test_getaddress(lianad):
     res = lianad.rpc.getnewaddress()
     assert "address" in res
@@ -376,6 +393,79 @@ def test_jsonrpc_server(lianad, bitcoind):
     assert "psbt" in res
 
 
+def test_jsonrpc_batch_and_errors(lianad, bitcoind):
+    """Test sending batches of requests and malformed requests."""
+    addr = lianad.rpc.getnewaddress()["address"]
+    bitcoind.rpc.sendtoaddress(addr, 1)
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) == 1)
+    outpoint = lianad.rpc.listcoins()["coins"][0]["outpoint"]
+    lianad.rpc.updatelabels({outpoint: "batched"})
+
+    # Send raw messages over a single connection and read the replies.
+    sock = UnixSocket(lianad.rpc.socket_path)
+
+    def send(msg):
+        sock.sendall(msg + b"\n")
+        return lianad.rpc._readobj(sock)
+
+    # We can query the labels, the coins and the spends in a single round trip.
+    batch = [
+        {"jsonrpc": "2.0", "id": 1, "method": "getlabels", "params": [[outpoint]]},
+        {"jsonrpc": "2.0", "id": 2, "method": "listcoins", "params": []},
+        {"jsonrpc": "2.0", "id": 3, "method": "listspendtxs"},
+    ]
+    res = send(json.dumps(batch).encode())
+    assert isinstance(res, list) and len(res) == 3
+    assert [r["id"] for r in res] == [1, 2, 3]
+    assert res[0]["result"]["labels"] == {outpoint: "batched"}
+    assert res[1]["result"]["coins"][0]["outpoint"] == outpoint
+    assert res[2]["result"]["spend_txs"] == []
+
+    # Invalid requests within a batch get an error without affecting the others.
+    batch = [
+        {"jsonrpc": "2.0", "id": 4, "method": "getinfo"},
+        {"jsonrpc": "2.0", "id": 5, "methd": "getinfo"},
+        {"jsonrpc": "2.0", "id": 6, "method": "nonexistent"},
+    ]
+    res = send(json.dumps(batch).encode())
+    assert "result" in res[0]
+    assert res[1]["id"] == 5 and res[1]["error"]["code"] == -32600
+    assert res[2]["id"] == 6 and res[2]["error"]["code"] == -32601
+
+    # An empty batch is an invalid request.
+    res = send(b"[]")
+    assert res["id"] is None and res["error"]["code"] == -32600
+
+    # Invalid JSON gets a parse error, and the connection stays usable.
+    res = send(b'{"jsonrpc": "2.0", "id": 7, "method"')
+    assert res["id"] is None and res["error"]["code"] == -32700
+    res = send(b'{"jsonrpc": "2.0", "id": 8, "method": "getinfo"}')
+    assert res["id"] == 8 and "result" in res
+    sock.close()
+
+
+def test_estimatefee(lianad, bitcoind):
+    # The confirmation target must be within bounds.
+    for conf_target in (0, 1009):
+        with pytest.raises(RpcError, match="Invalid confirmation target"):
+            lianad.rpc.estimatefee(conf_target)
+
+    # On a fresh regtest chain the backend has no data to estimate fees from.
+    with pytest.raises(RpcError, match="Fee estimation .* is not available"):
+        lianad.rpc.estimatefee(6)
+
+    # The feerate and the confirmation target are exclusive.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    destinations = {bitcoind.rpc.getnewaddress(): 200_000}
+    with pytest.raises(RpcError, match="Only one of 'feerate' and 'conf_target'"):
+        lianad.rpc.createspend(destinations, [], 2, None, 6)
+    with pytest.raises(RpcError, match="Missing 'feerate' or 'conf_target'"):
+        lianad.rpc.createspend(destinations, [], None)
+
+
 def test_create_spend(lianad, bitcoind):
     # Receive a number of coins in different blocks on different addresses, and
     # one more on the same address.
@@ -436,6 +526,124 @@ def test_create_spend(lianad, bitcoind):
         lianad.rpc.createspend(destinations, [imma_coin["outpoint"]], 1)
 
 
+def test_freezecoins(lianad, bitcoind):
+    # Receive two coins.
+    for _ in range(2):
+        addr = lianad.rpc.getnewaddress()["address"]
+        txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+        bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 2)
+    coins = lianad.rpc.listcoins()["coins"]
+    assert all(not c["is_frozen"] for c in coins)
+
+    # We can't freeze an unknown coin.
+    unknown_op = "0" * 64 + ":0"
+    with pytest.raises(RpcError, match="Unknown outpoint"):
+        lianad.rpc.freezecoins([unknown_op])
+
+    # Freeze one of the coins, it's reported as frozen.
+    frozen_op = coins[0]["outpoint"]
+    lianad.rpc.freezecoins([frozen_op])
+    for c in lianad.rpc.listcoins()["coins"]:
+        assert c["is_frozen"] == (c["outpoint"] == frozen_op)
+
+    # Automatic coin selection can only use the other coin.
+    destinations = {bitcoind.rpc.getnewaddress(): 1_500_000}
+    res = lianad.rpc.createspend(destinations, [], 2)
+    assert "missing" in res
+    destinations = {bitcoind.rpc.getnewaddress(): 500_000}
+    res = lianad.rpc.createspend(destinations, [], 2)
+    spend_psbt = PSBT.from_base64(res["psbt"])
+    assert len(spend_psbt.tx.vin) == 1
+    assert spend_psbt.tx.vin[0].prevout.hash != int(frozen_op[:64], 16)
+
+    # But the frozen coin can still be selected explicitly.
+    res = lianad.rpc.createspend(destinations, [frozen_op], 2)
+    assert "psbt" in res
+
+    # Once unfrozen, it can be selected automatically again.
+    lianad.rpc.unfreezecoins([frozen_op])
+    assert all(not c["is_frozen"] for c in lianad.rpc.listcoins()["coins"])
+    destinations = {bitcoind.rpc.getnewaddress(): 1_500_000}
+    res = lianad.rpc.createspend(destinations, [], 2)
+    assert "psbt" in res
+
+
+def test_reserved_coins(lianad, bitcoind):
+    # Receive a coin.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    coin = lianad.rpc.listcoins()["coins"][0]
+    assert coin["reserved_by"] == []
+
+    # Create a draft spending it and store it. The coin is now reserved by this draft.
+    destinations = {bitcoind.rpc.getnewaddress(): 200_000}
+    res = lianad.rpc.createspend(destinations, [], 2)
+    spend_psbt = PSBT.from_base64(res["psbt"])
+    spend_txid = spend_psbt.tx.txid().hex()
+    lianad.rpc.updatespend(res["psbt"])
+    assert lianad.rpc.listcoins()["coins"][0]["reserved_by"] == [spend_txid]
+
+    # Another draft can't use it through automatic coin selection, unless explicitly asked.
+    res = lianad.rpc.createspend(destinations, [], 2)
+    assert "missing" in res
+    res = lianad.rpc.createspend(destinations, [], 2, None, None, True)
+    assert "psbt" in res
+    res = lianad.rpc.createspend(destinations, [coin["outpoint"]], 2)
+    assert "psbt" in res
+
+    # Deleting the draft releases the reservation.
+    lianad.rpc.delspendtx(spend_txid)
+    assert lianad.rpc.listcoins()["coins"][0]["reserved_by"] == []
+    res = lianad.rpc.createspend(destinations, [], 2)
+    assert "psbt" in res
+
+
+def test_create_cpfp(lianad, bitcoind):
+    # Receive a deposit at 1 sat/vb.
+    addr = lianad.rpc.getnewaddress()["address"]
+    deposit_txid = bitcoind.rpc.sendtoaddress(
+        addr, 0.01, "", "", False, False, None, "unset", None, 1
+    )
+    wait_for(lambda: len(lianad.rpc.listcoins(["unconfirmed"])["coins"]) == 1)
+    coin = lianad.rpc.listcoins(["unconfirmed"])["coins"][0]
+
+    # We can't bump a coin we don't know about.
+    with pytest.raises(RpcError, match="Unknown outpoint"):
+        lianad.rpc.createcpfp(["0" * 64 + ":0"], 10)
+
+    # Create a child transaction for a package feerate of 10 sat/vb and broadcast it.
+    res = lianad.rpc.createcpfp([coin["outpoint"]], 10)
+    cpfp_psbt = PSBT.from_base64(res["psbt"])
+    assert len(cpfp_psbt.tx.vin) == 1
+    assert len(cpfp_psbt.tx.vout) == 1
+    cpfp_txid = sign_and_broadcast_psbt(lianad, cpfp_psbt)
+
+    # The package made of the deposit and its child has the requested feerate.
+    entry = bitcoind.rpc.getmempoolentry(cpfp_txid)
+    assert entry["ancestorcount"] == 2
+    package_feerate = entry["fees"]["ancestor"] * COIN / entry["ancestorsize"]
+    assert 10 <= package_feerate < 10.5
+
+    # Once spent by the child, the deposit can't be bumped anymore.
+    wait_for(
+        lambda: lianad.rpc.listcoins([], [coin["outpoint"]])["coins"][0]["spend_info"]
+        is not None
+    )
+    with pytest.raises(RpcError, match="is already spent"):
+        lianad.rpc.createcpfp([coin["outpoint"]], 10)
+
+    # Once confirmed, the child's output can't be bumped either.
+    bitcoind.generate_block(1, wait_for_mempool=[deposit_txid, cpfp_txid])
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    cpfp_coin = lianad.rpc.listcoins(["confirmed"])["coins"][0]
+    assert cpfp_coin["outpoint"] == f"{cpfp_txid}:0"
+    with pytest.raises(RpcError, match="is already confirmed"):
+        lianad.rpc.createcpfp([cpfp_coin["outpoint"]], 10)
+
+
 def test_list_spend(lianad, bitcoind):
     # Start by creating two conflicting Spend PSBTs. The first one will have a change
     # output but not the second one.
@@ -872,6 +1080,67 @@ def test_listtransactions(lianad, bitcoind):
     assert bit_txids == txids
 
 
+def test_exporthistory(lianad, bitcoind):
+    """Test exporting the transaction history with the running balance."""
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+
+    # Receive a coin, label the deposit.
+    addr = lianad.rpc.getnewaddress()["address"]
+    deposit_txid = bitcoind.rpc.sendtoaddress(addr, 0.5)
+    bitcoind.generate_block(1, wait_for_mempool=deposit_txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    coin = lianad.rpc.listcoins()["coins"][0]
+    lianad.rpc.updatelabels({deposit_txid: "deposit, from Bob"})
+
+    # Spend it to an external address with a change output.
+    destinations = {bitcoind.rpc.getnewaddress(): 100_000}
+    res = lianad.rpc.createspend(destinations, [coin["outpoint"]], 2)
+    psbt = PSBT.from_base64(res["psbt"])
+    fee = coin["amount"] - sum(o.nValue for o in psbt.tx.vout)
+    spend_txid = sign_and_broadcast_psbt(lianad, psbt)
+    bitcoind.generate_block(1, wait_for_mempool=spend_txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+
+    # Both are exported in order, with the running balance.
+    history = lianad.rpc.exporthistory(0, 2**32 - 1)["transactions"]
+    assert len(history) == 2
+    deposit, spend = history
+    assert deposit["txid"] == deposit_txid
+    assert deposit["direction"] == "incoming"
+    assert deposit["net_amount"] == coin["amount"] == deposit["balance"]
+    assert deposit["fee"] is None
+    assert deposit["label"] == "deposit, from Bob"
+    assert spend["txid"] == spend_txid
+    assert spend["direction"] == "outgoing"
+    assert spend["fee"] == fee
+    assert spend["net_amount"] == -100_000 - fee
+    assert spend["balance"] == coin["amount"] - 100_000 - fee
+    assert spend["label"] is None
+    assert spend["height"] == deposit["height"] + 1
+    assert spend["time"] >= deposit["time"]
+
+    # The same as CSV.
+    csv = lianad.rpc.exporthistory(0, 2**32 - 1, "csv")["csv"]
+    lines = csv.splitlines()
+    assert lines[0] == "txid,height,time,direction,net_amount,fee,balance,label"
+    assert lines[1].startswith(f"{deposit_txid},{deposit['height']},")
+    assert lines[1].endswith(',"deposit, from Bob"')
+    assert lines[2].endswith(
+        f",outgoing,{spend['net_amount']},{fee},{spend['balance']},"
+    )
+
+    # Only the transactions within the time window are exported.
+    assert lianad.rpc.exporthistory(0, deposit["time"] - 1)["transactions"] == []
+
+    # Invalid parameters.
+    with pytest.raises(RpcError, match="must be 'json' or 'csv'"):
+        lianad.rpc.exporthistory(0, 1, "pdf")
+    with pytest.raises(RpcError, match="must not be after"):
+        lianad.rpc.exporthistory(2, 1)
+
+
 def test_create_recovery(lianad, bitcoind):
     """Test the sweep of coins that are available through the timelocked path."""
     # Generate blocks in order to test locktime set correctly.
@@ -932,6 +1201,263 @@ def test_create_recovery(lianad, bitcoind):
     sign_and_broadcast(lianad, bitcoind, reco_psbt, recovery=True)
 
 
+def test_create_recovery_signers(lianad, bitcoind):
+    """Test the sweep of coins through the recovery paths available to the signers."""
+    prim_fg = xpub_fingerprint(lianad.signer.primary_hd)
+    reco_fg = xpub_fingerprint(lianad.signer.recovery_hd)
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.1)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.2)
+
+    # Make the timelock of the first coin mature (we use a csv of 10 in the fixture).
+    bitcoind.generate_block(9, wait_for_mempool=txid)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+
+    # The signers can't be combined with a timelock and must be fingerprints.
+    addr = bitcoind.rpc.getnewaddress()
+    with pytest.raises(RpcError, match="Only one of"):
+        lianad.rpc.createrecovery(addr, 2, 10, None, None, None, [reco_fg])
+    with pytest.raises(RpcError, match="Invalid value .* in 'signers' parameter"):
+        lianad.rpc.createrecovery(addr, 2, None, None, None, None, ["recovery"])
+
+    # The primary signer alone can't use the recovery path.
+    with pytest.raises(
+        RpcError,
+        match="No coin currently spendable through this timelocked recovery path",
+    ):
+        lianad.rpc.createrecovery(addr, 2, None, None, None, None, [prim_fg])
+
+    # With the recovery signer, only the coin with a mature timelock is swept.
+    res = lianad.rpc.createrecovery(addr, 2, None, None, None, None, [reco_fg])
+    reco_psbt = PSBT.from_base64(res["psbt"])
+    assert len(reco_psbt.tx.vin) == 1
+    assert reco_psbt.tx.vin[0].nSequence == 10
+    assert len(reco_psbt.tx.vout) == 1
+    assert int(0.0999 * COIN) < int(reco_psbt.tx.vout[0].nValue) < int(0.1 * COIN)
+    txid = sign_and_broadcast(lianad, bitcoind, reco_psbt, recovery=True)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+
+
+def test_createspend_recovery_path(lianad, bitcoind):
+    """Test paying a destination through the timelocked recovery path."""
+    # Receive a coin, and another one a few blocks later.
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.3)
+    bitcoind.generate_block(5, wait_for_mempool=txid)
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.4)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 2)
+    recent_op = next(
+        c["outpoint"]
+        for c in lianad.rpc.listcoins(["confirmed"])["coins"]
+        if c["amount"] == 40_000_000
+    )
+
+    # The spending path must exist.
+    destinations = {bitcoind.rpc.getnewaddress(): 100_000}
+    with pytest.raises(RpcError, match="Invalid 'spending_path' parameter"):
+        lianad.rpc.createspend(destinations, [], 2, None, None, None, "recovery")
+    timelock = {"type": "relative", "value": 11}
+    with pytest.raises(RpcError, match="There is no recovery path with a relative"):
+        lianad.rpc.createspend(destinations, [], 2, None, None, None, timelock)
+
+    # Make the timelock of the first coin mature (we use a csv of 10 in the fixture).
+    bitcoind.generate_block(4)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+    timelock = {"type": "relative", "value": 10}
+    with pytest.raises(RpcError, match="can't be spent through this spending path"):
+        lianad.rpc.createspend(destinations, [recent_op], 2, None, None, None, timelock)
+
+    # Only the first coin is used to pay the destination, the rest goes back to us.
+    res = lianad.rpc.createspend(destinations, [], 2, None, None, None, timelock)
+    psbt = PSBT.from_base64(res["psbt"])
+    assert len(psbt.tx.vin) == 1
+    assert psbt.tx.vin[0].nSequence == 10
+    assert len(psbt.tx.vout) == 2
+    txid = sign_and_broadcast(lianad, bitcoind, psbt, recovery=True)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+
+
+def test_list_expiring_coins(lianad, bitcoind):
+    """Test the report of the remaining blocks until the recovery path is available."""
+    assert lianad.rpc.listexpiringcoins()["coins"] == []
+
+    # Receive a coin, and another one a block later. Unconfirmed coins aren't listed.
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.1)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    first_height = bitcoind.rpc.getblockcount()
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.2)
+    wait_for(lambda: len(lianad.rpc.listcoins(["unconfirmed"])["coins"]) == 1)
+    first_op = lianad.rpc.listcoins(["confirmed"])["coins"][0]["outpoint"]
+    second_op = lianad.rpc.listcoins(["unconfirmed"])["coins"][0]["outpoint"]
+    assert [c["outpoint"] for c in lianad.rpc.listexpiringcoins()["coins"]] == [
+        first_op
+    ]
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+
+    # We use a csv of 10 in the fixture. The oldest coin comes first.
+    coins = lianad.rpc.listexpiringcoins()["coins"]
+    assert [c["outpoint"] for c in coins] == [first_op, second_op]
+    assert coins[0]["block_height"] == first_height
+    assert coins[0]["amount"] == 10_000_000
+    assert coins[0]["recovery_paths"] == [{"timelock": 10, "blocks_remaining": 8}]
+    assert coins[1]["recovery_paths"] == [{"timelock": 10, "blocks_remaining": 9}]
+
+    # We can filter by the number of remaining blocks.
+    coins = lianad.rpc.listexpiringcoins(8)["coins"]
+    assert [c["outpoint"] for c in coins] == [first_op]
+    assert lianad.rpc.listexpiringcoins(7)["coins"] == []
+    with pytest.raises(RpcError, match="Invalid 'max_blocks' parameter"):
+        lianad.rpc.listexpiringcoins(-1)
+
+    # Once the timelock is mature the path is reported as available.
+    bitcoind.generate_block(8)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+    coins = lianad.rpc.listexpiringcoins(0)["coins"]
+    assert [c["outpoint"] for c in coins] == [first_op]
+    assert coins[0]["recovery_paths"][0]["blocks_remaining"] == 0
+    lianad.rpc.createrecovery(bitcoind.rpc.getnewaddress(), 2)
+
+
+def test_getrecoverytimeline(lianad, bitcoind):
+    """Test the schedule of the coins becoming available through the recovery path."""
+    res = lianad.rpc.getrecoverytimeline()
+    assert len(res["recovery_paths"]) == 1
+    assert res["recovery_paths"][0]["timelock"] == {"type": "relative", "value": 10}
+    assert res["recovery_paths"][0]["total_amount"] == 0
+    assert res["recovery_paths"][0]["unlocks"] == []
+
+    # Receive two coins in a block, and another one in the next block.
+    destinations = {lianad.rpc.getnewaddress()["address"]: 0.1 for _ in range(2)}
+    txid = bitcoind.rpc.sendmany("", destinations)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    first_height = bitcoind.rpc.getblockcount()
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.2)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 3)
+
+    # We use a csv of 10 in the fixture. The coins are grouped by unlock height.
+    res = lianad.rpc.getrecoverytimeline()
+    assert res["tip_height"] == first_height + 1
+    path = res["recovery_paths"][0]
+    assert path["total_amount"] == 40_000_000
+    assert [(u["height"], u["amount"], len(u["coins"])) for u in path["unlocks"]] == [
+        (first_height + 10, 20_000_000, 2),
+        (first_height + 11, 20_000_000, 1),
+    ]
+    assert all(not u["available"] and u["time"] > 0 for u in path["unlocks"])
+
+    # Once the timelock of the first coins is mature they are reported as available.
+    bitcoind.generate_block(8)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+    unlocks = lianad.rpc.getrecoverytimeline()["recovery_paths"][0]["unlocks"]
+    assert [(u["available"], u["time"]) for u in unlocks][0] == (True, None)
+    assert not unlocks[1]["available"]
+
+
+def test_refresh_coins(lianad, bitcoind):
+    """Test the creation of transactions refreshing the coins close to expiry."""
+    assert lianad.rpc.refreshcoins(10, 1)["spends"] == []
+
+    # Receive 3 coins in a block and another one 3 blocks later.
+    destinations = {lianad.rpc.getnewaddress()["address"]: 0.1 for _ in range(3)}
+    txid = bitcoind.rpc.sendmany("", destinations)
+    bitcoind.generate_block(3, wait_for_mempool=txid)
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.2)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 4)
+    last_op = next(
+        c["outpoint"]
+        for c in lianad.rpc.listcoins(["confirmed"])["coins"]
+        if c["outpoint"].startswith(txid)
+    )
+
+    # We use a csv of 10 in the fixture. Only the first 3 coins are within 6 blocks of
+    # expiry. Limit the number of inputs per transaction.
+    with pytest.raises(RpcError, match="Invalid maximum number of inputs"):
+        lianad.rpc.refreshcoins(6, 1, 0)
+    spends = lianad.rpc.refreshcoins(6, 1, 2)["spends"]
+    assert [len(PSBT.from_base64(s["psbt"]).tx.vin) for s in spends] == [2, 1]
+    spends = lianad.rpc.refreshcoins(9, 1)["spends"]
+    assert len(spends) == 1
+    psbt = PSBT.from_base64(spends[0]["psbt"])
+    assert len(psbt.tx.vin) == 4
+    assert len(psbt.tx.vout) == 1
+
+    # Refresh the first 3 coins. Once broadcast they aren't close to expiry anymore.
+    psbt = PSBT.from_base64(lianad.rpc.refreshcoins(6, 2)["spends"][0]["psbt"])
+    assert len(psbt.tx.vin) == 3
+    txid = sign_and_broadcast_psbt(lianad, psbt)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 2)
+    spends = lianad.rpc.refreshcoins(8, 1)["spends"]
+    assert len(spends) == 1
+    psbt = PSBT.from_base64(spends[0]["psbt"])
+    assert [f"{i.prevout.hash:064x}:{i.prevout.n}" for i in psbt.tx.vin] == [last_op]
+
+
+def test_wait_for_events(lianad, bitcoind):
+    """Test following the changes to the wallet through the events journal."""
+    # Start from the end of the journal.
+    cursor = 0
+    while True:
+        res = lianad.rpc.waitforevents(cursor, 0)
+        if len(res["events"]) == 0:
+            break
+        cursor = res["cursor"]
+    assert res["cursor"] == cursor
+
+    with pytest.raises(RpcError, match="Invalid timeout"):
+        lianad.rpc.waitforevents(cursor, 301)
+
+    # We are notified about a new coin, and its confirmation.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.1)
+    res = lianad.rpc.waitforevents(cursor, 60)
+    assert len(res["events"]) >= 1
+    event = res["events"][0]["event"]
+    assert event["type"] == "received"
+    assert event["outpoint"].startswith(txid)
+    assert event["address"] == addr
+    assert event["amount"] == 10_000_000
+    cursor = res["cursor"]
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    res = lianad.rpc.waitforevents(cursor, 60)
+    event = res["events"][0]["event"]
+    assert event["type"] == "confirmed"
+    assert event["height"] == bitcoind.rpc.getblockcount()
+    cursor = res["cursor"]
+
+    # Storing and deleting a Spend transaction are recorded too.
+    outpoint = lianad.rpc.listcoins()["coins"][0]["outpoint"]
+    destinations = {bitcoind.rpc.getnewaddress(): 50_000}
+    psbt = lianad.rpc.createspend(destinations, [outpoint], 2)["psbt"]
+    spend_txid = PSBT.from_base64(psbt).tx.txid().hex()
+    lianad.rpc.updatespend(psbt)
+    lianad.rpc.delspendtx(spend_txid)
+    res = lianad.rpc.waitforevents(cursor, 0)
+    assert [e["event"] for e in res["events"]] == [
+        {"type": "spend_updated", "txid": spend_txid},
+        {"type": "spend_deleted", "txid": spend_txid},
+    ]
+    assert res["cursor"] == res["events"][-1]["id"]
+
+    # Without new event we time out with an unchanged cursor.
+    res = lianad.rpc.waitforevents(res["cursor"], 1)
+    assert res["events"] == []
+
+
 def test_labels(lianad, bitcoind):
     """Test the creation and updating of labels."""
     # We can set a label for an address.
@@ -1059,6 +1585,89 @@ def test_labels(lianad, bitcoind):
     assert res[random_address] == "this address is random"
 
 
+def test_bip329_labels(lianad, bitcoind):
+    """Test exporting and importing labels in the BIP329 format."""
+    # Nothing to export yet.
+    assert lianad.rpc.exportlabels()["labels"] == ""
+
+    # Label an address, a deposit and the coins it created. Freeze one of them.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 1)
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) == 1)
+    coin = lianad.rpc.listcoins()["coins"][0]
+    lianad.rpc.freezecoins([coin["outpoint"]])
+    inexistent_outpoint = "".join("1" for _ in range(64)) + ":42"
+    labels = {
+        addr: "deposit address",
+        txid: "deposit",
+        coin["outpoint"]: "frozen coin",
+        inexistent_outpoint: "not ours",
+    }
+    lianad.rpc.updatelabels(labels)
+
+    # They are all exported, with the spendable status of our coin.
+    exported = lianad.rpc.exportlabels()["labels"]
+    records = [json.loads(line) for line in exported.splitlines()]
+    assert len(records) == 4
+    types = {addr: "addr", txid: "tx", coin["outpoint"]: "output"}
+    types[inexistent_outpoint] = "output"
+    origin = records[0]["origin"]
+    assert origin.startswith("tr(" if USE_TAPROOT else "wsh(")
+    for rec in records:
+        assert rec["type"] == types[rec["ref"]]
+        assert rec["label"] == labels[rec["ref"]]
+        assert rec["origin"] == origin
+    assert next(r for r in records if r["ref"] == coin["outpoint"])["spendable"] is False
+    assert "spendable" not in next(
+        r for r in records if r["ref"] == inexistent_outpoint
+    )
+
+    # Remove the labels and unfreeze the coin, then import them back along with records for
+    # items we can't label.
+    lianad.rpc.updatelabels({item: None for item in labels})
+    lianad.rpc.unfreezecoins([coin["outpoint"]])
+    other_records = [
+        {
+            "type": "pubkey",
+            "ref": "0283409659355b6d1cc3c32decd5d561abaac86c37a353b52895a5e6c196d6f448",
+            "label": "Public Key",
+        },
+        {"type": "input", "ref": f"{txid}:0", "label": "Input"},
+    ]
+    jsonl = "\n".join([exported] + [json.dumps(r) for r in other_records])
+    res = lianad.rpc.importlabels(jsonl)
+    assert res == {"imported": 4, "skipped": 2}
+    assert lianad.rpc.getlabels(list(labels))["labels"] == labels
+    assert lianad.rpc.listcoins([], [coin["outpoint"]])["coins"][0]["is_frozen"]
+
+    # We can import labels exported by another wallet, extra fields are ignored.
+    lianad.rpc.importlabels(
+        json.dumps(
+            {
+                "type": "tx",
+                "ref": txid,
+                "label": "from another wallet",
+                "origin": "wpkh([d34db33f/84'/0'/0'])",
+                "height": 800000,
+            }
+        )
+    )
+    assert lianad.rpc.getlabels([txid])["labels"] == {txid: "from another wallet"}
+
+    # Nothing is imported if a record is invalid.
+    invalid_records = [
+        "not json",
+        json.dumps({"type": "tx", "ref": "not a txid", "label": "a"}),
+        json.dumps({"type": "tx", "ref": txid, "label": "a" * 101}),
+    ]
+    for invalid in invalid_records:
+        with pytest.raises(RpcError):
+            lianad.rpc.importlabels(
+                json.dumps({"type": "addr", "ref": addr, "label": "b"}) + "\n" + invalid
+            )
+    assert lianad.rpc.getlabels([addr])["labels"] == {addr: "deposit address"}
+
+
 def test_rbfpsbt_bump_fee(lianad, bitcoind):
     """Test the use of RBF to bump the fee of a transaction."""
 